@@ -0,0 +1,254 @@
+//! Accessibility checks of the content, for courses that have to meet accessibility
+//! requirements: images need alt text for screen readers, heading levels must not skip
+//! any, as screen reader users navigate by them, links need text that says where they
+//! lead, and tables need headers.
+
+use std::{collections::BTreeSet, path::PathBuf};
+
+use error_stack::Result;
+
+use crate::{
+    check::{CheckError, Finding},
+    io::PathExt,
+    markdown::{fence, inlines, Inline},
+    Track,
+};
+
+/// Link texts that don't say where a link leads when read out of context, as screen readers
+/// list them
+const VAGUE_LINK_TEXTS: &[&str] = &[
+    "click here",
+    "here",
+    "link",
+    "more",
+    "read more",
+    "this",
+    "this link",
+    "this page",
+];
+
+impl Track {
+    /// Report the accessibility problems in the content files of the track
+    pub(crate) fn a11y_findings(&self) -> Result<Vec<Finding>, CheckError> {
+        // Topics can be reused across units, but their content only needs checking once
+        let content_files: BTreeSet<_> = self
+            .content_files()
+            .chain(
+                self.exercises()
+                    .flat_map(|e| e.hints.iter().map(PathBuf::as_path)),
+            )
+            .collect();
+        let mut findings = vec![];
+        for path in content_files {
+            let content = path.read_to_string()?;
+            findings.extend(
+                a11y_problems(&content)
+                    .into_iter()
+                    .map(|(line, message)| Finding {
+                        path: path.to_path_buf(),
+                        line: Some(line),
+                        message,
+                    }),
+            );
+        }
+        Ok(findings)
+    }
+}
+
+/// The accessibility problems in the Markdown `content`, with the 1-based lines they are on.
+/// Code blocks, HTML comments and front matter are skipped.
+fn a11y_problems(content: &str) -> Vec<(usize, String)> {
+    let mut problems = vec![];
+    let mut heading_level = None;
+    let mut open_fence = None;
+    let mut in_comment = false;
+    let mut in_front_matter = false;
+    // The line and whether a header cell was found, of the HTML table being read
+    let mut html_table: Option<(usize, bool)> = None;
+    let mut previous = "";
+    for (line, line_i) in content.lines().zip(1..) {
+        let trimmed = line.trim();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed == marker {
+                open_fence = None;
+            }
+            continue;
+        }
+        if line_i == 1 && trimmed == "+++" {
+            in_front_matter = true;
+            continue;
+        }
+        if in_front_matter {
+            in_front_matter = trimmed != "+++";
+            continue;
+        }
+        if in_comment || trimmed.starts_with("<!--") {
+            in_comment = !trimmed.contains("-->");
+            continue;
+        }
+        if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+            continue;
+        }
+
+        if let Some(level) = heading(trimmed) {
+            if let Some(previous) = heading_level.filter(|&previous| level > previous + 1) {
+                problems.push((
+                    line_i,
+                    format!("Heading level skips from {previous} to {level}, so screen reader users miss where the content is nested"),
+                ));
+            }
+            heading_level = Some(level);
+        }
+
+        let mut elements = inlines(trimmed);
+        while let Some(element) = elements.pop() {
+            match element {
+                Inline::Image { alt, target } if alt.trim().is_empty() => {
+                    problems.push((line_i, format!("Image '{target}' has no alt text")))
+                }
+                Inline::Link { label, target } => {
+                    let text = inline_text(&label);
+                    let text = text.trim().trim_end_matches(['.', ':']).to_lowercase();
+                    if text.is_empty() {
+                        problems.push((line_i, format!("Link to '{target}' has no text")));
+                    } else if VAGUE_LINK_TEXTS.contains(&text.as_str()) {
+                        problems.push((
+                            line_i,
+                            format!(
+                                "Link text '{text}' doesn't say where the link to '{target}' leads"
+                            ),
+                        ));
+                    }
+                    elements.extend(label);
+                }
+                Inline::Strong(nested) | Inline::Emphasis(nested) => elements.extend(nested),
+                _ => {}
+            }
+        }
+        for img in trimmed.match_indices("<img").map(|(i, _)| &trimmed[i..]) {
+            let tag = &img[..img.find('>').map_or(img.len(), |end| end + 1)];
+            // An empty alt marks an image as decorative, for screen readers to skip
+            let has_alt = tag
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .any(|attribute| attribute == "alt" || attribute.starts_with("alt="));
+            if !has_alt {
+                problems.push((line_i, "HTML image has no alt text".to_string()));
+            }
+        }
+
+        if is_delimiter_row(trimmed)
+            && previous.contains('|')
+            && cells(previous).iter().all(|cell| cell.is_empty())
+        {
+            problems.push((line_i - 1, "Table has no header".to_string()));
+        }
+        if trimmed.contains("<table") {
+            html_table = Some((line_i, false));
+        }
+        if let Some((_, has_header)) = &mut html_table {
+            *has_header |= trimmed.contains("<th");
+        }
+        if let Some((table_line, false)) = html_table.filter(|_| trimmed.contains("</table>")) {
+            problems.push((table_line, "HTML table has no header cells".to_string()));
+        }
+        if trimmed.contains("</table>") {
+            html_table = None;
+        }
+        previous = trimmed;
+    }
+    problems.sort();
+    problems
+}
+
+/// The level of the ATX heading on `line`, if it is one
+fn heading(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' '))).then_some(level)
+}
+
+/// Whether `line` is the row that separates the header of a Markdown table from its body,
+/// like `|---|:---:|`
+fn is_delimiter_row(line: &str) -> bool {
+    line.contains('-')
+        && line.contains('|')
+        && line
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+/// The trimmed cells of the Markdown table row `line`
+fn cells(line: &str) -> Vec<&str> {
+    let line = line.trim().trim_start_matches('|').trim_end_matches('|');
+    line.split('|').map(str::trim).collect()
+}
+
+/// The text of `inlines`, without their markup
+fn inline_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) | Inline::Code(text) => text.to_string(),
+            Inline::Strong(nested) | Inline::Emphasis(nested) => inline_text(nested),
+            Inline::Link { label, .. } => inline_text(label),
+            Inline::Image { alt, .. } => alt.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inaccessible_content_is_reported_by_line() {
+        let content = r#"+++
+template = true
++++
+# Ownership
+
+![](images/stack.svg) and ![The heap](images/heap.svg)
+
+### Moves
+
+For details, [click here](https://doc.rust-lang.org/book/).
+See [the Rust book](https://doc.rust-lang.org/book/) and <https://doc.rust-lang.org>.
+
+<img src="images/borrow.png">
+
+| | |
+|---|---|
+| a | b |
+
+```md
+#### Not a heading
+![](fine.png)
+```
+
+<table>
+<tr><td>1</td></tr>
+</table>
+"#;
+        assert_eq!(
+            a11y_problems(content),
+            [
+                (6, "Image 'images/stack.svg' has no alt text".to_string()),
+                (8, "Heading level skips from 1 to 3, so screen reader users miss where the content is nested".to_string()),
+                (10, "Link text 'click here' doesn't say where the link to 'https://doc.rust-lang.org/book/' leads".to_string()),
+                (13, "HTML image has no alt text".to_string()),
+                (15, "Table has no header".to_string()),
+                (24, "HTML table has no header cells".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn decorative_html_images_are_not_reported() {
+        let content = "<img src=\"divider.svg\" alt=\"\">\n<img alt src=\"corner.svg\"/>\n<img data-alt=\"x\" src=\"logo.svg\">\n";
+        assert_eq!(
+            a11y_problems(content),
+            [(3, "HTML image has no alt text".to_string())]
+        );
+    }
+}
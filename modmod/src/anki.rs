@@ -0,0 +1,275 @@
+use std::{collections::HashSet, fmt, path::Path};
+
+use error_stack::{IntoReport, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::PathExt,
+    load::Indexed,
+    quiz::{OneOrMany, Question, Quiz},
+    to_tag,
+    visit::{Position, Visitor},
+    Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct AnkiExportError {}
+
+impl fmt::Display for AnkiExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to export Anki flashcards")
+    }
+}
+
+impl error_stack::Context for AnkiExportError {}
+
+/// The flashcards of a topic, as defined in the `flashcards.toml` next to the topic TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Flashcards {
+    pub cards: Vec<FlashcardDef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashcardDef {
+    pub front: String,
+    pub back: String,
+}
+
+/// A card in an exported Anki deck. `front` and `back` are HTML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    /// The Anki deck the card goes into, e.g. `Rust intro::2 Foundations::2.1 Basic syntax`
+    pub deck: String,
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+}
+
+impl Track {
+    /// Collect flashcards from the unit quizzes and the topic `flashcards.toml` files,
+    /// in track order. Cards go into a deck per unit, nested in a deck per module.
+    /// The flashcards of a reused topic go into the deck of the unit it first appears in.
+    pub fn flashcards(&self) -> Result<Vec<Card>, AnkiExportError> {
+        let mut collector = CardCollector {
+            track_name: &self.name,
+            ..Default::default()
+        };
+        self.walk(&mut collector)?;
+        Ok(collector.cards)
+    }
+}
+
+/// Render cards as a tab-separated file that Anki can import with `File > Import`.
+/// The header lines tell Anki which columns hold the deck and the tags.
+pub fn render_anki_csv(cards: &[Card]) -> String {
+    let mut csv = String::from(
+        "#separator:tab\n#html:true\n#notetype:Basic\n#deck column:3\n#tags column:4\n",
+    );
+    for card in cards {
+        let fields = [
+            csv_field(&card.front),
+            csv_field(&card.back),
+            csv_field(&card.deck),
+            csv_field(&card.tags.join(" ")),
+        ];
+        csv.push_str(&fields.join("\t"));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[derive(Default)]
+struct CardCollector<'t> {
+    cards: Vec<Card>,
+    track_name: &'t str,
+    module_deck: String,
+    unit_deck: String,
+    /// Flashcard files already exported, as topics can be reused across units
+    seen_flashcards: HashSet<&'t Path>,
+}
+
+impl<'t> Visitor<'t> for CardCollector<'t> {
+    type Error = AnkiExportError;
+
+    fn visit_module(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), AnkiExportError> {
+        self.module_deck = format!(
+            "{}::{} {}",
+            deck_name(self.track_name),
+            position.module,
+            deck_name(&module.data.name)
+        );
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), AnkiExportError> {
+        let number = position.unit_number().unwrap();
+        self.unit_deck = format!(
+            "{}::{number} {}",
+            self.module_deck,
+            deck_name(&unit.data.name)
+        );
+        let tags = card_tags(&unit.data.tags);
+        for quiz in unit.data.quizzes.iter() {
+            let quiz = Quiz::load::<AnkiExportError>(quiz)?;
+            for question in quiz.questions {
+                let (front, back) = question_card(question);
+                self.cards.push(Card {
+                    deck: self.unit_deck.clone(),
+                    front,
+                    back,
+                    tags: tags.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), AnkiExportError> {
+        let Some(path) = &topic.data.flashcards else {
+            return Ok(());
+        };
+        if !self.seen_flashcards.insert(path) {
+            return Ok(());
+        }
+        let flashcards: Flashcards = toml::from_str(&path.read_to_string()?)
+            .into_report()
+            .attach_printable_lazy(|| format!("Invalid flashcards file {}", path.to_string_lossy()))
+            .change_context(AnkiExportError::default())?;
+        let tags = card_tags(&topic.data.tags);
+        for card in flashcards.cards {
+            self.cards.push(Card {
+                deck: self.unit_deck.clone(),
+                front: text_to_html(&card.front),
+                back: text_to_html(&card.back),
+                tags: tags.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Turn a quiz question into the front and back of a card
+fn question_card(question: Question) -> (String, String) {
+    let (front, mut back, context) = match question {
+        Question::ShortAnswer {
+            prompt,
+            answer,
+            context,
+        } => (
+            text_to_html(&prompt.prompt),
+            text_to_html(&answer.answer),
+            context,
+        ),
+        Question::MultipleChoice {
+            prompt,
+            answer,
+            context,
+        } => {
+            let answers = match answer.answer {
+                OneOrMany::One(answer) => vec![answer],
+                OneOrMany::Many(answers) => answers,
+            };
+            let mut options: Vec<_> = answers.iter().chain(prompt.distractors.iter()).collect();
+            // Sort the options so the correct answers are not always first
+            options.sort();
+            let options: String = options
+                .into_iter()
+                .map(|o| format!("<li>{}</li>", text_to_html(o)))
+                .collect();
+            let answers: Vec<_> = answers.iter().map(|a| text_to_html(a)).collect();
+            (
+                format!("{}<ul>{options}</ul>", text_to_html(&prompt.prompt)),
+                answers.join("<br>"),
+                context,
+            )
+        }
+        Question::Tracing {
+            prompt,
+            answer,
+            context,
+        } => {
+            let front = format!(
+                "Does this program compile? If so, what does it print?<pre><code>{}</code></pre>",
+                html_escape(&prompt.program)
+            );
+            let back = match (answer.does_compile, answer.line_number) {
+                (true, _) => format!(
+                    "It compiles and prints:<pre>{}</pre>",
+                    html_escape(answer.stdout.as_deref().unwrap_or_default())
+                ),
+                (false, Some(line)) => format!("It does not compile, because of line {line}"),
+                (false, None) => "It does not compile".to_string(),
+            };
+            (front, back, context)
+        }
+    };
+    if let Some(context) = context {
+        back.push_str("<hr>");
+        back.push_str(&text_to_html(&context));
+    }
+    (front, back)
+}
+
+/// Anki uses `::` to separate nested deck names
+fn deck_name(name: &str) -> String {
+    name.replace("::", ":")
+}
+
+/// Anki tags are separated by spaces, so we use the slugified versions of the content tags
+fn card_tags(tags: &[String]) -> Vec<String> {
+    tags.iter().map(to_tag).collect()
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn text_to_html(s: &str) -> String {
+    html_escape(s.trim()).replace('\n', "<br>")
+}
+
+/// Quote a field if needed. Anki parses the file as CSV with tabs as separator.
+fn csv_field(s: &str) -> String {
+    if s.contains(['\t', '\n', '"']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_quoted_for_anki() {
+        let cards = [Card {
+            deck: "Track::1 Module::1.1 Unit".to_string(),
+            front: "What does <code>\"a\"</code>\tmean?".to_string(),
+            back: "A string".to_string(),
+            tags: vec!["basics".to_string(), "strings".to_string()],
+        }];
+        let csv = render_anki_csv(&cards);
+        assert_eq!(
+            csv.lines().last().unwrap(),
+            "\"What does <code>\"\"a\"\"</code>\tmean?\"\tA string\tTrack::1 Module::1.1 Unit\tbasics strings"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    fmt,
+    fs::{File, Metadata},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+use crate::{
+    handout::HANDOUTS_DIR,
+    io::PathExt,
+    load::Indexed,
+    numbering::{
+        chapter_file_name, module_book_images_dir, module_deck_file_name, module_exercises_dir,
+        section_file_name, solution_dir, UnitNumber, QUIZZES_DIR,
+    },
+    to_prefixed_tag, to_tag, Module, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PackageError {}
+
+impl fmt::Display for PackageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to package output")
+    }
+}
+
+impl error_stack::Context for PackageError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageFormat {
+    Zip,
+    TarGz,
+}
+
+impl PackageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PackageFormat::Zip => "zip",
+            PackageFormat::TarGz => "tar.gz",
+        }
+    }
+}
+
+impl FromStr for PackageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "zip" => Ok(PackageFormat::Zip),
+            "tar.gz" | "tgz" => Ok(PackageFormat::TarGz),
+            _ => Err(format!(
+                "unknown package format '{s}', expected zip or tar.gz"
+            )),
+        }
+    }
+}
+
+/// Name of the folder in the output directory the packages are written to
+pub const PACKAGES_DIR: &str = "packages";
+
+/// File permissions and modification time used for every archive entry,
+/// so that archives only depend on the contents of the packaged files.
+/// Files that are executable on disk get [`EXECUTABLE_ENTRY_MODE`] instead.
+pub(crate) const ENTRY_MODE: u32 = 0o644;
+pub(crate) const EXECUTABLE_ENTRY_MODE: u32 = 0o755;
+const ENTRY_MTIME: u64 = 0;
+
+impl Track {
+    /// Bundle the rendered output in `out_dir` into archives: one for the whole course,
+    /// and one per module containing its chapter page, book sections, quizzes, slides,
+    /// handouts, exercises and solutions.
+    /// Returns the paths of the written archives.
+    pub fn package(
+        &self,
+        out_dir: impl AsRef<Path>,
+        format: PackageFormat,
+    ) -> Result<Vec<PathBuf>, PackageError> {
+        let out_dir = out_dir.as_ref();
+        let packages_dir = out_dir.join(PACKAGES_DIR);
+        packages_dir.create_dir_all()?;
+
+        let all_files = output_files(out_dir)?;
+        let mut archives = vec![];
+
+        let course_archive = out_dir.join(self.course_archive_path(format));
+        write_archive(&course_archive, out_dir, &all_files, format)?;
+        archives.push(course_archive);
+
+        for module in self.modules.iter() {
+            let module_files: Vec<_> = all_files
+                .iter()
+                .filter(|f| module.contains_output(f))
+                .cloned()
+                .collect();
+            if module_files.is_empty() {
+                continue;
+            }
+            let module_archive = out_dir.join(module.archive_path(format));
+            write_archive(&module_archive, out_dir, &module_files, format)?;
+            archives.push(module_archive);
+        }
+
+        Ok(archives)
+    }
+
+    /// Path of the archive holding the whole course, relative to the output dir
+    pub(crate) fn course_archive_path(&self, format: PackageFormat) -> PathBuf {
+        Path::new(PACKAGES_DIR)
+            .join(to_tag(&self.name))
+            .with_extension(format.extension())
+    }
+}
+
+impl Indexed<Module> {
+    /// Path of the archive holding the output of the module, relative to the output dir
+    pub(crate) fn archive_path(&self, format: PackageFormat) -> PathBuf {
+        Path::new(PACKAGES_DIR)
+            .join(to_prefixed_tag(&self.data.name, self.index))
+            .with_extension(format.extension())
+    }
+
+    /// Whether `path`, relative to the output dir, is part of the output of the module
+    pub(crate) fn contains_output(&self, path: &Path) -> bool {
+        module_prefixes(self.index, &self.data)
+            .iter()
+            .any(|p| path.starts_with(p))
+    }
+}
+
+/// Paths relative to the output dir that hold the output of a single module
+fn module_prefixes(module_index: usize, module: &Module) -> Vec<PathBuf> {
+    let book_src_dir = Path::new("book").join("src");
+    let slides_dir = Path::new("slides");
+    let exercises_dir = module_exercises_dir(&module.name, module_index);
+    let mut prefixes = vec![
+        solution_dir(&exercises_dir),
+        exercises_dir,
+        book_src_dir.join(chapter_file_name(&module.name, module_index)),
+        book_src_dir.join(module_book_images_dir(module_index)),
+        slides_dir.join(module_deck_file_name(&module.name, module_index)),
+    ];
+    for unit in module.units.iter() {
+        let unit_number = UnitNumber::new(module_index, unit.index);
+        // The handouts are named after the deck they are made of
+        let deck_file_name = unit_number.deck_file_name(&unit.data.name);
+        prefixes.push(book_src_dir.join(section_file_name(&unit.data.name)));
+        prefixes.extend((1..=unit.data.quizzes.len()).map(|quiz| {
+            book_src_dir
+                .join(QUIZZES_DIR)
+                .join(unit_number.quiz_file_name(quiz))
+        }));
+        prefixes.push(book_src_dir.join(HANDOUTS_DIR).join(&deck_file_name));
+        prefixes.push(slides_dir.join(HANDOUTS_DIR).join(&deck_file_name));
+        prefixes.push(slides_dir.join(deck_file_name));
+    }
+    prefixes
+}
+
+/// Mode of the archive entry of a file with `metadata`, keeping whether it's executable
+pub(crate) fn entry_mode(metadata: &Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return EXECUTABLE_ENTRY_MODE;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    ENTRY_MODE
+}
+
+/// All files in the output dir, relative to it and sorted,
+/// leaving out previously written packages
+fn output_files(out_dir: &Path) -> Result<Vec<PathBuf>, PackageError> {
+    let mut files: Vec<_> = out_dir
+        .get_dir_content::<PackageError>()?
+        .files
+        .into_iter()
+        .map(|f| Path::new(&f).strip_prefix(out_dir).unwrap().to_path_buf())
+        .filter(|f| !f.starts_with(PACKAGES_DIR))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub(crate) fn write_archive(
+    archive_path: &Path,
+    base_dir: &Path,
+    files: &[PathBuf],
+    format: PackageFormat,
+) -> Result<(), PackageError> {
+    let archive_file = archive_path.create_file()?;
+    let attach_path = || format!("Error writing archive {}", archive_path.to_string_lossy());
+
+    match format {
+        PackageFormat::Zip => {
+            let mut zip = zip::ZipWriter::new(archive_file);
+            let options = zip::write::SimpleFileOptions::default()
+                .last_modified_time(zip::DateTime::default());
+            for file in files {
+                let mut source: File = base_dir.join(file).open_file()?;
+                let metadata = source
+                    .metadata()
+                    .into_report()
+                    .change_context(PackageError::default())?;
+                let options = options.unix_permissions(entry_mode(&metadata));
+                zip.start_file(entry_name(file), options)
+                    .into_report()
+                    .attach_printable_lazy(attach_path)
+                    .change_context(PackageError::default())?;
+                io::copy(&mut source, &mut zip)
+                    .into_report()
+                    .attach_printable_lazy(attach_path)
+                    .change_context(PackageError::default())?;
+            }
+            zip.finish()
+                .into_report()
+                .attach_printable_lazy(attach_path)
+                .change_context(PackageError::default())?;
+        }
+        PackageFormat::TarGz => {
+            // The gzip header mtime defaults to 0, keeping the output reproducible
+            let gz = flate2::write::GzEncoder::new(archive_file, flate2::Compression::default());
+            let mut tar = tar::Builder::new(gz);
+            for file in files {
+                let mut source: File = base_dir.join(file).open_file()?;
+                let metadata = source
+                    .metadata()
+                    .into_report()
+                    .change_context(PackageError::default())?;
+                let mut header = tar::Header::new_gnu();
+                header.set_size(metadata.len());
+                header.set_mode(entry_mode(&metadata));
+                header.set_mtime(ENTRY_MTIME);
+                header.set_uid(0);
+                header.set_gid(0);
+                header.set_entry_type(tar::EntryType::Regular);
+                tar.append_data(&mut header, entry_name(file), &mut source)
+                    .into_report()
+                    .attach_printable_lazy(attach_path)
+                    .change_context(PackageError::default())?;
+            }
+            tar.into_inner()
+                .and_then(|gz| gz.finish())
+                .into_report()
+                .attach_printable_lazy(attach_path)
+                .change_context(PackageError::default())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Archive entry names always use forward slashes, regardless of platform
+pub(crate) fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
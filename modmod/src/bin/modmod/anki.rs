@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    anki::render_anki_csv,
+    io::{PathExt, WriteExt},
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the cards to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        output,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let cards = track.flashcards().change_context(ModModError::default())?;
+    let csv = render_anki_csv(&cards);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(csv)?,
+        None => print!("{csv}"),
+    }
+
+    Ok(())
+}
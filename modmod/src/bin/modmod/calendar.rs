@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    calendar::{CalendarOptions, Date, TimeSlot},
+    io::{PathExt, WriteExt},
+    RenderFilter,
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the iCalendar file to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "start",
+        help = "The first day of the course run, e.g. 2026-11-02. Defaults to the start in the [schedule] table of the track"
+    )]
+    start: Option<Date>,
+    #[arg(
+        long = "slots",
+        value_delimiter = ',',
+        help = "The teaching time of each day, e.g. 09:00-12:00,13:00-17:00. Defaults to the slots in the [schedule] table of the track"
+    )]
+    slots: Vec<TimeSlot>,
+    #[arg(
+        long = "weekends",
+        help = "Also teach on Saturdays and Sundays, which are skipped otherwise"
+    )]
+    weekends: bool,
+    #[arg(
+        long = "timezone",
+        help = "The IANA time zone of the slots, e.g. Europe/Amsterdam. Defaults to the timezone in the [schedule] table of the track, or the local time of the attendee"
+    )]
+    timezone: Option<String>,
+    #[arg(
+        long = "book-url",
+        help = "The URL the book is deployed to, which the events link to. Defaults to the book_url in the [schedule] table of the track"
+    )]
+    book_url: Option<String>,
+    #[arg(
+        long = "modules",
+        value_delimiter = ',',
+        help = "Only schedule the modules with these indices or tags, e.g. 2,foundations-of-rust"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "units",
+        value_delimiter = ',',
+        help = "Only schedule the units with these numbers or tags, e.g. 2.1,basic-syntax"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "profile",
+        help = "Schedule the track for the audience profile with this name, as defined in the track TOML"
+    )]
+    profile: Option<String>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        output,
+        start,
+        slots,
+        weekends,
+        timezone,
+        book_url,
+        modules,
+        units,
+        profile,
+        track_toml_path,
+    } = args;
+
+    let mut track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    if let Some(profile) = profile {
+        let Some(profile_filter) = track.profiles.get(&profile).cloned() else {
+            return Err(ModModError::report()
+                .attach_printable(format!("No profile named '{profile}' in track")));
+        };
+        track.filter_tags(&profile_filter);
+    }
+
+    let schedule = track.schedule.clone();
+    let options = CalendarOptions {
+        start: start.or(schedule.start),
+        slots: match slots.is_empty() {
+            true => schedule.slots,
+            false => slots,
+        },
+        weekends: weekends || schedule.weekends,
+        timezone: timezone.or(schedule.timezone),
+        book_url: book_url.or(schedule.book_url),
+        filter: RenderFilter { modules, units },
+    };
+    let ics = track
+        .render_calendar(&options)
+        .attach_printable("Pass --start and --slots, or set them in the [schedule] table")
+        .change_context(ModModError::default())?;
+
+    match output {
+        Some(output) => output.create_file()?.write_all(ics)?,
+        None => print!("{ics}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::{path::PathBuf, process::exit};
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    check::{CheckOptions, RustVersion},
+    doctor::tool,
+};
+
+use crate::{tasks::TaskArgs, ModModError};
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        long = "rustfmt",
+        help = "Check that Rust code blocks in content are formatted with rustfmt"
+    )]
+    rustfmt: bool,
+    #[arg(
+        long = "links",
+        help = "Check that links in content and exercise folder references in exercise descriptions are not dead"
+    )]
+    links: bool,
+    #[arg(
+        long = "external-links",
+        requires = "links",
+        help = "Also check external links by requesting their URLs with curl"
+    )]
+    external_links: bool,
+    #[arg(
+        long = "orphans",
+        help = "Report Markdown files and exercise folders under the track root that none of the tracks next to the track TOML use"
+    )]
+    orphans: bool,
+    #[arg(
+        long = "a11y",
+        help = "Report images without alt text, skipped heading levels, link text like 'click here' and tables without headers in content"
+    )]
+    a11y: bool,
+    #[arg(
+        long = "rust-changes",
+        help = "Also list the units whose content mentions features that changed in recent Rust releases. These are advisories, which don't make the check fail"
+    )]
+    rust_changes: bool,
+    #[arg(
+        long = "since",
+        requires = "rust_changes",
+        help = "Only consider the Rust releases after this one, e.g. 1.80"
+    )]
+    since: Option<RustVersion>,
+    #[arg(
+        long = "require-tools",
+        help = "Fail if a check is skipped because a tool it needs is not installed"
+    )]
+    require_tools: bool,
+    #[command(flatten)]
+    tasks: TaskArgs,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        rustfmt,
+        links,
+        external_links,
+        orphans,
+        a11y,
+        rust_changes,
+        since,
+        require_tools,
+        tasks,
+        track_toml_path,
+    } = args;
+
+    // Checks that need a tool that is not installed are skipped, unless tools are required
+    let available = |requested: bool, check: &str, name: &str| {
+        let tool = tool(name);
+        if !requested || tool.is_installed() {
+            return Ok(requested);
+        }
+        let message = format!(
+            "Skipped {check}, as {name} is not installed. To install it: {}",
+            tool.install_hint()
+        );
+        if require_tools {
+            return Err(ModModError::report().attach_printable(message));
+        }
+        println!("warning: {message}");
+        Ok(false)
+    };
+    let rustfmt = available(rustfmt, "the rustfmt check", "rustfmt")?;
+    let external_links = available(external_links, "checking external links", "curl")?;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let findings = track
+        .check(&CheckOptions {
+            rustfmt,
+            links,
+            external_links,
+            orphans,
+            a11y,
+            runner: tasks.runner(),
+        })
+        .change_context(ModModError::default())?;
+
+    for finding in findings.iter() {
+        println!("{finding}");
+    }
+
+    for warning in track.schedule_warnings() {
+        println!("warning: {warning}");
+    }
+
+    if rust_changes {
+        let advisories = track
+            .rust_change_advisories(since)
+            .change_context(ModModError::default())?;
+        for advisory in advisories.iter() {
+            println!("advisory: {advisory}");
+        }
+        if !advisories.is_empty() {
+            eprintln!(
+                "Found {} mention(s) of features that changed in recent Rust releases",
+                advisories.len()
+            );
+        }
+    }
+
+    if !findings.is_empty() {
+        eprintln!("Found {} issue(s)", findings.len());
+        exit(1);
+    }
+
+    Ok(())
+}
@@ -1,10 +1,47 @@
-use std::ffi::OsStr;
-
-use error_stack::IntoReport;
-use modmod::load::ExerciseDef;
+use modmod::{
+    load::{serde_defaults, ExerciseDef},
+    to_tag,
+};
 
 use super::imports::*;
 
+const DESCRIPTION_MD: &str = r#"Open the `#[modmod:exercise_dir]` crate. Don't worry if its tests don't pass yet: it's your job to fix that in this exercise.
+
+# #[modmod:exercise_ref].A A first step
+
+Describe the first step of the exercise here.
+Run `cargo test` to check your solution.
+"#;
+
+const LIB_RS: &str = r#"/// Replace the `todo!()` with your solution
+pub fn solve() -> u32 {
+    todo!()
+}
+"#;
+
+const LIB_TEST_RS: &str = r#"use {crate}::solve;
+
+#[test]
+fn it_solves() {
+    assert_eq!(solve(), 42);
+}
+"#;
+
+const MAIN_RS: &str = r#"fn main() {
+    todo!("Print the answer")
+}
+"#;
+
+const MAIN_TEST_RS: &str = r#"use std::process::Command;
+
+#[test]
+fn it_prints_the_answer() {
+    let output = Command::new(env!("CARGO_BIN_EXE_{package}")).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "42");
+}
+"#;
+
 #[derive(Debug, Clone, clap::Args)]
 pub struct CreateExercise {
     topic: PathBuf,
@@ -14,6 +51,11 @@ pub struct CreateExercise {
         help = "The index of the exercise in the list of exercises for the given topic. Defaults to the last one"
     )]
     index: Option<usize>,
+    #[arg(
+        long = "lib",
+        help = "Scaffold a library crate rather than a binary one"
+    )]
+    lib: bool,
     name: String,
 }
 
@@ -28,55 +70,46 @@ impl CreateExercise {
             .unwrap_or(topic.exercises.len())
             .min(topic.exercises.len());
 
-        let exercises_path = self.topic.parent().unwrap().join("exercises");
-        exercises_path.create_dir_all()?;
-
-        let exercise_crate_path = exercises_path
-            .canonicalize()
-            .unwrap()
-            .join(self.name.to_lowercase());
-        if common_args.force {
-            fs_extra::dir::remove(&exercise_crate_path)
-                .into_report()
-                .change_context(ModModError::default())?;
-        }
+        // Crate names can only hold letters, digits, dashes and underscores
+        let package: String = to_tag(&self.name)
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        let exercise_dir = PathBuf::from("exercises").join(&package);
+        let exercise_path = self.topic.parent().unwrap().join(&exercise_dir);
 
-        let output = std::process::Command::new("cargo")
-            .args([
-                OsStr::new("new"),
-                OsStr::new("--name"),
-                OsStr::new(&self.name),
-                OsStr::new("--bin"),
-                exercise_crate_path.as_os_str(),
-            ])
-            .output()
-            .into_report()
-            .change_context(ModModError::default())?;
-
-        if !output.status.success() {
-            let e = ModModError::report()
-                .attach_printable("`cargo new` command process exited unsuccessfully")
-                .attach_printable(format!(
-                    r#"Stdout: "{}""#,
-                    String::from_utf8_lossy(&output.stdout)
-                ))
-                .attach_printable(format!(
-                    r#"Stderr: "{}""#,
-                    String::from_utf8_lossy(&output.stderr)
-                ))
-                .attach_printable(output.status);
-
-            return Err(e);
+        let (src, test) = match self.lib {
+            true => (("lib.rs", LIB_RS), LIB_TEST_RS),
+            false => (("main.rs", MAIN_RS), MAIN_TEST_RS),
+        };
+        let cargo_toml = format!(
+            "[package]\nname = \"{package}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n"
+        );
+        // Binaries are named after the package, while the library has underscores for dashes
+        let test = test
+            .replace("{package}", &package)
+            .replace("{crate}", &package.replace('-', "_"));
+        let files = [
+            (PathBuf::from("Cargo.toml"), cargo_toml.as_str()),
+            (serde_defaults::exercise_description_md(), DESCRIPTION_MD),
+            (PathBuf::from("src").join(src.0), src.1),
+            (PathBuf::from("tests").join(format!("{package}.rs")), &test),
+        ];
+        for (file, content) in files {
+            let path = exercise_path.join(file);
+            path.parent().unwrap().create_dir_all()?;
+            path.try_create_file(common_args.force)?
+                .write_all(content)?;
         }
 
+        let mut includes = serde_defaults::exercise_includes();
+        includes.push("tests/**/*".to_string());
         topic.exercises.insert(
             index,
             ExerciseDef {
                 name: self.name,
-                path: exercise_crate_path
-                    .strip_prefix(&self.topic.parent().unwrap().canonicalize().unwrap())
-                    .unwrap()
-                    .to_owned(),
+                path: exercise_dir,
+                includes,
                 ..Default::default()
             },
         );
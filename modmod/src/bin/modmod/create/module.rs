@@ -19,7 +19,7 @@ impl CreateModule {
         let module = ModuleDef {
             name: self.name,
             description: self.description,
-            units: vec![],
+            ..Default::default()
         };
 
         mod_toml.write_all(toml::to_string_pretty(&module).unwrap().as_bytes())?;
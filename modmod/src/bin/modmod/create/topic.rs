@@ -1,4 +1,4 @@
-use modmod::load::{ModuleDef, TopicDef};
+use modmod::load::{ModuleDef, TopicDef, TopicRef};
 
 use super::imports::*;
 
@@ -46,7 +46,9 @@ impl CreateTopic {
             name: self.name,
             ..Default::default()
         };
-        unit.topics.push(self.dir.join("mod.toml"));
+        unit.topics.push(TopicRef::Path(
+            PathBuf::from("topics").join(&self.dir).join("topic.toml"),
+        ));
         unit.topics.dedup();
 
         topic_toml.write_all(toml::to_string_pretty(&topic).unwrap().as_bytes())?;
@@ -1,17 +1,44 @@
-use modmod::load::{ModuleDef, UnitDef};
+use modmod::{
+    load::{ModuleDef, TopicDef, TopicRef, UnitDef},
+    to_tag,
+};
 
 use super::imports::*;
 
+const SLIDES_MD: &str = r#"---
+layout: cover
+---
+
+# {name}
+
+---
+layout: default
+---
+
+# A first slide
+
+<!-- Speaker notes go in comments like this one -->
+"#;
+
 #[derive(Debug, Clone, clap::Args)]
 pub struct CreateUnit {
     module: PathBuf,
     name: String,
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        help = "The position to insert the unit at among the units of the module, starting at 0. Defaults to the end"
+    )]
     index: Option<usize>,
+    #[arg(
+        long = "topic",
+        help = "The folder in the topics folder of the module to create the topic of the unit in. Defaults to the name of the unit in lower case, with dashes between the words"
+    )]
+    topic: Option<PathBuf>,
 }
 
 impl CreateUnit {
-    pub fn create(self, _common_args: &CommonArgs) -> Result<(), ModModError> {
+    pub fn create(self, common_args: &CommonArgs) -> Result<(), ModModError> {
         let mut module = ModuleDef::load(&self.module, None)
             .change_context(ModModError::default())?
             .data;
@@ -21,12 +48,31 @@ impl CreateUnit {
             .unwrap_or(module.units.len())
             .min(module.units.len());
 
+        let topic_dir = PathBuf::from("topics").join(
+            self.topic
+                .unwrap_or_else(|| PathBuf::from(to_tag(&self.name))),
+        );
+        let topic_path = self.module.parent().unwrap().join(&topic_dir);
+        topic_path.create_dir_all()?;
+        let topic = TopicDef {
+            name: self.name.clone(),
+            ..Default::default()
+        };
+        topic_path
+            .join("topic.toml")
+            .try_create_file(common_args.force)?
+            .write_all(toml::to_string_pretty(&topic).unwrap())?;
+        topic_path
+            .join(&topic.content)
+            .try_create_file(common_args.force)?
+            .write_all(SLIDES_MD.replace("{name}", &self.name))?;
+
         module.units.insert(
             index,
             UnitDef {
                 name: self.name,
-                template: None,
-                topics: vec![],
+                topics: vec![TopicRef::Path(topic_dir.join("topic.toml"))],
+                ..Default::default()
             },
         );
 
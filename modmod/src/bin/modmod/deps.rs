@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    archive::PackageFormat,
+    deps::DependencyFormat,
+    io::{PathExt, WriteExt},
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "dot",
+        help = "Graph format: dot or json"
+    )]
+    format: DependencyFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the graph to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "package",
+        value_name = "FORMAT",
+        help = "Also include the archives, in either zip or tar.gz format"
+    )]
+    package: Option<PackageFormat>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        output,
+        package,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let graph = track
+        .dependency_graph(package)
+        .change_context(ModModError::default())?
+        .render(format);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(graph)?,
+        None => print!("{graph}"),
+    }
+
+    Ok(())
+}
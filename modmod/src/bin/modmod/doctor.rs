@@ -0,0 +1,32 @@
+use std::process::exit;
+
+use clap::Parser;
+use error_stack::Result;
+use modmod::doctor::diagnose;
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {}
+
+pub fn run(_args: Args) -> Result<(), ModModError> {
+    let diagnoses = diagnose();
+    for diagnosis in diagnoses.iter() {
+        println!("{diagnosis}");
+    }
+
+    let missing: Vec<_> = diagnoses
+        .iter()
+        .filter(|d| d.tool.required && !d.is_ok())
+        .map(|d| d.tool.name)
+        .collect();
+    if !missing.is_empty() {
+        eprintln!(
+            "Install or update {} to render and build the course",
+            missing.join(", ")
+        );
+        exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    calendar::Date,
+    freshness::FreshnessFormat,
+    io::{PathExt, WriteExt},
+    tasks::TaskRunner,
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "text",
+        help = "Report format: text, json or markdown"
+    )]
+    format: FreshnessFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the report to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "date",
+        help = "Report which units are due as of this date, like 2026-11-02, rather than today"
+    )]
+    date: Option<Date>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        output,
+        date,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let report = track
+        .freshness_report(&TaskRunner::default(), date.unwrap_or_else(Date::today))
+        .change_context(ModModError::default())?
+        .render(format);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(report)?,
+        None => print!("{report}"),
+    }
+
+    Ok(())
+}
@@ -1,10 +1,21 @@
-use std::{fs, path::PathBuf};
+use std::path::PathBuf;
 
 use clap::Parser;
-use error_stack::{IntoReport, Result, ResultExt};
-use modmod::{patch::GenPatchOptions, SlidesRenderOptions, TrackRenderOptions};
+use error_stack::{Result, ResultExt};
+use modmod::{
+    archive::PackageFormat,
+    book::SectionSlides,
+    build::BuildTarget,
+    bundle::BundleKind,
+    cache::VerificationCache,
+    edition::Edition,
+    generate::{GenerateOptions, OutputMode},
+    patch::OutputChangeKind,
+    tags::TagFilter,
+    RenderFilter, RenderOptions, Track,
+};
 
-use crate::ModModError;
+use crate::{logger, tasks::TaskArgs, ModModError};
 
 #[derive(Parser)]
 pub struct Args {
@@ -16,79 +27,415 @@ pub struct Args {
     out_dir: PathBuf,
     #[arg(short = 'c', long = "clear", help = "Clear the output folder")]
     clear_output_dir: bool,
+    #[arg(
+        long = "clean",
+        conflicts_with_all = ["clear_output_dir", "patch_file", "dry_run", "diff"],
+        help = "Render into an output folder that holds an earlier render, writing the files that changed and removing the files modmod rendered before that are no longer produced. Other files in the folder are left alone"
+    )]
+    clean: bool,
     #[arg(
         long,
         help = "Use this as a base when deploying the slides to a web server",
         default_value = "/"
     )]
     slide_url_base: String,
+    #[arg(
+        long = "book-slides",
+        default_value = "link",
+        help = "What the top of each book section shows of the slide deck of its unit: none, a link that opens it, or the deck embedded along with the link"
+    )]
+    book_slides: SectionSlides,
+    #[arg(
+        long = "hints-folder",
+        help = "Also write the hints of every exercise into a hints folder in its package, next to the code"
+    )]
+    hints_folder: bool,
     #[arg(
         short = 'p',
         long = "patch",
         help = "Generate patch file to update output dir at given path"
     )]
     patch_file: Option<PathBuf>,
+    #[arg(
+        short = 'n',
+        long = "dry-run",
+        conflicts_with_all = ["patch_file", "build"],
+        help = "List the files in the output folder that rendering with --clear would create, modify or delete, without touching the output folder"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "diff",
+        conflicts_with_all = ["patch_file", "build"],
+        help = "Show what rendering with --clear would change in the output folder as a unified diff, without touching the output folder"
+    )]
+    diff: bool,
     track_toml_path: PathBuf,
     #[arg(
         long = "theme",
-        help = "The name of the Slidev theme to use in generated slide decks",
-        default_value = "teach-rs"
+        help = "The name of the Slidev theme to use in generated slide decks. Defaults to the theme in the [slides] table of the track, or teach-rs"
     )]
-    slide_theme: String,
+    slide_theme: Option<String>,
     #[arg(
         long = "json-stub",
         help = "The path of the package.json stub to use when generating the slide package"
     )]
     package_json: Option<PathBuf>,
+    #[arg(
+        long = "devcontainer",
+        help = "Emit a devcontainer definition and Dockerfile into the output folder"
+    )]
+    devcontainer: bool,
+    #[arg(
+        long = "nix",
+        help = "Emit a Nix flake that builds the book and provides a development shell into the output folder"
+    )]
+    nix: bool,
+    #[arg(
+        long = "docusaurus",
+        help = "Also convert the book into the docs folder and sidebars.js of a Docusaurus site"
+    )]
+    docusaurus: bool,
+    #[arg(
+        long = "confluence",
+        help = "Also convert the book into pages in Confluence storage format, with a page per module and unit"
+    )]
+    confluence: bool,
+    #[arg(
+        long = "docx",
+        help = "Also convert every unit of the book into a Word document"
+    )]
+    docx: bool,
+    #[arg(
+        long = "man",
+        help = "Also export the exercise instructions as man pages and plain text files, for reading in a terminal"
+    )]
+    man: bool,
+    #[arg(
+        long = "slide-handouts",
+        help = "Also convert every slide deck into a handout in the book, with a section per slide, linked from the book section of the unit"
+    )]
+    slide_handouts: bool,
+    #[arg(
+        long = "handout-notes",
+        requires = "slide_handouts",
+        help = "Include the speaker notes in the slide handouts. Requires the teacher edition"
+    )]
+    handout_notes: bool,
+    #[arg(
+        long = "lockfiles",
+        help = "Generate a Cargo.lock for every exercise package that doesn't include one, pinning its dependencies"
+    )]
+    lockfiles: bool,
+    #[arg(
+        long = "vendor",
+        help = "Vendor the dependencies of the exercise packages into the output, along with a .cargo/config.toml that has cargo build them offline. Implies --lockfiles"
+    )]
+    vendor: bool,
+    #[arg(
+        long = "package",
+        value_name = "FORMAT",
+        help = "Bundle the output into archives per module and for the whole course. Either zip or tar.gz"
+    )]
+    package: Option<PackageFormat>,
+    #[arg(
+        long = "bundle",
+        value_name = "KIND",
+        conflicts_with_all = ["patch_file", "dry_run", "diff"],
+        help = "After building the book and the slides, bundle them into a folder in the packages folder along with archives of the exercises. The offline bundle includes the scripts, stylesheets and fonts they load from other sites, so it needs no network access. With --package, the bundle is archived as well"
+    )]
+    bundle: Option<BundleKind>,
+    #[arg(
+        long = "modules",
+        value_delimiter = ',',
+        help = "Only render the modules with these indices or tags, e.g. 2,foundations-of-rust"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "units",
+        value_delimiter = ',',
+        help = "Only render the units with these numbers or tags, e.g. 2.1,basic-syntax"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "profile",
+        help = "Render the track for the audience profile with this name, as defined in the track TOML"
+    )]
+    profile: Option<String>,
+    #[arg(
+        long = "tags",
+        value_delimiter = ',',
+        help = "Only include tagged units, topics and exercises that have any of these tags"
+    )]
+    tags: Vec<String>,
+    #[arg(
+        long = "exclude-tags",
+        value_delimiter = ',',
+        help = "Exclude units, topics and exercises that have any of these tags"
+    )]
+    exclude_tags: Vec<String>,
+    #[arg(
+        long = "edition",
+        help = "Render the student, teacher or preview edition into a subfolder of the output folder with the same name. The teacher edition includes teacher-only blocks, speaker notes and solutions, which the others leave out. The preview edition only includes the units marked as preview in full. Defaults to the student edition, directly in the output folder, with the speaker notes left in the slides"
+    )]
+    edition: Option<Edition>,
+    #[arg(
+        long = "handouts",
+        help = "Also write the speaker notes of every slide deck into an instructor handout. Requires the teacher edition"
+    )]
+    handouts: bool,
+    #[arg(
+        long = "slides-pdf",
+        help = "Also add npm scripts to the slides package.json that export every slide deck to PDF, along with a combined deck per module. `npm run pdf` exports them all"
+    )]
+    slides_pdf: bool,
+    #[arg(
+        long = "module-decks",
+        help = "Also write a slide deck per module, which combines the decks of its units with a divider slide before each unit, along with npm scripts like dev-2 to present it"
+    )]
+    module_decks: bool,
+    #[arg(
+        long = "ascii",
+        help = "Replace emoji and typographic punctuation with ASCII in the slide decks, Word documents and man pages, for PDF pipelines and learning management systems that can't handle them. The book is left as it is"
+    )]
+    ascii: bool,
+    #[arg(
+        long = "lang",
+        help = "Render the track in this language, using translated content files like slides.nl.md where they exist and falling back to the track language otherwise"
+    )]
+    lang: Option<String>,
+    #[arg(
+        long = "build",
+        value_delimiter = ',',
+        conflicts_with = "patch_file",
+        help = "After rendering, build these targets with external tools, separated by commas: book, slides, pdf, linkcheck, exercises or lint. Targets whose tools are not installed are skipped with a warning"
+    )]
+    build: Vec<BuildTarget>,
+    #[arg(
+        long = "require-tools",
+        requires = "build",
+        help = "Fail if a build target is skipped because a tool it needs is not installed"
+    )]
+    require_tools: bool,
+    #[arg(
+        long = "cache",
+        value_name = "LOCATION",
+        requires = "build",
+        help = "Skip verifying exercise packages that passed before, according to the cache in this folder or S3 bucket, e.g. s3://bucket/prefix. S3 credentials are taken from AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, and MODMOD_S3_ENDPOINT sets the endpoint of other S3-compatible stores"
+    )]
+    cache: Option<VerificationCache>,
+    #[arg(
+        long = "test-runs",
+        value_name = "RUNS",
+        requires = "build",
+        value_parser = clap::value_parser!(u16).range(2..),
+        help = "When building the exercises target, also run the tests of every exercise package this many times, and fail naming the tests that pass in some runs and fail in others"
+    )]
+    test_runs: Option<u16>,
+    #[arg(
+        long = "git-init",
+        conflicts_with_all = ["patch_file", "dry_run", "diff"],
+        help = "Commit the output folder to a git repository in it, which is initialized with a .gitignore if there is none yet. The commit is tagged with the modmod version and the hash of the rendered track, like modmod-0.1.0-3f2a9c1d5e7b"
+    )]
+    git_init: bool,
+    #[arg(
+        long = "no-hooks",
+        help = "Don't run the pre-load and post-render hooks of the track. Post-render hooks are never run with --dry-run or --diff"
+    )]
+    no_hooks: bool,
+    #[arg(
+        long = "progress",
+        help = "Show a progress bar of the units being rendered on stderr"
+    )]
+    pub progress: bool,
+    #[command(flatten)]
+    tasks: TaskArgs,
 }
 
 pub fn run(args: Args) -> Result<(), ModModError> {
     let Args {
         out_dir,
         clear_output_dir,
+        clean,
         track_toml_path,
         slide_url_base,
+        book_slides,
+        hints_folder,
         slide_theme,
         package_json,
         patch_file,
+        dry_run,
+        diff,
+        devcontainer,
+        nix,
+        docusaurus,
+        confluence,
+        docx,
+        man,
+        slide_handouts,
+        handout_notes,
+        lockfiles,
+        vendor,
+        package,
+        bundle,
+        modules,
+        units,
+        profile,
+        tags,
+        exclude_tags,
+        edition,
+        handouts,
+        slides_pdf,
+        module_decks,
+        ascii,
+        lang,
+        build,
+        require_tools,
+        cache,
+        test_runs,
+        git_init,
+        no_hooks,
+        progress: _,
+        tasks,
     } = args;
-
-    let slide_opts = SlidesRenderOptions {
-        theme: &slide_theme,
-        package_json,
-        url_base: slide_url_base.as_str(),
+    let out_dir = match edition {
+        Some(edition) => out_dir.join(edition.name()),
+        None => out_dir,
     };
-
-    let (out_dir, patch_opts) = if let Some(patch_file) = patch_file {
-        let tmp_dir = std::env::temp_dir().join("modmod_tmp");
-        let patch_opts = GenPatchOptions {
-            new_dir: tmp_dir.clone(),
-            old_dir: out_dir,
-            patch_file,
-        };
-        (tmp_dir, Some(patch_opts))
-    } else {
-        (out_dir, None)
+    let output = match patch_file {
+        Some(patch_file) => OutputMode::Patch(patch_file),
+        None if dry_run || diff => OutputMode::Preview,
+        None if clean => OutputMode::Clean,
+        None => OutputMode::Direct,
     };
-
-    let track_opts = TrackRenderOptions {
-        out_dir,
-        slide_opts,
-        clear_output_dir,
+    let opts = GenerateOptions {
+        render: RenderOptions {
+            out_dir: out_dir.clone(),
+            clear_output_dir,
+            theme: slide_theme,
+            package_json,
+            slide_url_base,
+            book_slides,
+            hints_folder,
+            handouts,
+            pdf: slides_pdf,
+            module_decks,
+            devcontainer,
+            nix,
+            docusaurus,
+            confluence,
+            docx,
+            man,
+            slide_handouts,
+            handout_notes,
+            lockfiles,
+            vendor,
+            package,
+            filter: RenderFilter { modules, units },
+            edition: edition.unwrap_or_default(),
+            // Only an edition that's asked for leaves the speaker notes out
+            strip_notes: edition.is_some(),
+            runner: tasks.runner(),
+            ascii,
+        },
+        output,
+        profile,
+        tags: TagFilter { tags, exclude_tags },
+        lang,
+        hooks: !no_hooks,
+        git_init,
+        build,
+        require_tools,
+        cache,
+        test_runs: test_runs.map_or(0, usize::from),
+        bundle,
     };
 
-    let track =
-        modmod::Track::load_toml_def(track_toml_path).change_context(ModModError::default())?;
-    track
-        .render(track_opts)
-        .change_context(ModModError::default())?;
+    let generated = Track::generate(track_toml_path, &opts);
+    logger::finish();
+    let generated = generated.change_context(ModModError::default())?;
 
-    if let Some(patch_opts) = patch_opts {
-        let tmp_dir = patch_opts.new_dir.clone();
-        modmod::patch::Patch::render(patch_opts).change_context(ModModError::default())?;
-        fs::remove_dir_all(tmp_dir)
-            .into_report()
-            .change_context(ModModError::default())?;
+    if let (Some(lang), Some(coverage)) = (&opts.lang, &generated.translation) {
+        let total = coverage.translated.len() + coverage.missing.len();
+        println!(
+            "Translated {} of {total} content files into '{lang}'",
+            coverage.translated.len()
+        );
+        if !coverage.is_complete() {
+            println!("Falling back to '{}' for:", generated.track_language);
+            for path in coverage.missing.iter() {
+                println!("  {}", path.to_string_lossy());
+            }
+        }
+    }
+    for warning in generated.rendered.warnings.iter() {
+        println!("warning: {warning}");
+    }
+    if let Some(summary) = &generated.synced {
+        for path in summary.removed.iter() {
+            println!("Removed stale {}", path.to_string_lossy());
+        }
+        println!(
+            "Wrote {} files to {}, {} were unchanged and {} stale files were removed",
+            summary.written,
+            out_dir.to_string_lossy(),
+            summary.unchanged,
+            summary.removed.len()
+        );
+    }
+
+    if let Some(commit) = &generated.commit {
+        if commit.initialized {
+            println!(
+                "Initialized git repository in {}",
+                generated.out_dir.to_string_lossy()
+            );
+        }
+        match commit.committed {
+            true => println!("Committed the output as {}", commit.tag),
+            false => println!("No changes to commit, the output is still {}", commit.tag),
+        }
+    }
+    for skipped in generated.skipped_builds.iter() {
+        println!("warning: {skipped}");
+    }
+
+    if let (Some(kind), Some(bundle)) = (opts.bundle, &generated.bundle) {
+        println!(
+            "Wrote the {} bundle to {}, with {} asset(s) fetched from other sites",
+            kind.name(),
+            bundle.dir.to_string_lossy(),
+            bundle.assets.len()
+        );
+        if let Some(archive) = &bundle.archive {
+            println!("Archived the bundle as {}", archive.to_string_lossy());
+        }
+    }
+
+    if let Some(changes) = &generated.changes {
+        for change in changes.iter() {
+            match change.diff().filter(|_| diff) {
+                Some(diff) => print!("{diff}"),
+                None => println!("{change}"),
+            }
+        }
+        let count = |kind| {
+            changes
+                .iter()
+                .filter(|c| c.kind == kind)
+                .map(|c| c.files)
+                .sum::<usize>()
+        };
+        if changes.is_empty() {
+            println!("Rendering would not change {}", out_dir.to_string_lossy());
+        } else if dry_run {
+            println!(
+                "Rendering would create {} files, modify {} and delete {} in {}",
+                count(OutputChangeKind::Create),
+                count(OutputChangeKind::Modify),
+                count(OutputChangeKind::Delete),
+                out_dir.to_string_lossy()
+            );
+        }
     }
 
     Ok(())
@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    grade::{GradeFormat, GradeOptions},
+    io::{PathExt, WriteExt},
+    RenderFilter,
+};
+
+use crate::{tasks::TaskArgs, ModModError};
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "text",
+        help = "Report format: text or json"
+    )]
+    format: GradeFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the report to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "modules",
+        value_delimiter = ',',
+        help = "Only grade the exercises of the modules with these indices or tags, e.g. 2,foundations-of-rust"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "units",
+        value_delimiter = ',',
+        help = "Only grade the exercises of the units with these numbers or tags, e.g. 2.1,basic-syntax"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "profile",
+        help = "Grade the exercises of the track for the audience profile with this name, as defined in the track TOML"
+    )]
+    profile: Option<String>,
+    #[command(flatten)]
+    tasks: TaskArgs,
+    track_toml_path: PathBuf,
+    #[arg(help = "The folder holding the student's exercise crates")]
+    submission: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        output,
+        modules,
+        units,
+        profile,
+        tasks,
+        track_toml_path,
+        submission,
+    } = args;
+
+    let mut track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    if let Some(profile) = profile {
+        let Some(profile_filter) = track.profiles.get(&profile).cloned() else {
+            return Err(ModModError::report()
+                .attach_printable(format!("No profile named '{profile}' in track")));
+        };
+        track.filter_tags(&profile_filter);
+    }
+
+    let report = track
+        .grade(
+            submission,
+            &GradeOptions {
+                filter: RenderFilter { modules, units },
+                runner: tasks.runner(),
+            },
+        )
+        .change_context(ModModError::default())?
+        .render(format);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(report)?,
+        None => print!("{report}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    graph::GraphFormat,
+    io::{PathExt, WriteExt},
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "dot",
+        help = "Graph format: dot or mermaid"
+    )]
+    format: GraphFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the graph to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        output,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let graph = track.topic_graph().render(format);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(graph)?,
+        None => print!("{graph}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::archive::PackageFormat;
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        long = "package",
+        value_name = "FORMAT",
+        help = "Also report the archives that would be affected, in either zip or tar.gz format"
+    )]
+    package: Option<PackageFormat>,
+    track_toml_path: PathBuf,
+    #[arg(required = true, help = "The changed files")]
+    changed: Vec<PathBuf>,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        package,
+        track_toml_path,
+        changed,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let graph = track
+        .dependency_graph(package)
+        .change_context(ModModError::default())?;
+
+    for path in changed.iter() {
+        let impacted = graph.impact(path);
+        if impacted.is_empty() {
+            println!("{} does not feed into any output", path.to_string_lossy());
+            continue;
+        }
+        println!("{}:", path.to_string_lossy());
+        for artifact in impacted {
+            println!("\t{}", artifact.to_string_lossy());
+        }
+    }
+
+    Ok(())
+}
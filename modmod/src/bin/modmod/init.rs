@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::init::init_track;
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        long = "name",
+        default_value = "My Rust course",
+        help = "The name of the track"
+    )]
+    name: String,
+    #[arg(short = 'f', long = "force", help = "Overwrite existing files")]
+    force: bool,
+    #[arg(
+        default_value = ".",
+        help = "The folder to create the track in. Created if it doesn't exist"
+    )]
+    dir: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args { name, force, dir } = args;
+
+    let track_toml_path = init_track(&dir, &name, force).change_context(ModModError::default())?;
+    println!(
+        "Created track '{name}' in {}. To render it, run:\n  modmod generate -o course {}",
+        dir.to_string_lossy(),
+        track_toml_path.to_string_lossy()
+    );
+
+    Ok(())
+}
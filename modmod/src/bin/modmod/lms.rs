@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::lms::{LmsFormat, LmsOptions};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "The LMS to export for: canvas or moodle"
+    )]
+    format: LmsFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "The folder the track was generated into"
+    )]
+    out_dir: PathBuf,
+    #[arg(
+        long = "book-url",
+        help = "The URL the HTML book is served at, which the unit pages link to"
+    )]
+    book_url: String,
+    #[arg(
+        long = "slides-url",
+        help = "The URL the built slide decks are served at, for the unit pages to link to them as well"
+    )]
+    slides_url: Option<String>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        out_dir,
+        book_url,
+        slides_url,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let cartridge = track
+        .lms_cartridge(
+            out_dir,
+            &LmsOptions {
+                format,
+                book_url,
+                slides_url,
+            },
+        )
+        .change_context(ModModError::default())?;
+    println!("Wrote course cartridge to {}", cartridge.to_string_lossy());
+
+    Ok(())
+}
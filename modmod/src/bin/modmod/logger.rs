@@ -0,0 +1,106 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::Mutex,
+};
+
+use log::{kv::Key, Level, LevelFilter, Log, Metadata, Record};
+use modmod::progress::PROGRESS_TARGET;
+
+/// Writes the log records of modmod to stderr, and draws the progress records as a bar that
+/// is redrawn in place when stderr is a terminal
+struct Logger {
+    level: LevelFilter,
+    progress: bool,
+    /// Whether the last thing written is a progress bar without a line ending
+    bar_shown: Mutex<bool>,
+}
+
+/// Log the records at the level of `verbosity`: warnings by default, then info, debug and
+/// trace records for every `-v`. With `progress`, the progress of rendering is also shown.
+pub fn init(verbosity: u8, progress: bool) {
+    let level = match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    let max_level = match progress {
+        true => level.max(LevelFilter::Info),
+        false => level,
+    };
+    let logger = Box::leak(Box::new(Logger {
+        level,
+        progress,
+        bar_shown: Mutex::new(false),
+    }));
+    log::set_logger(logger).unwrap();
+    log::set_max_level(max_level);
+}
+
+/// Remove the progress bar, so that it doesn't run into the output that follows it
+pub fn finish() {
+    log::logger().flush();
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let progress = self.progress && metadata.target() == PROGRESS_TARGET;
+        metadata.target().starts_with("modmod")
+            && (metadata.level() <= self.level || (progress && metadata.level() <= Level::Info))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        let mut bar_shown = self.bar_shown.lock().unwrap();
+        if *bar_shown {
+            let _ = write!(stderr, "\r\x1b[2K");
+            *bar_shown = false;
+        }
+
+        if record.target() == PROGRESS_TARGET {
+            if !self.progress || !stderr.is_terminal() {
+                let _ = writeln!(stderr, "{}", record.args());
+                return;
+            }
+            let kv = record.key_values();
+            let count = |key| kv.get(Key::from_str(key)).and_then(|v| v.to_u64());
+            if let (Some(done), Some(total)) = (count("done"), count("total")) {
+                const WIDTH: u64 = 30;
+                let filled = (done * WIDTH).checked_div(total).unwrap_or(WIDTH) as usize;
+                let _ = write!(
+                    stderr,
+                    "[{}{}] {}",
+                    "#".repeat(filled),
+                    "-".repeat(WIDTH as usize - filled),
+                    record.args()
+                );
+                *bar_shown = true;
+            }
+            return;
+        }
+
+        let target = record.target().trim_start_matches("modmod::");
+        let _ = match record.level() {
+            Level::Error | Level::Warn => {
+                writeln!(stderr, "{}: {}", record.level(), record.args())
+            }
+            _ => writeln!(
+                stderr,
+                "{:>5} {target}: {}",
+                record.level().as_str().to_lowercase(),
+                record.args()
+            ),
+        };
+    }
+
+    fn flush(&self) {
+        let mut bar_shown = self.bar_shown.lock().unwrap();
+        if *bar_shown {
+            eprint!("\r\x1b[2K");
+            *bar_shown = false;
+        }
+    }
+}
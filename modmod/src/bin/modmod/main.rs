@@ -2,8 +2,30 @@ use clap::{Parser, Subcommand};
 use error_stack::Report;
 use std::{fmt, process::exit};
 
+mod anki;
+mod calendar;
+mod check;
 mod create;
+mod deps;
+mod doctor;
+mod freshness;
 mod gen;
+mod grade;
+mod graph;
+mod impact;
+mod init;
+mod lms;
+mod logger;
+mod migrate;
+mod preview;
+mod refactor;
+mod release;
+mod scorm;
+mod smoke;
+mod stats;
+mod tasks;
+mod test;
+mod todo;
 
 #[non_exhaustive]
 #[derive(Debug, Default)]
@@ -27,20 +49,57 @@ impl fmt::Display for ModModError {
 struct App {
     #[command(subcommand)]
     command: Command,
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        action = clap::ArgAction::Count,
+        global = true,
+        help = "Log what modmod is doing: the phases of loading and rendering with -v, how long they take and the files loaded with -vv, and every file written with -vvv"
+    )]
+    verbose: u8,
 }
 
 #[derive(Subcommand)]
 enum Command {
-    Generate(gen::Args),
+    Init(init::Args),
+    Generate(Box<gen::Args>),
+    #[command(alias = "new")]
     Create(create::Args),
+    Refactor(refactor::Args),
+    Migrate(migrate::Args),
+    Check(check::Args),
+    Smoke(smoke::Args),
+    Test(test::Args),
+    Grade(grade::Args),
+    Graph(graph::Args),
+    Impact(impact::Args),
+    Deps(deps::Args),
+    Anki(anki::Args),
+    Scorm(scorm::Args),
+    Preview(preview::Args),
+    Lms(lms::Args),
+    Doctor(doctor::Args),
+    Todo(todo::Args),
+    Freshness(freshness::Args),
+    Calendar(calendar::Args),
+    Stats(stats::Args),
+    Release(release::Args),
 }
 
 fn main() {
     let app = App::parse();
+    let progress = matches!(&app.command, Command::Generate(args) if args.progress);
+    logger::init(app.verbose, progress);
 
     match app.command {
+        Command::Init(args) => {
+            if let Err(e) = init::run(args) {
+                eprintln!("Error creating track: {e:?}");
+                exit(1);
+            }
+        }
         Command::Generate(args) => {
-            if let Err(e) = gen::run(args) {
+            if let Err(e) = gen::run(*args) {
                 eprintln!("Error rendering track: {e:?}");
                 exit(1);
             }
@@ -51,6 +110,136 @@ fn main() {
                 exit(1);
             }
         }
+        Command::Refactor(args) => {
+            if let Err(e) = refactor::run(args) {
+                eprintln!("Error refactoring track: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Migrate(args) => {
+            if let Err(e) = migrate::run(args) {
+                eprintln!("Error migrating track: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Check(args) => {
+            if let Err(e) = check::run(args) {
+                eprintln!("Error checking track: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Smoke(args) => {
+            if let Err(e) = smoke::run(args) {
+                eprintln!("Error smoke testing book: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Test(args) => {
+            if let Err(e) = test::run(args) {
+                eprintln!("Error testing exercises: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Grade(args) => {
+            if let Err(e) = grade::run(args) {
+                eprintln!("Error grading submission: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the report can be redirected into a file
+            return;
+        }
+        Command::Graph(args) => {
+            if let Err(e) = graph::run(args) {
+                eprintln!("Error exporting topic graph: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the graph can be piped into other tools
+            return;
+        }
+        Command::Impact(args) => {
+            if let Err(e) = impact::run(args) {
+                eprintln!("Error analyzing impact: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Deps(args) => {
+            if let Err(e) = deps::run(args) {
+                eprintln!("Error exporting dependency graph: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the graph can be consumed by other tools
+            return;
+        }
+        Command::Anki(args) => {
+            if let Err(e) = anki::run(args) {
+                eprintln!("Error exporting flashcards: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the cards can be redirected into a file
+            return;
+        }
+        Command::Scorm(args) => {
+            if let Err(e) = scorm::run(args) {
+                eprintln!("Error creating SCORM package: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Preview(args) => {
+            if let Err(e) = preview::run(args) {
+                eprintln!("Error serving preview: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Lms(args) => {
+            if let Err(e) = lms::run(args) {
+                eprintln!("Error exporting course for LMS: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Doctor(args) => {
+            if let Err(e) = doctor::run(args) {
+                eprintln!("Error diagnosing environment: {e:?}");
+                exit(1);
+            }
+        }
+        Command::Todo(args) => {
+            if let Err(e) = todo::run(args) {
+                eprintln!("Error collecting to-dos: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the report can be redirected into a file
+            return;
+        }
+        Command::Freshness(args) => {
+            if let Err(e) = freshness::run(args) {
+                eprintln!("Error reporting content freshness: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the report can be redirected into a file
+            return;
+        }
+        Command::Calendar(args) => {
+            if let Err(e) = calendar::run(args) {
+                eprintln!("Error creating calendar: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the calendar can be redirected into a file
+            return;
+        }
+        Command::Stats(args) => {
+            if let Err(e) = stats::run(args) {
+                eprintln!("Error collecting statistics: {e:?}");
+                exit(1);
+            }
+            // Keep stdout clean so the statistics can be redirected into a file
+            return;
+        }
+        Command::Release(args) => {
+            if let Err(e) = release::run(args) {
+                eprintln!("Error rendering releases: {e:?}");
+                exit(1);
+            }
+        }
     }
 
     println!("Done!");
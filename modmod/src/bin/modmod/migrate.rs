@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::migrate::{migrate_track, FORMAT_VERSION};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'n',
+        long = "dry-run",
+        help = "Print the diff of the changes instead of applying them"
+    )]
+    dry_run: bool,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        dry_run,
+        track_toml_path,
+    } = args;
+
+    let migrations = migrate_track(track_toml_path).change_context(ModModError::default())?;
+    if migrations.is_empty() {
+        println!("All files are at format version {FORMAT_VERSION} already");
+    }
+    for migration in migrations.iter() {
+        let change = &migration.change;
+        if dry_run {
+            print!("{}", change.diff());
+            continue;
+        }
+        change.apply().change_context(ModModError::default())?;
+        println!(
+            "Migrated {} file {} from format version {} to {FORMAT_VERSION}",
+            migration.kind,
+            change.relative_path.to_string_lossy(),
+            migration.from
+        );
+        for description in migration.changes.iter() {
+            println!("  {description}");
+        }
+    }
+
+    Ok(())
+}
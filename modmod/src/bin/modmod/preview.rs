@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::preview::{PreviewOptions, PreviewServer, PREVIEW_USER};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "The folder the track was generated into"
+    )]
+    out_dir: PathBuf,
+    #[arg(
+        short = 'p',
+        long = "port",
+        default_value_t = 3000,
+        help = "The port to listen on"
+    )]
+    port: u16,
+    #[arg(
+        long = "share",
+        help = "Listen on all network interfaces, so the preview can be shared over a tunnel. Generates a token unless --token is passed"
+    )]
+    share: bool,
+    #[arg(
+        long = "token",
+        help = "Password that visitors have to enter, with any user name"
+    )]
+    token: Option<String>,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        out_dir,
+        port,
+        share,
+        token,
+    } = args;
+
+    let server = PreviewServer::bind(out_dir, &PreviewOptions { port, share, token })
+        .change_context(ModModError::default())?;
+    println!("Serving preview at {}", server.url());
+    if let Some(token) = server.token() {
+        println!("Log in as '{PREVIEW_USER}' with token '{token}'");
+    }
+    if share {
+        println!(
+            "Listening on all interfaces. Forward port {} with a tunnel to share the preview",
+            server.port()
+        );
+    }
+    println!("Press Ctrl+C to stop");
+    server.serve().change_context(ModModError::default())
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::refactor::RenameTermOptions;
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        long = "rename-term",
+        num_args = 2,
+        value_names = ["FROM", "TO"],
+        required = true,
+        help = "Replace all occurrences of FROM with TO in content and exercise sources, except within longer identifiers. Can be passed multiple times"
+    )]
+    rename_term: Vec<String>,
+    #[arg(
+        short = 'm',
+        long = "module",
+        help = "Only refactor the module with this index or tag. Can be passed multiple times"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        short = 'n',
+        long = "dry-run",
+        help = "Print the diff of the changes instead of applying them"
+    )]
+    dry_run: bool,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        rename_term,
+        modules,
+        dry_run,
+        track_toml_path,
+    } = args;
+    // clap passes the two values of each occurrence of the argument one after the other
+    let renames: Vec<_> = rename_term
+        .chunks_exact(2)
+        .map(|pair| (pair[0].as_str(), pair[1].as_str()))
+        .collect();
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let changes = track
+        .rename_term(RenameTermOptions {
+            renames: &renames,
+            modules: &modules,
+        })
+        .change_context(ModModError::default())?;
+
+    for change in changes.iter() {
+        if dry_run {
+            print!("{}", change.diff());
+        } else {
+            change.apply().change_context(ModModError::default())?;
+            println!("Updated {}", change.path.to_string_lossy());
+        }
+    }
+
+    Ok(())
+}
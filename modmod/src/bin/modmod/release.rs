@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    edition::Edition,
+    release::{ReleaseOptions, ReleasesConfig},
+    RenderOptions,
+};
+
+use crate::{tasks::TaskArgs, ModModError};
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "The folder the versions are rendered into, each into a folder of its name, along with a landing page that links to them"
+    )]
+    out_dir: PathBuf,
+    #[arg(
+        long,
+        help = "Use this as a base when deploying the output folder to a web server. The slides of every version are served from the folder of its name below it",
+        default_value = "/"
+    )]
+    slide_url_base: String,
+    #[arg(
+        long = "edition",
+        help = "Render the student, teacher or preview edition of every version. Defaults to the student edition"
+    )]
+    edition: Option<Edition>,
+    #[arg(
+        long = "theme",
+        help = "The name of the Slidev theme to use in generated slide decks. Defaults to the theme in the [slides] table of each track, or teach-rs"
+    )]
+    slide_theme: Option<String>,
+    #[arg(
+        long = "force",
+        help = "Render the versions at git refs again, rather than keeping what was rendered for them before"
+    )]
+    force: bool,
+    #[arg(long = "no-hooks", help = "Don't run the hooks of the tracks")]
+    no_hooks: bool,
+    #[command(flatten)]
+    tasks: TaskArgs,
+    releases_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        out_dir,
+        slide_url_base,
+        edition,
+        slide_theme,
+        force,
+        no_hooks,
+        tasks,
+        releases_toml_path,
+    } = args;
+
+    let releases =
+        ReleasesConfig::load(releases_toml_path).change_context(ModModError::default())?;
+    let opts = ReleaseOptions {
+        render: RenderOptions {
+            out_dir: out_dir.clone(),
+            theme: slide_theme,
+            slide_url_base,
+            edition: edition.unwrap_or_default(),
+            runner: tasks.runner(),
+            ..Default::default()
+        },
+        force,
+        hooks: !no_hooks,
+    };
+    let versions = releases
+        .render(&opts)
+        .change_context(ModModError::default())?;
+    for version in versions {
+        match version.rendered {
+            true => println!("Rendered version {}", version.name),
+            false => println!("Kept version {}, rendered before", version.name),
+        }
+    }
+    println!(
+        "Wrote the landing page to {}",
+        out_dir.join("index.html").to_string_lossy()
+    );
+
+    Ok(())
+}
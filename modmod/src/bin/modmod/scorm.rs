@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::scorm::ScormOptions;
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "The folder the track was generated into, with the book built by mdbook"
+    )]
+    out_dir: PathBuf,
+    #[arg(
+        long = "slides",
+        help = "Also add the slide decks built into slides/dist"
+    )]
+    slides: bool,
+    #[arg(
+        long = "ascii",
+        help = "Replace emoji and typographic punctuation in the pages of the package with ASCII, for learning management systems that can't handle them"
+    )]
+    ascii: bool,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        out_dir,
+        slides,
+        ascii,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let package = track
+        .scorm_package(out_dir, &ScormOptions { slides, ascii })
+        .change_context(ModModError::default())?;
+    println!("Wrote SCORM package to {}", package.to_string_lossy());
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::{path::PathBuf, process::exit};
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{smoke::SmokeOptions, RenderFilter};
+
+use crate::{tasks::TaskArgs, ModModError};
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        long = "url",
+        help = "The URL the book is published at. Defaults to the book_url in the [schedule] table of the track"
+    )]
+    url: Option<String>,
+    #[arg(
+        long = "modules",
+        value_delimiter = ',',
+        help = "Only expect pages for the modules with these indices or tags, e.g. 2,foundations-of-rust"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "units",
+        value_delimiter = ',',
+        help = "Only expect pages for the units with these numbers or tags, e.g. 2.1,basic-syntax"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "profile",
+        help = "Expect the pages of the track for the audience profile with this name, as defined in the track TOML"
+    )]
+    profile: Option<String>,
+    #[arg(
+        long = "max-pages",
+        default_value_t = 1000,
+        help = "Stop following links after retrieving this many pages"
+    )]
+    max_pages: usize,
+    #[command(flatten)]
+    tasks: TaskArgs,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        url,
+        modules,
+        units,
+        profile,
+        max_pages,
+        tasks,
+        track_toml_path,
+    } = args;
+
+    let mut track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    if let Some(profile) = profile {
+        let Some(profile_filter) = track.profiles.get(&profile).cloned() else {
+            return Err(ModModError::report()
+                .attach_printable(format!("No profile named '{profile}' in track")));
+        };
+        track.filter_tags(&profile_filter);
+    }
+    let Some(url) = url.or(track.schedule.book_url.clone()) else {
+        return Err(ModModError::report()
+            .attach_printable("Pass --url, or set book_url in the [schedule] table"));
+    };
+
+    let findings = track
+        .smoke_test(&SmokeOptions {
+            url,
+            filter: RenderFilter { modules, units },
+            max_pages,
+            runner: tasks.runner(),
+        })
+        .change_context(ModModError::default())?;
+    for finding in findings.iter() {
+        println!("{finding}");
+    }
+    if !findings.is_empty() {
+        eprintln!("Found {} issue(s)", findings.len());
+        exit(1);
+    }
+
+    Ok(())
+}
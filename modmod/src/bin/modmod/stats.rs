@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    io::{PathExt, WriteExt},
+    stats::StatsFormat,
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "text",
+        help = "Statistics format: text or json"
+    )]
+    format: StatsFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the statistics to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        output,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let stats = track
+        .stats()
+        .change_context(ModModError::default())?
+        .render(format);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(stats)?,
+        None => print!("{stats}"),
+    }
+
+    Ok(())
+}
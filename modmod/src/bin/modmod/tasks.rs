@@ -0,0 +1,46 @@
+use std::{path::PathBuf, time::Duration};
+
+use modmod::tasks::TaskRunner;
+
+// Options for running external commands, shared by the subcommands that run them.
+// Not a doc comment, as clap would use that as the description of those subcommands.
+#[derive(Debug, Clone, clap::Args)]
+pub struct TaskArgs {
+    #[arg(
+        short = 'j',
+        long = "jobs",
+        help = "The number of external commands to run at the same time. Defaults to the number of CPUs"
+    )]
+    jobs: Option<usize>,
+    #[arg(
+        long = "timeout",
+        value_name = "SECONDS",
+        help = "Kill external commands that run longer than this"
+    )]
+    timeout: Option<u64>,
+    #[arg(
+        long = "retries",
+        default_value_t = 0,
+        help = "How many times to run a failed external command again"
+    )]
+    retries: usize,
+    #[arg(
+        long = "task-logs",
+        value_name = "DIR",
+        help = "Write the output of every external command into a log file in this folder"
+    )]
+    log_dir: Option<PathBuf>,
+}
+
+impl TaskArgs {
+    pub fn runner(self) -> TaskRunner {
+        let default = TaskRunner::default();
+        TaskRunner {
+            jobs: self.jobs.unwrap_or(default.jobs),
+            timeout: self.timeout.map(Duration::from_secs),
+            retries: self.retries,
+            log_dir: self.log_dir,
+            progress: true,
+        }
+    }
+}
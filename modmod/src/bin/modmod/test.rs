@@ -0,0 +1,95 @@
+use std::{path::PathBuf, process::exit};
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{testing::TestOptions, RenderFilter};
+
+use crate::{tasks::TaskArgs, ModModError};
+
+/// The number of lines at the end of the output of a failed exercise that are shown
+const OUTPUT_LINES: usize = 15;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "Test the exercise packages rendered into this folder by generate, rather than the exercise sources"
+    )]
+    out_dir: Option<PathBuf>,
+    #[arg(
+        long = "modules",
+        value_delimiter = ',',
+        help = "Only test the exercises of the modules with these indices or tags, e.g. 2,foundations-of-rust"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "units",
+        value_delimiter = ',',
+        help = "Only test the exercises of the units with these numbers or tags, e.g. 2.1,basic-syntax"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "profile",
+        help = "Test the exercises of the track for the audience profile with this name, as defined in the track TOML"
+    )]
+    profile: Option<String>,
+    #[command(flatten)]
+    tasks: TaskArgs,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        out_dir,
+        modules,
+        units,
+        profile,
+        tasks,
+        track_toml_path,
+    } = args;
+
+    let mut track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    if let Some(profile) = profile {
+        let Some(profile_filter) = track.profiles.get(&profile).cloned() else {
+            return Err(ModModError::report()
+                .attach_printable(format!("No profile named '{profile}' in track")));
+        };
+        track.filter_tags(&profile_filter);
+    }
+
+    let tests = track
+        .test_exercises(&TestOptions {
+            rendered: out_dir,
+            filter: RenderFilter { modules, units },
+            runner: tasks.runner(),
+        })
+        .change_context(ModModError::default())?;
+    for test in tests.iter() {
+        println!("{test}");
+    }
+    let failed: Vec<_> = tests.iter().filter(|t| !t.ok()).collect();
+    for test in failed.iter() {
+        println!(
+            "\n{} '{}' at {}:",
+            test.number,
+            test.exercise.name,
+            test.package.to_string_lossy()
+        );
+        let output = String::from_utf8_lossy(&test.output.stderr);
+        let lines: Vec<_> = output.lines().collect();
+        for line in &lines[lines.len().saturating_sub(OUTPUT_LINES)..] {
+            println!("  {line}");
+        }
+    }
+    println!(
+        "\n{} of {} exercises did what they are expected to",
+        tests.len() - failed.len(),
+        tests.len()
+    );
+    if !failed.is_empty() {
+        exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use error_stack::{Result, ResultExt};
+use modmod::{
+    io::{PathExt, WriteExt},
+    todo::TodoFormat,
+};
+
+use crate::ModModError;
+
+#[derive(Parser)]
+pub struct Args {
+    #[arg(
+        short = 'f',
+        long = "format",
+        default_value = "text",
+        help = "Report format: text or json"
+    )]
+    format: TodoFormat,
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "File to write the report to. Prints to stdout if omitted"
+    )]
+    output: Option<PathBuf>,
+    track_toml_path: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<(), ModModError> {
+    let Args {
+        format,
+        output,
+        track_toml_path,
+    } = args;
+
+    let track = modmod::Track::load(track_toml_path).change_context(ModModError::default())?;
+    let report = track
+        .todo_report()
+        .change_context(ModModError::default())?
+        .render(format);
+
+    match output {
+        Some(output) => output.create_file()?.write_all(report)?,
+        None => print!("{report}"),
+    }
+
+    Ok(())
+}
@@ -1,15 +1,31 @@
 use indoc::indoc;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fmt,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use error_stack::Result;
+use error_stack::{Report, Result, ResultExt};
 
 use crate::{
+    diagnostics::{locate, Errors, Location, Snippet},
+    edition::Edition,
+    include::expand_includes,
     io::{copy_files, PathExt, WriteExt},
-    to_tag,
+    markdown::{blocks, local_file, rewrite_headings, rewrite_links, teacher_only, Block},
+    numbering::{
+        chapter_file_name, roman_numeral, section_file_name, solution_dir, ExerciseNumber,
+        ModuleLabel, NumberingConfig, UnitNumber, QUIZZES_DIR,
+    },
+    progress::progress,
+    quiz::Quiz,
+    refs::{RefTarget, References},
+    schedule::Duration,
+    template::{Scope, TemplateContext},
+    unicode::{nfc, nfc_path},
+    Difficulty, Part,
 };
 
 #[derive(Debug, Default)]
@@ -23,7 +39,7 @@ impl fmt::Display for RenderBookError {
         f.write_str("unable to render book")?;
         if let Some(reason) = &self.reason {
             f.write_str("\n")?;
-            f.write_str(&reason)?;
+            f.write_str(reason)?;
         }
         Ok(())
     }
@@ -34,39 +50,107 @@ impl error_stack::Context for RenderBookError {}
 pub struct BookRenderOptions<'e, 'u> {
     pub exercise_paths: &'e HashMap<PathBuf, PathBuf>,
     pub slides_url_base: &'u str,
+    /// Teacher-only content and solution references are only included in the teacher edition
+    pub edition: Edition,
+    /// What the top of each section shows of the slide deck of its unit
+    pub slides: SectionSlides,
 }
 
-#[derive(Debug)]
+/// What the top of a book section shows of the slide deck of its unit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SectionSlides {
+    /// Nothing, for books that are read without the slides
+    None,
+    /// A link that opens the deck in a new tab
+    #[default]
+    Link,
+    /// The deck itself, in an iframe, along with the link
+    Embed,
+}
+
+impl FromStr for SectionSlides {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(SectionSlides::None),
+            "link" => Ok(SectionSlides::Link),
+            "embed" => Ok(SectionSlides::Embed),
+            _ => Err(format!(
+                "unknown slides option '{s}', expected none, link or embed"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Book<'track> {
     pub title: &'track str,
+    /// Language of the book content, as an IETF language tag
+    #[serde(default = "crate::book::default_language")]
+    pub language: &'track str,
+    #[serde(borrow)]
     pub chapters: Vec<Chapter<'track>>,
+    /// How modules and units are labelled in headings
+    #[serde(default)]
+    pub numbering: NumberingConfig,
+    /// What `#[modmod:ref(..)]` references in the content refer to
+    #[serde(skip)]
+    pub references: Option<&'track References>,
+    /// What templates in the content refer to
+    #[serde(skip)]
+    pub templates: Option<&'track TemplateContext>,
+    /// The groups of modules that get a part title in the summary
+    #[serde(skip)]
+    pub parts: &'track [Part],
 }
 
-const IMAGE_PLACEHOLDER: &str = "#[modmod:images]";
-const EXERCISE_DIR_PLACEHOLDER: &str = "#[modmod:exercise_dir]";
+pub fn default_language() -> &'static str {
+    "en"
+}
+
+pub(crate) const IMAGE_PLACEHOLDER: &str = "#[modmod:images]";
+pub(crate) const EXERCISE_DIR_PLACEHOLDER: &str = "#[modmod:exercise_dir]";
 const EXERCISE_REF_PLACEHOLDER: &str = "#[modmod:exercise_ref]";
 
 impl<'track> Book<'track> {
-    pub fn builder(title: &'track str) -> BookBuilder {
+    /// Start building a book with the given title. Chapters are rendered in the
+    /// order they are added using [`BookBuilder::chapter`].
+    pub fn builder(title: &'track str) -> BookBuilder<'track> {
         BookBuilder {
             book: Book {
                 title,
+                language: default_language(),
                 chapters: vec![],
+                numbering: NumberingConfig::default(),
+                references: None,
+                templates: None,
+                parts: &[],
             },
         }
     }
 
+    /// Write the mdbook sources into the `book` folder in `out_dir`
     pub fn render(
         &self,
         BookRenderOptions {
             exercise_paths,
             slides_url_base,
+            edition,
+            slides,
         }: BookRenderOptions,
         out_dir: impl AsRef<Path>,
     ) -> Result<(), RenderBookError> {
         let slides_url_base = slides_url_base.trim_matches('/');
         let slides_url_base_separator = if slides_url_base.is_empty() { "" } else { "/" };
-        let book_out_dir = out_dir.as_ref().join("book");
+        let deck_url = |unit_number: UnitNumber| {
+            format!(
+                "/{slides_url_base}{slides_url_base_separator}slides/{}/",
+                unit_number.deck_id()
+            )
+        };
+        let out_dir = out_dir.as_ref();
+        let book_out_dir = out_dir.join("book");
         let book_src_dir = book_out_dir.join("src");
         book_src_dir.create_dir_all()?;
 
@@ -76,27 +160,106 @@ impl<'track> Book<'track> {
             indoc! {r#"
                 [book]
                 title = "{}"
-                language = "en"
+                language = "{}"
                 multilingual = false
 
                 [build]
                 build-dir = "./target"
             "#},
-            self.title
+            self.title, self.language
         ))?;
+        let has_quizzes = self
+            .chapters
+            .iter()
+            .flat_map(|c| &c.sections)
+            .any(|s| !s.draft && !s.quizzes.is_empty());
+        if has_quizzes {
+            book_toml.write_all("\n[preprocessor.quiz]\n")?;
+        }
+        // The headings carry the labels, which mdbook's own chapter numbers would contradict
+        let numbered = self.numbering.is_default();
+        if !numbered {
+            book_toml.write_all("\n[output.html]\nno-section-label = true\n")?;
+        }
 
         let summary_md_path = book_src_dir.join("SUMMARY.md");
 
         let mut summary_md = summary_md_path.create_file()?;
         summary_md.write_all("# Summary\n\n")?;
 
-        for (chapter, chapter_i) in self.chapters.iter().zip(1..) {
-            // Sadly, at the time of writing, mdbook does not allow for custom section numbering.
-            // Therefore, we insert a draft chapter to keep the section numbering in sync
-            summary_md.write_fmt(format_args!("- [{}]()\n", chapter.title))?;
+        // Render all sections, and report the problems in their content together
+        let mut errors = Errors::default();
 
-            for (section, section_i) in chapter.sections.iter().zip(1..) {
-                let section_file_name = Path::new(&to_tag(section.title)).with_extension("md");
+        let total = self
+            .chapters
+            .iter()
+            .flat_map(|c| &c.sections)
+            .filter(|s| !s.draft)
+            .count();
+        let mut done = 0;
+        let no_references = References::default();
+        let references = self.references.unwrap_or(&no_references);
+        let no_templates = TemplateContext::default();
+        let templates = self.templates.unwrap_or(&no_templates);
+        let part_of = |module_index| {
+            self.parts
+                .iter()
+                .position(|p| p.modules.contains(&module_index))
+        };
+        let labels = self.numbering.module_labels(
+            self.chapters
+                .iter()
+                .map(|c| (c.module_index, c.appendix, part_of(c.module_index))),
+        );
+        let prefix = self.numbering.heading_prefix;
+        let mut current_part = None;
+        for (chapter, &label) in self.chapters.iter().zip(&labels) {
+            let part = part_of(chapter.module_index);
+            if let Some(index) = part.filter(|_| part != current_part) {
+                summary_md.write_all(part_title(&self.parts[index].title, index, prefix))?;
+            }
+            current_part = part;
+            // Every chapter has a landing page, which keeps the mdbook numbering of the
+            // sections in sync with their unit numbers
+            let intro = chapter.intro.and_then(|intro| {
+                errors.collect(
+                    render_intro(
+                        intro,
+                        references,
+                        templates,
+                        Scope::Module(chapter.module_index),
+                        edition,
+                    )
+                    .attach_printable_lazy(|| {
+                        format!("in module {} '{}'", chapter.module_index, chapter.title)
+                    }),
+                )
+            });
+            let chapter_file_name = chapter_file_name(chapter.title, chapter.module_index);
+            summary_md.write_fmt(format_args!(
+                "- [{}]({})\n",
+                chapter.title,
+                chapter_file_name.to_str().unwrap()
+            ))?;
+            book_src_dir
+                .join(chapter_file_name)
+                .create_file()?
+                .write_all(chapter_page(chapter, label, prefix, intro.as_deref()))?;
+
+            for section in chapter.sections.iter() {
+                let unit_number = UnitNumber::new(section.module_index, section.unit_index);
+                if section.draft {
+                    summary_md.write_fmt(format_args!("\t- [{}]()\n", section.title))?;
+                    continue;
+                }
+                done += 1;
+                progress(
+                    "book",
+                    done,
+                    total,
+                    format!("{unit_number} {}", section.title),
+                );
+                let section_file_name = section_file_name(section.title);
                 summary_md.write_fmt(format_args!(
                     "\t- [{}]({})\n",
                     section.title,
@@ -105,99 +268,523 @@ impl<'track> Book<'track> {
 
                 let section_file_path = book_src_dir.join(&section_file_name);
                 let mut section_file = section_file_path.create_file()?;
+                if section.teaser {
+                    section_file.write_all(teaser(section, label, prefix))?;
+                    continue;
+                }
                 section_file.write_fmt(format_args!(
-                    indoc! {r#"
-                        # Unit {chapter_i}.{section_i} - {}
-
-                        <a href="/{url_base}{url_base_separator}slides/{chapter_i}_{section_i}/" target="_blank">Slides</a>
-
-
-                        "#},
-                    section.title,
-                    chapter_i = chapter_i,
-                    section_i = section_i,
-                    url_base = slides_url_base,
-                    url_base_separator = slides_url_base_separator,
+                    "# {}\n\n{}\n",
+                    unit_heading(section, label, prefix),
+                    section_slides(slides, &deck_url(unit_number), section.title),
                 ))?;
+                if let Some(intro) = section.intro {
+                    let intro = render_intro(
+                        intro,
+                        references,
+                        templates,
+                        Scope::Unit(unit_number),
+                        edition,
+                    )
+                    .attach_printable_lazy(|| in_unit(section));
+                    if let Some(intro) = errors.collect(intro) {
+                        section_file.write_fmt(format_args!("{}\n\n", intro.trim()))?;
+                    }
+                }
 
+                // The exercises and their parts, to nest below the section in the summary
+                let mut summary_exercises = vec![];
                 if !section.subsections.is_empty() {
                     for (subsection, subsection_i) in section.subsections.iter().zip(1..) {
+                        let exercise_number = unit_number.exercise(subsection_i);
+                        let exercise_label = exercise_label(section, label, subsection_i);
                         section_file.write_fmt(format_args!(
-                            "## Exercise {chapter_i}.{section_i}.{subsection_i}: {}\n\n",
+                            "## Exercise {exercise_label}: {}\n\n",
                             subsection.title
                         ))?;
-                        let exercise_out_dir = &exercise_paths[subsection.exercise_path];
-                        let book_images_subdir =
-                            format!("images/{chapter_i}/{section_i}/{subsection_i}");
-                        if !subsection.images.is_empty() {
-                            let book_images_dir = book_src_dir.join(&book_images_subdir);
-                            book_images_dir.create_dir_all()?;
-                            copy_files(&subsection.images, &book_images_dir)?;
+                        if let Some(badges) = badges(subsection) {
+                            section_file.write_fmt(format_args!("{badges}\n\n"))?;
                         }
-
-                        let content = subsection.content.read_to_string()?;
-                        check_images(
-                            &subsection.exercise_path,
-                            &content,
-                            &subsection.images,
-                            &subsection.exercise_path.join("images"),
-                        )?;
-                        let content = content
-                            // Insert exercise directory paths
-                            .replace(
-                                EXERCISE_DIR_PLACEHOLDER,
-                                &exercise_out_dir.to_string_lossy(),
-                            )
-                            // Insert exercise references
-                            .replace(
-                                EXERCISE_REF_PLACEHOLDER,
-                                &format!("{chapter_i}.{section_i}.{subsection_i}"),
-                            )
-                            // Insert exercise image directory paths
-                            .replace(IMAGE_PLACEHOLDER, &book_images_subdir)
-                            // Convert exercise sections into subsubsections
-                            .replace("\n# ", "\n### ");
+                        let exercise_out_dir = &exercise_paths[subsection.exercise_path];
+                        let content = render_description(
+                            subsection,
+                            exercise_number,
+                            &exercise_label,
+                            exercise_out_dir,
+                            &book_src_dir,
+                            references,
+                            templates,
+                            edition,
+                        )
+                        .attach_printable_lazy(|| in_unit(section));
+                        let Some((content, parts)) = errors.collect(content) else {
+                            continue;
+                        };
                         section_file.write_fmt(format_args!("{}\n", content.trim()))?;
+                        summary_exercises.push((
+                            exercise_number,
+                            exercise_label,
+                            subsection.title,
+                            parts,
+                        ));
+
+                        let solution_dir = solution_dir(exercise_out_dir);
+                        if edition.is_teacher() && out_dir.join(&solution_dir).is_dir() {
+                            section_file.write_fmt(format_args!(
+                                "\n*Reference solution: `{}`*\n",
+                                solution_dir.to_string_lossy()
+                            ))?;
+                        }
                     }
                 } else {
-                    section_file.write_all("*There are no exercises for this unit*")?;
+                    section_file.write_all("*There are no exercises for this unit*\n")?;
+                }
+
+                // Exercises can only be listed as draft entries, as they have no file of their
+                // own. Only list them if there are parts, which are numbered down to `2.1.3.1`.
+                if summary_exercises
+                    .iter()
+                    .any(|(.., parts)| !parts.is_empty())
+                {
+                    for (number, label, title, parts) in summary_exercises {
+                        let entry = summary_entry(&number.to_string(), &label, title, numbered);
+                        summary_md.write_fmt(format_args!("\t\t- [{entry}]()\n"))?;
+                        for ((part_label, part_title), part) in parts.iter().zip(1..) {
+                            let entry = summary_entry(
+                                &format!("{number}.{part}"),
+                                part_label,
+                                part_title,
+                                numbered,
+                            );
+                            summary_md.write_fmt(format_args!("\t\t\t- [{entry}]()\n"))?;
+                        }
+                    }
+                }
+
+                if !section.quizzes.is_empty() {
+                    let quizzes_dir = book_src_dir.join(QUIZZES_DIR);
+                    quizzes_dir.create_dir_all()?;
+                    section_file.write_all("\n## Quiz\n")?;
+                    for (quiz, quiz_i) in section.quizzes.iter().zip(1..) {
+                        let loaded = Quiz::load::<RenderBookError>(quiz)
+                            .attach_printable_lazy(|| in_unit(section));
+                        if errors.collect(loaded).is_none() {
+                            continue;
+                        }
+                        let quiz_path =
+                            Path::new(QUIZZES_DIR).join(unit_number.quiz_file_name(quiz_i));
+                        quiz.copy(book_src_dir.join(&quiz_path))?;
+                        section_file.write_fmt(format_args!(
+                            "\n{{{{#quiz {}}}}}\n",
+                            quiz_path.to_string_lossy()
+                        ))?;
+                    }
                 }
             }
             summary_md.write_all("\n")?;
         }
 
-        Ok(())
+        errors.finish(())
+    }
+}
+
+/// The heading of the page of the unit of `section` in a module labelled `label`, like
+/// `Unit 2.1 - Basic Syntax`, or only its title without a `prefix`
+fn unit_heading(section: &Section, label: ModuleLabel, prefix: bool) -> String {
+    match label.of(&[section.unit_index]) {
+        Some(unit_label) if prefix => format!("Unit {unit_label} - {}", section.title),
+        _ => section.title.to_string(),
+    }
+}
+
+/// What the top of the section titled `title` shows of the slide deck at `url`
+fn section_slides(slides: SectionSlides, url: &str, title: &str) -> String {
+    let link = format!("<a href=\"{url}\" target=\"_blank\">Slides</a>\n\n");
+    match slides {
+        SectionSlides::None => String::new(),
+        SectionSlides::Link => link,
+        SectionSlides::Embed => format!(
+            "<iframe src=\"{url}\" title=\"Slides of {title}\" loading=\"lazy\" allowfullscreen=\"true\" style=\"width: 100%; aspect-ratio: 16 / 9; border: 0\"></iframe>\n\n{link}"
+        ),
+    }
+}
+
+/// The title of the part at `index` in the summary, which mdbook renders above the
+/// chapters of its modules, like `Part I: Fundamentals`, or only its title without a `prefix`
+fn part_title(title: &str, index: usize, prefix: bool) -> String {
+    match prefix {
+        true => format!("\n# Part {}: {title}\n\n", roman_numeral(index + 1)),
+        false => format!("\n# {title}\n\n"),
     }
 }
 
-#[derive(Debug)]
+/// The label of the `exercise`th exercise of `section` in a module labelled `label`, like
+/// `2.1.3`. Exercises in unnumbered modules keep their canonical number, so that references
+/// to them still work.
+fn exercise_label(section: &Section, label: ModuleLabel, exercise: usize) -> String {
+    label
+        .of(&[section.unit_index, exercise])
+        .unwrap_or_else(|| {
+            UnitNumber::new(section.module_index, section.unit_index)
+                .exercise(exercise)
+                .to_string()
+        })
+}
+
+/// The landing page of `chapter`, labelled `label`, with its `intro`, the sections in it and
+/// how many exercises they have and how long they take. With `prefix`, the headings start
+/// with the labels.
+fn chapter_page(
+    chapter: &Chapter,
+    label: ModuleLabel,
+    prefix: bool,
+    intro: Option<&str>,
+) -> String {
+    let exercises = |count: usize| match count {
+        0 => "no exercises".to_string(),
+        1 => "1 exercise".to_string(),
+        count => format!("{count} exercises"),
+    };
+
+    let mut page = match label {
+        ModuleLabel::Number(module) if prefix => format!("# Module {module} - "),
+        ModuleLabel::Letter(letter) if prefix => format!("# Appendix {letter} - "),
+        _ => "# ".to_string(),
+    };
+    page += &format!("{}\n\n", chapter.title);
+    if let Some(intro) = intro {
+        page += intro.trim();
+        page += "\n\n";
+    }
+    page += "## Units\n\n";
+    let mut exercise_count = 0;
+    let mut durations = vec![];
+    for section in chapter.sections.iter() {
+        let title = unit_heading(section, label, prefix);
+        if section.draft {
+            page += &format!("- {title}\n");
+            continue;
+        }
+        exercise_count += section.subsections.len();
+        let link = format!(
+            "[{title}]({})",
+            section_file_name(section.title).to_string_lossy()
+        );
+        page += &format!("- {link}: {}", exercises(section.subsections.len()));
+        if let Some(duration) = section.duration {
+            page += &format!(", {duration}");
+            durations.push(duration);
+        }
+        page += "\n";
+    }
+
+    let units = chapter.sections.iter().filter(|s| !s.draft).count();
+    page += &format!(
+        "\n*This module has {units} unit{} with {}",
+        if units == 1 { "" } else { "s" },
+        exercises(exercise_count)
+    );
+    if !durations.is_empty() {
+        page += &format!(", which take {}", durations.into_iter().sum::<Duration>());
+    }
+    page += ".*\n";
+    page
+}
+
+/// The difficulty and duration of the exercise of `subsection`, if it has any, as a block
+/// quote to put below its heading
+fn badges(subsection: &SubSection) -> Option<String> {
+    let mut badges = vec![];
+    if let Some(difficulty) = subsection.difficulty {
+        badges.push(format!("**Difficulty:** {difficulty}"));
+    }
+    if let Some(duration) = subsection.duration {
+        badges.push(format!("**Estimated time:** {duration}"));
+    }
+    (!badges.is_empty()).then(|| format!("> {}", badges.join(" · ")))
+}
+
+/// Where the unit of `section` is defined, to attach to errors about its content
+fn in_unit(section: &Section) -> String {
+    let unit_number = UnitNumber::new(section.module_index, section.unit_index);
+    let mut description = format!("in unit {unit_number} '{}'", section.title);
+    if let Some(source) = section.source {
+        description += &format!(", defined at {source}");
+    }
+    description
+}
+
+/// Render the intro of a chapter or section at `path` into Markdown for the book, with its
+/// headings nested below the heading of the page
+fn render_intro(
+    path: &Path,
+    references: &References,
+    templates: &TemplateContext,
+    scope: Scope,
+    edition: Edition,
+) -> Result<String, RenderBookError> {
+    let original = path.read_to_string()?;
+    let content = templates
+        .render(&nfc(&original), scope)
+        .and_then(|content| teacher_only(&content, edition.is_teacher()))
+        .and_then(|content| expand_includes(&content, path.parent().unwrap()))
+        .and_then(|content| references.expand(&content, RefTarget::Book));
+    let content = locate(content, path, &original)?;
+    Ok(rewrite_headings(&content, |level, text| {
+        Some(format!("{} {text}", "#".repeat((level + 1).min(6))))
+    }))
+}
+
+/// Render the description of the exercise of `subsection` into Markdown for the book,
+/// copying the images it uses into `book_src_dir`, followed by its hints as collapsed
+/// blocks. Returns the description, with its headings nested below that of the exercise,
+/// along with the labels and titles of the parts of the exercise, see [nest_headings].
+#[allow(clippy::too_many_arguments)]
+fn render_description(
+    subsection: &SubSection,
+    exercise_number: ExerciseNumber,
+    exercise_label: &str,
+    exercise_out_dir: &Path,
+    book_src_dir: &Path,
+    references: &References,
+    templates: &TemplateContext,
+    edition: Edition,
+) -> Result<(String, Vec<(String, String)>), RenderBookError> {
+    let book_images_subdir = exercise_number.book_images_dir();
+    if !subsection.images.is_empty() {
+        let book_images_dir = book_src_dir.join(&book_images_subdir);
+        book_images_dir.create_dir_all()?;
+        copy_files(&subsection.images, &book_images_dir)?;
+    }
+    let render = |file| {
+        render_exercise_file(
+            subsection,
+            file,
+            exercise_number,
+            exercise_label,
+            exercise_out_dir,
+            book_src_dir,
+            references,
+            templates,
+            edition,
+        )
+    };
+
+    let (mut content, parts) = nest_headings(&render(subsection.content)?, exercise_label);
+    let mut errors = Errors::default();
+    for (hint, number) in subsection.hints.iter().zip(1..) {
+        if let Some(hint) = errors.collect(render(hint)) {
+            content += &format!(
+                "\n\n<details>\n<summary>Hint {number}</summary>\n\n{}\n\n</details>",
+                hint.trim()
+            );
+        }
+    }
+    errors.finish((content, parts))
+}
+
+/// Render `file`, the description or a hint of the exercise of `subsection`, into Markdown
+/// for the book, copying the files it links to into `book_src_dir`
+#[allow(clippy::too_many_arguments)]
+fn render_exercise_file(
+    subsection: &SubSection,
+    file: &Path,
+    exercise_number: ExerciseNumber,
+    exercise_label: &str,
+    exercise_out_dir: &Path,
+    book_src_dir: &Path,
+    references: &References,
+    templates: &TemplateContext,
+    edition: Edition,
+) -> Result<String, RenderBookError> {
+    let book_images_subdir = exercise_number.book_images_dir();
+    let original = file.read_to_string()?;
+    let content = locate(
+        templates.render(&nfc(&original), Scope::Exercise(exercise_number)),
+        file,
+        &original,
+    )?;
+    // Point links to files next to the description at their copies in the book
+    let description_dir = file.parent().unwrap();
+    let exercise_images_dir = subsection.exercise_path.join("images");
+    let mut assets = vec![];
+    let content = rewrite_links(&content, |target| {
+        let file = local_file(description_dir, target)?;
+        if let Ok(image) = file.strip_prefix(&exercise_images_dir) {
+            return Some(format!("{IMAGE_PLACEHOLDER}/{}", image.to_string_lossy()));
+        }
+        let name = nfc_path(Path::new(file.file_name()?));
+        let asset = book_images_subdir.join(name);
+        assets.push(file);
+        Some(asset.to_string_lossy().into_owned())
+    });
+    if !assets.is_empty() {
+        let book_images_dir = book_src_dir.join(&book_images_subdir);
+        book_images_dir.create_dir_all()?;
+        copy_files(&assets, &book_images_dir)?;
+    }
+    let content = check_images(
+        subsection.exercise_path,
+        &content,
+        &subsection.images,
+        &subsection.exercise_path.join("images"),
+    )
+    .and_then(|_| teacher_only(&content, edition.is_teacher()))
+    .and_then(|content| expand_includes(&content, subsection.exercise_path))
+    .and_then(|content| references.expand(&content, RefTarget::Book));
+    let content = locate(content, file, &original)?;
+    Ok(content
+        // Insert exercise directory paths
+        .replace(
+            EXERCISE_DIR_PLACEHOLDER,
+            &exercise_out_dir.to_string_lossy(),
+        )
+        // Insert exercise references
+        .replace(EXERCISE_REF_PLACEHOLDER, exercise_label)
+        // Insert exercise image directory paths
+        .replace(IMAGE_PLACEHOLDER, &book_images_subdir.to_string_lossy()))
+}
+
+/// Nest the headings of an exercise description below the `## Exercise` heading of the
+/// exercise. The heading levels the description uses become levels 3 and up, in order, so
+/// that no level is skipped. If the description has several top-level headings, these divide
+/// the exercise into parts, which are numbered after the `exercise_label`, like `2.1.3.1`,
+/// unless the content numbers them itself with `#[modmod:exercise_ref]`. Returns the nested
+/// description and the labels and titles of the parts.
+fn nest_headings(content: &str, exercise_label: &str) -> (String, Vec<(String, String)>) {
+    let levels: BTreeSet<_> = blocks(content)
+        .iter()
+        .filter_map(|block| match block {
+            Block::Heading { level, .. } => Some(*level),
+            _ => None,
+        })
+        .collect();
+    let top_level_headings = blocks(content)
+        .iter()
+        .filter(|block| matches!(block, Block::Heading { level: 1, .. }))
+        .count();
+    let own_numbering = format!("{exercise_label}.");
+    let mut parts = vec![];
+    let content = rewrite_headings(content, |level, text| {
+        let nested = levels.iter().position(|l| *l == level).unwrap_or(0) + 3;
+        let hashes = "#".repeat(nested.min(6));
+        if level > 1 || top_level_headings < 2 {
+            return Some(format!("{hashes} {text}"));
+        }
+        let (label, title) = match text
+            .strip_prefix(&own_numbering)
+            .and_then(|numbered| numbered.split_once(' '))
+        {
+            Some((own, title)) => (format!("{own_numbering}{own}"), title),
+            None => (format!("{exercise_label}.{}", parts.len() + 1), text),
+        };
+        let heading = format!("{hashes} {label} {title}");
+        // Difficulty stars are for the page, not the summary
+        let title = title.trim_end_matches(|c: char| c == '⭐' || c.is_whitespace());
+        parts.push((label, title.to_string()));
+        Some(heading)
+    });
+    (content, parts)
+}
+
+/// The title of a draft entry in the summary of the exercise or part labelled `label`, which
+/// mdbook numbers `number` if the sidebar is `numbered`. The title starts with the label,
+/// unless mdbook's number already shows it.
+fn summary_entry(number: &str, label: &str, title: &str, numbered: bool) -> String {
+    match numbered && label == number {
+        true => title.to_string(),
+        false => format!("{label} {title}"),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Chapter<'track> {
     pub title: &'track str,
+    #[serde(borrow)]
     pub sections: Vec<Section<'track>>,
     pub module_index: usize,
+    /// Introduction at the top of the chapter page
+    #[serde(borrow)]
+    pub intro: Option<&'track Path>,
+    /// Appendices are labelled according to [NumberingConfig::appendices]
+    pub appendix: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Section<'track> {
     pub title: &'track str,
+    #[serde(borrow)]
     pub subsections: Vec<SubSection<'track>>,
     pub module_index: usize,
     pub unit_index: usize,
+    /// Draft sections are listed in the summary without content
+    pub draft: bool,
+    /// Teaser sections only list the learning objectives and exercise titles of the unit
+    pub teaser: bool,
+    #[serde(borrow)]
+    pub objectives: Vec<&'track str>,
+    #[serde(borrow)]
+    pub quizzes: Vec<&'track Path>,
+    /// Introduction at the top of the section, before its exercises
+    #[serde(borrow)]
+    pub intro: Option<&'track Path>,
+    /// The time the unit takes, including its exercises, if it's known
+    pub duration: Option<Duration>,
+    /// Where the unit is defined
+    #[serde(skip)]
+    pub source: Option<&'track Location>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubSection<'track> {
     pub title: &'track str,
+    #[serde(borrow)]
     pub content: &'track Path,
-    pub images: &'track [PathBuf],
+    #[serde(borrow)]
+    pub images: Vec<&'track Path>,
+    #[serde(borrow)]
     pub exercise_path: &'track Path,
+    pub difficulty: Option<Difficulty>,
+    /// The time students need for the exercise
+    pub duration: Option<Duration>,
+    /// Hints that follow the description, collapsed so that students can open them one
+    /// at a time
+    #[serde(borrow, default)]
+    pub hints: Vec<&'track Path>,
+}
+
+impl<'track> SubSection<'track> {
+    /// Set the difficulty of the exercise, which is shown below its heading
+    pub fn difficulty(&mut self, difficulty: Option<Difficulty>) -> &mut Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// Set the time students need for the exercise, which is shown below its heading
+    pub fn duration(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set the hints that follow the description
+    pub fn hints(&mut self, hints: &'track [PathBuf]) -> &mut Self {
+        self.hints = hints.iter().map(PathBuf::as_path).collect();
+        self
+    }
 }
 
+/// Builds a [`Book`]. See [`Book::builder`].
 pub struct BookBuilder<'track> {
     book: Book<'track>,
 }
 
 impl<'track> BookBuilder<'track> {
+    /// Set the language of the book content. Defaults to `en`.
+    pub fn language(&mut self, language: &'track str) -> &mut Self {
+        self.book.language = language;
+        self
+    }
+
+    /// Start a new chapter. Call [`ChapterBuilder::add`] to add it to the book.
     pub fn chapter<'b>(
         &'b mut self,
         title: &'track str,
@@ -209,21 +796,60 @@ impl<'track> BookBuilder<'track> {
                 title,
                 module_index,
                 sections: vec![],
+                intro: None,
+                appendix: false,
             },
         }
     }
 
+    /// Continue the chapter that was added last, to add more sections to it
+    pub(crate) fn reopen_chapter(&mut self) -> ChapterBuilder<'track, '_> {
+        let chapter = self.book.chapters.pop().expect("a chapter was added");
+        ChapterBuilder {
+            book_builder: self,
+            chapter,
+        }
+    }
+
+    /// Set how modules and units are labelled in headings
+    pub fn numbering(&mut self, numbering: NumberingConfig) -> &mut Self {
+        self.book.numbering = numbering;
+        self
+    }
+
+    /// Set what `#[modmod:ref(..)]` references in the content refer to. Without these, every
+    /// reference is an error.
+    pub fn references(&mut self, references: &'track References) -> &mut Self {
+        self.book.references = Some(references);
+        self
+    }
+
+    /// Set what templates in the content refer to. Without these, the variables of
+    /// templates are empty.
+    pub fn templates(&mut self, templates: &'track TemplateContext) -> &mut Self {
+        self.book.templates = Some(templates);
+        self
+    }
+
+    /// Set the groups of modules that get a part title in the summary
+    pub fn parts(&mut self, parts: &'track [Part]) -> &mut Self {
+        self.book.parts = parts;
+        self
+    }
+
     pub fn build(self) -> Book<'track> {
         self.book
     }
 }
 
+/// Builds a [`Chapter`]. See [`BookBuilder::chapter`].
 pub struct ChapterBuilder<'track, 'b> {
     book_builder: &'b mut BookBuilder<'track>,
     chapter: Chapter<'track>,
 }
 
 impl<'track, 'b> ChapterBuilder<'track, 'b> {
+    /// Start a new section. Call [`SectionBuilder::add`] to add it to the chapter.
     pub fn section<'c>(
         &'c mut self,
         module_index: usize,
@@ -237,55 +863,163 @@ impl<'track, 'b> ChapterBuilder<'track, 'b> {
                 module_index,
                 unit_index,
                 subsections: vec![],
+                draft: false,
+                teaser: false,
+                objectives: vec![],
+                quizzes: vec![],
+                intro: None,
+                duration: None,
+                source: None,
             },
         }
     }
 
+    /// Continue the section that was added last, to add more subsections to it
+    pub(crate) fn reopen_section(&mut self) -> SectionBuilder<'track, 'b, '_> {
+        let section = self.chapter.sections.pop().expect("a section was added");
+        SectionBuilder {
+            chapter_builder: self,
+            section,
+        }
+    }
+
+    /// Set the introduction at the top of the chapter page
+    pub fn intro(&mut self, intro: &'track Path) -> &mut Self {
+        self.chapter.intro = Some(intro);
+        self
+    }
+
+    /// Mark the chapter as an appendix
+    pub fn appendix(&mut self) -> &mut Self {
+        self.chapter.appendix = true;
+        self
+    }
+
+    /// Add the chapter to the book
     pub fn add(self) -> &'b mut BookBuilder<'track> {
         self.book_builder.book.chapters.push(self.chapter);
         self.book_builder
     }
 }
 
+/// Builds a [`Section`]. See [`ChapterBuilder::section`].
 pub struct SectionBuilder<'track, 'b, 'c> {
     chapter_builder: &'c mut ChapterBuilder<'track, 'b>,
     section: Section<'track>,
 }
 
 impl<'track, 'b, 'c> SectionBuilder<'track, 'b, 'c> {
+    /// Add an exercise subsection. `exercise_path` must be one of the keys of
+    /// [`BookRenderOptions::exercise_paths`] when rendering the book. Returns the subsection,
+    /// to set its difficulty, duration and hints.
     pub fn subsection(
         &mut self,
         title: &'track str,
         content: &'track Path,
         images: &'track [PathBuf],
         exercise_path: &'track Path,
-    ) {
+    ) -> &mut SubSection<'track> {
         self.section.subsections.push(SubSection {
             title,
             content,
-            images,
+            images: images.iter().map(PathBuf::as_path).collect(),
             exercise_path,
-        })
+            difficulty: None,
+            duration: None,
+            hints: vec![],
+        });
+        self.section.subsections.last_mut().unwrap()
+    }
+
+    /// Add a quiz in the mdbook-quiz format to the end of the section
+    pub fn quiz(&mut self, quiz: &'track Path) {
+        self.section.quizzes.push(quiz);
+    }
+
+    /// Set the introduction at the top of the section
+    pub fn intro(&mut self, intro: &'track Path) {
+        self.section.intro = Some(intro);
+    }
+
+    /// Set the time the unit takes, which is listed on the chapter page
+    pub fn duration(&mut self, duration: Duration) {
+        self.section.duration = Some(duration);
+    }
+
+    /// Set where the unit is defined, which errors about its content point to
+    pub fn source(mut self, source: &'track Location) -> Self {
+        self.section.source = Some(source);
+        self
+    }
+
+    /// Mark the section as a draft, rendering only its entry in the summary
+    pub fn draft(mut self) -> Self {
+        self.section.draft = true;
+        self
     }
 
+    /// Mark the section as a teaser, rendering only the learning objectives and the
+    /// titles of the exercises
+    pub fn teaser(mut self) -> Self {
+        self.section.teaser = true;
+        self
+    }
+
+    /// Add a learning objective, which is listed on teaser pages
+    pub fn objective(&mut self, objective: &'track str) {
+        self.section.objectives.push(objective);
+    }
+
+    /// Add the section to the chapter
     pub fn add(self) -> &'c mut ChapterBuilder<'track, 'b> {
         self.chapter_builder.chapter.sections.push(self.section);
         self.chapter_builder
     }
 }
 
+/// The page of a unit that is left out of the preview edition
+fn teaser(section: &Section, label: ModuleLabel, prefix: bool) -> String {
+    let mut page = format!(
+        "# {}\n\n*This unit is part of the full course.*\n",
+        unit_heading(section, label, prefix)
+    );
+    if !section.objectives.is_empty() {
+        page.push_str("\nIn this unit, you will learn:\n\n");
+        for objective in section.objectives.iter() {
+            page.push_str(&format!("- {}\n", objective.trim()));
+        }
+    }
+    if !section.subsections.is_empty() {
+        page.push_str("\nExercises:\n\n");
+        for (subsection, subsection_i) in section.subsections.iter().zip(1..) {
+            page.push_str(&format!(
+                "- Exercise {}: {}\n",
+                exercise_label(section, label, subsection_i),
+                subsection.title
+            ));
+        }
+    }
+    page
+}
+
+/// Remove the mdbook-quiz directives from a rendered section, as well as the heading above them,
+/// for output formats that don't support mdbook preprocessors
+pub(crate) fn without_quizzes(content: &str) -> String {
+    match content.find("\n## Quiz\n") {
+        Some(i) if content[i..].contains("{{#quiz ") => format!("{}\n", content[..i].trim_end()),
+        _ => content.to_string(),
+    }
+}
+
 /// Scan content for #[modmod:images] references.
 fn find_image_placeholders(mut content: &str) -> std::collections::HashSet<&str> {
     let mut found_images = std::collections::HashSet::new();
-    loop {
-        let Some(pos) = content.find(IMAGE_PLACEHOLDER) else {
-            break;
-        };
+    while let Some(pos) = content.find(IMAGE_PLACEHOLDER) {
         content = &content[pos + IMAGE_PLACEHOLDER.len()..];
         let Some(end) = content.find(')') else {
             break;
         };
-        found_images.insert((&content[..end]).trim_start_matches('/'));
+        found_images.insert(content[..end].trim_start_matches('/'));
         content = &content[end + 1..];
     }
     found_images
@@ -295,7 +1029,7 @@ fn find_image_placeholders(mut content: &str) -> std::collections::HashSet<&str>
 fn check_images(
     exercise_path: &std::path::Path,
     content: &str,
-    existing_images: &[std::path::PathBuf],
+    existing_images: &[&Path],
     base_path: &Path,
 ) -> Result<(), RenderBookError> {
     let mut referenced_images = find_image_placeholders(content);
@@ -305,9 +1039,9 @@ fn check_images(
             let Ok(relative_path) = image.strip_prefix(base_path) else {
                 return Some(image.to_string_lossy());
             };
-            (!referenced_images.remove(std::borrow::Borrow::<str>::borrow(
-                &relative_path.to_string_lossy(),
-            )))
+            (!referenced_images.remove(std::borrow::Borrow::<str>::borrow(&nfc(
+                &relative_path.to_string_lossy()
+            ))))
             .then_some(image.to_string_lossy())
         })
         .collect::<Vec<_>>();
@@ -318,24 +1052,31 @@ fn check_images(
             unused_images.join(", ")
         )
     });
+    // Point errors at the first missing image
+    let first_missing_image = referenced_images.iter().min().map(|i| i.to_string());
     let non_existing_images_err_msg = (!referenced_images.is_empty()).then(|| {
+        let mut images: Vec<_> = referenced_images.into_iter().collect();
+        images.sort();
         format!(
             "💥 {}: Non existing images: {}",
             exercise_path.to_string_lossy(),
-            referenced_images.into_iter().collect::<Vec<_>>().join(", ")
+            images.join(", ")
         )
     });
     if unused_images_err_msg.is_some() || non_existing_images_err_msg.is_some() {
-        Err(RenderBookError {
+        let report = Report::new(RenderBookError {
             reason: Some(
                 unused_images_err_msg
                     .into_iter()
-                    .chain(non_existing_images_err_msg.into_iter())
+                    .chain(non_existing_images_err_msg)
                     .collect::<Vec<_>>()
                     .join("\n"),
             ),
-        }
-        .into())
+        });
+        Err(match first_missing_image {
+            Some(image) => report.attach_printable(Snippet(image)),
+            None => report,
+        })
     } else {
         Ok(())
     }
@@ -372,4 +1113,144 @@ mod tests {
             std::collections::HashSet::from(["image_1.svg", "image_2.svg"])
         );
     }
+
+    #[test]
+    fn exercise_parts_are_nested_and_numbered() {
+        let exercise = "2.1.3";
+        let (content, parts) = nest_headings(
+            "# Numbers\n\n## Hints\n\n```rust\n# fn main() {}\n```\n",
+            exercise,
+        );
+        assert_eq!(
+            content,
+            "### Numbers\n\n#### Hints\n\n```rust\n# fn main() {}\n```\n"
+        );
+        assert!(parts.is_empty());
+
+        let (content, parts) = nest_headings(
+            "Intro\n\n# Server\n\n## Hints\n\n# 2.1.3.B Client ⭐⭐\n",
+            exercise,
+        );
+        assert_eq!(
+            content,
+            "Intro\n\n### 2.1.3.1 Server\n\n#### Hints\n\n### 2.1.3.B Client ⭐⭐\n"
+        );
+        assert_eq!(
+            parts,
+            [
+                ("2.1.3.1".to_string(), "Server".to_string()),
+                ("2.1.3.B".to_string(), "Client".to_string())
+            ]
+        );
+        assert_eq!(
+            summary_entry("2.1.3.1", "2.1.3.1", "Server", true),
+            "Server"
+        );
+        assert_eq!(
+            summary_entry("2.1.3.2", "2.1.3.B", "Client", true),
+            "2.1.3.B Client"
+        );
+        assert_eq!(
+            summary_entry("2.1.3.1", "B.1.3.1", "Server", false),
+            "B.1.3.1 Server"
+        );
+    }
+
+    #[test]
+    fn exercise_headings_are_nested_without_skipping_levels() {
+        let (content, _) = nest_headings("## Steps\n\nFirst\n\n## Hints\n", "2.1.3");
+        assert_eq!(content, "### Steps\n\nFirst\n\n### Hints\n");
+
+        let (content, _) = nest_headings("# Server\n\n### Routes\n\n# Client\n", "2.1.3");
+        assert_eq!(
+            content,
+            "### 2.1.3.1 Server\n\n#### Routes\n\n### 2.1.3.2 Client\n"
+        );
+    }
+
+    #[test]
+    fn chapter_pages_list_their_sections() {
+        let section = |unit_index, title, draft, duration| Section {
+            title,
+            subsections: vec![],
+            module_index: 2,
+            unit_index,
+            draft,
+            teaser: false,
+            objectives: vec![],
+            quizzes: vec![],
+            intro: None,
+            duration,
+            source: None,
+        };
+        let mut basic_syntax = section(1, "Basic Syntax", false, Some("1h".parse().unwrap()));
+        basic_syntax.subsections.push(SubSection {
+            title: "Numbers",
+            content: Path::new("numbers.md"),
+            images: vec![],
+            exercise_path: Path::new("numbers"),
+            difficulty: None,
+            duration: None,
+            hints: vec![],
+        });
+        let chapter = Chapter {
+            title: "Foundations",
+            sections: vec![
+                basic_syntax,
+                section(2, "Ownership", true, None),
+                section(3, "Traits", false, Some("30m".parse().unwrap())),
+            ],
+            module_index: 2,
+            intro: None,
+            appendix: false,
+        };
+        assert_eq!(
+            chapter_page(
+                &chapter,
+                ModuleLabel::Number(2),
+                true,
+                Some("The basics.\n")
+            ),
+            "# Module 2 - Foundations\n\nThe basics.\n\n## Units\n\n\
+            - [Unit 2.1 - Basic Syntax](basic-syntax.md): 1 exercise, 1h\n\
+            - Unit 2.2 - Ownership\n\
+            - [Unit 2.3 - Traits](traits.md): no exercises, 30m\n\n\
+            *This module has 2 units with 1 exercise, which take 1h30m.*\n"
+        );
+    }
+
+    #[test]
+    fn teasers_list_objectives_and_exercises() {
+        let section = Section {
+            title: "Basic Syntax",
+            subsections: vec![SubSection {
+                title: "Numbers",
+                content: Path::new("numbers.md"),
+                images: vec![],
+                exercise_path: Path::new("numbers"),
+                difficulty: Some(2.try_into().unwrap()),
+                duration: Some("30m".parse().unwrap()),
+                hints: vec![],
+            }],
+            module_index: 2,
+            unit_index: 1,
+            draft: false,
+            teaser: true,
+            objectives: vec!["Declare variables "],
+            quizzes: vec![],
+            intro: None,
+            duration: None,
+            source: None,
+        };
+        assert_eq!(
+            teaser(&section, ModuleLabel::Number(2), true),
+            "# Unit 2.1 - Basic Syntax\n\n*This unit is part of the full course.*\n\n\
+            In this unit, you will learn:\n\n- Declare variables\n\n\
+            Exercises:\n\n- Exercise 2.1.1: Numbers\n"
+        );
+        assert_eq!(
+            badges(&section.subsections[0]).as_deref(),
+            Some("> **Difficulty:** ⭐⭐ · **Estimated time:** 30m")
+        );
+    }
 }
@@ -0,0 +1,426 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    cache::{package_key, CachedVerification, VerificationCache},
+    check::CheckOptions,
+    doctor::{tool, Tool},
+    flaky::{find_flaky, FlakyTest},
+    io::PathExt,
+    numbering::EXERCISES_DIR,
+    tasks::{Task, TaskOutput, TaskRunner},
+    testing::TestExpectation,
+    RenderFilter, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct BuildError {}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to build rendered track")
+    }
+}
+
+impl error_stack::Context for BuildError {}
+
+/// Something to build from the rendered track with external tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BuildTarget {
+    /// The HTML book, with mdbook
+    Book,
+    /// The slide decks, with Slidev
+    Slides,
+    /// PDFs of the slide decks, with Slidev and Chromium. Needs the `pdf` scripts of
+    /// [`SlidesRenderOptions::pdf`](crate::slides::SlidesRenderOptions::pdf).
+    Pdf,
+    /// Check the links in the content, including external ones
+    Linkcheck,
+    /// Run `cargo check` on every exercise package, and optionally run its tests
+    /// several times to find flaky ones
+    Exercises,
+    /// Run clippy, denying warnings, and `cargo fmt --check` on every exercise package
+    Lint,
+}
+
+impl fmt::Display for BuildTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BuildTarget::Book => "book",
+            BuildTarget::Slides => "slides",
+            BuildTarget::Pdf => "pdf",
+            BuildTarget::Linkcheck => "linkcheck",
+            BuildTarget::Exercises => "exercises",
+            BuildTarget::Lint => "lint",
+        })
+    }
+}
+
+impl FromStr for BuildTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "book" => Ok(BuildTarget::Book),
+            "slides" => Ok(BuildTarget::Slides),
+            "pdf" => Ok(BuildTarget::Pdf),
+            "linkcheck" => Ok(BuildTarget::Linkcheck),
+            "exercises" => Ok(BuildTarget::Exercises),
+            "lint" => Ok(BuildTarget::Lint),
+            _ => Err(format!(
+                "unknown build target '{s}', expected book, slides, pdf, linkcheck, exercises or lint"
+            )),
+        }
+    }
+}
+
+/// How to build targets
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions<'a> {
+    /// Runs the external tools
+    pub runner: TaskRunner,
+    /// Exercise packages that passed verification before, according to this cache, are
+    /// not verified again
+    pub cache: Option<&'a VerificationCache>,
+    /// Run the tests of every exercise package this many times, failing if a test passes
+    /// in some runs and fails in others. Tests are not run if this is below 2.
+    pub test_runs: usize,
+}
+
+/// The outcome of building a target
+#[derive(Debug)]
+pub enum BuildOutcome {
+    Built,
+    /// The target was not built, as a tool it needs is not installed
+    Skipped {
+        missing: &'static Tool,
+    },
+}
+
+impl Track {
+    /// Build `target` from the track rendered into `out_dir`. Targets whose tools are not
+    /// installed are skipped rather than failing, so callers can decide whether that's an error.
+    pub fn build(
+        &self,
+        out_dir: impl AsRef<Path>,
+        target: BuildTarget,
+        opts: &BuildOptions,
+    ) -> Result<BuildOutcome, BuildError> {
+        let out_dir = out_dir.as_ref();
+        let runner = &opts.runner;
+        let slides_dir = out_dir.join("slides");
+        let required = match target {
+            BuildTarget::Book => "mdbook",
+            BuildTarget::Slides | BuildTarget::Pdf => "node",
+            BuildTarget::Linkcheck => "curl",
+            BuildTarget::Exercises | BuildTarget::Lint => "cargo",
+        };
+        let missing = tool(required);
+        if !missing.is_installed() {
+            return Ok(BuildOutcome::Skipped { missing });
+        }
+
+        match target {
+            BuildTarget::Book => {
+                let task = Task::new("mdbook build", "mdbook")
+                    .arg("build")
+                    .current_dir(out_dir.join("book"));
+                check_all(vec![runner.run_one(task)])?;
+            }
+            BuildTarget::Slides => {
+                npm_install(&slides_dir, runner)?;
+                let package_json: serde_json::Value =
+                    serde_json::from_str(&slides_dir.join("package.json").read_to_string()?)
+                        .into_report()
+                        .change_context(BuildError::default())?;
+                let scripts = package_json["scripts"].as_object().into_iter().flatten();
+                let tasks = scripts
+                    .filter(|(s, _)| s.starts_with("build-"))
+                    .map(|(script, _)| {
+                        npm(&format!("npm run {script}"), &slides_dir, ["run", script])
+                    })
+                    .collect();
+                check_all(runner.run(tasks))?;
+            }
+            BuildTarget::Pdf => {
+                npm_install(&slides_dir, runner)?;
+                // Installing the slides package downloads Chromium through Playwright
+                let missing = tool("chromium");
+                if !missing.is_installed() {
+                    return Ok(BuildOutcome::Skipped { missing });
+                }
+                check_all(vec![runner.run_one(npm(
+                    "npm run pdf",
+                    &slides_dir,
+                    ["run", "pdf"],
+                ))])?;
+            }
+            BuildTarget::Linkcheck => {
+                let findings = self
+                    .check(&CheckOptions {
+                        links: true,
+                        external_links: true,
+                        runner: runner.clone(),
+                        ..Default::default()
+                    })
+                    .change_context(BuildError::default())?;
+                if !findings.is_empty() {
+                    let mut report = Report::new(BuildError::default())
+                        .attach_printable(format!("Found {} dead link(s)", findings.len()));
+                    for finding in findings {
+                        report = report.attach_printable(finding.to_string());
+                    }
+                    return Err(report);
+                }
+            }
+            BuildTarget::Exercises => {
+                // Exercises about a compile error are left out
+                let compile_errors: Vec<_> = self
+                    .exercise_packages(&RenderFilter::default())
+                    .into_iter()
+                    .filter(|p| p.exercise.expect == TestExpectation::CompileError)
+                    .map(|p| out_dir.join(p.dir))
+                    .collect();
+                let verified = verify_exercises(out_dir, &compile_errors, runner, opts.cache);
+                // Flaky tests are reported even if some packages don't compile
+                let flaky = match opts.test_runs {
+                    0 | 1 => Ok(()),
+                    runs => find_flaky_tests(out_dir, runner, runs),
+                };
+                match (verified, flaky) {
+                    (Err(mut report), Err(flaky)) => {
+                        report.extend_one(flaky);
+                        return Err(report);
+                    }
+                    (verified, flaky) => verified.and(flaky)?,
+                }
+            }
+            BuildTarget::Lint => {
+                for required in ["clippy", "rustfmt"] {
+                    let missing = tool(required);
+                    if !missing.is_installed() {
+                        return Ok(BuildOutcome::Skipped { missing });
+                    }
+                }
+                check_all(runner.run(self.lint_tasks(out_dir)))?;
+            }
+        }
+
+        Ok(BuildOutcome::Built)
+    }
+}
+
+/// The arguments `cargo` verifies exercise packages with
+const CHECK_ARGS: [&str; 3] = ["check", "--all-targets", "--quiet"];
+
+/// Run `cargo check` on the exercise packages in `out_dir` that `cache` has no passing
+/// verification of, apart from those in `skip`, and add those that pass to it
+fn verify_exercises(
+    out_dir: &Path,
+    skip: &[PathBuf],
+    runner: &TaskRunner,
+    cache: Option<&VerificationCache>,
+) -> Result<(), BuildError> {
+    let mut packages = exercise_packages(&out_dir.join(EXERCISES_DIR))?;
+    packages.retain(|p| !skip.contains(p));
+    // The toolchain of a package can be overridden by a rust-toolchain.toml in it
+    let quiet = TaskRunner {
+        progress: false,
+        ..runner.clone()
+    };
+    let toolchains = match cache {
+        Some(_) => quiet
+            .run(
+                packages
+                    .iter()
+                    .map(|p| Task::new("rustc -vV", "rustc").arg("-vV").current_dir(p))
+                    .collect(),
+            )
+            .into_iter()
+            .map(|o| {
+                o.into_result()
+                    .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .change_context(BuildError::default())?,
+        None => vec![String::new(); packages.len()],
+    };
+
+    let mut tasks = vec![];
+    let mut keys = vec![];
+    for ((package, toolchain), i) in packages.iter().zip(toolchains).zip(1..) {
+        let name = package.strip_prefix(out_dir).unwrap_or(package);
+        let name = format!("cargo check {}", name.to_string_lossy());
+        if let Some(cache) = cache {
+            let key = package_key(package, &CHECK_ARGS.join(" "), &toolchain)
+                .change_context(BuildError::default())?;
+            if cache
+                .contains(&key, runner)
+                .change_context(BuildError::default())?
+            {
+                if runner.progress {
+                    log::info!("cached {name}");
+                }
+                continue;
+            }
+            keys.push((key, toolchain));
+        }
+        tasks.push(
+            Task::new(name, "cargo")
+                .args(CHECK_ARGS)
+                .current_dir(package)
+                .env("CARGO_TARGET_DIR", exercise_target_dir(i)),
+        );
+    }
+
+    let outputs = runner.run(tasks);
+    if let Some(cache) = cache {
+        for (output, (key, toolchain)) in outputs.iter().zip(keys) {
+            if !output.success() {
+                continue;
+            }
+            let verification = CachedVerification {
+                task: output.task.name.clone(),
+                toolchain,
+            };
+            cache
+                .insert(&key, &verification, runner)
+                .change_context(BuildError::default())?;
+        }
+    }
+    check_all(outputs)
+}
+
+/// Run `cargo test` `runs` times on each exercise package in `out_dir`, and fail with the
+/// tests that passed in some runs and failed in others
+fn find_flaky_tests(out_dir: &Path, runner: &TaskRunner, runs: usize) -> Result<(), BuildError> {
+    let packages = exercise_packages(&out_dir.join(EXERCISES_DIR))?;
+    let names: Vec<_> = packages
+        .iter()
+        .map(|p| {
+            p.strip_prefix(out_dir)
+                .unwrap_or(p)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    let mut tasks = vec![];
+    for ((package, name), i) in packages.iter().zip(names.iter()).zip(1..) {
+        for run in 1..=runs {
+            tasks.push(
+                Task::new(format!("cargo test {name} ({run}/{runs})"), "cargo")
+                    .arg("test")
+                    .current_dir(package)
+                    .env("CARGO_TARGET_DIR", exercise_target_dir(i))
+                    // A retry would hide the failures this looks for
+                    .no_retry(),
+            );
+        }
+    }
+
+    let outputs = runner.run(tasks);
+    let flaky: Vec<FlakyTest> = outputs
+        .chunks(runs)
+        .zip(names.iter())
+        .flat_map(|(outputs, name)| find_flaky(name, outputs))
+        .collect();
+    if flaky.is_empty() {
+        return Ok(());
+    }
+    let mut report = Report::new(BuildError::default()).attach_printable(format!(
+        "Found {} flaky test(s) in {runs} runs of each exercise test suite",
+        flaky.len()
+    ));
+    for test in flaky {
+        report = report.attach_printable(test.to_string());
+    }
+    Err(report)
+}
+
+/// The target folder for the `i`th exercise package, which keeps build artifacts out of
+/// the rendered exercises
+pub(crate) fn exercise_target_dir(i: usize) -> PathBuf {
+    std::env::temp_dir()
+        .join("modmod-exercises-target")
+        .join(i.to_string())
+}
+
+/// The folders in `exercises_dir` that hold a Cargo package, leaving out the members
+/// of packages that are workspaces
+pub(crate) fn exercise_packages(exercises_dir: &Path) -> Result<Vec<PathBuf>, BuildError> {
+    let mut manifests: Vec<_> = exercises_dir
+        .get_dir_content::<BuildError>()?
+        .files
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|f| f.file_name().is_some_and(|n| n == "Cargo.toml"))
+        .filter_map(|f| f.parent().map(Path::to_path_buf))
+        .collect();
+    manifests.sort();
+    let mut packages: Vec<PathBuf> = vec![];
+    for dir in manifests {
+        if !packages.iter().any(|p| dir.starts_with(p)) {
+            packages.push(dir);
+        }
+    }
+    Ok(packages)
+}
+
+fn npm<'a>(name: &str, slides_dir: &Path, args: impl IntoIterator<Item = &'a str>) -> Task {
+    Task::new(name, if cfg!(windows) { "npm.cmd" } else { "npm" })
+        .args(args)
+        .current_dir(slides_dir)
+}
+
+fn npm_install(slides_dir: &Path, runner: &TaskRunner) -> Result<(), BuildError> {
+    if slides_dir.join("node_modules").is_dir() {
+        return Ok(());
+    }
+    check_all(vec![runner.run_one(npm(
+        "npm install",
+        slides_dir,
+        ["install"],
+    ))])
+}
+
+/// Fail if any of `outputs` failed, with the errors of all failed tasks
+fn check_all(outputs: Vec<TaskOutput>) -> Result<(), BuildError> {
+    let mut report: Option<Report<BuildError>> = None;
+    for output in outputs {
+        let Err(error) = output.into_result() else {
+            continue;
+        };
+        let error = error.change_context(BuildError::default());
+        match report.as_mut() {
+            Some(report) => report.extend_one(error),
+            None => report = Some(error),
+        }
+    }
+    report.map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_are_parsed_by_name() {
+        for target in [
+            BuildTarget::Book,
+            BuildTarget::Slides,
+            BuildTarget::Pdf,
+            BuildTarget::Linkcheck,
+            BuildTarget::Exercises,
+            BuildTarget::Lint,
+        ] {
+            assert_eq!(target.to_string().parse(), Ok(target));
+        }
+        assert!("docs".parse::<BuildTarget>().is_err());
+    }
+}
@@ -0,0 +1,782 @@
+//! Bundling the built output of a track into a single folder, for trainings in air-gapped
+//! environments: the HTML book, the slide decks and the exercises, with the scripts,
+//! stylesheets, fonts and images they load from other sites fetched into the bundle.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+    fmt, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    archive::{entry_name, write_archive, PackageFormat, PACKAGES_DIR},
+    cache::sha256,
+    check::Curl,
+    diagnostics::Errors,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    numbering::{module_exercises_dir, EXERCISES_DIR},
+    scorm::book_html_dir,
+    tasks::TaskRunner,
+    to_prefixed_tag, to_tag,
+    vendor::VENDOR_DIR,
+    visit::{Position, Visitor},
+    Module, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct BundleError {}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to bundle output")
+    }
+}
+
+impl error_stack::Context for BundleError {}
+
+/// What a bundle is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleKind {
+    /// Everything needed to follow the course without network access
+    Offline,
+}
+
+impl BundleKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BundleKind::Offline => "offline",
+        }
+    }
+}
+
+impl FromStr for BundleKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "offline" => Ok(BundleKind::Offline),
+            _ => Err(format!("unknown bundle '{s}', expected offline")),
+        }
+    }
+}
+
+/// How to bundle the built output
+#[derive(Debug, Clone)]
+pub struct BundleOptions {
+    pub kind: BundleKind,
+    /// Also archive the whole bundle in this format, which is the format of the exercise
+    /// archives in it as well. These are zip files otherwise.
+    pub format: Option<PackageFormat>,
+    /// Fetches the external assets with curl
+    pub runner: TaskRunner,
+}
+
+/// A written bundle
+#[derive(Debug)]
+pub struct Bundle {
+    pub dir: PathBuf,
+    /// The archive of the bundle, if a format was given
+    pub archive: Option<PathBuf>,
+    /// The URLs of the external assets that were fetched into the bundle
+    pub assets: Vec<String>,
+}
+
+/// Folder in the bundle the external assets are fetched into, by host and path
+pub const ASSETS_DIR: &str = "assets";
+
+/// Tags that load an asset, and the attribute that holds its URL. Other tags, like
+/// anchors, link to pages, which are left alone.
+const ASSET_ATTRIBUTES: &[(&str, &str)] = &[
+    ("audio", "src"),
+    ("img", "src"),
+    ("link", "href"),
+    ("script", "src"),
+    ("source", "src"),
+    ("video", "src"),
+];
+
+/// Relations of `link` tags that load an asset
+const ASSET_RELS: &[&str] = &[
+    "apple-touch-icon",
+    "icon",
+    "manifest",
+    "modulepreload",
+    "preload",
+    "shortcut icon",
+    "stylesheet",
+];
+
+/// A slide deck built by one of the build scripts of the slides package
+struct BuiltDeck {
+    /// Folder in `slides` the deck was built into
+    dist: PathBuf,
+    /// Folder in the bundle the deck is copied to, which matches where it's served from
+    /// below the slide URL base
+    path: PathBuf,
+}
+
+impl Track {
+    /// Bundle the book built by mdbook in `out_dir`, the slide decks built into
+    /// `slides/dist` and an archive of the exercises of every module into the folder
+    /// `packages/<track>-<kind>`, along with a landing page that links to all of them.
+    /// The external assets that the pages and stylesheets in the bundle load are fetched
+    /// into it, and the references to them rewritten.
+    pub fn bundle(
+        &self,
+        out_dir: impl AsRef<Path>,
+        opts: &BundleOptions,
+    ) -> Result<Bundle, BundleError> {
+        let out_dir = out_dir.as_ref();
+        let name = format!("{}-{}", to_tag(&self.name), opts.kind.name());
+        let bundle_dir = out_dir.join(PACKAGES_DIR).join(&name);
+        if bundle_dir.exists() {
+            fs::remove_dir_all(&bundle_dir)
+                .into_report()
+                .change_context(BundleError::default())?;
+        }
+        bundle_dir.create_dir_all()?;
+
+        copy_dir(&book_html_dir(out_dir)?, &bundle_dir.join("book"))?;
+        let slides_dir = out_dir.join("slides");
+        let decks = built_decks(&slides_dir)?;
+        for deck in decks.iter() {
+            copy_dir(&slides_dir.join(&deck.dist), &bundle_dir.join(&deck.path))?;
+        }
+        let format = opts.format.unwrap_or(PackageFormat::Zip);
+        let exercises = self.bundle_exercises(out_dir, &bundle_dir, format)?;
+        bundle_dir
+            .join("index.html")
+            .create_file()?
+            .write_all(self.bundle_landing_page(&decks, &exercises))?;
+
+        let assets = localize_assets(&bundle_dir, &opts.runner)?;
+
+        let archive = match opts.format {
+            Some(format) => {
+                let archive = bundle_dir.with_extension(format.extension());
+                let files = files_below(&bundle_dir, &[""])?;
+                write_archive(&archive, &bundle_dir, &files, format)
+                    .change_context(BundleError::default())?;
+                Some(archive)
+            }
+            None => None,
+        };
+
+        Ok(Bundle {
+            dir: bundle_dir,
+            archive,
+            assets,
+        })
+    }
+
+    /// Write an archive of the exercises of every module in `out_dir` into the exercises
+    /// folder of the bundle, and one of the vendored dependencies if there are any.
+    /// Returns their paths relative to the bundle.
+    fn bundle_exercises(
+        &self,
+        out_dir: &Path,
+        bundle_dir: &Path,
+        format: PackageFormat,
+    ) -> Result<Vec<PathBuf>, BundleError> {
+        let mut archives = vec![];
+        let mut contents: Vec<(String, Vec<PathBuf>)> = self
+            .modules
+            .iter()
+            .map(|m| {
+                (
+                    to_prefixed_tag(&m.data.name, m.index),
+                    vec![module_exercises_dir(&m.data.name, m.index)],
+                )
+            })
+            .collect();
+        // With the vendored dependencies next to the extracted exercises, they build offline
+        contents.push((
+            "dependencies".to_string(),
+            vec![PathBuf::from(VENDOR_DIR), PathBuf::from(".cargo")],
+        ));
+
+        for (name, dirs) in contents {
+            let files = files_below(out_dir, &dirs)?;
+            if files.is_empty() {
+                continue;
+            }
+            let archive = Path::new(EXERCISES_DIR)
+                .join(name)
+                .with_extension(format.extension());
+            let archive_path = bundle_dir.join(&archive);
+            archive_path.parent().unwrap().create_dir_all()?;
+            write_archive(&archive_path, out_dir, &files, format)
+                .change_context(BundleError::default())?;
+            archives.push(archive);
+        }
+        Ok(archives)
+    }
+
+    /// The landing page of the bundle, which links to the book, the slide decks and the
+    /// exercise archives
+    fn bundle_landing_page(&self, decks: &[BuiltDeck], exercises: &[PathBuf]) -> String {
+        let mut titles = Titles::default();
+        self.walk_infallible(&mut titles);
+        let titles = titles.0;
+
+        let title = html_escape(&self.name);
+        let mut body = "    <ul>\n      <li><a href=\"book/index.html\">Book</a></li>\n    </ul>\n"
+            .to_string();
+        if !decks.is_empty() {
+            body += "    <h2>Slides</h2>\n    <ul>\n";
+            for deck in decks {
+                let slug = deck.dist.file_name().unwrap().to_string_lossy();
+                let name = titles.get(slug.as_ref()).map_or(slug.as_ref(), |t| t);
+                body += &format!(
+                    "      <li><a href=\"{}/index.html\">{}</a></li>\n",
+                    entry_name(&deck.path),
+                    html_escape(name)
+                );
+            }
+            body += "    </ul>\n";
+        }
+        if !exercises.is_empty() {
+            body += "    <h2>Exercises</h2>\n    <ul>\n";
+            for archive in exercises {
+                body += &format!(
+                    "      <li><a href=\"{}\">{}</a></li>\n",
+                    entry_name(archive),
+                    html_escape(&archive.file_name().unwrap().to_string_lossy())
+                );
+            }
+            body += "    </ul>\n";
+        }
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{title}</title>
+  </head>
+  <body>
+    <h1>{title}</h1>
+{body}  </body>
+</html>
+"#
+        )
+    }
+}
+
+/// The slide decks that the build scripts of the package in `slides_dir` build, which
+/// must have been built
+fn built_decks(slides_dir: &Path) -> Result<Vec<BuiltDeck>, BundleError> {
+    let package_json = slides_dir.join("package.json");
+    if !package_json.is_file() {
+        return Ok(vec![]);
+    }
+    let package_json: serde_json::Value = serde_json::from_str(&package_json.read_to_string()?)
+        .into_report()
+        .change_context(BundleError::default())?;
+    let scripts = package_json["scripts"].as_object().into_iter().flatten();
+
+    let mut decks = vec![];
+    for (_, command) in scripts.filter(|(s, _)| s.starts_with("build-")) {
+        let args: Vec<_> = command.as_str().unwrap_or_default().split(' ').collect();
+        let arg = |flag| args.windows(2).find(|a| a[0] == flag).map(|a| a[1]);
+        let (Some(dist), Some(base)) = (arg("--out"), arg("--base")) else {
+            continue;
+        };
+        let Some((_, deck)) = base.trim_end_matches('/').rsplit_once("slides/") else {
+            continue;
+        };
+        if !slides_dir.join(dist).join("index.html").is_file() {
+            return Err(
+                Report::new(BundleError::default()).attach_printable(format!(
+                    "Slide deck {} is not built. Build the slides with Slidev first",
+                    slides_dir.join(dist).to_string_lossy()
+                )),
+            );
+        }
+        decks.push(BuiltDeck {
+            dist: PathBuf::from(dist),
+            path: Path::new("slides").join(deck),
+        });
+    }
+    Ok(decks)
+}
+
+/// Fetch the external assets that the pages and stylesheets in `bundle_dir` load into its
+/// assets folder, along with the assets that the fetched stylesheets load in turn, and point
+/// the references to them to the fetched copies. Returns the URLs of the fetched assets.
+fn localize_assets(bundle_dir: &Path, runner: &TaskRunner) -> Result<Vec<String>, BundleError> {
+    let mut assets = BTreeMap::new();
+    for file in files_below(bundle_dir, &[""])? {
+        let html = match file.extension().and_then(|e| e.to_str()) {
+            Some("html" | "htm") => true,
+            Some("css") => false,
+            _ => continue,
+        };
+        let path = bundle_dir.join(&file);
+        let content = path.read_to_string()?;
+        let references = match html {
+            true => html_references(&content),
+            false => css_references(&content),
+        };
+        let localized = localize(&content, &references, &file, absolute_url, &mut assets);
+        if localized != content {
+            path.create_file()?.write_all(localized)?;
+        }
+    }
+
+    let mut curl = None;
+    let mut fetched = BTreeSet::new();
+    let mut errors = Errors::default();
+    loop {
+        let pending: Vec<(String, Asset)> = assets
+            .iter()
+            .filter(|(url, _)| !fetched.contains(*url))
+            .map(|(url, asset)| (url.clone(), asset.clone()))
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+        let curl = match &mut curl {
+            Some(curl) => curl,
+            None => curl.insert(Curl::new(runner).change_context(BundleError::default())?),
+        };
+        let urls: Vec<_> = pending.iter().map(|(url, _)| url.clone()).collect();
+        for ((url, asset), (status, body)) in pending.into_iter().zip(curl.fetch(&urls)) {
+            fetched.insert(url.clone());
+            if !(200..300).contains(&status) {
+                let problem = match status {
+                    0 => "it could not be reached".to_string(),
+                    status => format!("the server responded with HTTP status {status}"),
+                };
+                errors.push(
+                    Report::new(BundleError::default())
+                        .attach_printable(format!("Unable to fetch {url}, as {problem}")),
+                );
+                continue;
+            }
+            let body = match asset.css {
+                true => {
+                    let css = String::from_utf8_lossy(&body);
+                    let references = css_references(&css);
+                    let resolve = |reference: &str| resolve_url(&url, reference);
+                    localize(&css, &references, &asset.path, resolve, &mut assets).into_bytes()
+                }
+                false => body,
+            };
+            let path = bundle_dir.join(&asset.path);
+            path.parent().unwrap().create_dir_all()?;
+            path.create_file()?.write_all(body)?;
+        }
+    }
+    errors.finish(fetched.into_iter().collect())
+}
+
+/// An external asset, fetched into the bundle
+#[derive(Debug, Clone)]
+struct Asset {
+    /// Where the asset is fetched to, relative to the bundle
+    path: PathBuf,
+    /// Whether the asset is a stylesheet, whose references are localized in turn
+    css: bool,
+}
+
+/// A reference to an asset in a page or stylesheet
+#[derive(Debug, PartialEq)]
+struct Reference {
+    /// Where the URL of the asset is in the content
+    range: Range<usize>,
+    /// Whether the asset is a stylesheet
+    css: bool,
+}
+
+/// Replace the URLs of `references` in `content`, of the file at `path` in the bundle, that
+/// `resolve` turns into the URL of an asset by the relative URL of the copy the asset is
+/// fetched into. These assets are added to `assets`.
+fn localize(
+    content: &str,
+    references: &[Reference],
+    path: &Path,
+    resolve: impl Fn(&str) -> Option<String>,
+    assets: &mut BTreeMap<String, Asset>,
+) -> String {
+    let mut localized = String::with_capacity(content.len());
+    let mut end = 0;
+    for reference in references {
+        let reference_url = content[reference.range.clone()].replace("&amp;", "&");
+        let Some(url) = resolve(reference_url.trim()) else {
+            continue;
+        };
+        let (url, fragment) = url.split_at(url.find('#').unwrap_or(url.len()));
+        let asset = assets.entry(url.to_string()).or_insert_with(|| Asset {
+            path: asset_path(url, reference.css),
+            css: reference.css,
+        });
+        localized.push_str(&content[end..reference.range.start]);
+        localized.push_str(&relative_url(path, &asset.path));
+        localized.push_str(fragment);
+        end = reference.range.end;
+    }
+    localized.push_str(&content[end..]);
+    localized
+}
+
+/// The references to assets in the tags of `html`, and in its stylesheets and style attributes
+fn html_references(html: &str) -> Vec<Reference> {
+    // Lowering the case of ASCII keeps the offsets the same
+    let lower = html.to_ascii_lowercase();
+    let mut references = vec![];
+    let mut from = 0;
+    while let Some(start) = lower[from..].find('<').map(|i| from + i + 1) {
+        let end = lower[start..].find('>').map_or(lower.len(), |i| start + i);
+        from = end;
+        let tag = &lower[start..end];
+        let name = &tag[..tag
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(tag.len())];
+        let offset = |range: Range<usize>| start + range.start..start + range.end;
+
+        if let Some(style) = attribute_value(tag, "style") {
+            let style = offset(style);
+            references.extend(css_references(&html[style.clone()]).into_iter().map(|r| {
+                Reference {
+                    range: style.start + r.range.start..style.start + r.range.end,
+                    css: r.css,
+                }
+            }));
+        }
+        if name == "style" {
+            let css_end = lower[end..]
+                .find("</style")
+                .map_or(lower.len(), |i| end + i);
+            let css_start = (end + 1).min(css_end);
+            references.extend(
+                css_references(&html[css_start..css_end])
+                    .into_iter()
+                    .map(|r| Reference {
+                        range: css_start + r.range.start..css_start + r.range.end,
+                        css: r.css,
+                    }),
+            );
+            from = css_end;
+            continue;
+        }
+
+        let Some((_, attribute)) = ASSET_ATTRIBUTES.iter().find(|(t, _)| *t == name) else {
+            continue;
+        };
+        let Some(value) = attribute_value(tag, attribute) else {
+            continue;
+        };
+        let css = match name {
+            "link" => {
+                let rel = attribute_value(tag, "rel").map_or("", |rel| tag[rel].trim());
+                if !ASSET_RELS.contains(&rel) {
+                    continue;
+                }
+                rel == "stylesheet"
+            }
+            _ => false,
+        };
+        references.push(Reference {
+            range: offset(value),
+            css,
+        });
+    }
+    references.sort_by_key(|r| r.range.start);
+    references
+}
+
+/// Where the value of the attribute `name` is in `tag`, the lowercase content of an HTML tag
+fn attribute_value(tag: &str, name: &str) -> Option<Range<usize>> {
+    let mut from = 0;
+    while let Some(i) = tag[from..].find(name).map(|i| from + i) {
+        from = i + name.len();
+        let rest = tag[from..].trim_start();
+        if !tag[..i].ends_with(|c: char| c.is_ascii_whitespace()) || !rest.starts_with('=') {
+            continue;
+        }
+        let value = rest[1..].trim_start();
+        let start = tag.len() - value.len();
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let len = value[1..].find(quote).unwrap_or(value.len() - 1);
+                start + 1..start + 1 + len
+            }
+            _ => {
+                let len = value
+                    .find(|c: char| c.is_ascii_whitespace())
+                    .unwrap_or(value.len());
+                start..start + len
+            }
+        });
+    }
+    None
+}
+
+/// The references to assets in the `url()` values and `@import` rules of `css`
+fn css_references(css: &str) -> Vec<Reference> {
+    let mut references = vec![];
+    for (i, _) in css.match_indices("url(") {
+        let value = css[i + 4..].trim_start();
+        let start = css.len() - value.len();
+        let range = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let len = value[1..].find(quote).unwrap_or(value.len() - 1);
+                start + 1..start + 1 + len
+            }
+            _ => start..start + value.find(')').unwrap_or(value.len()),
+        };
+        references.push(Reference {
+            range,
+            css: css[..i].trim_end().ends_with("@import"),
+        });
+    }
+    for (i, _) in css.match_indices("@import") {
+        let value = css[i + 7..].trim_start();
+        let start = css.len() - value.len();
+        if let Some(quote @ ('"' | '\'')) = value.chars().next() {
+            let len = value[1..].find(quote).unwrap_or(value.len() - 1);
+            references.push(Reference {
+                range: start + 1..start + 1 + len,
+                css: true,
+            });
+        }
+    }
+    references.sort_by_key(|r| r.range.start);
+    references
+}
+
+/// The URL of `reference` if it points to another site, leaving out references to files in
+/// the bundle
+fn absolute_url(reference: &str) -> Option<String> {
+    if reference.starts_with("http://") || reference.starts_with("https://") {
+        Some(reference.to_string())
+    } else {
+        reference
+            .strip_prefix("//")
+            .map(|reference| format!("https://{reference}"))
+    }
+}
+
+/// The URL that `reference`, in the asset at `base`, points to. Data URLs and references to
+/// fragments of the asset itself are left out.
+fn resolve_url(base: &str, reference: &str) -> Option<String> {
+    if reference.is_empty() || reference.starts_with('#') || reference.starts_with("data:") {
+        return None;
+    }
+    if let Some(url) = absolute_url(reference) {
+        return Some(url);
+    }
+    let (scheme, rest) = base.split_once("://")?;
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = &path[..path.find(['?', '#']).unwrap_or(path.len())];
+    let joined = match reference.starts_with('/') {
+        true => reference.to_string(),
+        false => format!("{}/{reference}", &path[..path.rfind('/').unwrap_or(0)]),
+    };
+
+    let (path, query) = joined.split_at(joined.find(['?', '#']).unwrap_or(joined.len()));
+    let mut segments = vec![];
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("{scheme}://{host}/{}{query}", segments.join("/")))
+}
+
+/// Where the asset at `url` is fetched to, relative to the bundle: its host and path in
+/// the assets folder. Assets with a query get its hash in their name, and stylesheets get
+/// the `.css` extension, so that they are served as such.
+fn asset_path(url: &str, css: bool) -> PathBuf {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (location, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut segments: Vec<_> = location
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != "." && *s != "..")
+        .map(|s| s.replace(':', "_"))
+        .collect();
+    if location.ends_with('/') || segments.len() < 2 {
+        segments.push("index".to_string());
+    }
+    let mut file = segments.pop().unwrap();
+    if !query.is_empty() {
+        let hash = &sha256(query.as_bytes())[..8];
+        file = match file.rsplit_once('.') {
+            Some((stem, extension)) => format!("{stem}-{hash}.{extension}"),
+            None => format!("{file}-{hash}"),
+        };
+    }
+    if css && !file.ends_with(".css") {
+        file += ".css";
+    }
+    let mut path = PathBuf::from(ASSETS_DIR);
+    path.extend(segments);
+    path.join(file)
+}
+
+/// The URL of the file at `to` relative to the file at `from`, both relative to the bundle
+fn relative_url(from: &Path, to: &Path) -> String {
+    let depth = from.parent().map_or(0, |p| p.components().count());
+    format!("{}{}", "../".repeat(depth), entry_name(to))
+}
+
+/// The files in `dirs` in `base`, relative to it and sorted. Folders that don't exist are
+/// left out.
+fn files_below(base: &Path, dirs: &[impl AsRef<Path>]) -> Result<Vec<PathBuf>, BundleError> {
+    let mut files = vec![];
+    for dir in dirs.iter().map(|d| base.join(d)).filter(|d| d.is_dir()) {
+        files.extend(
+            dir.get_dir_content::<BundleError>()?
+                .files
+                .into_iter()
+                .map(|f| Path::new(&f).strip_prefix(base).unwrap().to_path_buf()),
+        );
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The titles of the modules and units of a track, by the names of their output
+#[derive(Default)]
+struct Titles(BTreeMap<String, String>);
+
+impl<'t> Visitor<'t> for Titles {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.0.insert(
+            to_prefixed_tag(&module.data.name, module.index),
+            format!("{} {}", module.index, module.data.name),
+        );
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        let number = position.unit_number().unwrap();
+        self.0.insert(
+            number.deck_slug(&unit.data.name),
+            format!("{number} {}", unit.data.name),
+        );
+        Ok(())
+    }
+}
+
+/// Copy the files in the folder `from` into the folder `to`
+fn copy_dir(from: &Path, to: &Path) -> Result<(), BundleError> {
+    for file in files_below(from, &[""])? {
+        let target = to.join(&file);
+        target.parent().unwrap().create_dir_all()?;
+        from.join(&file).copy(&target)?;
+    }
+    Ok(())
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn external_assets_are_pointed_to_their_copies() {
+        let html = r#"<html><head>
+<link rel="preconnect" href="https://fonts.gstatic.com">
+<link rel="stylesheet" href="https://fonts.googleapis.com/css2?family=Fira+Sans&amp;display=swap">
+<script src="https://cdn.example.com/mathjax/tex.js"></script>
+<link rel="stylesheet" href="../css/general.css">
+<style>body { background: url('//cdn.example.com/bg.png'); }</style>
+</head><body>
+<a href="https://doc.rust-lang.org/book/">The book</a>
+<img src="https://example.com/ferris.svg" alt="Ferris">
+<p>Write url(https://example.com/x.png) in a stylesheet</p>
+</body></html>"#;
+        let mut assets = BTreeMap::new();
+        let references = html_references(html);
+        let localized = localize(
+            html,
+            &references,
+            Path::new("book/basics/index.html"),
+            absolute_url,
+            &mut assets,
+        );
+        for (original, copy) in [
+            (
+                r#"href="https://fonts.googleapis.com/css2?family=Fira+Sans&amp;display=swap""#,
+                r#"href="../../assets/fonts.googleapis.com/css2-"#,
+            ),
+            (
+                r#"src="https://cdn.example.com/mathjax/tex.js""#,
+                r#"src="../../assets/cdn.example.com/mathjax/tex.js""#,
+            ),
+            (
+                "url('//cdn.example.com/bg.png')",
+                "url('../../assets/cdn.example.com/bg.png')",
+            ),
+            (
+                r#"src="https://example.com/ferris.svg""#,
+                r#"src="../../assets/example.com/ferris.svg""#,
+            ),
+        ] {
+            assert!(!localized.contains(original));
+            assert!(localized.contains(copy), "{copy} not in {localized}");
+        }
+        for kept in [
+            r#"href="https://fonts.gstatic.com""#,
+            r#"href="../css/general.css""#,
+            r#"href="https://doc.rust-lang.org/book/""#,
+            "url(https://example.com/x.png)",
+        ] {
+            assert!(localized.contains(kept));
+        }
+        let fonts = &assets["https://fonts.googleapis.com/css2?family=Fira+Sans&display=swap"];
+        assert!(fonts.css);
+        assert!(fonts.path.to_string_lossy().ends_with(".css"));
+        assert_eq!(assets.len(), 4);
+
+        // Fonts in fetched stylesheets are fetched as well
+        let css = "@font-face { src: url(../fonts/fira.woff2?v=2#iefix) format('woff2'); }";
+        let base = "https://cdn.example.com/fira/css/fira.css";
+        let localized = localize(
+            css,
+            &css_references(css),
+            &asset_path(base, true),
+            |r| resolve_url(base, r),
+            &mut assets,
+        );
+        let font = asset_path("https://cdn.example.com/fira/fonts/fira.woff2?v=2", false);
+        assert!(font.to_string_lossy().ends_with(".woff2"));
+        assert_eq!(
+            localized,
+            format!(
+                "@font-face {{ src: url(../../../../{}#iefix) format('woff2'); }}",
+                entry_name(&font)
+            )
+        );
+    }
+}
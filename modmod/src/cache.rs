@@ -0,0 +1,321 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::{PathExt, WriteExt},
+    tasks::{Task, TaskRunner},
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CacheError {}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to use verification cache")
+    }
+}
+
+impl error_stack::Context for CacheError {}
+
+/// Where the results of verifying exercise packages are kept, so that packages that
+/// didn't change since they were last verified can be skipped. Only successful
+/// verifications are cached, so failures are run again and show their output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationCache {
+    /// A folder on this machine
+    Dir(PathBuf),
+    /// A bucket of an S3-compatible object store, written like `s3://bucket/prefix`
+    S3 { bucket: String, prefix: String },
+}
+
+impl FromStr for VerificationCache {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let Some(location) = s.strip_prefix("s3://") else {
+            return Ok(VerificationCache::Dir(PathBuf::from(s)));
+        };
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err(format!(
+                "invalid cache location '{s}', expected a folder or one like s3://bucket/prefix"
+            ));
+        }
+        Ok(VerificationCache::S3 {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+}
+
+/// What is stored for a verification that passed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedVerification {
+    /// The task that verified the package
+    pub task: String,
+    pub toolchain: String,
+}
+
+impl VerificationCache {
+    /// Whether a verification with `key` passed before
+    pub fn contains(&self, key: &str, runner: &TaskRunner) -> Result<bool, CacheError> {
+        match self {
+            VerificationCache::Dir(dir) => Ok(dir.join(entry_path(key)).is_file()),
+            VerificationCache::S3 { .. } => {
+                let output = runner.run_one(
+                    self.s3_request(key)?
+                        .args(["--output", if cfg!(windows) { "NUL" } else { "/dev/null" }])
+                        .args(["--write-out", "%{http_code}"]),
+                );
+                let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                match status.as_str() {
+                    "200" => Ok(true),
+                    // Without permission to list the bucket, missing objects are forbidden
+                    "403" | "404" => Ok(false),
+                    "000" => Err(Report::new(CacheError::default()).attach_printable(format!(
+                        "Unable to reach the object store: {}",
+                        output.status
+                    ))),
+                    status => Err(Report::new(CacheError::default()).attach_printable(format!(
+                        "The object store responded with HTTP status {status}"
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Record that the verification with `key` passed
+    pub fn insert(
+        &self,
+        key: &str,
+        verification: &CachedVerification,
+        runner: &TaskRunner,
+    ) -> Result<(), CacheError> {
+        let entry = serde_json::to_string_pretty(verification).unwrap();
+        match self {
+            VerificationCache::Dir(dir) => {
+                let path = dir.join(entry_path(key));
+                path.parent().unwrap().create_dir_all()?;
+                path.create_file()?.write_all(entry)?;
+            }
+            VerificationCache::S3 { .. } => {
+                let upload = std::env::temp_dir().join(format!("modmod-cache-{key}.json"));
+                upload.create_file::<CacheError>()?.write_all(entry)?;
+                let output = runner.run_one(
+                    self.s3_request(key)?
+                        .args([
+                            "--fail",
+                            "--output",
+                            if cfg!(windows) { "NUL" } else { "/dev/null" },
+                        ])
+                        .arg("--upload-file")
+                        .arg(&upload),
+                );
+                let _ = std::fs::remove_file(&upload);
+                output
+                    .into_result()
+                    .attach_printable("Unable to upload to the verification cache")
+                    .change_context(CacheError::default())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A curl request for the object of `key`, signed with the credentials in the standard
+    /// AWS environment variables. `MODMOD_S3_ENDPOINT` points it at other object stores.
+    fn s3_request(&self, key: &str) -> Result<Task, CacheError> {
+        let VerificationCache::S3 { bucket, prefix } = self else {
+            unreachable!()
+        };
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let (Some(access_key), Some(secret_key)) =
+            (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY"))
+        else {
+            return Err(Report::new(CacheError::default()).attach_printable(
+                "Set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY to use an S3 verification cache",
+            ));
+        };
+        let region = var("AWS_REGION")
+            .or_else(|| var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = var("MODMOD_S3_ENDPOINT")
+            .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"));
+        let object = match prefix.is_empty() {
+            true => entry_path(key),
+            false => format!("{prefix}/{}", entry_path(key)),
+        };
+
+        // Credentials go in a config on stdin, rather than in the arguments that other
+        // users of the machine can see
+        let mut config = format!("user = \"{access_key}:{secret_key}\"\n");
+        if let Some(token) = var("AWS_SESSION_TOKEN") {
+            config.push_str(&format!("header = \"x-amz-security-token: {token}\"\n"));
+        }
+        Ok(Task::new(format!("cache {key}"), "curl")
+            .args(["--silent", "--max-time", "30", "--config", "-"])
+            .arg("--aws-sigv4")
+            .arg(format!("aws:amz:{region}:s3"))
+            .arg(format!(
+                "{}/{bucket}/{object}",
+                endpoint.trim_end_matches('/')
+            ))
+            .stdin(config))
+    }
+}
+
+/// Where the entry of `key` is kept, relative to the cache root. Entries are spread over
+/// folders by their first characters, to keep folders small.
+fn entry_path(key: &str) -> String {
+    format!("{}/{key}.json", &key[..2])
+}
+
+/// The cache key for running `task` with `toolchain` over the package in `dir`. It covers
+/// the relative paths and contents of all files in the package, apart from build artifacts.
+pub fn package_key(dir: &Path, task: &str, toolchain: &str) -> Result<String, CacheError> {
+    let content = dir.get_dir_content::<CacheError>()?;
+    let mut files: Vec<_> = content
+        .files
+        .iter()
+        .map(PathBuf::from)
+        .filter_map(|f| f.strip_prefix(dir).ok().map(Path::to_path_buf))
+        .filter(|f| !f.starts_with("target"))
+        .collect();
+    files.sort();
+
+    let mut hashed = format!("{task}\n{toolchain}\n").into_bytes();
+    for file in files {
+        let bytes = std::fs::read(dir.join(&file))
+            .map_err(|e| Report::new(CacheError::default()).attach_printable(e.to_string()))
+            .attach_printable_lazy(|| format!("Unable to read {}", file.to_string_lossy()))?;
+        // Separators that can't occur in paths keep names and contents apart
+        hashed.extend(file.to_string_lossy().replace('\\', "/").as_bytes());
+        hashed.push(0);
+        hashed.extend((bytes.len() as u64).to_le_bytes());
+        hashed.extend(bytes);
+    }
+    Ok(sha256(&hashed))
+}
+
+/// The SHA-256 hash of `data` in hexadecimal
+pub(crate) fn sha256(data: &[u8]) -> String {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    h.iter().map(|v| format!("{v:08x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packages_are_keyed_by_their_content() {
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(&[b'a'; 1000]),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("Cargo.toml", "[package]\nname = \"ex\"\n");
+        write("src/main.rs", "fn main() {}\n");
+        let key = || package_key(dir.path(), "cargo check", "rustc 1.80.0").unwrap();
+        let first = key();
+        write("target/debug/ex", "binary");
+        assert_eq!(key(), first);
+        assert_ne!(
+            package_key(dir.path(), "cargo check", "rustc 1.81.0").unwrap(),
+            first
+        );
+        write("src/main.rs", "fn main() { todo!() }\n");
+        assert_ne!(key(), first);
+
+        assert_eq!(
+            "s3://course-ci/modmod/".parse(),
+            Ok(VerificationCache::S3 {
+                bucket: "course-ci".to_string(),
+                prefix: "modmod".to_string()
+            })
+        );
+        assert_eq!(
+            "target/cache".parse(),
+            Ok(VerificationCache::Dir(PathBuf::from("target/cache")))
+        );
+    }
+}
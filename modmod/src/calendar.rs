@@ -0,0 +1,562 @@
+use std::{
+    convert::Infallible,
+    fmt::{self, Write},
+    str::FromStr,
+    time::SystemTime,
+};
+
+use error_stack::{Report, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    load::Indexed,
+    numbering::section_file_name,
+    to_tag,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CalendarError {}
+
+impl fmt::Display for CalendarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to lay out calendar")
+    }
+}
+
+impl error_stack::Context for CalendarError {}
+
+/// A calendar date, written like `2026-11-02`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The number of days since 1970-01-01
+    pub(crate) fn days(&self) -> i64 {
+        // Count years from March, so that leap days end the year
+        let year = self.year as i64 - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((self.month as i64 + 9) % 12) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    pub(crate) fn from_days(days: i64) -> Self {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+        let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Date { year, month, day }
+    }
+
+    fn is_weekend(&self) -> bool {
+        // 1970-01-01 was a Thursday
+        matches!(self.days().rem_euclid(7), 2 | 3)
+    }
+
+    fn next_day(&self) -> Self {
+        Self::from_days(self.days() + 1)
+    }
+
+    /// The date `months` months later, on the last day of the month if it's shorter
+    pub(crate) fn add_months(&self, months: u32) -> Self {
+        let month = self.month - 1 + months;
+        let year = self.year + (month / 12) as i32;
+        let month = month % 12 + 1;
+        let first_of_next = Date {
+            year: year + i32::from(month == 12),
+            month: month % 12 + 1,
+            day: 1,
+        };
+        let month_length = (first_of_next.days()
+            - Date {
+                year,
+                month,
+                day: 1,
+            }
+            .days()) as u32;
+        Date {
+            year,
+            month,
+            day: self.day.min(month_length),
+        }
+    }
+
+    /// The current date in UTC
+    pub fn today() -> Self {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        Self::from_days(now / 86400)
+    }
+
+    /// The date in the compact form of iCalendar, e.g. `20261102`
+    fn to_ics(self) -> String {
+        format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || format!("invalid date '{s}', expected one like 2026-11-02");
+        let mut parts = s.trim().splitn(3, '-');
+        let mut part = || parts.next().and_then(|p| p.parse().ok()).ok_or_else(error);
+        let (year, month, day) = (part()?, part()?, part()?);
+        let date = Date {
+            year: year as i32,
+            month,
+            day,
+        };
+        // Dates like 2026-02-30 don't survive the round trip
+        match (1..=12).contains(&month) && Date::from_days(date.days()) == date {
+            true => Ok(date),
+            false => Err(error()),
+        }
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+/// A span of teaching time within a day, written like `09:00-12:30`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeSlot {
+    /// Minutes since midnight
+    pub start: u32,
+    pub end: u32,
+}
+
+impl fmt::Display for TimeSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |minutes: u32| format!("{:02}:{:02}", minutes / 60, minutes % 60);
+        write!(f, "{}-{}", time(self.start), time(self.end))
+    }
+}
+
+impl FromStr for TimeSlot {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || format!("invalid time slot '{s}', expected one like 09:00-12:30");
+        let time = |t: &str| {
+            let (hours, minutes) = t.trim().split_once(':')?;
+            let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+            (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+        };
+        let (start, end) = s.split_once('-').ok_or_else(error)?;
+        match (time(start), time(end)) {
+            (Some(start), Some(end)) if start < end => Ok(TimeSlot { start, end }),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeSlot {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TimeSlot> for String {
+    fn from(slot: TimeSlot) -> Self {
+        slot.to_string()
+    }
+}
+
+/// How to lay out a course run in a calendar
+#[derive(Debug, Default)]
+pub struct CalendarOptions {
+    /// The first day of the course run
+    pub start: Option<Date>,
+    /// The teaching time of each day, in order
+    pub slots: Vec<TimeSlot>,
+    /// Whether the course is also taught on Saturdays and Sundays
+    pub weekends: bool,
+    /// The IANA time zone of the slots, e.g. `Europe/Amsterdam`. If not set, the events
+    /// are at the same local time wherever the calendar is opened.
+    pub timezone: Option<String>,
+    /// The URL the book is deployed to, which the events link to
+    pub book_url: Option<String>,
+    pub filter: RenderFilter,
+}
+
+impl CalendarOptions {
+    /// The first day from `date` on that the course is taught
+    fn teaching_day(&self, mut date: Date) -> Date {
+        while !self.weekends && date.is_weekend() {
+            date = date.next_day();
+        }
+        date
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EventTime {
+    /// Minutes since midnight
+    Timed { date: Date, start: u32, end: u32 },
+    /// From the first day up to and including the last day
+    AllDay { first: Date, last: Date },
+}
+
+/// An event in the calendar of a course run
+#[derive(Debug)]
+pub struct Event {
+    /// Stable across runs of modmod, so that calendars can update the events after an import
+    pub id: String,
+    pub summary: String,
+    pub description: String,
+    pub url: Option<String>,
+    pub time: EventTime,
+}
+
+impl Track {
+    /// Lay out the modules of the track as all-day events and their units as events in the
+    /// time slots of `options`, one after the other. Each module starts on a new day, and
+    /// units that don't fit in a slot continue in the next one. Units without a duration are
+    /// left out.
+    pub fn calendar_events(&self, options: &CalendarOptions) -> Result<Vec<Event>, CalendarError> {
+        let Some(start) = options.start else {
+            return Err(Report::new(CalendarError::default())
+                .attach_printable("No start date set for the calendar"));
+        };
+        if options.slots.is_empty() {
+            return Err(Report::new(CalendarError::default())
+                .attach_printable("No time slots set for the calendar"));
+        }
+        let mut layout = CalendarLayout {
+            options,
+            book_url: options
+                .book_url
+                .as_deref()
+                .map(|url| url.trim_end_matches('/')),
+            events: vec![],
+            date: options.teaching_day(start),
+            first: None,
+            slot_i: 0,
+            time: options.slots[0].start,
+        };
+        self.walk_infallible(&mut layout);
+
+        Ok(layout.events)
+    }
+
+    /// Render the [`calendar_events`](Self::calendar_events) of the track as an iCalendar
+    /// file, which can be imported into or subscribed to by most calendar apps
+    pub fn render_calendar(&self, options: &CalendarOptions) -> Result<String, CalendarError> {
+        let events = self.calendar_events(options)?;
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let stamp = format!(
+            "{}T{:02}{:02}{:02}Z",
+            Date::from_days(now / 86400).to_ics(),
+            now % 86400 / 3600,
+            now % 3600 / 60,
+            now % 60
+        );
+        Ok(to_ics(
+            &self.name,
+            &events,
+            options.timezone.as_deref(),
+            &stamp,
+        ))
+    }
+}
+
+/// Render `events` as an iCalendar file, with `stamp` as the time of creation
+fn to_ics(name: &str, events: &[Event], timezone: Option<&str>, stamp: &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//teach-rs//modmod//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+    if let Some(timezone) = timezone {
+        lines.push(format!("X-WR-TIMEZONE:{timezone}"));
+    }
+    let track_tag = to_tag(name);
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@{track_tag}.modmod", event.id));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        match event.time {
+            EventTime::Timed { date, start, end } => {
+                let zone = timezone.map(|tz| format!(";TZID={tz}")).unwrap_or_default();
+                let time = |minutes: u32| format!("T{:02}{:02}00", minutes / 60, minutes % 60);
+                lines.push(format!("DTSTART{zone}:{}{}", date.to_ics(), time(start)));
+                lines.push(format!("DTEND{zone}:{}{}", date.to_ics(), time(end)));
+            }
+            EventTime::AllDay { first, last } => {
+                // The end of an all-day event is exclusive
+                lines.push(format!("DTSTART;VALUE=DATE:{}", first.to_ics()));
+                lines.push(format!("DTEND;VALUE=DATE:{}", last.next_day().to_ics()));
+                lines.push("TRANSP:TRANSPARENT".to_string());
+            }
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if !event.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&event.description)));
+        }
+        if let Some(url) = &event.url {
+            lines.push(format!("URL:{url}"));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&fold(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Split `line` into lines of at most 75 bytes, as iCalendar requires. Continuation lines
+/// start with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded
+}
+
+/// Lays out the scheduled units of a track in the time slots of a course run, while
+/// walking over the track
+struct CalendarLayout<'o> {
+    options: &'o CalendarOptions,
+    book_url: Option<&'o str>,
+    events: Vec<Event>,
+    /// The day the next unit starts on
+    date: Date,
+    /// The day the module that is being visited starts on, once it has a scheduled unit
+    first: Option<Date>,
+    /// The slot and the time the next unit starts at
+    slot_i: usize,
+    time: u32,
+}
+
+impl<'t> Visitor<'t> for CalendarLayout<'_> {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.first = None;
+        self.slot_i = 0;
+        self.time = self.options.slots[0].start;
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        let options = self.options;
+        if !options.filter.includes(module, unit) || unit.duration().minutes == 0 {
+            return Ok(());
+        }
+        self.first.get_or_insert(self.date);
+
+        let number = position.unit_number().unwrap();
+        let mut parts = vec![];
+        let mut remaining = unit.duration().minutes;
+        while remaining > 0 {
+            if self.time >= options.slots[self.slot_i].end {
+                self.slot_i += 1;
+                if self.slot_i == options.slots.len() {
+                    self.slot_i = 0;
+                    self.date = options.teaching_day(self.date.next_day());
+                }
+                self.time = options.slots[self.slot_i].start;
+                continue;
+            }
+            let end = options.slots[self.slot_i].end.min(self.time + remaining);
+            parts.push(EventTime::Timed {
+                date: self.date,
+                start: self.time,
+                end,
+            });
+            remaining -= end - self.time;
+            self.time = end;
+        }
+
+        let url = self.book_url.map(|url| {
+            let section = section_file_name(&unit.data.name).with_extension("html");
+            format!("{url}/{}", section.to_string_lossy())
+        });
+        let mut description = format!("Module {}: {}", module.index, module.data.name);
+        if let Some(duration) = unit.data.duration {
+            write!(description, "\n{duration} of teaching").unwrap();
+        }
+        let exercises = unit.exercises_duration();
+        if exercises.minutes > 0 {
+            write!(description, "\n{exercises} of exercises").unwrap();
+        }
+        if let Some(url) = &url {
+            write!(description, "\n\n{url}").unwrap();
+        }
+        let part_count = parts.len();
+        for (time, part) in parts.into_iter().zip(1..) {
+            let mut summary = format!("{number} {}", unit.data.name);
+            if part_count > 1 {
+                write!(summary, " ({part}/{part_count})").unwrap();
+            }
+            self.events.push(Event {
+                id: format!("unit-{}-{}-{part}", number.module, number.unit),
+                summary,
+                description: description.clone(),
+                url: url.clone(),
+                time,
+            });
+        }
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        // Modules without scheduled units take no time
+        let Some(first) = self.first else {
+            return Ok(());
+        };
+        self.events.push(Event {
+            id: format!("module-{}", module.index),
+            summary: format!("Module {}: {}", module.index, module.data.name),
+            description: String::new(),
+            url: self.book_url.map(str::to_string),
+            time: EventTime::AllDay {
+                first,
+                last: self.date,
+            },
+        });
+        self.date = self.options.teaching_day(self.date.next_day());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_and_slots_are_parsed() {
+        let date = |s: &str| s.parse::<Date>();
+        assert_eq!(
+            date("2026-11-02"),
+            Ok(Date {
+                year: 2026,
+                month: 11,
+                day: 2
+            })
+        );
+        assert!(date("2026-02-29").is_err());
+        assert!(date("2028-02-29").is_ok());
+        assert!(date("2026-13-01").is_err());
+        assert!(date("2 November").is_err());
+        assert_eq!(date("1970-01-01").unwrap().days(), 0);
+        assert_eq!(
+            date("2026-12-31").unwrap().next_day().to_string(),
+            "2027-01-01"
+        );
+        // 2026-11-07 is a Saturday
+        assert!(!date("2026-11-06").unwrap().is_weekend());
+        assert!(date("2026-11-07").unwrap().is_weekend());
+        assert!(date("2026-11-08").unwrap().is_weekend());
+
+        assert_eq!(
+            "09:00-12:30".parse(),
+            Ok(TimeSlot {
+                start: 540,
+                end: 750
+            })
+        );
+        assert!("12:30-09:00".parse::<TimeSlot>().is_err());
+        assert!("9-12".parse::<TimeSlot>().is_err());
+
+        let events = [Event {
+            id: "unit-1-1-1".to_string(),
+            summary: "1.1 Basic syntax, part one".to_string(),
+            description: "Module 1: Foundations\n\nhttps://example.com/basic-syntax.html"
+                .to_string(),
+            url: None,
+            time: EventTime::Timed {
+                date: date("2026-11-02").unwrap(),
+                start: 540,
+                end: 585,
+            },
+        }];
+        let ics = to_ics(
+            "Rust intro",
+            &events,
+            Some("Europe/Amsterdam"),
+            "20261014T120000Z",
+        );
+        assert!(ics.contains("UID:unit-1-1-1@rust-intro.modmod\r\n"));
+        assert!(ics.contains("DTSTART;TZID=Europe/Amsterdam:20261102T090000\r\n"));
+        assert!(ics.contains("SUMMARY:1.1 Basic syntax\\, part one\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+        assert!(ics.replace("\r\n ", "").contains(
+            "DESCRIPTION:Module 1: Foundations\\n\\nhttps://example.com/basic-syntax.html\r\n"
+        ));
+    }
+}
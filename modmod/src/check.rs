@@ -0,0 +1,1372 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::Deserialize;
+
+use crate::{
+    book::{EXERCISE_DIR_PLACEHOLDER, IMAGE_PLACEHOLDER},
+    exercises::included_files,
+    include::included_sources,
+    io::PathExt,
+    markdown::{blocks, code_blocks, inlines, links, local_path, Block, CodeBlock, Inline},
+    numbering::{section_file_name, UnitNumber},
+    tasks::{Task, TaskRunner, TaskStatus},
+    toolchain::RustEdition,
+    unicode::nfc,
+    visit::{Position, Visitor},
+    Exercise, Indexed, Module, Topic, Track, Unit,
+};
+
+const RUST_CHANGES: &str = include_str!("../include/check/rust-changes.toml");
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CheckError {}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to check track")
+    }
+}
+
+impl error_stack::Context for CheckError {}
+
+#[derive(Debug, Default)]
+pub struct CheckOptions {
+    /// Check that Rust code blocks in content are formatted according to the
+    /// `rustfmt.toml` in the track root, if any
+    pub rustfmt: bool,
+    /// Check that links in content, and exercise folder references in exercise
+    /// descriptions, point to something that exists in the rendered output
+    pub links: bool,
+    /// When checking links, also request the URLs of external links
+    pub external_links: bool,
+    /// Report Markdown files and exercise folders that no track in the track root uses
+    pub orphans: bool,
+    /// Report images without alt text, skipped heading levels, vague link text and tables
+    /// without headers in content
+    pub a11y: bool,
+    /// Runs rustfmt and the requests for external links
+    pub runner: TaskRunner,
+}
+
+/// An issue found while checking the track
+#[derive(Debug)]
+pub struct Finding {
+    pub path: PathBuf,
+    /// 1-based line number, if the finding relates to a specific line
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.to_string_lossy())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Track {
+    /// Run the checks enabled in `opts` over the track content
+    pub fn check(&self, opts: &CheckOptions) -> Result<Vec<Finding>, CheckError> {
+        let mut findings = vec![];
+
+        if opts.rustfmt {
+            let rustfmt = Rustfmt::new(&self.root, &opts.runner);
+            let mut editions = CodeEditions::default();
+            self.walk_infallible(&mut editions);
+            for (path, edition) in editions.files {
+                let content = path.read_to_string()?;
+                let edition = edition.unwrap_or(RustEdition::E2021);
+                for block in code_blocks(&content).iter().filter(|b| b.lang() == "rust") {
+                    if !rustfmt.is_formatted(block, edition)? {
+                        findings.push(Finding {
+                            path: path.to_path_buf(),
+                            line: Some(block.line),
+                            message: "Rust code block is not formatted according to rustfmt"
+                                .to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        if opts.links {
+            let runner = opts.external_links.then_some(&opts.runner);
+            findings.extend(self.link_findings(runner)?);
+        }
+
+        if opts.orphans {
+            findings.extend(self.orphan_findings()?);
+        }
+
+        if opts.a11y {
+            findings.extend(self.a11y_findings()?);
+        }
+
+        findings.extend(self.prerequisite_findings());
+        findings.extend(self.output_path_findings()?);
+        findings.extend(self.normalization_findings()?);
+        findings.extend(self.edition_findings()?);
+
+        Ok(findings)
+    }
+
+    /// Report topics that are covered before their prerequisites,
+    /// or that require topics that are not part of the track
+    fn prerequisite_findings(&self) -> Vec<Finding> {
+        let graph = self.topic_graph();
+        let mut findings = vec![];
+        for (i, node) in graph.nodes.iter().enumerate() {
+            for req in node.topic.requires.iter() {
+                let message = match graph.position(req) {
+                    Some(req_i) if req_i < i => continue,
+                    Some(req_i) => format!(
+                        "Topic '{}' in unit {} requires '{req}', which is only covered later in unit {}",
+                        node.topic.name, node.unit, graph.nodes[req_i].unit
+                    ),
+                    None => format!(
+                        "Topic '{}' in unit {} requires '{req}', which is not part of the track",
+                        node.topic.name, node.unit
+                    ),
+                };
+                findings.push(Finding {
+                    path: node.topic.content.clone(),
+                    line: None,
+                    message,
+                });
+            }
+        }
+        findings
+    }
+}
+
+impl Track {
+    /// Report prose that mentions another edition than the one set for its exercises, so
+    /// that courses covering several editions stay consistent, and exercises whose
+    /// toolchain predates their edition
+    fn edition_findings(&self) -> Result<Vec<Finding>, CheckError> {
+        let mut findings = vec![];
+        let mut check_prose = |path: &Path,
+                               edition: RustEdition,
+                               subject: &str|
+         -> Result<(), CheckError> {
+            let content = path.read_to_string()?;
+            for (line, mentioned) in mentioned_editions(&content) {
+                if mentioned != edition {
+                    findings.push(Finding {
+                        path: path.to_path_buf(),
+                        line: Some(line),
+                        message: format!(
+                            "Mentions the {mentioned} edition, but {subject} built with the {edition} edition"
+                        ),
+                    });
+                }
+            }
+            Ok(())
+        };
+        let mut prose = EditionProse::default();
+        self.walk_infallible(&mut prose);
+        for (path, edition, subject) in prose.files {
+            check_prose(path, edition, &subject)?;
+        }
+
+        for exercise in self.exercises() {
+            let (Some(edition), Some(release)) =
+                (exercise.toolchain.edition, exercise.toolchain.release())
+            else {
+                continue;
+            };
+            if release < edition.first_release() {
+                findings.push(Finding {
+                    path: exercise.source.path.clone(),
+                    line: exercise.source.line,
+                    message: format!(
+                        "Exercise '{}' is built with the {edition} edition, which needs Rust {} or later, but its toolchain is Rust {release}",
+                        exercise.name,
+                        edition.first_release()
+                    ),
+                });
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// The editions mentioned in the prose of `content`, like "the 2024 edition" or "edition
+/// 2021", along with the line they are mentioned on. Code blocks are left out.
+fn mentioned_editions(content: &str) -> Vec<(usize, RustEdition)> {
+    let mut mentions = vec![];
+    let mut in_code = false;
+    for (line, i) in content.lines().zip(1..) {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let words: Vec<_> = line
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        for pair in words.windows(2) {
+            let year = match pair {
+                [year, edition] | [edition, year] if edition.eq_ignore_ascii_case("edition") => {
+                    year
+                }
+                _ => continue,
+            };
+            if let Some(edition) = RustEdition::ALL
+                .into_iter()
+                .find(|e| e.to_string() == *year)
+            {
+                mentions.push((i, edition));
+            }
+        }
+    }
+    mentions
+}
+
+/// The Windows path length limit, `MAX_PATH`, which is still enforced by many tools
+const WINDOWS_MAX_PATH: usize = 260;
+/// Room left for the location of the output folder, e.g. `C:\Users\<name>\Documents\GitHub\<course>\`
+const OUTPUT_DIR_PATH_BUDGET: usize = 60;
+
+impl Track {
+    /// Report output paths that would collide on case-insensitive file systems, or that
+    /// would exceed the Windows path limit once the output folder is checked out somewhere.
+    /// Each finding points to a source file the output path is derived from.
+    fn output_path_findings(&self) -> Result<Vec<Finding>, CheckError> {
+        let graph = self
+            .dependency_graph(None)
+            .change_context(CheckError::default())?;
+        let inputs = graph.inputs();
+        let source_of = |path: &Path| {
+            inputs
+                .iter()
+                .find(|(artifact, _)| artifact.starts_with(path))
+                .and_then(|(_, sources)| sources.first())
+                .map(|source| source.to_path_buf())
+                .unwrap_or_else(|| self.root.clone())
+        };
+
+        let mut findings = vec![];
+        for (path, other) in case_collisions(inputs.keys().copied()) {
+            findings.push(Finding {
+                path: source_of(path),
+                line: None,
+                message: format!(
+                    "Output paths {} and {} only differ in case, so they collide on case-insensitive file systems like those of macOS and Windows",
+                    path.to_string_lossy(),
+                    other.to_string_lossy()
+                ),
+            });
+        }
+        for artifact in inputs.keys() {
+            let len = artifact.to_string_lossy().chars().count();
+            if len + OUTPUT_DIR_PATH_BUDGET > WINDOWS_MAX_PATH {
+                findings.push(Finding {
+                    path: source_of(artifact),
+                    line: None,
+                    message: format!(
+                        "Output path {} is {len} characters long, which exceeds the Windows limit of {WINDOWS_MAX_PATH} characters once the output folder is nested in a typical checkout path",
+                        artifact.to_string_lossy()
+                    ),
+                });
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// The content files of a track whose prose should mention the edition their exercises
+/// are built with, along with that edition and what is built with it
+#[derive(Default)]
+struct EditionProse<'t> {
+    /// The edition of the exercises of the unit that is being visited, if it sets one
+    unit: Option<(RustEdition, String)>,
+    files: Vec<(&'t Path, RustEdition, String)>,
+}
+
+impl<'t> Visitor<'t> for EditionProse<'t> {
+    type Error = Infallible;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.unit = unit.data.toolchain.edition.map(|edition| {
+            let number = position.unit_number().unwrap();
+            (edition, format!("the exercises of unit {number} are"))
+        });
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), Infallible> {
+        if let Some((edition, subject)) = &self.unit {
+            self.files
+                .push((&topic.data.content, *edition, subject.clone()));
+        }
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        let Some(edition) = exercise.data.toolchain.edition else {
+            return Ok(());
+        };
+        let subject = format!("exercise '{}' is", exercise.data.name);
+        for path in std::iter::once(&exercise.data.description).chain(&exercise.data.hints) {
+            self.files.push((path, edition, subject.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// The content files of a track, in the order of [`Track::content_files`], along with the
+/// edition their Rust code blocks are written in: the edition of the exercise for its
+/// description, and the edition of the exercises of the unit for the rest of the unit
+#[derive(Default)]
+struct CodeEditions<'t> {
+    /// The edition of the exercises of the unit that is being visited, if it sets one
+    unit: Option<RustEdition>,
+    files: Vec<(&'t Path, Option<RustEdition>)>,
+}
+
+impl<'t> Visitor<'t> for CodeEditions<'t> {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.files
+            .extend(module.data.intro.as_deref().map(|intro| (intro, None)));
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.unit = unit.data.toolchain.edition;
+        self.files
+            .extend(unit.data.intro.as_deref().map(|intro| (intro, self.unit)));
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), Infallible> {
+        self.files.push((&topic.data.content, self.unit));
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        self.files
+            .push((&exercise.data.description, exercise.data.toolchain.edition));
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Report links in content whose targets only match the name of an image after
+    /// Unicode normalization, typically because the image was created on macOS.
+    /// The render normalizes both, but the links break when the content is viewed as is.
+    fn normalization_findings(&self) -> Result<Vec<Finding>, CheckError> {
+        let images: Vec<&Path> = self
+            .modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .flat_map(|u| &u.data.topics)
+            .flat_map(|t| {
+                t.data.images.iter().chain(
+                    t.data
+                        .exercises
+                        .iter()
+                        .flat_map(|e| &e.data.description_images),
+                )
+            })
+            .map(PathBuf::as_path)
+            .collect();
+
+        // Topics can be reused across units, but their content only needs checking once
+        let content_files: BTreeSet<_> = self.content_files().collect();
+        let mut findings = vec![];
+        for path in content_files {
+            let content = path.read_to_string()?;
+            for (line, target) in link_targets(&content) {
+                let Some(name) = Path::new(target).file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                let mismatch = images.iter().find(|image| {
+                    image
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|image_name| {
+                            image_name != name && nfc(image_name) == nfc(name)
+                        })
+                });
+                if let Some(image) = mismatch {
+                    findings.push(Finding {
+                        path: path.to_path_buf(),
+                        line: Some(line),
+                        message: format!(
+                            "Link to '{target}' only matches {} after Unicode normalization, so it breaks when the repository moves between operating systems",
+                            image.to_string_lossy()
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(findings)
+    }
+}
+
+impl Track {
+    /// Report dead links in content: links between book sections, and to headings in them,
+    /// that don't resolve, links to files that don't exist, and `#[modmod:exercise_dir]`
+    /// references to files that are not included in the exercise. If `external` is set,
+    /// it requests the URLs of external links as well, which needs `curl`.
+    fn link_findings(&self, external: Option<&TaskRunner>) -> Result<Vec<Finding>, CheckError> {
+        let mut linking = LinkingContent::default();
+        self.walk(&mut linking)?;
+        let LinkingContent {
+            sections, contents, ..
+        } = linking;
+        // Slidev serves the topic images from the root of the deck
+        let slide_images: BTreeSet<_> = self
+            .modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .flat_map(|u| &u.data.topics)
+            .flat_map(|t| &t.data.images)
+            .filter_map(|i| i.file_name())
+            .collect();
+
+        let mut external_links: BTreeMap<String, (PathBuf, usize)> = BTreeMap::new();
+        let mut findings = vec![];
+        let mut dead_link = |path: &Path, line, message| {
+            findings.push(Finding {
+                path: path.to_path_buf(),
+                line: Some(line),
+                message,
+            })
+        };
+        for (path, exercise, section) in contents {
+            let anchors = &sections[&section];
+            let content = path.read_to_string()?;
+            let dir = path.parent().unwrap();
+            for (line, target) in links(&content) {
+                if target.starts_with("http://") || target.starts_with("https://") {
+                    external_links
+                        .entry(target.to_string())
+                        .or_insert_with(|| (path.to_path_buf(), line));
+                    continue;
+                }
+                let Some(exercise) = exercise else {
+                    // Slides
+                    if let Some(image) = target.strip_prefix("/images/") {
+                        if !slide_images.contains(std::ffi::OsStr::new(image)) {
+                            dead_link(
+                                path,
+                                line,
+                                format!("Link to '{target}' doesn't match any of the topic images"),
+                            );
+                        }
+                    } else if let Some(local) = local_path(target) {
+                        if !dir.join(local).exists() {
+                            dead_link(
+                                path,
+                                line,
+                                format!("Link to '{target}' points to a file that doesn't exist"),
+                            );
+                        }
+                    }
+                    continue;
+                };
+                if let Some(image) = target.strip_prefix(IMAGE_PLACEHOLDER) {
+                    let image = image.trim_start_matches('/');
+                    if !exercise.path.join("images").join(image).is_file() {
+                        dead_link(
+                            path,
+                            line,
+                            format!("Link to '{target}' points to an image that doesn't exist in the images folder of the exercise"),
+                        );
+                    }
+                } else if let Some(anchor) = target.strip_prefix('#') {
+                    if !anchors.contains(anchor) {
+                        dead_link(
+                            path,
+                            line,
+                            format!(
+                                "Link to '{target}' doesn't match any heading in the book section"
+                            ),
+                        );
+                    }
+                } else if let Some(local) = local_path(target) {
+                    if local.ends_with(".md") {
+                        let section = sections.get(Path::new(local));
+                        let anchor = target.split_once('#').map(|(_, anchor)| anchor);
+                        let message = match (section, anchor) {
+                            (None, _) => {
+                                format!("Link to '{target}' doesn't point to a section of the book")
+                            }
+                            (Some(anchors), Some(anchor)) if !anchors.contains(anchor) => {
+                                format!("Link to '{target}' doesn't match any heading in that book section")
+                            }
+                            _ => continue,
+                        };
+                        dead_link(path, line, message);
+                    } else if !dir.join(local).exists() {
+                        dead_link(
+                            path,
+                            line,
+                            format!("Link to '{target}' points to a file that doesn't exist"),
+                        );
+                    }
+                }
+            }
+
+            if let Some(exercise) = exercise {
+                for (line, message) in exercise_dir_findings(&content, exercise)? {
+                    dead_link(path, line, message);
+                }
+            }
+        }
+
+        if let Some(runner) = external {
+            let curl = Curl::new(runner)?;
+            let urls: Vec<_> = external_links.keys().cloned().collect();
+            for (url, problem) in urls.iter().zip(curl.problems(&urls)) {
+                if let Some(problem) = problem {
+                    let (path, line) = &external_links[url];
+                    dead_link(path, *line, format!("Link to {url} is dead: {problem}"));
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+/// The content of a track that links can be in, along with the anchors mdBook generates for
+/// the headings in every book section
+#[derive(Default)]
+struct LinkingContent<'t> {
+    /// The anchors of each book section, by section file name
+    sections: BTreeMap<PathBuf, BTreeSet<String>>,
+    /// Topic content, exercise descriptions and hints, along with their exercise and the
+    /// file name of their book section
+    contents: Vec<(&'t Path, Option<&'t Exercise>, PathBuf)>,
+    /// Topics can be reused across units, but are only checked for the first one
+    checked: BTreeSet<&'t Path>,
+    /// The file name and the headings of the book section of the unit that is being visited
+    section: PathBuf,
+    headings: Vec<String>,
+}
+
+impl<'t> LinkingContent<'t> {
+    fn push(&mut self, path: &'t Path, exercise: Option<&'t Exercise>) {
+        if self.checked.insert(path) {
+            self.contents.push((path, exercise, self.section.clone()));
+        }
+    }
+}
+
+impl<'t> Visitor<'t> for LinkingContent<'t> {
+    type Error = CheckError;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), CheckError> {
+        let number = position.unit_number().unwrap();
+        self.section = section_file_name(&unit.data.name);
+        self.headings = vec![format!("Unit {number} - {}", unit.data.name)];
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), CheckError> {
+        self.push(&topic.data.content, None);
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), CheckError> {
+        let data = &exercise.data;
+        self.push(&data.description, Some(data));
+        for hint in data.hints.iter() {
+            self.push(hint, Some(data));
+        }
+
+        self.headings.push(format!(
+            "Exercise {}: {}",
+            position.exercise_number().unwrap(),
+            data.name
+        ));
+        let description = data.description.read_to_string()?;
+        self.headings.extend(
+            blocks(&description)
+                .into_iter()
+                .filter_map(|block| match block {
+                    Block::Heading { text, .. } => Some(heading_text(&inlines(&text))),
+                    _ => None,
+                }),
+        );
+        Ok(())
+    }
+
+    fn leave_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), CheckError> {
+        let mut headings = std::mem::take(&mut self.headings);
+        if !unit.data.quizzes.is_empty() {
+            headings.push("Quiz".to_string());
+        }
+
+        // mdBook makes anchors unique by numbering the ones that are used more than once
+        let mut anchors = BTreeSet::new();
+        for heading in headings {
+            let anchor = anchor(&heading);
+            let unique = (1..)
+                .map(|i| format!("{anchor}-{i}"))
+                .find(|a| !anchors.contains(a))
+                .unwrap();
+            if !anchors.insert(anchor) {
+                anchors.insert(unique);
+            }
+        }
+        self.sections.insert(self.section.clone(), anchors);
+        Ok(())
+    }
+}
+
+/// The anchor mdBook generates for a heading with `text`
+pub(crate) fn anchor(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_ascii_lowercase())
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn heading_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(t) | Inline::Code(t) => text.push_str(t),
+            Inline::Strong(inner) | Inline::Emphasis(inner) => text.push_str(&heading_text(inner)),
+            Inline::Link { label, .. } => text.push_str(&heading_text(label)),
+            Inline::Image { alt, .. } => text.push_str(alt),
+        }
+    }
+    text
+}
+
+/// Report the `#[modmod:exercise_dir]` references in the description of `exercise` to
+/// files or folders that are not included in the exercise. Files created by the generation
+/// step of the exercise are not known up front, so missing ones are not reported.
+fn exercise_dir_findings(
+    content: &str,
+    exercise: &Exercise,
+) -> Result<Vec<(usize, String)>, CheckError> {
+    let included =
+        included_files(&exercise.path, &exercise.includes).change_context(CheckError::default())?;
+    let mut findings = vec![];
+    for (text, line) in content.lines().zip(1..) {
+        for (start, _) in text.match_indices(EXERCISE_DIR_PLACEHOLDER) {
+            let rest = &text[start + EXERCISE_DIR_PLACEHOLDER.len()..];
+            let end = rest
+                .find(|c: char| c.is_whitespace() || "`'\")>]".contains(c))
+                .unwrap_or(rest.len());
+            let reference = rest[..end].trim_matches('/');
+            if reference.is_empty() {
+                continue;
+            }
+            let path = exercise.path.join(reference);
+            let is_included = included.iter().any(|f| f.starts_with(&path));
+            if is_included || (!path.exists() && exercise.generate.is_some()) {
+                continue;
+            }
+            let problem = match path.exists() {
+                true => "is not included in the exercise",
+                false => "doesn't exist",
+            };
+            findings.push((
+                line,
+                format!(
+                    "Reference to '{}' in the exercise folder {problem}",
+                    &rest[..end]
+                ),
+            ));
+        }
+    }
+    Ok(findings)
+}
+
+/// Requests external URLs using `curl`
+pub(crate) struct Curl<'r> {
+    runner: &'r TaskRunner,
+}
+
+impl<'r> Curl<'r> {
+    pub(crate) fn new(runner: &'r TaskRunner) -> Result<Self, CheckError> {
+        let output = runner.run_one(Task::new("curl --version", "curl").arg("--version"));
+        if let TaskStatus::Failed(error) = output.status {
+            return Err(Report::new(CheckError::default())
+                .attach_printable(error)
+                .attach_printable("Unable to run curl, which is needed to check external links. Make sure it is installed"));
+        }
+        Ok(Self { runner })
+    }
+
+    /// What's wrong with each of `urls`, if it can't be retrieved. Servers that don't
+    /// support `HEAD` requests get a regular `GET` request.
+    pub(crate) fn problems(&self, urls: &[String]) -> Vec<Option<String>> {
+        let mut statuses = self.statuses(urls.iter(), true);
+        let refused: Vec<_> = (0..urls.len()).filter(|i| statuses[*i] >= 400).collect();
+        let retried = self.statuses(refused.iter().map(|i| &urls[*i]), false);
+        for (i, status) in refused.into_iter().zip(retried) {
+            statuses[i] = status;
+        }
+        statuses
+            .into_iter()
+            .map(|status| match status {
+                0 => Some("it could not be reached".to_string()),
+                400.. => Some(format!("the server responded with HTTP status {status}")),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Retrieve each of `urls`, returning the HTTP status the server responds with after
+    /// redirects, or 0 if there is no response, and the body of the response
+    pub(crate) fn fetch(&self, urls: &[String]) -> Vec<(u32, Vec<u8>)> {
+        let tasks = urls
+            .iter()
+            .map(|url| {
+                Task::new(format!("curl {url}"), "curl")
+                    .args(["--silent", "--location", "--max-time", "15"])
+                    .args(["--write-out", "\n%{http_code}"])
+                    .arg(url)
+            })
+            .collect();
+        self.runner
+            .run(tasks)
+            .into_iter()
+            .map(|output| {
+                let mut body = output.stdout;
+                let split = body.iter().rposition(|b| *b == b'\n').unwrap_or(0);
+                let status = String::from_utf8_lossy(&body[split..]).trim().parse();
+                body.truncate(split);
+                (status.unwrap_or(0), body)
+            })
+            .collect()
+    }
+
+    /// The HTTP status each server responds with after redirects, or 0 if there is no response
+    fn statuses<'u>(&self, urls: impl Iterator<Item = &'u String>, head: bool) -> Vec<u32> {
+        let tasks = urls
+            .map(|url| {
+                let task = Task::new(format!("curl {url}"), "curl")
+                    .args(["--silent", "--location", "--max-time", "15"])
+                    .args(["--output", if cfg!(windows) { "NUL" } else { "/dev/null" }])
+                    .args(["--write-out", "%{http_code}"]);
+                match head {
+                    true => task.arg("--head"),
+                    false => task,
+                }
+                .arg(url)
+            })
+            .collect();
+        self.runner
+            .run(tasks)
+            .into_iter()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+impl Track {
+    /// Report the Markdown files and exercise folders under the track root that are not
+    /// used by this track, nor by any of the other track TOMLs next to it. Translations of
+    /// used files and files included into them count as used, and READMEs, which document
+    /// folders, are left alone.
+    fn orphan_findings(&self) -> Result<Vec<Finding>, CheckError> {
+        let mut tracks = vec![];
+        for file in self
+            .root
+            .read_dir()
+            .into_report()
+            .change_context(CheckError::default())?
+        {
+            let path = file
+                .into_report()
+                .change_context(CheckError::default())?
+                .path();
+            let is_track = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n == "track.toml" || n.ends_with(".track.toml"));
+            if is_track {
+                tracks.push(
+                    Track::load(&path)
+                        .change_context(CheckError::default())
+                        .attach_printable_lazy(|| {
+                            format!(
+                                "Unable to load {} to find the content it uses",
+                                path.to_string_lossy()
+                            )
+                        })?,
+                );
+            }
+        }
+
+        let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        let mut used_files = BTreeSet::new();
+        let mut exercise_dirs = BTreeSet::new();
+        for track in tracks.iter().chain([self]) {
+            used_files.extend(track.content_files().map(canonical));
+            used_files.extend(track.slides.template.as_deref().map(canonical));
+            let mut templates = SlideTemplates::default();
+            track.walk_infallible(&mut templates);
+            used_files.extend(templates.0.into_iter().map(canonical));
+            let mut included = IncludedSources::default();
+            track.walk_infallible(&mut included);
+            used_files.extend(included.0.iter().map(|p| canonical(p)));
+            exercise_dirs.extend(track.exercises().map(|e| canonical(&e.path)));
+        }
+
+        let mut findings = vec![];
+        let mut pending = vec![canonical(&self.root)];
+        while let Some(dir) = pending.pop() {
+            let mut entries: Vec<_> = dir
+                .read_dir()
+                .into_report()
+                .change_context(CheckError::default())?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect();
+            entries.sort();
+            for path in entries.into_iter().rev() {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if path.is_dir() {
+                    // Hidden folders, build output and downloaded packages
+                    if name.starts_with('.') || name == "target" || name == "node_modules" {
+                        continue;
+                    }
+                    if exercise_dirs.contains(&path) {
+                        continue;
+                    }
+                    let in_exercises = dir.file_name().is_some_and(|n| n == "exercises");
+                    if in_exercises && !exercise_dirs.iter().any(|e| e.starts_with(&path)) {
+                        findings.push(Finding {
+                            path,
+                            line: None,
+                            message: "Exercise folder is not used by any track in the track folder"
+                                .to_string(),
+                        });
+                        continue;
+                    }
+                    pending.push(path);
+                } else if name.ends_with(".md")
+                    && name != "README.md"
+                    && !used_files.contains(&path)
+                    && !is_translation_of_any(&path, &used_files)
+                {
+                    findings.push(Finding {
+                        path,
+                        line: None,
+                        message: "Markdown file is not used by any track in the track folder"
+                            .to_string(),
+                    });
+                }
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// The slide templates of the modules and units of a track
+#[derive(Default)]
+struct SlideTemplates<'t>(Vec<&'t Path>);
+
+impl<'t> Visitor<'t> for SlideTemplates<'t> {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.0.extend(module.data.slides.template.as_deref());
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.0.extend(unit.data.template.as_deref());
+        Ok(())
+    }
+}
+
+/// The files included into the module and unit introductions and the exercise descriptions of a
+/// track. Files that can't be read are left to the other checks.
+#[derive(Default)]
+struct IncludedSources(Vec<PathBuf>);
+
+impl IncludedSources {
+    fn add(&mut self, file: &Path, base_dir: &Path) {
+        let sources = file
+            .read_to_string::<CheckError>()
+            .and_then(|content| included_sources::<CheckError>(&content, base_dir));
+        self.0.extend(sources.unwrap_or_default());
+    }
+}
+
+impl<'t> Visitor<'t> for IncludedSources {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        if let Some(intro) = &module.data.intro {
+            self.add(intro, intro.parent().unwrap());
+        }
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        if let Some(intro) = &unit.data.intro {
+            self.add(intro, intro.parent().unwrap());
+        }
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        self.add(&exercise.data.description, &exercise.data.path);
+        Ok(())
+    }
+}
+
+/// Whether `path` is named like a translation of one of `files`, e.g. `slides.nl.md` for `slides.md`
+fn is_translation_of_any(path: &Path, files: &BTreeSet<PathBuf>) -> bool {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    let Some((original, _language)) = stem.rsplit_once('.') else {
+        return false;
+    };
+    let original = match path.extension() {
+        Some(extension) => format!("{original}.{}", extension.to_string_lossy()),
+        None => original.to_string(),
+    };
+    files.contains(&path.with_file_name(original))
+}
+
+/// A Rust release, like `1.80`. Patch versions are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RustVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl fmt::Display for RustVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for RustVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = s.split('.').map(str::parse);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => Ok(RustVersion { major, minor }),
+            _ => Err(format!(
+                "invalid Rust version '{s}', expected one like 1.80"
+            )),
+        }
+    }
+}
+
+/// A change in a Rust release that can make course material outdated, as listed in
+/// `include/check/rust-changes.toml`
+#[derive(Debug, Deserialize)]
+struct RustChange {
+    version: String,
+    title: String,
+    /// Text in content that is likely affected by the change
+    patterns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustChanges {
+    changes: Vec<RustChange>,
+}
+
+impl Track {
+    /// Report units whose content mentions a feature that changed in a recent Rust release,
+    /// going by a bundled list of notable changes. Only releases after `since` are considered,
+    /// if it is passed. These are advisories: the content may well be up to date.
+    pub fn rust_change_advisories(
+        &self,
+        since: Option<RustVersion>,
+    ) -> Result<Vec<Finding>, CheckError> {
+        let RustChanges { changes } = toml::from_str(RUST_CHANGES).unwrap();
+        let changes: Vec<_> = changes
+            .iter()
+            .filter(|c| {
+                since.is_none_or(|since| c.version.parse::<RustVersion>().is_ok_and(|v| v > since))
+            })
+            .collect();
+
+        let mut content = UnitContent::default();
+        self.walk_infallible(&mut content);
+        let mut findings = vec![];
+        for (path, number) in content.files {
+            let content = path.read_to_string()?;
+            for change in changes.iter() {
+                let Some((line, pattern)) = find_pattern(&content, &change.patterns) else {
+                    continue;
+                };
+                findings.push(Finding {
+                    path: path.to_path_buf(),
+                    line: Some(line),
+                    message: format!(
+                        "Unit {number} mentions `{pattern}`, which may be outdated since Rust {}: {}",
+                        change.version, change.title
+                    ),
+                });
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// The topic content and exercise descriptions of a track, along with the number of their
+/// unit. Topics can be reused across units, but are only listed for the first one.
+#[derive(Default)]
+struct UnitContent<'t> {
+    files: Vec<(&'t Path, UnitNumber)>,
+    listed: BTreeSet<&'t Path>,
+}
+
+impl<'t> Visitor<'t> for UnitContent<'t> {
+    type Error = Infallible;
+
+    fn visit_topic(
+        &mut self,
+        position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), Infallible> {
+        if self.listed.insert(&topic.data.content) {
+            self.files
+                .push((&topic.data.content, position.unit_number().unwrap()));
+        }
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        if self.listed.insert(&exercise.data.description) {
+            self.files
+                .push((&exercise.data.description, position.unit_number().unwrap()));
+        }
+        Ok(())
+    }
+}
+
+/// The 1-based line number of the first occurrence of any of `patterns` in `content`,
+/// along with the pattern
+fn find_pattern<'p>(content: &str, patterns: &'p [String]) -> Option<(usize, &'p str)> {
+    content.lines().zip(1..).find_map(|(text, line)| {
+        patterns
+            .iter()
+            .find(|p| text.contains(p.as_str()))
+            .map(|p| (line, p.as_str()))
+    })
+}
+
+/// The targets of the Markdown links and images in `content`, with their 1-based line numbers
+fn link_targets(content: &str) -> Vec<(usize, &str)> {
+    let mut targets = vec![];
+    for (text, line) in content.lines().zip(1..) {
+        let mut rest = text;
+        while let Some(start) = rest.find("](") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find(')') else {
+                break;
+            };
+            let target = rest[..end]
+                .split([' ', '#', '?'])
+                .next()
+                .unwrap_or_default();
+            if !target.is_empty() {
+                targets.push((line, target));
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    targets
+}
+
+/// Pairs of distinct paths, files or the directories containing them, that are equal when ignoring case
+fn case_collisions<'p>(paths: impl Iterator<Item = &'p Path>) -> Vec<(&'p Path, &'p Path)> {
+    let mut by_lowercase: BTreeMap<String, &Path> = BTreeMap::new();
+    let mut collisions = vec![];
+    for path in paths {
+        for ancestor in path.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+            let lowercase = ancestor.to_string_lossy().to_lowercase();
+            match by_lowercase.get(&lowercase) {
+                Some(&existing) if existing != ancestor => {
+                    if !collisions.contains(&(existing, ancestor)) {
+                        collisions.push((existing, ancestor));
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    by_lowercase.insert(lowercase, ancestor);
+                }
+            }
+        }
+    }
+    collisions
+}
+
+struct Rustfmt<'r> {
+    config_path: Option<PathBuf>,
+    runner: &'r TaskRunner,
+}
+
+impl<'r> Rustfmt<'r> {
+    fn new(track_root: &Path, runner: &'r TaskRunner) -> Self {
+        let config_path = ["rustfmt.toml", ".rustfmt.toml"]
+            .into_iter()
+            .map(|name| track_root.join(name))
+            .find(|p| p.is_file());
+        Self {
+            config_path,
+            runner,
+        }
+    }
+
+    /// Whether the code in `block`, written in `edition`, is left untouched by rustfmt.
+    /// Snippets consisting of statements rather than items are formatted as the body of a
+    /// function. Snippets that can't be parsed by rustfmt are not reported.
+    fn is_formatted(&self, block: &CodeBlock, edition: RustEdition) -> Result<bool, CheckError> {
+        let code = block.code.trim();
+        if let Some(formatted) = self.format(code, edition)? {
+            return Ok(formatted.trim() == code);
+        }
+
+        let wrapped = format!(
+            "fn main() {{\n{}\n}}\n",
+            code.lines()
+                .map(|l| if l.is_empty() {
+                    String::new()
+                } else {
+                    format!("    {l}")
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        let Some(formatted) = self.format(&wrapped, edition)? else {
+            return Ok(true);
+        };
+        Ok(formatted.trim() == wrapped.trim())
+    }
+
+    /// Format `code`, written in `edition`, with rustfmt, returning `None` if rustfmt could not parse it
+    fn format(&self, code: &str, edition: RustEdition) -> Result<Option<String>, CheckError> {
+        let mut task = Task::new("rustfmt", "rustfmt")
+            .args(["--emit", "stdout", "--edition"])
+            .arg(edition.to_string())
+            .stdin(code)
+            .no_retry();
+        if let Some(config_path) = &self.config_path {
+            task = task.arg("--config-path").arg(config_path);
+        }
+        let output = self.runner.run_one(task);
+
+        // Code that rustfmt can't parse makes it exit unsuccessfully
+        if !matches!(output.status, TaskStatus::Exited(_)) {
+            return output
+                .into_result()
+                .attach_printable("Unable to run rustfmt. Make sure it is installed")
+                .change_context(CheckError::default())
+                .map(|_| None);
+        }
+        if !output.success() {
+            return Ok(None);
+        }
+        String::from_utf8(output.stdout)
+            .map(Some)
+            .map_err(|e| Report::new(CheckError::default()).attach_printable(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_differing_in_case_collide() {
+        let paths = [
+            Path::new("exercises/1-a/README.md"),
+            Path::new("exercises/1-a/readme.md"),
+            Path::new("book/src/images/Diagram/x.svg"),
+            Path::new("book/src/images/diagram/y.svg"),
+            Path::new("book/src/images/diagram/z.svg"),
+        ];
+        assert_eq!(
+            case_collisions(paths.into_iter()),
+            [
+                (paths[0], paths[1]),
+                (
+                    Path::new("book/src/images/Diagram"),
+                    Path::new("book/src/images/diagram")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn editions_are_found_in_prose() {
+        let content = "Since the 2024 edition, `gen` is reserved.\n\
+            ```rust\n\
+            // edition 2015\n\
+            ```\n\
+            Edition 2021 closures capture disjoint fields, unlike in 2018.\n";
+        assert_eq!(
+            mentioned_editions(content),
+            [(1, RustEdition::E2024), (5, RustEdition::E2021)]
+        );
+    }
+
+    #[test]
+    fn anchors_match_those_of_mdbook() {
+        assert_eq!(
+            anchor("Exercise 2.1.1: Basic Syntax"),
+            "exercise-211-basic-syntax"
+        );
+        assert_eq!(
+            anchor(&heading_text(&inlines(
+                "2.1.1.A *Using* `Option`s & [links](x.md)"
+            ))),
+            "211a-using-options--links"
+        );
+    }
+
+    #[test]
+    fn translations_of_used_files_are_recognized() {
+        let used = BTreeSet::from([PathBuf::from("/c/topics/a/slides.md")]);
+        assert!(is_translation_of_any(
+            Path::new("/c/topics/a/slides.nl.md"),
+            &used
+        ));
+        assert!(!is_translation_of_any(
+            Path::new("/c/topics/a/slides.md"),
+            &used
+        ));
+        assert!(!is_translation_of_any(
+            Path::new("/c/topics/b/slides.nl.md"),
+            &used
+        ));
+    }
+
+    #[test]
+    fn bundled_rust_changes_are_in_release_order() {
+        let RustChanges { changes } = toml::from_str(RUST_CHANGES).unwrap();
+        let versions: Vec<RustVersion> =
+            changes.iter().map(|c| c.version.parse().unwrap()).collect();
+        assert!(versions.windows(2).all(|w| w[0] <= w[1]));
+        assert!(changes.iter().all(|c| !c.patterns.is_empty()));
+        assert_eq!(
+            find_pattern(
+                "use std::sync::Mutex;\nlazy_static! {",
+                &changes[5].patterns
+            ),
+            Some((2, "lazy_static"))
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use indoc::indoc;
+use std::{fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use error_stack::Result;
+
+use crate::io::{PathExt, WriteExt};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderCiError {}
+
+impl fmt::Display for RenderCiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render CI workflow")
+    }
+}
+
+impl error_stack::Context for RenderCiError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CiProvider {
+    #[default]
+    Github,
+    Gitlab,
+}
+
+/// CI configuration as defined in the `[ci]` table of the track TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CiConfig {
+    #[serde(default)]
+    pub provider: CiProvider,
+    #[serde(default = "crate::ci::default_toolchains")]
+    pub toolchains: Vec<String>,
+}
+
+pub fn default_toolchains() -> Vec<String> {
+    vec!["stable".to_string()]
+}
+
+impl CiConfig {
+    /// Write the CI workflow that builds and tests each of the exercise crates
+    /// into `out_dir`. Exercise directories are expected to be relative to `out_dir`.
+    pub fn render<P: AsRef<Path>>(
+        &self,
+        exercise_dirs: &[P],
+        out_dir: impl AsRef<Path>,
+    ) -> Result<(), RenderCiError> {
+        let out_dir = out_dir.as_ref();
+        let toolchains = yaml_list(&self.toolchains);
+        let exercises = yaml_list(
+            &exercise_dirs
+                .iter()
+                .map(|d| d.as_ref().to_string_lossy())
+                .collect::<Vec<_>>(),
+        );
+
+        let (workflow_path, workflow) = match self.provider {
+            CiProvider::Github => (
+                out_dir
+                    .join(".github")
+                    .join("workflows")
+                    .join("exercises.yml"),
+                format!(
+                    indoc! {r#"
+                        name: Exercises
+                        on: [push, pull_request]
+                        jobs:
+                          exercises:
+                            runs-on: ubuntu-latest
+                            strategy:
+                              fail-fast: false
+                              matrix:
+                                toolchain: {toolchains}
+                                exercise: {exercises}
+                            defaults:
+                              run:
+                                working-directory: ${{{{ matrix.exercise }}}}
+                            steps:
+                              - uses: actions/checkout@v4
+                              - uses: dtolnay/rust-toolchain@master
+                                with:
+                                  toolchain: ${{{{ matrix.toolchain }}}}
+                              - run: cargo build --all-targets
+                              - run: cargo test
+                    "#},
+                    toolchains = toolchains,
+                    exercises = exercises,
+                ),
+            ),
+            CiProvider::Gitlab => (
+                out_dir.join(".gitlab-ci.yml"),
+                format!(
+                    indoc! {r#"
+                        exercises:
+                          image: rust:latest
+                          parallel:
+                            matrix:
+                              - TOOLCHAIN: {toolchains}
+                                EXERCISE: {exercises}
+                          before_script:
+                            - rustup toolchain install "$TOOLCHAIN"
+                            - rustup default "$TOOLCHAIN"
+                          script:
+                            - cd "$EXERCISE"
+                            - cargo build --all-targets
+                            - cargo test
+                    "#},
+                    toolchains = toolchains,
+                    exercises = exercises,
+                ),
+            ),
+        };
+
+        workflow_path.parent().unwrap().create_dir_all()?;
+        workflow_path.create_file()?.write_all(workflow)?;
+
+        Ok(())
+    }
+}
+
+/// Render a flow-style YAML list. JSON strings are valid YAML scalars,
+/// so we let serde_json take care of quoting and escaping.
+fn yaml_list<S: AsRef<str>>(items: &[S]) -> String {
+    let items: Vec<_> = items
+        .iter()
+        .map(|i| serde_json::to_string(i.as_ref()).unwrap())
+        .collect();
+    format!("[{}]", items.join(", "))
+}
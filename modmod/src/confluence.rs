@@ -0,0 +1,350 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::Result;
+use serde::Serialize;
+
+use crate::{
+    book::without_quizzes,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    markdown::{blocks, inlines, strip_comments, Block, Inline},
+    numbering::section_file_name,
+    to_prefixed_tag, to_tag,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderConfluenceError {}
+
+impl fmt::Display for RenderConfluenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render Confluence pages")
+    }
+}
+
+impl error_stack::Context for RenderConfluenceError {}
+
+/// Name of the folder in the output directory the Confluence pages are written to
+pub const CONFLUENCE_DIR: &str = "confluence";
+
+/// A page in the page tree written to `pages.json`. Paths are relative to the Confluence folder.
+#[derive(Debug, Serialize)]
+pub struct ConfluencePage {
+    /// Page titles must be unique within a Confluence space, so they include the module or unit number
+    pub title: String,
+    /// File holding the page body in Confluence storage format
+    pub body: PathBuf,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ConfluencePage>,
+}
+
+impl Track {
+    /// Convert the rendered book in `out_dir` into a page tree for Confluence: a page for the
+    /// course, with a child page per module, which in turn has a child page per unit `filter`
+    /// selects. The page bodies are written in Confluence storage format, and the tree and the
+    /// images to attach to each page are described in `pages.json`, for uploading through the
+    /// REST API. Quizzes need an mdBook preprocessor, so they are left out.
+    pub fn render_confluence(
+        &self,
+        out_dir: impl AsRef<Path>,
+        filter: &RenderFilter,
+    ) -> Result<(), RenderConfluenceError> {
+        let out_dir = out_dir.as_ref();
+        let book_src_dir = out_dir.join("book").join("src");
+        let confluence_dir = out_dir.join(CONFLUENCE_DIR);
+        let pages_dir = Path::new("pages");
+        confluence_dir.join(pages_dir).create_dir_all()?;
+
+        let mut pages = ConfluencePages {
+            filter,
+            book_src_dir: &book_src_dir,
+            confluence_dir: &confluence_dir,
+            pages_dir,
+            module_pages: vec![],
+            unit_pages: vec![],
+        };
+        self.walk(&mut pages)?;
+        let module_pages = pages.module_pages;
+
+        let mut body = String::from("<ul>\n");
+        for page in module_pages.iter() {
+            body.push_str(&format!("<li>{}</li>\n", page_link(&page.title)));
+        }
+        body.push_str("</ul>\n");
+        let body_path = pages_dir.join(to_tag(&self.name)).with_extension("xml");
+        confluence_dir
+            .join(&body_path)
+            .create_file()?
+            .write_all(body)?;
+        let course_page = ConfluencePage {
+            title: self.name.clone(),
+            body: body_path,
+            attachments: vec![],
+            children: module_pages,
+        };
+
+        confluence_dir
+            .join("pages.json")
+            .create_file()?
+            .write_all(serde_json::to_string_pretty(&course_page).unwrap() + "\n")?;
+
+        Ok(())
+    }
+}
+
+/// Writes the page bodies of the modules and units while walking over the track
+struct ConfluencePages<'p> {
+    /// The units that were rendered into the book
+    filter: &'p RenderFilter,
+    book_src_dir: &'p Path,
+    confluence_dir: &'p Path,
+    pages_dir: &'p Path,
+    module_pages: Vec<ConfluencePage>,
+    /// The pages of the units of the module that is being visited
+    unit_pages: Vec<ConfluencePage>,
+}
+
+impl<'t> Visitor<'t> for ConfluencePages<'_> {
+    type Error = RenderConfluenceError;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RenderConfluenceError> {
+        if !self.filter.includes(module, unit) {
+            return Ok(());
+        }
+        let number = position.unit_number().unwrap();
+        let section_path = self.book_src_dir.join(section_file_name(&unit.data.name));
+        let content = section_path.read_to_string()?;
+        let (body, images) = to_storage_format(&without_quizzes(&strip_comments(&content)));
+
+        let slug = number.deck_slug(&unit.data.name);
+        let mut attachments = vec![];
+        for image in images {
+            let attachment = Path::new("attachments")
+                .join(&slug)
+                .join(attachment_name(&image));
+            let dest = self.confluence_dir.join(&attachment);
+            dest.parent().unwrap().create_dir_all()?;
+            self.book_src_dir.join(&image).copy(dest)?;
+            attachments.push(attachment);
+        }
+
+        let body_path = self.pages_dir.join(&slug).with_extension("xml");
+        self.confluence_dir
+            .join(&body_path)
+            .create_file()?
+            .write_all(body)?;
+        self.unit_pages.push(ConfluencePage {
+            title: format!("{number} {}", unit.data.name),
+            body: body_path,
+            attachments,
+            children: vec![],
+        });
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), RenderConfluenceError> {
+        let unit_pages = std::mem::take(&mut self.unit_pages);
+        if unit_pages.is_empty() {
+            return Ok(());
+        }
+
+        let mut body = format!(
+            "<p>{}</p>\n<ul>\n",
+            storage_inline(&inlines(&module.data.description), &mut vec![])
+        );
+        for page in unit_pages.iter() {
+            body.push_str(&format!("<li>{}</li>\n", page_link(&page.title)));
+        }
+        body.push_str("</ul>\n");
+        let body_path = self
+            .pages_dir
+            .join(to_prefixed_tag(&module.data.name, module.index))
+            .with_extension("xml");
+        self.confluence_dir
+            .join(&body_path)
+            .create_file()?
+            .write_all(body)?;
+        self.module_pages.push(ConfluencePage {
+            title: format!("{} {}", module.index, module.data.name),
+            body: body_path,
+            attachments: vec![],
+            children: unit_pages,
+        });
+        Ok(())
+    }
+}
+
+/// Attachment names must be unique per page, so the exercise number in the image path is kept,
+/// e.g. `2-1-3-diagram.svg` for `images/2/1/3/diagram.svg`
+fn attachment_name(image: &str) -> String {
+    image.trim_start_matches("images/").replace('/', "-")
+}
+
+fn page_link(title: &str) -> String {
+    format!(
+        r#"<ac:link><ri:page ri:content-title="{}"/></ac:link>"#,
+        escape(title)
+    )
+}
+
+/// Convert the Markdown of a rendered book section to Confluence storage format, returning
+/// the body and the local images it refers to. Lines of HTML are passed on as they are.
+fn to_storage_format(markdown: &str) -> (String, Vec<String>) {
+    let mut output = String::new();
+    let mut images = vec![];
+    // The tags of the open lists, innermost last. Each has an open `li`.
+    let mut lists: Vec<&str> = vec![];
+    for block in blocks(markdown) {
+        if let Block::ListItem {
+            depth,
+            ordered,
+            text,
+        } = &block
+        {
+            let list = if *ordered { "ol" } else { "ul" };
+            while lists.len() > depth + 1 {
+                output.push_str(&format!("</li></{}>", lists.pop().unwrap()));
+            }
+            if lists.len() == depth + 1 && lists[*depth] != list {
+                output.push_str(&format!("</li></{}>\n", lists.pop().unwrap()));
+            }
+            if lists.len() == depth + 1 {
+                output.push_str("</li>\n<li>");
+            } else {
+                output.push_str(&format!("<{list}>\n<li>"));
+                lists.push(list);
+            }
+            output.push_str(&storage_inline(&inlines(text), &mut images));
+            continue;
+        }
+        while let Some(list) = lists.pop() {
+            output.push_str(&format!("</li></{list}>\n"));
+        }
+
+        match block {
+            Block::Heading { level, text } => {
+                let text = storage_inline(&inlines(&text), &mut images);
+                output.push_str(&format!("<h{level}>{text}</h{level}>\n"));
+            }
+            Block::Paragraph(text) => {
+                let text = storage_inline(&inlines(&text), &mut images);
+                output.push_str(&format!("<p>{text}</p>\n"));
+            }
+            Block::Quote(text) => {
+                let text = storage_inline(&inlines(&text), &mut images);
+                output.push_str(&format!("<blockquote><p>{text}</p></blockquote>\n"));
+            }
+            Block::Code { info, code } => output.push_str(&code_macro(&info, &code)),
+            Block::Rule => output.push_str("<hr/>\n"),
+            Block::Html(html) => {
+                output.push_str(&html);
+                output.push('\n');
+            }
+            Block::ListItem { .. } => unreachable!(),
+        }
+    }
+    while let Some(list) = lists.pop() {
+        output.push_str(&format!("</li></{list}>\n"));
+    }
+    (output, images)
+}
+
+/// Convert inline Markdown, collecting the local images
+fn storage_inline(inlines: &[Inline], images: &mut Vec<String>) -> String {
+    let mut output = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => output.push_str(&escape(text)),
+            Inline::Code(code) => output.push_str(&format!("<code>{}</code>", escape(code))),
+            Inline::Strong(inner) => output.push_str(&format!(
+                "<strong>{}</strong>",
+                storage_inline(inner, images)
+            )),
+            Inline::Emphasis(inner) => {
+                output.push_str(&format!("<em>{}</em>", storage_inline(inner, images)))
+            }
+            Inline::Link { label, target } => output.push_str(&format!(
+                r#"<a href="{}">{}</a>"#,
+                escape(target),
+                storage_inline(label, images)
+            )),
+            Inline::Image { alt, target } if target.contains("://") => {
+                output.push_str(&format!(
+                    r#"<ac:image ac:alt="{}"><ri:url ri:value="{}"/></ac:image>"#,
+                    escape(alt),
+                    escape(target)
+                ));
+            }
+            Inline::Image { alt, target } => {
+                let target = target.trim_start_matches('/');
+                images.push(target.to_string());
+                output.push_str(&format!(
+                    r#"<ac:image ac:alt="{}"><ri:attachment ri:filename="{}"/></ac:image>"#,
+                    escape(alt),
+                    escape(&attachment_name(target))
+                ));
+            }
+        }
+    }
+    output
+}
+
+fn code_macro(language: &str, code: &str) -> String {
+    let language = match language {
+        "" => String::new(),
+        language => format!(
+            r#"<ac:parameter ac:name="language">{}</ac:parameter>"#,
+            escape(language)
+        ),
+    };
+    format!(
+        "<ac:structured-macro ac:name=\"code\">{language}<ac:plain-text-body><![CDATA[{}]]></ac:plain-text-body></ac:structured-macro>\n",
+        // A CDATA section can't contain its own terminator, so split it up
+        code.trim_end().replace("]]>", "]]]]><![CDATA[>")
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_is_converted_to_storage_format() {
+        let markdown = "## Exercise 2.1.1: Basic Syntax\n\nOpen `exercises/2-foundations`, *then*:\n\n1. Run **`cargo run`**\n    - Check [the docs](https://doc.rust-lang.org) or <https://docs.rs>\n2. Look at ![diagram](images/2/1/1/diagram.svg)\n\n```rust\nfn main() {}\n```\n";
+        let (body, images) = to_storage_format(markdown);
+        assert_eq!(
+            body,
+            "<h2>Exercise 2.1.1: Basic Syntax</h2>\n\
+            <p>Open <code>exercises/2-foundations</code>, <em>then</em>:</p>\n\
+            <ol>\n<li>Run <strong><code>cargo run</code></strong>\
+            <ul>\n<li>Check <a href=\"https://doc.rust-lang.org\">the docs</a> or <a href=\"https://docs.rs\">https://docs.rs</a></li></ul>\
+            </li>\n<li>Look at <ac:image ac:alt=\"diagram\"><ri:attachment ri:filename=\"2-1-1-diagram.svg\"/></ac:image></li></ol>\n\
+            <ac:structured-macro ac:name=\"code\"><ac:parameter ac:name=\"language\">rust</ac:parameter>\
+            <ac:plain-text-body><![CDATA[fn main() {}]]></ac:plain-text-body></ac:structured-macro>\n"
+        );
+        assert_eq!(images, ["images/2/1/1/diagram.svg"]);
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{Result, ResultExt};
+
+use crate::{
+    archive::PackageFormat,
+    exercises::included_files,
+    include::included_sources,
+    io::PathExt,
+    load::Indexed,
+    markdown::local_files,
+    numbering::{
+        exercise_dir, module_exercises_dir, section_file_name, unit_exercises_dir, QUIZZES_DIR,
+    },
+    unicode::nfc_path,
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct DependencyError {}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to determine content dependencies")
+    }
+}
+
+impl error_stack::Context for DependencyError {}
+
+/// Which artifacts are derived from which files. Source files are absolute paths,
+/// artifacts are relative to the output dir. Artifacts can in turn feed into
+/// other artifacts, like the book section that ends up in a package.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    edges: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl DependencyGraph {
+    fn add(&mut self, from: &Path, to: impl Into<PathBuf>) {
+        let from = from.canonicalize().unwrap_or_else(|_| from.to_path_buf());
+        self.edges.entry(from).or_default().insert(to.into());
+    }
+
+    /// The direct dependencies, from each file to the artifacts derived from it
+    pub fn edges(&self) -> &BTreeMap<PathBuf, BTreeSet<PathBuf>> {
+        &self.edges
+    }
+
+    /// All artifacts that `path` feeds into, directly or through other artifacts
+    pub fn impact(&self, path: &Path) -> BTreeSet<&Path> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.reachable(&path)
+    }
+
+    /// For each artifact, all source files it is derived from, directly or through other artifacts
+    pub fn inputs(&self) -> BTreeMap<&Path, BTreeSet<&Path>> {
+        let mut inputs: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        // Source paths are absolute, artifact paths relative to the output dir
+        for source in self.edges.keys().filter(|p| p.is_absolute()) {
+            for artifact in self.reachable(source) {
+                inputs.entry(artifact).or_default().insert(source.as_path());
+            }
+        }
+        inputs
+    }
+
+    pub fn render(&self, format: DependencyFormat) -> String {
+        match format {
+            DependencyFormat::Dot => self.to_dot(),
+            DependencyFormat::Json => self.to_json(),
+        }
+    }
+
+    /// Render the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n    rankdir=LR;\n");
+        for (from, to) in self.edges.iter() {
+            for to in to.iter() {
+                writeln!(dot, "    {} -> {};", quote(from), quote(to)).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as JSON, holding both the direct `edges` from each file
+    /// to the artifacts derived from it, and the transitive `inputs` of each artifact
+    pub fn to_json(&self) -> String {
+        let json = serde_json::json!({
+            "edges": self.edges,
+            "inputs": self.inputs(),
+        });
+        serde_json::to_string_pretty(&json).unwrap() + "\n"
+    }
+
+    fn reachable(&self, path: &Path) -> BTreeSet<&Path> {
+        let mut impacted = BTreeSet::new();
+        let mut pending: Vec<&Path> = self
+            .edges
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(PathBuf::as_path)
+            .collect();
+        while let Some(artifact) = pending.pop() {
+            if impacted.insert(artifact) {
+                pending.extend(
+                    self.edges
+                        .get(artifact)
+                        .into_iter()
+                        .flatten()
+                        .map(PathBuf::as_path),
+                );
+            }
+        }
+        impacted
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DependencyFormat {
+    #[default]
+    Dot,
+    Json,
+}
+
+impl FromStr for DependencyFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(DependencyFormat::Dot),
+            "json" => Ok(DependencyFormat::Json),
+            _ => Err(format!(
+                "unknown dependency graph format '{s}', expected dot or json"
+            )),
+        }
+    }
+}
+
+impl Track {
+    /// Determine which book pages, slide decks and exercise files each content file
+    /// feeds into. If `package` is passed, the archives are included as well.
+    pub fn dependency_graph(
+        &self,
+        package: Option<PackageFormat>,
+    ) -> Result<DependencyGraph, DependencyError> {
+        let mut collector = DependencyCollector::default();
+        self.walk(&mut collector)?;
+        let mut graph = collector.graph;
+        if let Some(css) = &self.slides.css {
+            graph.add(css, Path::new("slides").join("style.css"));
+        }
+        if let (Some(logo), Some(dest)) = (&self.slides.logo, self.slides.logo_output_path()) {
+            graph.add(logo, Path::new("slides").join(dest));
+        }
+
+        if let Some(format) = package {
+            let artifacts: BTreeSet<_> = graph.edges.values().flatten().cloned().collect();
+            for artifact in artifacts {
+                graph
+                    .edges
+                    .entry(artifact.clone())
+                    .or_default()
+                    .insert(self.course_archive_path(format));
+                for module in self.modules.iter().filter(|m| m.contains_output(&artifact)) {
+                    graph
+                        .edges
+                        .entry(artifact.clone())
+                        .or_default()
+                        .insert(module.archive_path(format));
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+#[derive(Default)]
+struct DependencyCollector<'t> {
+    graph: DependencyGraph,
+    module_name: &'t str,
+    unit_name: &'t str,
+}
+
+impl<'t> Visitor<'t> for DependencyCollector<'t> {
+    type Error = DependencyError;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), DependencyError> {
+        self.module_name = &module.data.name;
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), DependencyError> {
+        self.unit_name = &unit.data.name;
+        if let Some(template) = &unit.data.template {
+            self.graph
+                .add(template, deck_path(position, self.unit_name));
+        }
+        let book_src_dir = Path::new("book").join("src");
+        let unit_number = position.unit_number().unwrap();
+        for (quiz, quiz_i) in unit.data.quizzes.iter().zip(1..) {
+            self.graph
+                .add(quiz, book_src_dir.join(section_file_name(self.unit_name)));
+            self.graph.add(
+                quiz,
+                book_src_dir
+                    .join(QUIZZES_DIR)
+                    .join(unit_number.quiz_file_name(quiz_i)),
+            );
+        }
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), DependencyError> {
+        let deck = deck_path(position, self.unit_name);
+        self.graph.add(&topic.data.content, &deck);
+        let content = topic.data.content.read_to_string()?;
+        let linked = local_files(&content, topic.data.content.parent().unwrap());
+        for image in topic.data.images.iter().chain(linked.iter()) {
+            if let Some(name) = image.file_name() {
+                self.graph.add(
+                    image,
+                    Path::new("slides")
+                        .join("images")
+                        .join(nfc_path(Path::new(name))),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), DependencyError> {
+        let number = position.exercise_number().unwrap();
+        let book_src_dir = Path::new("book").join("src");
+        let section = book_src_dir.join(section_file_name(self.unit_name));
+        let exercise = &exercise.data;
+
+        self.graph.add(&exercise.description, &section);
+        let mut linked = vec![];
+        for file in std::iter::once(&exercise.description).chain(&exercise.hints) {
+            self.graph.add(file, &section);
+            let content = file.read_to_string()?;
+            for source in included_sources::<DependencyError>(&content, &exercise.path)? {
+                self.graph.add(&source, &section);
+            }
+            linked.extend(local_files(&content, file.parent().unwrap()));
+        }
+        for image in exercise.description_images.iter().chain(linked.iter()) {
+            if let Some(name) = image.file_name() {
+                self.graph.add(
+                    image,
+                    book_src_dir
+                        .join(number.book_images_dir())
+                        .join(nfc_path(Path::new(name))),
+                );
+            }
+        }
+
+        let exercise_out_dir = module_exercises_dir(self.module_name, position.module)
+            .join(unit_exercises_dir(self.unit_name, number.unit))
+            .join(exercise_dir(&exercise.name, number.exercise));
+        for file in included_files(&exercise.path, &exercise.includes)
+            .change_context(DependencyError::default())?
+        {
+            let relative = file.strip_prefix(&exercise.path).unwrap();
+            self.graph
+                .add(&file, exercise_out_dir.join(nfc_path(relative)));
+        }
+        Ok(())
+    }
+}
+
+/// Quote a DOT identifier. JSON strings are valid DOT strings,
+/// so we let serde_json take care of escaping.
+fn quote(path: &Path) -> String {
+    serde_json::to_string(&path.to_string_lossy()).unwrap()
+}
+
+fn deck_path(position: Position, unit_name: &str) -> PathBuf {
+    let number = position.unit_number().unwrap();
+    Path::new("slides").join(number.deck_file_name(unit_name))
+}
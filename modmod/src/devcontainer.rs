@@ -0,0 +1,89 @@
+use indoc::formatdoc;
+use std::{collections::BTreeSet, fmt, path::Path};
+
+use error_stack::Result;
+use serde_json::json;
+
+use crate::{
+    io::{PathExt, WriteExt},
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderDevcontainerError {}
+
+impl fmt::Display for RenderDevcontainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render devcontainer")
+    }
+}
+
+impl error_stack::Context for RenderDevcontainerError {}
+
+impl Track {
+    /// Write a `.devcontainer` folder with a `devcontainer.json` and a `Dockerfile`
+    /// into `out_dir`, setting up the track toolchain and the system packages
+    /// required by its modules.
+    pub fn render_devcontainer(
+        &self,
+        out_dir: impl AsRef<Path>,
+    ) -> Result<(), RenderDevcontainerError> {
+        let devcontainer_dir = out_dir.as_ref().join(".devcontainer");
+        devcontainer_dir.create_dir_all()?;
+
+        let system_packages: BTreeSet<_> = self
+            .modules
+            .iter()
+            .flat_map(|m| &m.data.system_packages)
+            .map(String::as_str)
+            .collect();
+
+        let mut dockerfile = formatdoc! {"
+            FROM rust:1-bookworm
+
+            RUN rustup toolchain install {channel} --profile minimal{components} \\
+                && rustup default {channel}
+            ",
+            channel = self.toolchain.channel,
+            components = self
+                .toolchain
+                .components
+                .iter()
+                .map(|c| format!(" --component {c}"))
+                .collect::<String>(),
+        };
+        if !system_packages.is_empty() {
+            dockerfile += &formatdoc! {"
+
+                RUN apt-get update \\
+                    && apt-get install -y --no-install-recommends {packages} \\
+                    && rm -rf /var/lib/apt/lists/*
+                ",
+                packages = system_packages.into_iter().collect::<Vec<_>>().join(" "),
+            };
+        }
+        devcontainer_dir
+            .join("Dockerfile")
+            .create_file()?
+            .write_all(dockerfile)?;
+
+        let devcontainer_json = json!({
+            "name": self.name,
+            "build": {
+                "dockerfile": "Dockerfile"
+            },
+            "customizations": {
+                "vscode": {
+                    "extensions": ["rust-lang.rust-analyzer"]
+                }
+            }
+        });
+        devcontainer_dir
+            .join("devcontainer.json")
+            .create_file()?
+            .write_all(serde_json::to_string_pretty(&devcontainer_json).unwrap())?;
+
+        Ok(())
+    }
+}
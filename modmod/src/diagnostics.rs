@@ -0,0 +1,266 @@
+//! Where the problems in a track are. Errors about content get the location of the file
+//! and line they are about attached, along with the TOML table of the unit or exercise
+//! that uses the file, and [Errors] collects them, so that a render reports all problems
+//! at once rather than only the first. [Diagnostic::of_report] turns the reports into data,
+//! for tools that drive modmod as a library.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{AttachmentKind, Context, Frame, FrameKind, Report, Result};
+use serde::{Deserialize, Serialize};
+
+/// A place in a source file of the track
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub path: PathBuf,
+    /// 1-based line number, if the location is a specific line
+    pub line: Option<usize>,
+}
+
+impl Location {
+    pub fn new(path: impl Into<PathBuf>, line: Option<usize>) -> Self {
+        Self {
+            path: path.into(),
+            line,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.to_string_lossy())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The location of a content file attached to an error by [locate]
+#[derive(Debug)]
+struct At(Location);
+
+impl fmt::Display for At {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}", self.0)
+    }
+}
+
+/// The text in a content file that an error is about, like an include directive. Attached
+/// to errors so that [locate] can tell on which line of the file it is.
+#[derive(Debug)]
+pub(crate) struct Snippet(pub String);
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in `{}`", self.0)
+    }
+}
+
+/// Attach the location of the content file at `path` to the error of `result`, pointing at
+/// the line of the [Snippet] the error is about in its `content`, if any
+pub(crate) fn locate<T, C>(result: Result<T, C>, path: &Path, content: &str) -> Result<T, C> {
+    result.map_err(|report| {
+        let line = report
+            .downcast_ref::<Snippet>()
+            .and_then(|Snippet(snippet)| line_of(content, snippet));
+        report.attach_printable(At(Location::new(path, line)))
+    })
+}
+
+/// The 1-based line number of the first occurrence of `needle` in `content`
+pub(crate) fn line_of(content: &str, needle: &str) -> Option<usize> {
+    let offset = content.find(needle)?;
+    Some(content[..offset].matches('\n').count() + 1)
+}
+
+/// The 1-based line numbers of the headers of the `[[name]]` array of tables in the TOML
+/// `content`, like the `[[units]]` of a module. Tables that are written as inline tables
+/// have no header, in which case there are no line numbers at all.
+pub(crate) fn table_lines(content: &str, name: &str, count: usize) -> Vec<Option<usize>> {
+    let header = format!("[[{name}]]");
+    let lines: Vec<_> = content
+        .lines()
+        .zip(1..)
+        .filter(|(line, _)| line.trim_start().starts_with(&header))
+        .map(|(_, number)| Some(number))
+        .collect();
+    match lines.len() == count {
+        true => lines,
+        false => vec![None; count],
+    }
+}
+
+/// One of the problems of a failed load or render, like a broken include directive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// What went wrong, from the most specific to the most general, like
+    /// `Unknown variable 'nope'` followed by the template tag and the unit it's in
+    pub messages: Vec<String>,
+    /// The file and line of the content the problem is in, if known
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    /// The problems reported by `report`, one for every error that [Errors] collected
+    pub fn of_report<C>(report: &Report<C>) -> Vec<Self> {
+        let mut diagnostics = vec![];
+        for frame in report.current_frames() {
+            collect_diagnostics(frame, &mut vec![], &mut diagnostics);
+        }
+        diagnostics
+    }
+}
+
+/// Collect the problem of each error in the frames from `frame`, which are below the
+/// `outer` frames
+fn collect_diagnostics<'f>(
+    frame: &'f Frame,
+    outer: &mut Vec<&'f Frame>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    outer.push(frame);
+    if frame.sources().is_empty() {
+        let mut messages = vec![];
+        let mut location = None;
+        let mut innermost_context = None;
+        for frame in outer.iter().rev() {
+            if let Some(At(at)) = frame.downcast_ref::<At>() {
+                location.get_or_insert_with(|| at.clone());
+                continue;
+            }
+            match frame.kind() {
+                FrameKind::Attachment(AttachmentKind::Printable(printable)) => {
+                    messages.push(printable.to_string())
+                }
+                FrameKind::Context(context) => {
+                    innermost_context.get_or_insert_with(|| context.to_string());
+                }
+                _ => {}
+            }
+        }
+        if messages.is_empty() {
+            messages.extend(innermost_context);
+        }
+        diagnostics.push(Diagnostic { messages, location });
+    }
+    for source in frame.sources() {
+        collect_diagnostics(source, outer, diagnostics);
+    }
+    outer.pop();
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{location}: ")?;
+        }
+        f.write_str(&self.messages.join(", "))
+    }
+}
+
+/// Collects errors, so that they are reported together
+#[derive(Debug)]
+pub struct Errors<C> {
+    report: Option<Report<C>>,
+}
+
+impl<C> Default for Errors<C> {
+    fn default() -> Self {
+        Self { report: None }
+    }
+}
+
+impl<C: Context> Errors<C> {
+    pub fn push(&mut self, report: Report<C>) {
+        match &mut self.report {
+            Some(first) => first.extend_one(report),
+            None => self.report = Some(report),
+        }
+    }
+
+    /// The value of `result`, or none if it's an error, which is collected
+    pub fn collect<T>(&mut self, result: Result<T, C>) -> Option<T> {
+        result.map_err(|report| self.push(report)).ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.report.is_none()
+    }
+
+    /// Fail with all collected errors, if there are any
+    pub fn finish<T>(self, value: T) -> Result<T, C> {
+        match self.report {
+            Some(report) => Err(report),
+            None => Ok(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_point_at_the_lines_they_are_about() {
+        let module_toml = "name = \"Basics\"\n\n[[units]]\nname = \"Syntax\"\n\n[[units]]\nname = \"Ownership\"\n";
+        assert_eq!(table_lines(module_toml, "units", 2), [Some(3), Some(6)]);
+        assert_eq!(table_lines(module_toml, "units", 3), [None, None, None]);
+
+        #[derive(Debug, Default)]
+        struct TestError;
+        impl fmt::Display for TestError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("test error")
+            }
+        }
+        impl Context for TestError {}
+
+        let content = "# Numbers\n\n```rust\n#[modmod:include(src/main.rs)]\n```\n";
+        let mut errors = Errors::default();
+        for (snippet, path) in [
+            ("#[modmod:include(src/main.rs)]", "a.md"),
+            ("#[modmod:images]/missing.png", "b.md"),
+        ] {
+            let result: Result<(), _> =
+                Err(Report::new(TestError).attach_printable(Snippet(snippet.to_string())));
+            errors.collect(locate(result, Path::new(path), content));
+        }
+        let report = errors.finish(()).unwrap_err();
+        let locations: Vec<_> = report
+            .frames()
+            .filter_map(|frame| frame.downcast_ref::<At>())
+            .map(|at| at.to_string())
+            .collect();
+        assert_eq!(locations, ["at a.md:4", "at b.md"]);
+
+        let report = report.attach_printable("in unit 1.1 'Syntax'");
+        let diagnostics = Diagnostic::of_report(&report);
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic {
+                    messages: vec![
+                        "in `#[modmod:include(src/main.rs)]`".to_string(),
+                        "in unit 1.1 'Syntax'".to_string()
+                    ],
+                    location: Some(Location::new("a.md", Some(4))),
+                },
+                Diagnostic {
+                    messages: vec![
+                        "in `#[modmod:images]/missing.png`".to_string(),
+                        "in unit 1.1 'Syntax'".to_string()
+                    ],
+                    location: Some(Location::new("b.md", None)),
+                },
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "a.md:4: in `#[modmod:include(src/main.rs)]`, in unit 1.1 'Syntax'"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    fmt,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+/// An external tool that rendering or building the course relies on
+#[derive(Debug)]
+pub struct Tool {
+    pub name: &'static str,
+    /// What the tool is used for, completing "needed for ..."
+    pub purpose: &'static str,
+    /// Whether every course build needs the tool, rather than only some outputs
+    pub required: bool,
+    /// Commands that run the tool, the first one found is used
+    commands: &'static [&'static str],
+    /// Arguments to print the version
+    version_args: &'static [&'static str],
+    minimum: Option<&'static str>,
+    /// Install instructions for Linux, macOS and Windows
+    hints: [&'static str; 3],
+}
+
+/// The tools checked by [`diagnose`]
+pub const TOOLS: &[Tool] = &[
+    Tool {
+        name: "mdbook",
+        purpose: "building the book",
+        required: true,
+        commands: &["mdbook"],
+        version_args: &["--version"],
+        minimum: Some("0.4.35"),
+        hints: ["cargo install mdbook --locked"; 3],
+    },
+    Tool {
+        name: "mdbook-quiz",
+        purpose: "the quizzes in the book",
+        required: false,
+        commands: &["mdbook-quiz"],
+        version_args: &["--version"],
+        minimum: None,
+        hints: ["cargo install mdbook-quiz --locked"; 3],
+    },
+    Tool {
+        name: "node",
+        purpose: "building the slides with Slidev",
+        required: true,
+        commands: &["node"],
+        version_args: &["--version"],
+        minimum: Some("18.0"),
+        hints: [
+            "install the nodejs package of your distribution, or use nvm to get a recent release",
+            "brew install node",
+            "winget install OpenJS.NodeJS.LTS",
+        ],
+    },
+    Tool {
+        name: "chromium",
+        purpose: "exporting the slides to PDF",
+        required: false,
+        commands: &[
+            "chromium",
+            "chromium-browser",
+            "google-chrome",
+            "google-chrome-stable",
+        ],
+        version_args: &["--version"],
+        minimum: None,
+        hints: ["run `npm install -D playwright-chromium` in the slides folder"; 3],
+    },
+    Tool {
+        name: "probe-rs",
+        purpose: "flashing and verifying the embedded exercises",
+        required: false,
+        commands: &["probe-rs"],
+        version_args: &["--version"],
+        minimum: Some("0.24"),
+        hints: [
+            "cargo install probe-rs-tools --locked, then install the udev rules for your probe",
+            "cargo install probe-rs-tools --locked",
+            "cargo install probe-rs-tools --locked",
+        ],
+    },
+    Tool {
+        name: "rustfmt",
+        purpose: "`modmod check --rustfmt` and `modmod generate --build lint`",
+        required: false,
+        commands: &["rustfmt"],
+        version_args: &["--version"],
+        minimum: None,
+        hints: ["rustup component add rustfmt"; 3],
+    },
+    Tool {
+        name: "clippy",
+        purpose: "`modmod generate --build lint`",
+        required: false,
+        commands: &["cargo-clippy", "clippy-driver"],
+        version_args: &["--version"],
+        minimum: None,
+        hints: ["rustup component add clippy"; 3],
+    },
+    Tool {
+        name: "cargo",
+        purpose: "`modmod generate --build exercises`",
+        required: false,
+        commands: &["cargo"],
+        version_args: &["--version"],
+        minimum: None,
+        hints: ["install Rust with rustup, see https://rustup.rs"; 3],
+    },
+    Tool {
+        name: "git",
+        purpose: "`modmod generate --git-init`",
+        required: false,
+        commands: &["git"],
+        version_args: &["--version"],
+        minimum: None,
+        hints: [
+            "install the git package of your distribution",
+            "xcode-select --install, or brew install git",
+            "winget install Git.Git",
+        ],
+    },
+    Tool {
+        name: "curl",
+        purpose: "`modmod check --external-links`",
+        required: false,
+        commands: &["curl"],
+        version_args: &["--version"],
+        minimum: None,
+        hints: [
+            "install the curl package of your distribution",
+            "curl ships with macOS, or brew install curl",
+            "curl ships with Windows 10 and later, or winget install cURL.cURL",
+        ],
+    },
+];
+
+impl Tool {
+    /// How to install the tool on the platform modmod runs on
+    pub fn install_hint(&self) -> &'static str {
+        match std::env::consts::OS {
+            "macos" => self.hints[1],
+            "windows" => self.hints[2],
+            _ => self.hints[0],
+        }
+    }
+
+    /// Whether the tool can be run, regardless of its version
+    pub fn is_installed(&self) -> bool {
+        self.status() != ToolStatus::Missing
+    }
+
+    fn status(&self) -> ToolStatus {
+        let found = self.commands.iter().find_map(|command| {
+            let output = Command::new(command)
+                .args(self.version_args)
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .output()
+                .ok()?;
+            Some(parse_version(&String::from_utf8_lossy(&output.stdout)))
+        });
+        // Playwright keeps the browsers it downloads out of the PATH
+        let found = found.or_else(|| {
+            (self.name == "chromium" && playwright_chromium().is_some()).then_some(None)
+        });
+        match (found, self.minimum) {
+            (None, _) => ToolStatus::Missing,
+            (Some(Some(version)), Some(minimum))
+                if version_numbers(&version) < version_numbers(minimum) =>
+            {
+                ToolStatus::Outdated { version, minimum }
+            }
+            (Some(version), _) => ToolStatus::Found { version },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ToolStatus {
+    /// The version is missing if it could not be determined
+    Found {
+        version: Option<String>,
+    },
+    Outdated {
+        version: String,
+        minimum: &'static str,
+    },
+    Missing,
+}
+
+/// The outcome of looking for a tool
+#[derive(Debug)]
+pub struct Diagnosis {
+    pub tool: &'static Tool,
+    pub status: ToolStatus,
+}
+
+impl Diagnosis {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status, ToolStatus::Found { .. })
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Diagnosis { tool, status } = self;
+        match status {
+            ToolStatus::Found { version } => {
+                write!(f, "ok       {}", tool.name)?;
+                if let Some(version) = version {
+                    write!(f, " {version}")?;
+                }
+                return write!(f, ", for {}", tool.purpose);
+            }
+            ToolStatus::Outdated { version, minimum } => write!(
+                f,
+                "outdated {} {version}, {minimum} or later is needed for {}",
+                tool.name, tool.purpose
+            )?,
+            ToolStatus::Missing => write!(
+                f,
+                "missing  {}, which is needed for {}",
+                tool.name, tool.purpose
+            )?,
+        }
+        write!(f, "\n         To install it: {}", tool.install_hint())
+    }
+}
+
+/// The tool in [`TOOLS`] named `name`
+pub fn tool(name: &str) -> &'static Tool {
+    TOOLS
+        .iter()
+        .find(|t| t.name == name)
+        .unwrap_or_else(|| panic!("unknown tool {name}"))
+}
+
+/// Look for each of the [`TOOLS`] and check its version
+pub fn diagnose() -> Vec<Diagnosis> {
+    TOOLS
+        .iter()
+        .map(|tool| Diagnosis {
+            tool,
+            status: tool.status(),
+        })
+        .collect()
+}
+
+/// The first version number in the output of a `--version` flag, e.g. `0.4.40` in `mdbook v0.4.40`
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| {
+            let word = word.trim_start_matches('v').trim_end_matches([',', ')']);
+            // Drop pre-release and build suffixes, like in `1.8.0-stable`
+            word.split(['-', '+']).next().unwrap_or_default()
+        })
+        .find(|word| word.contains('.') && word.split('.').all(|n| n.parse::<u32>().is_ok()))
+        .map(str::to_string)
+}
+
+fn version_numbers(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|n| n.parse().unwrap_or_default())
+        .collect()
+}
+
+/// A Chromium downloaded by Playwright, which Slidev uses to export PDFs
+fn playwright_chromium() -> Option<PathBuf> {
+    let cache_dir = match std::env::consts::OS {
+        "macos" => PathBuf::from(std::env::var_os("HOME")?).join("Library/Caches"),
+        "windows" => PathBuf::from(std::env::var_os("LOCALAPPDATA")?),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    std::fs::read_dir(cache_dir.join("ms-playwright"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("chromium"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_parsed_from_tool_output() {
+        assert_eq!(parse_version("mdbook v0.4.40\n").as_deref(), Some("0.4.40"));
+        assert_eq!(parse_version("v20.11.1\n").as_deref(), Some("20.11.1"));
+        assert_eq!(
+            parse_version("probe-rs 0.24.0 (git commit: crates.io)").as_deref(),
+            Some("0.24.0")
+        );
+        assert_eq!(
+            parse_version("Chromium 120.0.6099.224 snap").as_deref(),
+            Some("120.0.6099.224")
+        );
+        assert_eq!(
+            parse_version("rustfmt 1.8.0-stable (2025-05-09)").as_deref(),
+            Some("1.8.0")
+        );
+        assert_eq!(parse_version("rustfmt"), None);
+        assert!(version_numbers("0.4.9") < version_numbers("0.4.35"));
+        assert!(version_numbers("18.0") < version_numbers("18.0.1"));
+    }
+}
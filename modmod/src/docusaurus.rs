@@ -0,0 +1,145 @@
+use std::{
+    fmt::{self, Write as _},
+    path::Path,
+};
+
+use error_stack::Result;
+
+use crate::{
+    book::without_quizzes,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    markdown::{escape_mdx, strip_comments},
+    numbering::section_file_name,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderDocusaurusError {}
+
+impl fmt::Display for RenderDocusaurusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render Docusaurus site")
+    }
+}
+
+impl error_stack::Context for RenderDocusaurusError {}
+
+/// Name of the folder in the output directory the Docusaurus site structure is written to
+pub const DOCUSAURUS_DIR: &str = "docusaurus";
+
+impl Track {
+    /// Convert the rendered book in `out_dir` into the `docs` folder and `sidebars.js`
+    /// of a Docusaurus site, with an MDX page per unit `filter` selects and a sidebar category
+    /// per module. The pages are kept in a single folder, so that the image paths of the book
+    /// still work. Quizzes need an mdBook preprocessor, so they are left out.
+    pub fn render_docusaurus(
+        &self,
+        out_dir: impl AsRef<Path>,
+        filter: &RenderFilter,
+    ) -> Result<(), RenderDocusaurusError> {
+        let out_dir = out_dir.as_ref();
+        let book_src_dir = out_dir.join("book").join("src");
+        let site_dir = out_dir.join(DOCUSAURUS_DIR);
+        let docs_dir = site_dir.join("docs");
+        docs_dir.create_dir_all()?;
+
+        let mut sidebar = Sidebar {
+            filter,
+            book_src_dir: &book_src_dir,
+            docs_dir: &docs_dir,
+            categories: String::new(),
+            items: vec![],
+        };
+        self.walk(&mut sidebar)?;
+        let categories = sidebar.categories;
+
+        let images_dir = book_src_dir.join("images");
+        if images_dir.is_dir() {
+            for image in images_dir.get_dir_content::<RenderDocusaurusError>()?.files {
+                let image = Path::new(&image);
+                let dest = docs_dir.join(image.strip_prefix(&book_src_dir).unwrap());
+                dest.parent().unwrap().create_dir_all()?;
+                image.copy(dest)?;
+            }
+        }
+
+        site_dir.join("sidebars.js").create_file()?.write_all(format!(
+            "/** @type {{import('@docusaurus/plugin-content-docs').SidebarsConfig}} */\nconst sidebars = {{\n  course: [\n{categories}  ],\n}};\n\nmodule.exports = sidebars;\n"
+        ))?;
+
+        Ok(())
+    }
+}
+
+/// Writes the pages of the units and collects the sidebar categories of the modules while
+/// walking over the track
+struct Sidebar<'p> {
+    /// The units that were rendered into the book
+    filter: &'p RenderFilter,
+    book_src_dir: &'p Path,
+    docs_dir: &'p Path,
+    categories: String,
+    /// The pages of the units of the module that is being visited
+    items: Vec<String>,
+}
+
+impl<'t> Visitor<'t> for Sidebar<'_> {
+    type Error = RenderDocusaurusError;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RenderDocusaurusError> {
+        if !self.filter.includes(module, unit) {
+            return Ok(());
+        }
+        let number = position.unit_number().unwrap();
+        let section_path = self.book_src_dir.join(section_file_name(&unit.data.name));
+        let content = section_path.read_to_string()?;
+        let doc_path = self
+            .docs_dir
+            .join(section_file_name(&unit.data.name).with_extension("mdx"));
+        doc_path.create_file()?.write_all(format!(
+            "---\nsidebar_label: {}\nsidebar_position: {}\n---\n\n{}",
+            serde_json::to_string(&format!("{number} {}", unit.data.name)).unwrap(),
+            self.items.len() + 1,
+            escape_mdx(&without_quizzes(&strip_comments(&content)))
+        ))?;
+        self.items
+            .push(doc_path.file_stem().unwrap().to_string_lossy().into_owned());
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), RenderDocusaurusError> {
+        let items = std::mem::take(&mut self.items);
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(
+            self.categories,
+            "    {{\n      type: \"category\",\n      label: {},\n      items: [",
+            serde_json::to_string(&format!("{} {}", module.index, module.data.name)).unwrap()
+        )
+        .unwrap();
+        for item in items {
+            writeln!(
+                self.categories,
+                "        {},",
+                serde_json::to_string(&item).unwrap()
+            )
+            .unwrap();
+        }
+        self.categories.push_str("      ],\n    },\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,537 @@
+use std::{
+    fmt::{self, Write as _},
+    fs,
+    io::Write,
+    path::Path,
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+use crate::{
+    archive::ENTRY_MODE,
+    book::without_quizzes,
+    io::PathExt,
+    load::Indexed,
+    markdown::{blocks, inlines, strip_comments, strip_tags, Block, Inline},
+    numbering::section_file_name,
+    unicode::to_ascii,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderDocxError {}
+
+impl fmt::Display for RenderDocxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render Word documents")
+    }
+}
+
+impl error_stack::Context for RenderDocxError {}
+
+/// Name of the folder in the output directory the Word documents are written to
+pub const DOCX_DIR: &str = "docx";
+
+const STYLES: &str = include_str!("../include/docx/styles.xml");
+
+/// Width of the text on an A4 page with margins of an inch, in EMU
+const TEXT_WIDTH: u64 = 5_731_510;
+/// Size of a pixel at 96 DPI, in EMU
+const PIXEL_SIZE: u64 = 9525;
+
+impl Track {
+    /// Convert every unit of the rendered book in `out_dir` that `filter` selects into a Word
+    /// document, for editing outside of the course. Headings, lists, quotes and code use the
+    /// Word styles of the same name, so the look can be changed in Word. PNG and JPEG images
+    /// are embedded; Word can't show other formats, so their description is used instead.
+    /// Quizzes need an mdBook preprocessor, so they are left out. With `ascii`, emoji and
+    /// typographic punctuation are replaced, for pipelines that turn the documents into PDF.
+    pub fn render_docx(
+        &self,
+        out_dir: impl AsRef<Path>,
+        filter: &RenderFilter,
+        ascii: bool,
+    ) -> Result<(), RenderDocxError> {
+        let out_dir = out_dir.as_ref();
+        let book_src_dir = out_dir.join("book").join("src");
+        let docx_dir = out_dir.join(DOCX_DIR);
+        docx_dir.create_dir_all()?;
+
+        self.walk(&mut UnitDocuments {
+            filter,
+            book_src_dir: &book_src_dir,
+            docx_dir: &docx_dir,
+            ascii,
+            language: &self.language,
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Writes a Word document for every unit in the book while walking over the track
+struct UnitDocuments<'p> {
+    /// The units that were rendered into the book
+    filter: &'p RenderFilter,
+    book_src_dir: &'p Path,
+    docx_dir: &'p Path,
+    ascii: bool,
+    language: &'p str,
+}
+
+impl<'t> Visitor<'t> for UnitDocuments<'_> {
+    type Error = RenderDocxError;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RenderDocxError> {
+        if !self.filter.includes(module, unit) {
+            return Ok(());
+        }
+        let number = position.unit_number().unwrap();
+        let section_path = self.book_src_dir.join(section_file_name(&unit.data.name));
+        let content: String = section_path.read_to_string()?;
+        let title = format!("{number} {}", unit.data.name);
+        let (content, title) = match self.ascii {
+            true => (
+                to_ascii(&content).into_owned(),
+                to_ascii(&title).into_owned(),
+            ),
+            false => (content, title),
+        };
+        let mut document = Document::new(self.book_src_dir);
+        document.push_markdown(&without_quizzes(&strip_comments(&content)))?;
+
+        let docx_path = self
+            .docx_dir
+            .join(number.deck_slug(&unit.data.name))
+            .with_extension("docx");
+        document.write(&docx_path, &title, self.language)
+    }
+}
+
+/// The parts of a Word document that depend on its content
+struct Document<'b> {
+    /// Folder that image paths are relative to
+    base_dir: &'b Path,
+    body: String,
+    /// Relationships of the document besides the styles and numbering, as type and target
+    relationships: Vec<(&'static str, String)>,
+    /// Embedded images, as file name in the `media` folder and content
+    media: Vec<(String, Vec<u8>)>,
+    /// Every numbered list gets a numbering instance of its own, so that it starts at 1
+    numbered_lists: usize,
+}
+
+/// How a run of text is formatted
+#[derive(Debug, Clone, Copy, Default)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    code: bool,
+    link: bool,
+}
+
+impl<'b> Document<'b> {
+    fn new(base_dir: &'b Path) -> Self {
+        Self {
+            base_dir,
+            body: String::new(),
+            relationships: vec![],
+            media: vec![],
+            numbered_lists: 0,
+        }
+    }
+
+    fn push_markdown(&mut self, markdown: &str) -> Result<(), RenderDocxError> {
+        // The numbering instance of the numbered items of the current list
+        let mut list_num_id = None;
+        for block in blocks(markdown) {
+            if !matches!(block, Block::ListItem { .. }) {
+                list_num_id = None;
+            }
+            match block {
+                Block::Heading { level, text } => {
+                    let runs = self.runs(&inlines(&text), Format::default())?;
+                    self.paragraph(&format!(r#"<w:pStyle w:val="Heading{level}"/>"#), &runs);
+                }
+                Block::Paragraph(text) => {
+                    let runs = self.runs(&inlines(&text), Format::default())?;
+                    self.paragraph("", &runs);
+                }
+                Block::ListItem {
+                    depth,
+                    ordered,
+                    text,
+                } => {
+                    // Bullets of all lists share the first numbering instance
+                    let num_id = match ordered {
+                        true => *list_num_id.get_or_insert_with(|| {
+                            self.numbered_lists += 1;
+                            self.numbered_lists + 1
+                        }),
+                        false => 1,
+                    };
+                    let runs = self.runs(&inlines(&text), Format::default())?;
+                    self.paragraph(
+                        &format!(
+                            r#"<w:pStyle w:val="ListParagraph"/><w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{num_id}"/></w:numPr>"#,
+                            depth.min(8)
+                        ),
+                        &runs,
+                    );
+                }
+                Block::Quote(text) => {
+                    let runs = self.runs(&inlines(&text), Format::default())?;
+                    self.paragraph(r#"<w:pStyle w:val="Quote"/>"#, &runs);
+                }
+                Block::Code { code, .. } => {
+                    for line in code.trim_end().lines() {
+                        let runs = text_run(&line.replace('\t', "    "), Format::default());
+                        self.paragraph(r#"<w:pStyle w:val="Code"/>"#, &runs);
+                    }
+                    // Code paragraphs have no spacing after them, so add an empty paragraph
+                    self.paragraph("", "");
+                }
+                Block::Rule => self.paragraph(
+                    r#"<w:pBdr><w:bottom w:val="single" w:sz="6" w:space="1" w:color="auto"/></w:pBdr>"#,
+                    "",
+                ),
+                Block::Html(html) => {
+                    let text = strip_tags(&html);
+                    if !text.trim().is_empty() {
+                        let runs = text_run(text.trim(), Format::default());
+                        self.paragraph("", &runs);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn paragraph(&mut self, properties: &str, runs: &str) {
+        let properties = match properties {
+            "" => String::new(),
+            properties => format!("<w:pPr>{properties}</w:pPr>"),
+        };
+        writeln!(self.body, "<w:p>{properties}{runs}</w:p>").unwrap();
+    }
+
+    fn runs(&mut self, inlines: &[Inline], format: Format) -> Result<String, RenderDocxError> {
+        let mut runs = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => runs.push_str(&text_run(text, format)),
+                Inline::Code(code) => runs.push_str(&text_run(
+                    code,
+                    Format {
+                        code: true,
+                        ..format
+                    },
+                )),
+                Inline::Strong(inner) => runs.push_str(&self.runs(
+                    inner,
+                    Format {
+                        bold: true,
+                        ..format
+                    },
+                )?),
+                Inline::Emphasis(inner) => runs.push_str(&self.runs(
+                    inner,
+                    Format {
+                        italic: true,
+                        ..format
+                    },
+                )?),
+                // Links within the course don't lead anywhere from a document
+                Inline::Link { label, target } if !target.contains("://") => {
+                    runs.push_str(&self.runs(label, format)?)
+                }
+                Inline::Link { label, target } => {
+                    let id = self.relationship("hyperlink", target.to_string());
+                    let label = self.runs(
+                        label,
+                        Format {
+                            link: true,
+                            ..format
+                        },
+                    )?;
+                    write!(runs, r#"<w:hyperlink r:id="{id}">{label}</w:hyperlink>"#).unwrap();
+                }
+                Inline::Image { alt, target } => runs.push_str(&self.image(alt, target, format)?),
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Embed the image at `target`, or describe it if it can't be embedded
+    fn image(
+        &mut self,
+        alt: &str,
+        target: &str,
+        format: Format,
+    ) -> Result<String, RenderDocxError> {
+        let path = self.base_dir.join(target.trim_start_matches('/'));
+        let extension = match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => "png",
+            Some("jpg" | "jpeg") => "jpeg",
+            _ => return Ok(text_run(alt, format)),
+        };
+        if target.contains("://") || !path.is_file() {
+            return Ok(text_run(alt, format));
+        }
+        let content = fs::read(&path)
+            .into_report()
+            .attach_printable_lazy(|| format!("Error reading {}", path.to_string_lossy()))
+            .change_context(RenderDocxError::default())?;
+        let Some((width, height)) = image_size(&content) else {
+            return Ok(text_run(alt, format));
+        };
+
+        // Scale the image to 96 DPI, shrinking it to fit the page if needed
+        let mut cx = width * PIXEL_SIZE;
+        let mut cy = height * PIXEL_SIZE;
+        if cx > TEXT_WIDTH {
+            cy = cy * TEXT_WIDTH / cx;
+            cx = TEXT_WIDTH;
+        }
+        let picture = self.media.len() + 1;
+        let name = format!("image{picture}.{extension}");
+        let id = self.relationship("image", format!("media/{name}"));
+        self.media.push((name.clone(), content));
+        let alt = escape(alt);
+        Ok(format!(
+            r#"<w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="{picture}" name="{name}" descr="{alt}"/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="{picture}" name="{name}" descr="{alt}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="{id}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"#
+        ))
+    }
+
+    /// Add a relationship, returning its ID. `rId1` and `rId2` are taken by the styles and numbering.
+    fn relationship(&mut self, kind: &'static str, target: String) -> String {
+        self.relationships.push((kind, target));
+        format!("rId{}", self.relationships.len() + 2)
+    }
+
+    fn numbering(&self) -> String {
+        let mut numbering = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+"#,
+        );
+        for (abstract_id, ordered) in [(0, false), (1, true)] {
+            writeln!(numbering, r#"<w:abstractNum w:abstractNumId="{abstract_id}"><w:multiLevelType w:val="hybridMultilevel"/>"#).unwrap();
+            for level in 0..9 {
+                let (format, text) = match ordered {
+                    true => ("decimal", format!("%{}.", level + 1)),
+                    false => ("bullet", ["•", "◦", "▪"][level % 3].to_string()),
+                };
+                writeln!(
+                    numbering,
+                    r#"<w:lvl w:ilvl="{level}"><w:start w:val="1"/><w:numFmt w:val="{format}"/><w:lvlText w:val="{text}"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="{}" w:hanging="360"/></w:pPr></w:lvl>"#,
+                    720 * (level + 1)
+                )
+                .unwrap();
+            }
+            numbering.push_str("</w:abstractNum>\n");
+        }
+        numbering.push_str(r#"<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#);
+        numbering.push('\n');
+        for list in 0..self.numbered_lists {
+            writeln!(
+                numbering,
+                r#"<w:num w:numId="{}"><w:abstractNumId w:val="1"/></w:num>"#,
+                list + 2
+            )
+            .unwrap();
+        }
+        numbering.push_str("</w:numbering>\n");
+        numbering
+    }
+
+    fn document(&self) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture">
+<w:body>
+{}<w:sectPr><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr>
+</w:body>
+</w:document>
+"#,
+            self.body
+        )
+    }
+
+    fn document_relationships(&self) -> String {
+        let mut relationships = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>
+"#,
+        );
+        for ((kind, target), id) in self.relationships.iter().zip(3..) {
+            let mode = match *kind {
+                "hyperlink" => r#" TargetMode="External""#,
+                _ => "",
+            };
+            writeln!(
+                relationships,
+                r#"<Relationship Id="rId{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{kind}" Target="{}"{mode}/>"#,
+                escape(target)
+            )
+            .unwrap();
+        }
+        relationships.push_str("</Relationships>\n");
+        relationships
+    }
+
+    /// Write the document as a DOCX package to `path`
+    fn write(&self, path: &Path, title: &str, language: &str) -> Result<(), RenderDocxError> {
+        let core = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>{}</dc:title><dc:language>{}</dc:language></cp:coreProperties>
+"#,
+            escape(title),
+            escape(language)
+        );
+        let mut parts: Vec<(String, Vec<u8>)> = vec![
+            ("[Content_Types].xml".into(), CONTENT_TYPES.into()),
+            ("_rels/.rels".into(), PACKAGE_RELATIONSHIPS.into()),
+            ("docProps/core.xml".into(), core.into_bytes()),
+            ("word/document.xml".into(), self.document().into_bytes()),
+            (
+                "word/_rels/document.xml.rels".into(),
+                self.document_relationships().into_bytes(),
+            ),
+            ("word/styles.xml".into(), STYLES.into()),
+            ("word/numbering.xml".into(), self.numbering().into_bytes()),
+        ];
+        for (name, content) in self.media.iter() {
+            parts.push((format!("word/media/{name}"), content.clone()));
+        }
+
+        let attach_path = || format!("Error writing {}", path.to_string_lossy());
+        let mut zip = zip::ZipWriter::new(path.create_file()?);
+        let options = zip::write::SimpleFileOptions::default()
+            .last_modified_time(zip::DateTime::default())
+            .unix_permissions(ENTRY_MODE);
+        for (name, content) in parts {
+            zip.start_file(name, options)
+                .and_then(|_| Ok(zip.write_all(&content)?))
+                .into_report()
+                .attach_printable_lazy(attach_path)
+                .change_context(RenderDocxError::default())?;
+        }
+        zip.finish()
+            .into_report()
+            .attach_printable_lazy(attach_path)
+            .change_context(RenderDocxError::default())?;
+        Ok(())
+    }
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="png" ContentType="image/png"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>
+"#;
+
+const PACKAGE_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>
+"#;
+
+fn text_run(text: &str, format: Format) -> String {
+    let mut properties = String::new();
+    if format.code {
+        properties.push_str(r#"<w:rStyle w:val="CodeChar"/>"#);
+    } else if format.link {
+        properties.push_str(r#"<w:rStyle w:val="Hyperlink"/>"#);
+    }
+    if format.bold {
+        properties.push_str("<w:b/>");
+    }
+    if format.italic {
+        properties.push_str("<w:i/>");
+    }
+    if !properties.is_empty() {
+        properties = format!("<w:rPr>{properties}</w:rPr>");
+    }
+    format!(
+        r#"<w:r>{properties}<w:t xml:space="preserve">{}</w:t></w:r>"#,
+        escape(text)
+    )
+}
+
+/// The width and height in pixels of a PNG or JPEG image
+fn image_size(image: &[u8]) -> Option<(u64, u64)> {
+    let be = |bytes: &[u8]| bytes.iter().fold(0, |n, b| n << 8 | u64::from(*b));
+    if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some((be(image.get(16..20)?), be(image.get(20..24)?)));
+    }
+    if !image.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+    // Walk the JPEG segments up to the start of frame, which holds the size
+    let mut i = 2;
+    while let (Some(0xff), Some(&marker)) = (image.get(i), image.get(i + 1)) {
+        let len = be(image.get(i + 2..i + 4)?) as usize;
+        if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            return Some((be(image.get(i + 7..i + 9)?), be(image.get(i + 5..i + 7)?)));
+        }
+        i += 2 + len;
+    }
+    None
+}
+
+/// Escape text for XML, dropping the control characters XML doesn't allow
+fn escape(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_is_converted_to_word_paragraphs() {
+        let mut document = Document::new(Path::new("."));
+        document
+            .push_markdown("## Exercise 2.1.1\n\n1. Run **`cargo run`**, see [std](https://doc.rust-lang.org/std)\n\n```rust\nfn main() {}\n```\n")
+            .unwrap();
+        assert_eq!(
+            document.body,
+            "<w:p><w:pPr><w:pStyle w:val=\"Heading2\"/></w:pPr><w:r><w:t xml:space=\"preserve\">Exercise 2.1.1</w:t></w:r></w:p>\n\
+            <w:p><w:pPr><w:pStyle w:val=\"ListParagraph\"/><w:numPr><w:ilvl w:val=\"0\"/><w:numId w:val=\"2\"/></w:numPr></w:pPr>\
+            <w:r><w:t xml:space=\"preserve\">Run </w:t></w:r>\
+            <w:r><w:rPr><w:rStyle w:val=\"CodeChar\"/><w:b/></w:rPr><w:t xml:space=\"preserve\">cargo run</w:t></w:r>\
+            <w:r><w:t xml:space=\"preserve\">, see </w:t></w:r>\
+            <w:hyperlink r:id=\"rId3\"><w:r><w:rPr><w:rStyle w:val=\"Hyperlink\"/></w:rPr><w:t xml:space=\"preserve\">std</w:t></w:r></w:hyperlink></w:p>\n\
+            <w:p><w:pPr><w:pStyle w:val=\"Code\"/></w:pPr><w:r><w:t xml:space=\"preserve\">fn main() {}</w:t></w:r></w:p>\n\
+            <w:p></w:p>\n"
+        );
+        assert_eq!(
+            document.relationships,
+            [("hyperlink", "https://doc.rust-lang.org/std".to_string())]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::{fmt, str::FromStr};
+
+/// The version of the course to render. The teacher edition keeps
+/// teacher-only blocks and slide speaker notes, and includes the
+/// exercise solutions. The student edition leaves these out, apart from the speaker notes
+/// unless [`RenderOptions::strip_notes`](crate::RenderOptions::strip_notes) is set.
+/// The preview edition is the student edition of just the units marked
+/// as `preview`, with a teaser page in the book for each of the others.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Edition {
+    #[default]
+    Student,
+    Teacher,
+    Preview,
+}
+
+impl Edition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Edition::Student => "student",
+            Edition::Teacher => "teacher",
+            Edition::Preview => "preview",
+        }
+    }
+
+    pub fn is_teacher(&self) -> bool {
+        *self == Edition::Teacher
+    }
+
+    pub fn is_preview(&self) -> bool {
+        *self == Edition::Preview
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Edition {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "student" => Ok(Edition::Student),
+            "teacher" => Ok(Edition::Teacher),
+            "preview" => Ok(Edition::Preview),
+            _ => Err(format!(
+                "unknown edition '{s}', expected student, teacher or preview"
+            )),
+        }
+    }
+}
@@ -2,16 +2,33 @@ use std::{
     collections::HashMap,
     fmt,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use error_stack::{IntoReport, Result, ResultExt};
 
-use crate::{io::PathExt, to_prefixed_tag};
+use crate::{
+    diagnostics::Errors,
+    edition::Edition,
+    io::{PathExt, WriteExt},
+    markdown::{front_matter, teacher_only},
+    numbering::{
+        exercise_dir, module_exercises_dir, solution_dir, unit_exercises_dir, UnitNumber,
+        EXERCISES_DIR,
+    },
+    progress::progress,
+    tasks::{Task, TaskRunner},
+    toolchain::{set_edition, ExerciseToolchain},
+    unicode::nfc_path,
+};
 
 #[non_exhaustive]
 #[derive(Debug, Default)]
 pub struct RenderExercisesError {}
 
+/// Name of the folder in an exercise package that holds its hints
+pub const HINTS_DIR: &str = "hints";
+
 impl fmt::Display for RenderExercisesError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("unable to render exercises")
@@ -23,86 +40,137 @@ impl error_stack::Context for RenderExercisesError {}
 #[derive(Debug)]
 pub struct ExerciseCollection<'track> {
     module_exercises: Vec<ModuleExercises<'track>>,
+    /// Whether the hints of the exercises are written into their packages
+    hints_folder: bool,
 }
 
 impl<'track> ExerciseCollection<'track> {
+    /// Start building an exercise collection. Modules are added using
+    /// [`ExerciseCollectionBuilder::module`].
     pub fn builder() -> ExerciseCollectionBuilder<'track> {
         ExerciseCollectionBuilder {
             collection: ExerciseCollection {
                 module_exercises: vec![],
+                hints_folder: false,
             },
         }
     }
 
+    /// Copy the exercise packages into the `exercises` folder in `output_dir`,
+    /// and in the teacher edition their solutions into the `solutions` folder.
+    /// Returns a map from exercise source directories to their output directories,
+    /// relative to `output_dir`. Generate steps are run with `runner`.
     pub fn render(
         &self,
         output_dir: impl AsRef<Path>,
+        edition: Edition,
+        runner: &TaskRunner,
     ) -> Result<HashMap<PathBuf, PathBuf>, RenderExercisesError> {
         let output_dir = output_dir.as_ref();
-        let exercise_root_dir = output_dir.join("exercises");
-        exercise_root_dir.create_dir_all()?;
+        output_dir.join(EXERCISES_DIR).create_dir_all()?;
         let mut exercise_output_paths = HashMap::new();
+        let total = self
+            .module_exercises
+            .iter()
+            .map(|m| m.unit_exercises.len())
+            .sum();
+        let mut done = 0;
+        // Render all packages, and report the problems of all of them together
+        let mut errors = Errors::default();
 
         for mod_ex in self.module_exercises.iter() {
-            let mod_ex_out_dir = {
-                let mut d = exercise_root_dir.clone();
-                d.push(to_prefixed_tag(mod_ex.name, mod_ex.index));
-                d
-            };
+            let mod_ex_out_dir = output_dir.join(module_exercises_dir(mod_ex.name, mod_ex.index));
             mod_ex_out_dir.create_dir_all()?;
 
             for unit_ex in mod_ex.unit_exercises.iter() {
-                let unit_ex_out_dir = {
-                    let mut d = mod_ex_out_dir.clone();
-                    d.push(to_prefixed_tag(unit_ex.name, unit_ex.index));
-                    d
-                };
+                done += 1;
+                let unit_number = UnitNumber::new(mod_ex.index, unit_ex.index);
+                progress(
+                    "exercises",
+                    done,
+                    total,
+                    format!("{unit_number} {}", unit_ex.name),
+                );
+                let unit_ex_out_dir =
+                    mod_ex_out_dir.join(unit_exercises_dir(unit_ex.name, unit_ex.index));
                 unit_ex_out_dir.create_dir_all()?;
 
                 for ex_pack in unit_ex.exercises.iter() {
-                    let ex_pack_out_dir = {
-                        let mut d = unit_ex_out_dir.clone();
-                        d.push(to_prefixed_tag(ex_pack.name, ex_pack.index));
-                        d
-                    };
+                    let ex_pack_out_dir =
+                        unit_ex_out_dir.join(exercise_dir(ex_pack.name, ex_pack.index));
                     ex_pack_out_dir.create_dir_all()?;
 
-                    let content = ex_pack.path.get_dir_content()?;
-
-                    // Create globset to match included files
-                    let mut globset = globset::GlobSetBuilder::new();
-                    for include in ex_pack.includes {
-                        globset.add(
-                            globset::Glob::new(ex_pack.path.join(include).to_str().unwrap())
-                                .into_report()
-                                .attach_printable_lazy(|| {
-                                    format!("Error parsing include glob '{include}'")
-                                })
-                                .change_context(RenderExercisesError::default())?,
-                        );
+                    // Data is generated in a scratch copy of the sources, so that it never
+                    // ends up in the content repository
+                    let scratch_dir = ex_pack.generate.map(|_| scratch_dir());
+                    let source_dir = scratch_dir.as_deref().unwrap_or(ex_pack.path);
+                    let hidden_tests =
+                        ex_pack
+                            .hidden_tests
+                            .map(|h| match h.strip_prefix(ex_pack.path) {
+                                Ok(relative) => source_dir.join(relative),
+                                Err(_) => h.to_path_buf(),
+                            });
+                    let copied = ex_pack
+                        .generate
+                        .map_or(Ok(()), |generate| {
+                            run_generate_step(
+                                generate,
+                                ex_pack.path,
+                                source_dir,
+                                &ex_pack_out_dir,
+                                runner,
+                            )
+                        })
+                        .and_then(|_| {
+                            copy_included_files(
+                                source_dir,
+                                ex_pack.includes,
+                                hidden_tests.as_deref(),
+                                &ex_pack_out_dir,
+                            )
+                        })
+                        .and_then(|_| match ex_pack.toolchain {
+                            Some(toolchain) => apply_toolchain(toolchain, &ex_pack_out_dir),
+                            None => Ok(()),
+                        })
+                        .and_then(|_| match self.hints_folder {
+                            true => write_hints(ex_pack.hints, &ex_pack_out_dir, edition),
+                            false => Ok(()),
+                        })
+                        .attach_printable_lazy(|| {
+                            format!(
+                                "in exercise {} '{}' at {}",
+                                unit_number.exercise(ex_pack.index),
+                                ex_pack.name,
+                                ex_pack.path.to_string_lossy()
+                            )
+                        });
+                    if let Some(scratch_dir) = scratch_dir {
+                        let _ = std::fs::remove_dir_all(scratch_dir);
                     }
-                    let globset = globset.build().unwrap();
-
-                    for included_file in content.files.iter().filter(|f| globset.is_match(f)) {
-                        let included_file_relative = Path::new(&included_file)
-                            .strip_prefix(ex_pack.path)
-                            .unwrap();
-                        let included_file_dest = ex_pack_out_dir.join(included_file_relative);
-                        let include_file_dest_dir = included_file_dest.parent().unwrap();
-                        include_file_dest_dir.create_dir_all()?;
-                        included_file.copy(included_file_dest)?;
+                    if errors.collect(copied).is_none() {
+                        continue;
                     }
 
                     let ex_pack_out_dir = ex_pack_out_dir
                         .strip_prefix(output_dir)
                         .unwrap()
                         .to_path_buf();
+                    if let (Some(solution), true) = (ex_pack.solution, edition.is_teacher()) {
+                        let solution_out_dir = output_dir.join(solution_dir(&ex_pack_out_dir));
+                        solution_out_dir.create_dir_all()?;
+                        copy_included_files(solution, ex_pack.includes, None, &solution_out_dir)?;
+                        if let Some(toolchain) = ex_pack.toolchain {
+                            apply_toolchain(toolchain, &solution_out_dir)?;
+                        }
+                    }
                     exercise_output_paths.insert(ex_pack.path.to_path_buf(), ex_pack_out_dir);
                 }
             }
         }
 
-        Ok(exercise_output_paths)
+        errors.finish(exercise_output_paths)
     }
 }
 
@@ -126,13 +194,42 @@ pub struct ExercisePackage<'track> {
     name: &'track str,
     path: &'track Path,
     includes: &'track [String],
+    generate: Option<&'track str>,
+    solution: Option<&'track Path>,
+    toolchain: Option<&'track ExerciseToolchain>,
+    hints: &'track [PathBuf],
+    hidden_tests: Option<&'track Path>,
+}
+
+impl<'track> ExercisePackage<'track> {
+    /// Pin the toolchain and set the edition the package requires
+    pub fn toolchain(&mut self, toolchain: &'track ExerciseToolchain) -> &mut Self {
+        self.toolchain = Some(toolchain);
+        self
+    }
+
+    /// Set the hints of the exercise, which are written into the `hints` folder of the
+    /// package if the collection has [`ExerciseCollectionBuilder::hints_folder`] set
+    pub fn hints(&mut self, hints: &'track [PathBuf]) -> &mut Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Set the folder of instructor-only tests of the exercise, whose files are left out
+    /// of the package even if they match its includes
+    pub fn hidden_tests(&mut self, hidden_tests: Option<&'track Path>) -> &mut Self {
+        self.hidden_tests = hidden_tests;
+        self
+    }
 }
 
+/// Builds an [`ExerciseCollection`]. See [`ExerciseCollection::builder`].
 pub struct ExerciseCollectionBuilder<'track> {
     collection: ExerciseCollection<'track>,
 }
 
 impl<'track> ExerciseCollectionBuilder<'track> {
+    /// Start a new module. Call [`ModuleExercisesBuilder::add`] to add it to the collection.
     pub fn module(
         &mut self,
         name: &'track str,
@@ -148,17 +245,39 @@ impl<'track> ExerciseCollectionBuilder<'track> {
         }
     }
 
+    /// Continue the module that was added last, to add more units to it
+    pub(crate) fn reopen_module(&mut self) -> ModuleExercisesBuilder<'track, '_> {
+        let module_exercises = self
+            .collection
+            .module_exercises
+            .pop()
+            .expect("a module was added");
+        ModuleExercisesBuilder {
+            collection_builder: self,
+            module_exercises,
+        }
+    }
+
+    /// Also write the hints of every exercise into a `hints` folder in its package, so that
+    /// students can open them one at a time next to the code
+    pub fn hints_folder(&mut self, hints_folder: bool) -> &mut Self {
+        self.collection.hints_folder = hints_folder;
+        self
+    }
+
     pub fn build(self) -> ExerciseCollection<'track> {
         self.collection
     }
 }
 
+/// Builds [`ModuleExercises`]. See [`ExerciseCollectionBuilder::module`].
 pub struct ModuleExercisesBuilder<'track, 'c> {
     collection_builder: &'c mut ExerciseCollectionBuilder<'track>,
     module_exercises: ModuleExercises<'track>,
 }
 
 impl<'track, 'c> ModuleExercisesBuilder<'track, 'c> {
+    /// Start a new unit. Call [`UnitExercisesBuilder::add`] to add it to the module.
     pub fn unit<'m>(
         &'m mut self,
         name: &'track str,
@@ -174,6 +293,20 @@ impl<'track, 'c> ModuleExercisesBuilder<'track, 'c> {
         }
     }
 
+    /// Continue the unit that was added last, to add more packages to it
+    pub(crate) fn reopen_unit(&mut self) -> UnitExercisesBuilder<'track, 'c, '_> {
+        let unit_exercises = self
+            .module_exercises
+            .unit_exercises
+            .pop()
+            .expect("a unit was added");
+        UnitExercisesBuilder {
+            module_builder: self,
+            unit_exercises,
+        }
+    }
+
+    /// Add the module to the collection
     pub fn add(self) -> &'c mut ExerciseCollectionBuilder<'track> {
         self.collection_builder
             .collection
@@ -183,22 +316,39 @@ impl<'track, 'c> ModuleExercisesBuilder<'track, 'c> {
     }
 }
 
+/// Builds [`UnitExercises`]. See [`ModuleExercisesBuilder::unit`].
 pub struct UnitExercisesBuilder<'track, 'c, 'm> {
     module_builder: &'m mut ModuleExercisesBuilder<'track, 'c>,
     unit_exercises: UnitExercises<'track>,
 }
 
 impl<'track, 'c, 'm> UnitExercisesBuilder<'track, 'c, 'm> {
-    pub fn package(&mut self, name: &'track str, path: &'track Path, includes: &'track [String]) {
+    /// Add an exercise package, numbered in the order packages are added.
+    /// The files in `solution` matching `includes` are copied in the teacher edition.
+    pub fn package(
+        &mut self,
+        name: &'track str,
+        path: &'track Path,
+        includes: &'track [String],
+        generate: Option<&'track str>,
+        solution: Option<&'track Path>,
+    ) -> &mut ExercisePackage<'track> {
         let index = self.unit_exercises.exercises.len() + 1;
         self.unit_exercises.exercises.push(ExercisePackage {
             index,
             name,
             path,
             includes,
-        })
+            generate,
+            solution,
+            toolchain: None,
+            hints: &[],
+            hidden_tests: None,
+        });
+        self.unit_exercises.exercises.last_mut().unwrap()
     }
 
+    /// Add the unit to the module
     pub fn add(self) -> &'m mut ModuleExercisesBuilder<'track, 'c> {
         self.module_builder
             .module_exercises
@@ -207,3 +357,146 @@ impl<'track, 'c, 'm> UnitExercisesBuilder<'track, 'c, 'm> {
         self.module_builder
     }
 }
+
+/// Copy the files in `path` that match any of the `includes` globs into `dest`,
+/// keeping their location relative to `path`. Files in `exclude` are left out.
+fn copy_included_files(
+    path: &Path,
+    includes: &[String],
+    exclude: Option<&Path>,
+    dest: &Path,
+) -> Result<(), RenderExercisesError> {
+    let included = included_files(path, includes)?;
+    for included_file in included
+        .into_iter()
+        .filter(|f| !exclude.is_some_and(|exclude| f.starts_with(exclude)))
+    {
+        let included_file_dest = dest.join(nfc_path(included_file.strip_prefix(path).unwrap()));
+        included_file_dest.parent().unwrap().create_dir_all()?;
+        included_file.copy(included_file_dest)?;
+    }
+    Ok(())
+}
+
+/// Write the `hints` of an exercise into the `hints` folder of its package in `dir`,
+/// leaving out their front matter, and their teacher-only content unless it's the teacher
+/// `edition`
+fn write_hints(
+    hints: &[PathBuf],
+    dir: &Path,
+    edition: Edition,
+) -> Result<(), RenderExercisesError> {
+    if hints.is_empty() {
+        return Ok(());
+    }
+    let hints_dir = dir.join(HINTS_DIR);
+    hints_dir.create_dir_all()?;
+    for hint in hints {
+        let content = hint.read_to_string()?;
+        let (_, body) = front_matter(&content);
+        let body = teacher_only(body, edition.is_teacher())
+            .attach_printable_lazy(|| format!("in hint {}", hint.to_string_lossy()))?;
+        hints_dir
+            .join(hint.file_name().unwrap())
+            .create_file()?
+            .write_all(body.trim_start())?;
+    }
+    Ok(())
+}
+
+/// Pin the toolchain the exercise package in `dir` requires in a `rust-toolchain.toml`,
+/// and set its edition in its `Cargo.toml`
+fn apply_toolchain(toolchain: &ExerciseToolchain, dir: &Path) -> Result<(), RenderExercisesError> {
+    if let Some(rust_toolchain) = toolchain.rust_toolchain_toml() {
+        dir.join("rust-toolchain.toml")
+            .create_file()?
+            .write_all(rust_toolchain)?;
+    }
+    let manifest = dir.join("Cargo.toml");
+    if let (Some(edition), true) = (toolchain.edition, manifest.is_file()) {
+        let content = manifest.read_to_string()?;
+        manifest
+            .create_file()?
+            .write_all(set_edition(&content, edition))?;
+    }
+    Ok(())
+}
+
+/// List the files in the exercise directory at `path` that match any of the `includes` globs
+pub(crate) fn included_files(
+    path: &Path,
+    includes: &[String],
+) -> Result<Vec<PathBuf>, RenderExercisesError> {
+    let content = path.get_dir_content()?;
+
+    // Create globset to match included files
+    let mut globset = globset::GlobSetBuilder::new();
+    for include in includes {
+        globset.add(
+            globset::Glob::new(path.join(include).to_str().unwrap())
+                .into_report()
+                .attach_printable_lazy(|| format!("Error parsing include glob '{include}'"))
+                .change_context(RenderExercisesError::default())?,
+        );
+    }
+    let globset = globset.build().unwrap();
+
+    let mut files: Vec<_> = content
+        .files
+        .into_iter()
+        .filter(|f| globset.is_match(f))
+        .map(PathBuf::from)
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// A fresh folder in the system temp folder to generate exercise data in
+fn scratch_dir() -> PathBuf {
+    static SCRATCH: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "modmod-generate-{}-{}",
+        std::process::id(),
+        SCRATCH.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Run the data generation step of an exercise in `scratch_dir`, after copying the
+/// exercise sources at `exercise_path` into it, leaving out build output. Generated
+/// files that match the exercise includes are copied into the scaffold along with the
+/// rest of the sources. The scaffold output directory is exposed to the command through
+/// the `MODMOD_SCAFFOLD_DIR` environment variable.
+fn run_generate_step(
+    command: &str,
+    exercise_path: &Path,
+    scratch_dir: &Path,
+    scaffold_dir: &Path,
+    runner: &TaskRunner,
+) -> Result<(), RenderExercisesError> {
+    for file in exercise_path.get_dir_content()?.files {
+        let relative = Path::new(&file).strip_prefix(exercise_path).unwrap();
+        if relative.starts_with("target") {
+            continue;
+        }
+        let dest = scratch_dir.join(relative);
+        dest.parent().unwrap().create_dir_all()?;
+        file.copy(dest)?;
+    }
+
+    let task = Task::shell(format!("generate {command}"), command)
+        .current_dir(scratch_dir)
+        .env("MODMOD_SCAFFOLD_DIR", scaffold_dir);
+
+    runner
+        .run_one(task)
+        .into_result()
+        .attach_printable_lazy(|| {
+            format!(
+                "Generate command '{command}' for {path} exited unsuccessfully",
+                path = exercise_path.to_string_lossy()
+            )
+        })
+        .change_context(RenderExercisesError::default())?;
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::tasks::TaskOutput;
+
+/// A test that passed in some runs of a test suite and failed in others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakyTest {
+    /// The exercise package the test is in, relative to the output folder
+    pub package: String,
+    pub test: String,
+    pub failures: usize,
+    /// The runs that reported a result for the test
+    pub runs: usize,
+}
+
+impl fmt::Display for FlakyTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} failed in {} of {} runs",
+            self.package, self.test, self.failures, self.runs
+        )
+    }
+}
+
+/// Collect the tests of `package` whose outcome differs between the `runs` of its test suite.
+/// Tests that fail every time are not flaky: exercises often come with tests that only
+/// pass once students have solved them.
+pub(crate) fn find_flaky(package: &str, runs: &[TaskOutput]) -> Vec<FlakyTest> {
+    let mut outcomes: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for run in runs {
+        for (test, passed) in test_results(&run.stdout) {
+            let (passes, failures) = outcomes.entry(test).or_default();
+            match passed {
+                true => *passes += 1,
+                false => *failures += 1,
+            }
+        }
+    }
+    outcomes
+        .into_iter()
+        .filter(|(_, (passes, failures))| *passes > 0 && *failures > 0)
+        .map(|(test, (passes, failures))| FlakyTest {
+            package: package.to_string(),
+            test: test.to_string(),
+            failures,
+            runs: passes + failures,
+        })
+        .collect()
+}
+
+/// The outcome of each test in the output of `cargo test`, which has a
+/// `test <name> ... ok` or `test <name> ... FAILED` line per test
+fn test_results(stdout: &[u8]) -> Vec<(&str, bool)> {
+    let Ok(stdout) = std::str::from_utf8(stdout) else {
+        return vec![];
+    };
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("test "))
+        .filter_map(|line| {
+            let (test, outcome) = line.rsplit_once(" ... ")?;
+            match outcome.trim_end() {
+                "ok" => Some((test, true)),
+                "FAILED" => Some((test, false)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::tasks::{Task, TaskStatus};
+
+    #[test]
+    fn tests_with_differing_outcomes_are_flaky() {
+        let run = |stdout: &str| TaskOutput {
+            task: Task::new("cargo test", "cargo"),
+            status: TaskStatus::Exited(Default::default()),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: vec![],
+            attempts: 1,
+            elapsed: Duration::ZERO,
+            log: None,
+        };
+        let runs = [
+            run("running 3 tests\ntest tests::timeout ... ok\ntest tests::todo ... FAILED\ntest tests::slow ... ignored\n\ntest result: FAILED. 1 passed; 1 failed; 1 ignored\n"),
+            run("running 3 tests\ntest tests::timeout ... FAILED\ntest tests::todo ... FAILED\ntest tests::slow ... ignored\n"),
+            run("running 3 tests\ntest tests::timeout ... ok\ntest tests::todo ... FAILED\ntest tests::slow ... ignored\n"),
+        ];
+        assert_eq!(
+            find_flaky("exercises/2-foundations-of-rust/1-async/1-timeout", &runs),
+            vec![FlakyTest {
+                package: "exercises/2-foundations-of-rust/1-async/1-timeout".to_string(),
+                test: "tests::timeout".to_string(),
+                failures: 1,
+                runs: 3,
+            }]
+        );
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    convert::Infallible,
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar::Date,
+    exercises::included_files,
+    load::Indexed,
+    tasks::{Task, TaskRunner},
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct FreshnessError {}
+
+impl fmt::Display for FreshnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to report content freshness")
+    }
+}
+
+impl error_stack::Context for FreshnessError {}
+
+/// Units whose review is due within this many days are reported as due soon
+const DUE_SOON_DAYS: i64 = 30;
+
+/// Freshness configuration as defined in the `[freshness]` table of the track TOML
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FreshnessConfig {
+    /// Every unit should be reviewed or changed within this many months
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_within_months: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FreshnessStatus {
+    Fresh,
+    DueSoon,
+    Overdue,
+    /// The unit was never reviewed, and has no history in git
+    Unknown,
+}
+
+impl fmt::Display for FreshnessStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FreshnessStatus::Fresh => "fresh",
+            FreshnessStatus::DueSoon => "due soon",
+            FreshnessStatus::Overdue => "overdue",
+            FreshnessStatus::Unknown => "unknown",
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnitFreshness {
+    /// The unit number including the module index, like `2.1`
+    pub number: String,
+    pub name: String,
+    /// The `reviewed` date of the unit
+    pub reviewed: Option<Date>,
+    /// The date of the last commit that changed the content or exercises of the unit
+    pub changed: Option<Date>,
+    /// The date the next review is due, counting from the later of the other two
+    pub due: Option<Date>,
+    pub status: FreshnessStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleFreshness {
+    pub index: usize,
+    pub name: String,
+    pub units: Vec<UnitFreshness>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FreshnessReport {
+    pub review_within_months: u32,
+    /// The date the report was made for
+    pub date: Date,
+    pub modules: Vec<ModuleFreshness>,
+}
+
+impl FreshnessReport {
+    pub fn render(&self, format: FreshnessFormat) -> String {
+        match format {
+            FreshnessFormat::Text => self.to_text(),
+            FreshnessFormat::Json => serde_json::to_string_pretty(self).unwrap() + "\n",
+            FreshnessFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// The units that are overdue or unknown, which need attention
+    pub fn out_of_sla(&self) -> impl Iterator<Item = &UnitFreshness> {
+        self.modules.iter().flat_map(|m| &m.units).filter(|u| {
+            matches!(
+                u.status,
+                FreshnessStatus::Overdue | FreshnessStatus::Unknown
+            )
+        })
+    }
+
+    fn summary(&self) -> String {
+        let units = self.modules.iter().map(|m| m.units.len()).sum::<usize>();
+        format!(
+            "{} of {units} units are out of SLA, which is a review within {} months, as of {}",
+            self.out_of_sla().count(),
+            self.review_within_months,
+            self.date
+        )
+    }
+
+    /// Render the report as a list per module, with units out of SLA first
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for module in self.modules.iter() {
+            writeln!(text, "Module {}: {}", module.index, module.name).unwrap();
+            for unit in sorted_by_urgency(&module.units) {
+                write!(text, "    {} {}: {}", unit.number, unit.name, unit.status).unwrap();
+                if let Some(due) = unit.due {
+                    write!(text, ", due {due}").unwrap();
+                }
+                let mut history = vec![];
+                if let Some(reviewed) = unit.reviewed {
+                    history.push(format!("reviewed {reviewed}"));
+                }
+                if let Some(changed) = unit.changed {
+                    history.push(format!("changed {changed}"));
+                }
+                if !history.is_empty() {
+                    write!(text, " ({})", history.join(", ")).unwrap();
+                }
+                text.push('\n');
+            }
+        }
+        writeln!(text, "{}", self.summary()).unwrap();
+        text
+    }
+
+    /// Render the report as a Markdown dashboard with a table per module, to publish in a
+    /// wiki or an issue
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Content freshness\n\n");
+        writeln!(markdown, "{}.\n", self.summary()).unwrap();
+        let date = |date: Option<Date>| date.map(|d| d.to_string()).unwrap_or_default();
+        for module in self.modules.iter() {
+            writeln!(markdown, "## Module {}: {}\n", module.index, module.name).unwrap();
+            markdown.push_str("| Unit | Status | Due | Reviewed | Changed |\n");
+            markdown.push_str("| --- | --- | --- | --- | --- |\n");
+            for unit in sorted_by_urgency(&module.units) {
+                let status = match unit.status {
+                    FreshnessStatus::Overdue | FreshnessStatus::Unknown => {
+                        format!("**{}**", unit.status)
+                    }
+                    _ => unit.status.to_string(),
+                };
+                writeln!(
+                    markdown,
+                    "| {} {} | {status} | {} | {} | {} |",
+                    unit.number,
+                    unit.name.replace('|', "\\|"),
+                    date(unit.due),
+                    date(unit.reviewed),
+                    date(unit.changed)
+                )
+                .unwrap();
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+}
+
+/// The units from the most to the least urgent, and by number if they are as urgent
+fn sorted_by_urgency(units: &[UnitFreshness]) -> Vec<&UnitFreshness> {
+    let mut units: Vec<_> = units.iter().collect();
+    units.sort_by_key(|u| match u.status {
+        FreshnessStatus::Unknown => (0, None),
+        _ => (1, u.due),
+    });
+    units
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FreshnessFormat {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+}
+
+impl FromStr for FreshnessFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(FreshnessFormat::Text),
+            "json" => Ok(FreshnessFormat::Json),
+            "markdown" => Ok(FreshnessFormat::Markdown),
+            _ => Err(format!(
+                "unknown freshness report format '{s}', expected text, json or markdown"
+            )),
+        }
+    }
+}
+
+impl Track {
+    /// Report when each unit is due for review, according to the freshness SLA of the track.
+    /// A unit counts as reviewed at the later of its `reviewed` date and the last commit that
+    /// changed its slides, exercises or quizzes. Without git, only `reviewed` dates are used.
+    pub fn freshness_report(
+        &self,
+        runner: &TaskRunner,
+        today: Date,
+    ) -> Result<FreshnessReport, FreshnessError> {
+        let Some(months) = self.freshness.review_within_months else {
+            return Err(Report::new(FreshnessError::default()).attach_printable(
+                "The track has no freshness SLA. Set `review_within_months` in its [freshness] table",
+            ));
+        };
+
+        let mut unit_files = UnitFiles {
+            root: &self.root,
+            files: vec![],
+            tasks: vec![],
+            has_files: vec![],
+        };
+        self.walk(&mut unit_files)?;
+        // Without git, outside of a repository, and for files without commits, there's no date
+        let mut changed = runner
+            .run(unit_files.tasks)
+            .into_iter()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok());
+        let changed = unit_files
+            .has_files
+            .into_iter()
+            .map(|has_files| match has_files {
+                true => changed.next().unwrap(),
+                false => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut reviews = UnitReviews {
+            months,
+            today,
+            changed: changed.into_iter(),
+            units: vec![],
+            modules: vec![],
+        };
+        self.walk_infallible(&mut reviews);
+
+        Ok(FreshnessReport {
+            review_within_months: months,
+            date: today,
+            modules: reviews.modules,
+        })
+    }
+}
+
+/// Collects the files of every unit while walking over the track, along with the tasks
+/// that find the last commit that changed them
+struct UnitFiles<'p> {
+    root: &'p Path,
+    /// The files of the unit that is being visited
+    files: Vec<PathBuf>,
+    tasks: Vec<Task>,
+    /// Whether each unit has files, and so a task
+    has_files: Vec<bool>,
+}
+
+impl<'t> Visitor<'t> for UnitFiles<'_> {
+    type Error = FreshnessError;
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), FreshnessError> {
+        self.files = unit.data.quizzes.clone();
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), FreshnessError> {
+        self.files.push(topic.data.content.clone());
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), FreshnessError> {
+        self.files.push(exercise.data.description.clone());
+        self.files.extend(exercise.data.hints.iter().cloned());
+        self.files.extend(
+            included_files(&exercise.data.path, &exercise.data.includes)
+                .change_context(FreshnessError::default())?,
+        );
+        Ok(())
+    }
+
+    fn leave_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        _unit: &'t Indexed<Unit>,
+    ) -> Result<(), FreshnessError> {
+        let files = std::mem::take(&mut self.files);
+        // Without paths, git would give the last commit of the whole repository
+        self.has_files.push(!files.is_empty());
+        if files.is_empty() {
+            return Ok(());
+        }
+        self.tasks.push(
+            Task::new(format!("git log {position}"), "git")
+                .args(["log", "-1", "--format=%cs", "--"])
+                .args(files)
+                .current_dir(self.root)
+                .no_retry(),
+        );
+        Ok(())
+    }
+}
+
+/// Determines when each unit is due for review while walking over the track
+struct UnitReviews {
+    months: u32,
+    today: Date,
+    /// The date of the last commit that changed the files of each unit
+    changed: std::vec::IntoIter<Option<Date>>,
+    /// The units of the module that is being visited
+    units: Vec<UnitFreshness>,
+    modules: Vec<ModuleFreshness>,
+}
+
+impl<'t> Visitor<'t> for UnitReviews {
+    type Error = Infallible;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        let reviewed = unit.data.reviewed;
+        let changed = self.changed.next().unwrap();
+        let due = reviewed.max(changed).map(|d| d.add_months(self.months));
+        let status = match due.map(|d| d.days() - self.today.days()) {
+            None => FreshnessStatus::Unknown,
+            Some(days) if days < 0 => FreshnessStatus::Overdue,
+            Some(days) if days <= DUE_SOON_DAYS => FreshnessStatus::DueSoon,
+            Some(_) => FreshnessStatus::Fresh,
+        };
+        self.units.push(UnitFreshness {
+            number: position.to_string(),
+            name: unit.data.name.clone(),
+            reviewed,
+            changed,
+            due,
+            status,
+        });
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.modules.push(ModuleFreshness {
+            index: module.index,
+            name: module.data.name.clone(),
+            units: std::mem::take(&mut self.units),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reviews_are_due_months_later() {
+        let date = |s: &str| s.parse::<Date>().unwrap();
+        assert_eq!(date("2025-03-02").add_months(12), date("2026-03-02"));
+        assert_eq!(date("2025-11-30").add_months(3), date("2026-02-28"));
+        assert_eq!(date("2024-01-31").add_months(1), date("2024-02-29"));
+        assert_eq!(date("2025-12-15").add_months(1), date("2026-01-15"));
+
+        let unit = |number: &str, status, due: Option<&str>| UnitFreshness {
+            number: number.to_string(),
+            name: format!("Unit {number}"),
+            reviewed: None,
+            changed: None,
+            due: due.map(date),
+            status,
+        };
+        let units = [
+            unit("1.1", FreshnessStatus::Fresh, Some("2027-01-01")),
+            unit("1.2", FreshnessStatus::Overdue, Some("2026-01-01")),
+            unit("1.3", FreshnessStatus::Unknown, None),
+        ];
+        let numbers: Vec<_> = sorted_by_urgency(&units)
+            .iter()
+            .map(|u| u.number.as_str())
+            .collect();
+        assert_eq!(numbers, ["1.3", "1.2", "1.1"]);
+    }
+}
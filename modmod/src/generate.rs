@@ -0,0 +1,301 @@
+//! Generating a course the way the `generate` subcommand does: loading the track with its
+//! hooks, selecting the content of a profile, tags and language, rendering it, and the steps
+//! that follow on the rendered output, like committing it, building it and bundling it.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    build::{BuildOptions, BuildOutcome, BuildTarget},
+    bundle::{Bundle, BundleKind, BundleOptions},
+    cache::VerificationCache,
+    doctor::Tool,
+    git::OutputCommit,
+    hooks::HooksConfig,
+    i18n::TranslationCoverage,
+    manifest::{sync_output, SyncSummary},
+    patch::{compare_output, GenPatchOptions, OutputChange, Patch},
+    progress::progress,
+    tags::TagFilter,
+    RenderOptions, RenderOutput, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct GenerateError {}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to generate course")
+    }
+}
+
+impl error_stack::Context for GenerateError {}
+
+/// How the render ends up in the output folder
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Render into the output folder
+    #[default]
+    Direct,
+    /// Render into a staging folder, and sync it into the output folder: only the files that
+    /// changed are written, and the files of an earlier render that are no longer produced
+    /// are removed
+    Clean,
+    /// Render into a staging folder, only to compare it with the output folder
+    Preview,
+    /// Render into a temporary folder, and write a patch to this file that updates the
+    /// output folder to it
+    Patch(PathBuf),
+}
+
+/// How [`Track::generate`] generates a course
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    /// How the track is rendered. Its `out_dir` is the output folder.
+    pub render: RenderOptions,
+    pub output: OutputMode,
+    /// The audience profile of the track to render for, whose tags are combined with `tags`
+    pub profile: Option<String>,
+    pub tags: TagFilter,
+    /// Render the translated content files of this language where they exist
+    pub lang: Option<String>,
+    /// Run the pre-load and post-render hooks of the track. Post-render hooks are not run
+    /// for previews.
+    pub hooks: bool,
+    /// Commit the output to a git repository in the output folder
+    pub git_init: bool,
+    /// The targets to build with external tools after rendering
+    pub build: Vec<BuildTarget>,
+    /// Fail if a build target is skipped because a tool it needs is not installed, rather
+    /// than reporting it in [`GenerateOutput::skipped_builds`]
+    pub require_tools: bool,
+    /// Skip verifying the exercise packages that passed before, according to this cache
+    pub cache: Option<VerificationCache>,
+    /// Run the tests of every exercise package this many times, see [`BuildOptions`]
+    pub test_runs: usize,
+    /// Bundle the built book and slides along with archives of the exercises
+    pub bundle: Option<BundleKind>,
+}
+
+/// A build target that was not built, as a tool it needs is not installed
+#[derive(Debug)]
+pub struct SkippedBuild {
+    pub target: BuildTarget,
+    pub missing: &'static Tool,
+}
+
+impl fmt::Display for SkippedBuild {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Skipped building {}, as {} is not installed. To install it: {}",
+            self.target,
+            self.missing.name,
+            self.missing.install_hint()
+        )
+    }
+}
+
+/// What [`Track::generate`] did
+#[derive(Debug)]
+pub struct GenerateOutput {
+    /// The folder the post-render hooks, git, the builds and the bundle worked in. That's the
+    /// output folder, unless the track was rendered into a folder of its own for a preview or
+    /// a patch, which is removed afterwards.
+    pub out_dir: PathBuf,
+    /// The language the track is written in, which files without a translation are
+    /// rendered in
+    pub track_language: String,
+    /// Which content files were translated, if a language was selected
+    pub translation: Option<TranslationCoverage>,
+    pub rendered: RenderOutput,
+    /// How a clean render was synced into the output folder
+    pub synced: Option<SyncSummary>,
+    pub commit: Option<OutputCommit>,
+    pub skipped_builds: Vec<SkippedBuild>,
+    pub bundle: Option<Bundle>,
+    /// What rendering would change in the output folder, for a preview
+    pub changes: Option<Vec<OutputChange>>,
+}
+
+impl Track {
+    /// Load the track at `track_toml_path` and generate the course as set by `opts`.
+    /// Previews and patches don't leave a render in the output folder to work on, so they
+    /// can't be combined with git, builds or bundles.
+    pub fn generate(
+        track_toml_path: impl AsRef<Path>,
+        opts: &GenerateOptions,
+    ) -> Result<GenerateOutput, GenerateError> {
+        let track_toml_path = track_toml_path.as_ref();
+        let render = &opts.render;
+        if (render.handouts || render.handout_notes) && !render.edition.is_teacher() {
+            return Err(Report::new(GenerateError::default()).attach_printable(
+                "Handouts hold the speaker notes, so they need the teacher edition",
+            ));
+        }
+        let follow_on = opts.git_init || !opts.build.is_empty() || opts.bundle.is_some();
+        if follow_on && matches!(opts.output, OutputMode::Preview | OutputMode::Patch(_)) {
+            return Err(Report::new(GenerateError::default())
+                .attach_printable("Previews and patches can't be committed, built or bundled"));
+        }
+
+        let runner = &render.runner;
+        if opts.hooks {
+            HooksConfig::of_track_toml(track_toml_path)
+                .and_then(|hooks| hooks.run_pre_load(track_toml_path, runner))
+                .change_context(GenerateError::default())?;
+        }
+        let mut track = Track::load(track_toml_path).change_context(GenerateError::default())?;
+
+        let mut tag_filter = opts.tags.clone();
+        if let Some(profile) = &opts.profile {
+            let Some(profile_filter) = track.profiles.get(profile) else {
+                return Err(Report::new(GenerateError::default())
+                    .attach_printable(format!("No profile named '{profile}' in track")));
+            };
+            tag_filter = tag_filter.merge(profile_filter);
+        }
+        track.filter_tags(&tag_filter);
+        let track_language = track.language.clone();
+        let translation = opts.lang.as_deref().map(|lang| track.localize(lang));
+
+        // Everything but direct renders goes into a folder of its own, which is then synced
+        // into the output folder, compared with it or turned into a patch for it
+        let out_dir = &render.out_dir;
+        let render_dir = match &opts.output {
+            OutputMode::Direct => out_dir.clone(),
+            OutputMode::Clean | OutputMode::Preview => {
+                std::env::temp_dir().join(format!("modmod_staging_{}", std::process::id()))
+            }
+            OutputMode::Patch(_) => std::env::temp_dir().join("modmod_tmp"),
+        };
+        let rendered = track
+            .render(&RenderOptions {
+                out_dir: render_dir.clone(),
+                // The pdf build target runs the pdf scripts
+                pdf: render.pdf || opts.build.contains(&BuildTarget::Pdf),
+                ..render.clone()
+            })
+            .change_context(GenerateError::default())?;
+
+        let synced = match opts.output {
+            OutputMode::Clean => {
+                let summary = sync_output(&render_dir, out_dir);
+                remove_render_dir(&render_dir)?;
+                Some(summary.change_context(GenerateError::default())?)
+            }
+            _ => None,
+        };
+        // A clean render ends up in the output folder, the others stay where they were rendered
+        let work_dir = match opts.output {
+            OutputMode::Clean => out_dir.clone(),
+            _ => render_dir.clone(),
+        };
+
+        if opts.hooks && opts.output != OutputMode::Preview {
+            track
+                .run_post_render_hooks(&work_dir, render.edition, runner)
+                .change_context(GenerateError::default())?;
+        }
+
+        let commit = match opts.git_init {
+            true => Some(
+                track
+                    .commit_output(&work_dir, runner)
+                    .change_context(GenerateError::default())?,
+            ),
+            false => None,
+        };
+
+        let build_opts = BuildOptions {
+            runner: runner.clone(),
+            cache: opts.cache.as_ref(),
+            test_runs: opts.test_runs,
+        };
+        let mut targets = opts.build.clone();
+        // A bundle holds the built book and slides
+        if opts.bundle.is_some() {
+            targets.extend([BuildTarget::Book, BuildTarget::Slides]);
+        }
+        targets.sort();
+        targets.dedup();
+        let mut skipped_builds = vec![];
+        for (target, i) in targets.iter().zip(1..) {
+            progress("build", i, targets.len(), target);
+            let outcome = track
+                .build(&work_dir, *target, &build_opts)
+                .change_context(GenerateError::default())?;
+            let BuildOutcome::Skipped { missing } = outcome else {
+                continue;
+            };
+            let skipped = SkippedBuild {
+                target: *target,
+                missing,
+            };
+            if opts.require_tools {
+                return Err(
+                    Report::new(GenerateError::default()).attach_printable(skipped.to_string())
+                );
+            }
+            skipped_builds.push(skipped);
+        }
+
+        let bundle = opts
+            .bundle
+            .map(|kind| {
+                track.bundle(
+                    &work_dir,
+                    &BundleOptions {
+                        kind,
+                        format: render.package,
+                        runner: runner.clone(),
+                    },
+                )
+            })
+            .transpose()
+            .change_context(GenerateError::default())?;
+
+        if let OutputMode::Patch(patch_file) = &opts.output {
+            Patch::render(GenPatchOptions {
+                new_dir: &render_dir,
+                old_dir: out_dir,
+                patch_file,
+            })
+            .change_context(GenerateError::default())?;
+            remove_render_dir(&render_dir)?;
+        }
+
+        let changes = match opts.output {
+            OutputMode::Preview => {
+                let changes = compare_output(&render_dir, out_dir);
+                remove_render_dir(&render_dir)?;
+                Some(changes.change_context(GenerateError::default())?)
+            }
+            _ => None,
+        };
+
+        Ok(GenerateOutput {
+            out_dir: work_dir,
+            track_language,
+            translation,
+            rendered,
+            synced,
+            commit,
+            skipped_builds,
+            bundle,
+            changes,
+        })
+    }
+}
+
+fn remove_render_dir(render_dir: &Path) -> Result<(), GenerateError> {
+    fs::remove_dir_all(render_dir)
+        .into_report()
+        .attach_printable_lazy(|| format!("Error removing {}", render_dir.to_string_lossy()))
+        .change_context(GenerateError::default())
+}
@@ -0,0 +1,123 @@
+//! Committing the rendered track to a git repository in the output folder, so that it can
+//! be pushed to a repository for students after every render.
+
+use std::{fmt, path::Path};
+
+use error_stack::{Report, Result, ResultExt};
+
+use crate::{
+    cache::sha256,
+    doctor::tool,
+    io::{PathExt, WriteExt},
+    manifest::MANIFEST_FILE,
+    tasks::{Task, TaskRunner},
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct GitError {}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to commit output to git")
+    }
+}
+
+impl error_stack::Context for GitError {}
+
+/// What git leaves out of the output repository: what builds add to the rendered track
+pub const GITIGNORE: &str = "target/\nbook/target/\nslides/node_modules/\nslides/dist/\n";
+
+/// How the rendered track was committed
+#[derive(Debug)]
+pub struct OutputCommit {
+    /// Whether the output folder was initialized as a repository, rather than already
+    /// being one
+    pub initialized: bool,
+    /// Whether there were changes to commit
+    pub committed: bool,
+    /// The tag of the commit, like `modmod-0.1.0-3f2a9c1d5e7b`
+    pub tag: String,
+}
+
+impl Track {
+    /// Commit everything rendered into `out_dir` to the git repository in it, initializing
+    /// it if there is none yet, and tag the commit with the version of modmod and the hash
+    /// of the track. The hash is that of the output manifest, so rendering the same track
+    /// gives the same tag.
+    pub fn commit_output(
+        &self,
+        out_dir: impl AsRef<Path>,
+        runner: &TaskRunner,
+    ) -> Result<OutputCommit, GitError> {
+        let out_dir = out_dir.as_ref();
+        let git = tool("git");
+        if !git.is_installed() {
+            return Err(Report::new(GitError::default()).attach_printable(format!(
+                "git is not installed. To install it: {}",
+                git.install_hint()
+            )));
+        }
+        let git = |name: &str, args: &[&str]| {
+            runner
+                .run_one(
+                    Task::new(name, "git")
+                        .args(args)
+                        .current_dir(out_dir)
+                        .no_retry(),
+                )
+                .into_result()
+                .change_context(GitError::default())
+        };
+
+        let initialized = !out_dir.join(".git").exists();
+        if initialized {
+            git("git init", &["init", "--quiet"])?;
+        }
+        out_dir
+            .join(".gitignore")
+            .create_file()?
+            .write_all(GITIGNORE)?;
+
+        let hash = sha256(
+            out_dir
+                .join(MANIFEST_FILE)
+                .read_to_string::<GitError>()?
+                .as_bytes(),
+        );
+        let version = env!("CARGO_PKG_VERSION");
+        let tag = format!("modmod-{version}-{}", &hash[..12]);
+
+        git("git add", &["add", "--all"])?;
+        let status = git("git status", &["status", "--porcelain"])?;
+        let committed = !status.stdout.is_empty();
+        if committed {
+            // Commit as modmod on machines without a git identity, like CI runners
+            let identity: &[&str] = match git("git config", &["config", "user.email"]) {
+                Ok(_) => &[],
+                Err(_) => &[
+                    "-c",
+                    "user.name=modmod",
+                    "-c",
+                    "user.email=modmod@localhost",
+                ],
+            };
+            let message = format!(
+                "Render {}\n\nmodmod {version}\nTrack hash {hash}",
+                self.name
+            );
+            let commit = ["commit", "--quiet", "--message", &message];
+            git("git commit", &[identity, &commit].concat())?;
+            let message = format!("modmod {version}, track hash {hash}");
+            let tag_args = ["tag", "--annotate", &tag, "--message", &message];
+            git("git tag", &[identity, &tag_args].concat())?;
+        }
+
+        Ok(OutputCommit {
+            initialized,
+            committed,
+            tag,
+        })
+    }
+}
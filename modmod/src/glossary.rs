@@ -0,0 +1,326 @@
+//! The glossary of a track, which keeps terminology consistent across modules written by
+//! different authors. The terms are defined in a glossary TOML, rendered into a Glossary
+//! chapter at the end of the book, and optionally linked to from the first time each book
+//! section uses them.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check::anchor,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    markdown::fence,
+    numbering::section_file_name,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderGlossaryError {}
+
+impl fmt::Display for RenderGlossaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render glossary")
+    }
+}
+
+impl error_stack::Context for RenderGlossaryError {}
+
+/// Name of the glossary page in the book sources
+pub const GLOSSARY_FILE: &str = "glossary.md";
+
+/// Glossary configuration as defined in the `[glossary]` table of the track TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryConfig {
+    /// The glossary TOML, relative to the track TOML
+    pub terms: PathBuf,
+    /// Whether the first use of a term in a book section links to its definition
+    #[serde(default)]
+    pub link: bool,
+}
+
+/// The terms defined in a glossary TOML
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Glossary {
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Term {
+    pub term: String,
+    /// Markdown
+    pub definition: String,
+    /// Other ways the term is written in the content, like its plural
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl Glossary {
+    /// Load the glossary at `path`, making sure no term or alias is defined twice
+    pub fn load<C: error_stack::Context + Default>(path: &Path) -> Result<Self, C> {
+        let glossary: Glossary = toml::from_str(&path.read_to_string()?)
+            .into_report()
+            .attach_printable_lazy(|| format!("Invalid glossary file {}", path.to_string_lossy()))
+            .change_context(C::default())?;
+        let mut names = HashSet::new();
+        for term in glossary.terms.iter() {
+            for name in std::iter::once(&term.term).chain(&term.aliases) {
+                if !names.insert(name.to_lowercase()) {
+                    return Err(Report::new(C::default()).attach_printable(format!(
+                        "Glossary file {} defines '{name}' more than once",
+                        path.to_string_lossy()
+                    )));
+                }
+            }
+        }
+        Ok(glossary)
+    }
+
+    /// The glossary page of the book, with the terms in alphabetical order
+    pub fn page(&self) -> String {
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|t| t.term.to_lowercase());
+        let mut page = String::from("# Glossary\n");
+        for term in terms {
+            page += &format!("\n## {}\n\n{}\n", term.term, term.definition.trim());
+            if !term.aliases.is_empty() {
+                page += &format!("\n*Also: {}*\n", term.aliases.join(", "));
+            }
+        }
+        page
+    }
+
+    /// Link the first use of each term in `content`, or one of its aliases, to its definition
+    /// on the glossary page at `glossary_path`. Terms are only linked in running text, not in
+    /// headings, code, HTML or existing links.
+    pub fn link_terms(&self, content: &str, glossary_path: &str) -> String {
+        // Look for longer names first, so that `smart pointer` wins over `pointer`
+        let mut names: Vec<_> = self
+            .terms
+            .iter()
+            .flat_map(|t| {
+                let anchor = anchor(&t.term);
+                std::iter::once(&t.term)
+                    .chain(&t.aliases)
+                    .map(move |name| (name.to_ascii_lowercase(), anchor.clone()))
+            })
+            .collect();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+        let mut linked = HashSet::new();
+        let mut output = String::with_capacity(content.len());
+        let mut open_fence: Option<&str> = None;
+        for line in content.lines() {
+            let trimmed = line.trim_start();
+            if let Some(marker) = open_fence {
+                if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                    open_fence = None;
+                }
+                output.push_str(line);
+            } else if let Some(marker) = fence(trimmed) {
+                open_fence = Some(marker);
+                output.push_str(line);
+            } else if trimmed.starts_with(['#', '<', '{']) {
+                output.push_str(line);
+            } else {
+                let mut end = 0;
+                for (start, span_end) in plain_spans(line) {
+                    output.push_str(&line[end..start]);
+                    let mut rest = start;
+                    while let Some((at, len, anchor)) =
+                        first_use(&line[rest..span_end], &names, &linked)
+                    {
+                        let word = &line[rest + at..rest + at + len];
+                        output.push_str(&line[rest..rest + at]);
+                        output.push_str(&format!("[{word}]({glossary_path}#{anchor})"));
+                        linked.insert(anchor);
+                        rest += at + len;
+                    }
+                    output.push_str(&line[rest..span_end]);
+                    end = span_end;
+                }
+                output.push_str(&line[end..]);
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// The first whole-word use in `text` of one of the `names` whose anchor is not `linked` yet,
+/// as its offset, length and anchor
+fn first_use(
+    text: &str,
+    names: &[(String, String)],
+    linked: &HashSet<String>,
+) -> Option<(usize, usize, String)> {
+    let lowercase = text.to_ascii_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    names
+        .iter()
+        .filter(|(_, anchor)| !linked.contains(anchor))
+        .filter_map(|(name, anchor)| {
+            let at = lowercase.match_indices(name.as_str()).find_map(|(at, _)| {
+                let before = lowercase[..at].chars().next_back();
+                let after = lowercase[at + name.len()..].chars().next();
+                (!is_word(before) && !is_word(after)).then_some(at)
+            })?;
+            Some((at, name.len(), anchor.clone()))
+        })
+        .min_by_key(|(at, _, _)| *at)
+}
+
+/// The byte ranges of `line` that hold running text, leaving out inline code, links and
+/// HTML tags
+fn plain_spans(line: &str) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        let skip = if rest.starts_with('`') {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            rest[ticks..]
+                .find(&"`".repeat(ticks))
+                .map(|end| ticks + end + ticks)
+        } else if rest.starts_with('[') {
+            rest.find(']')
+                .map(|end| match rest[end + 1..].starts_with('(') {
+                    true => rest[end..]
+                        .find(')')
+                        .map_or(rest.len(), |close| end + close + 1),
+                    false => end + 1,
+                })
+        } else if rest.starts_with('<') {
+            rest.find('>').map(|end| end + 1)
+        } else {
+            None
+        };
+        match skip {
+            Some(len) => {
+                spans.push((start, i));
+                i += len;
+                start = i;
+            }
+            None => i += rest.chars().next().unwrap().len_utf8(),
+        }
+    }
+    spans.push((start, line.len()));
+    spans
+}
+
+impl Track {
+    /// Add the glossary of the track, if it has one, to the end of the rendered book in
+    /// `out_dir`, and link the terms in the sections of the units `filter` selects to their
+    /// definitions if configured
+    pub fn render_glossary(
+        &self,
+        out_dir: impl AsRef<Path>,
+        filter: &RenderFilter,
+    ) -> Result<(), RenderGlossaryError> {
+        let Some(config) = &self.glossary else {
+            return Ok(());
+        };
+        let glossary = Glossary::load::<RenderGlossaryError>(&config.terms)?;
+        let book_src_dir = out_dir.as_ref().join("book").join("src");
+        // The other pages got their license footer before the glossary was added
+        let mut page = glossary.page();
+        if let Some(license) = &self.license {
+            page += &license.book_footer();
+        }
+        book_src_dir
+            .join(GLOSSARY_FILE)
+            .create_file()?
+            .write_all(page)?;
+
+        // A link after the last numbered chapter makes the glossary a suffix chapter
+        let summary_path = book_src_dir.join("SUMMARY.md");
+        let summary = summary_path.read_to_string()?;
+        summary_path.create_file()?.write_all(format!(
+            "{}\n\n[Glossary]({GLOSSARY_FILE})\n",
+            summary.trim_end()
+        ))?;
+
+        if config.link {
+            self.walk(&mut TermLinks {
+                filter,
+                book_src_dir: &book_src_dir,
+                glossary: &glossary,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Links the terms in every unit in the book to their definitions while walking over the track
+struct TermLinks<'p> {
+    /// The units that were rendered into the book
+    filter: &'p RenderFilter,
+    book_src_dir: &'p Path,
+    glossary: &'p Glossary,
+}
+
+impl<'t> Visitor<'t> for TermLinks<'_> {
+    type Error = RenderGlossaryError;
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RenderGlossaryError> {
+        if !self.filter.includes(module, unit) {
+            return Ok(());
+        }
+        let section_path = self.book_src_dir.join(section_file_name(&unit.data.name));
+        let section = section_path.read_to_string()?;
+        section_path
+            .create_file()?
+            .write_all(self.glossary.link_terms(&section, GLOSSARY_FILE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_uses_of_terms_link_to_their_definitions() {
+        let glossary: Glossary = toml::from_str(
+            r#"
+            [[terms]]
+            term = "Borrow checker"
+            definition = "Checks references."
+
+            [[terms]]
+            term = "Ownership"
+            definition = "Who frees a value."
+            aliases = ["owned", "owner"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            glossary.page(),
+            "# Glossary\n\n## Borrow checker\n\nChecks references.\n\n\
+            ## Ownership\n\nWho frees a value.\n\n*Also: owned, owner*\n"
+        );
+
+        let content = "# Ownership\n\nThe `owner` of an [owned](x.md) value: the owner.\n\
+            ```rust\nlet owner = 1;\n```\nOwnership and the borrow checker, ownership again.\n";
+        assert_eq!(
+            glossary.link_terms(content, "glossary.md"),
+            "# Ownership\n\nThe `owner` of an [owned](x.md) value: the \
+            [owner](glossary.md#ownership).\n```rust\nlet owner = 1;\n```\n\
+            Ownership and the [borrow checker](glossary.md#borrow-checker), ownership again.\n"
+        );
+    }
+}
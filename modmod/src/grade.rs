@@ -0,0 +1,542 @@
+//! Grading a student's submission of the exercises, so that every submission is graded by
+//! the same criteria: the crates in the submission are matched to the exercises of the
+//! track, and scored by the share of the instructor-only hidden tests of each exercise that
+//! pass, which students never get to see and so can't game.
+
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::Serialize;
+
+use crate::{
+    build::{exercise_packages, exercise_target_dir},
+    io::{PathExt, WriteExt},
+    numbering::ExerciseNumber,
+    tasks::{Task, TaskOutput, TaskRunner, TaskStatus},
+    Exercise, RenderFilter, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct GradeError {}
+
+impl fmt::Display for GradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to grade submission")
+    }
+}
+
+impl error_stack::Context for GradeError {}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GradeFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for GradeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(GradeFormat::Text),
+            "json" => Ok(GradeFormat::Json),
+            _ => Err(format!(
+                "unknown report format '{s}', expected text or json"
+            )),
+        }
+    }
+}
+
+/// What to grade
+#[derive(Debug, Default)]
+pub struct GradeOptions {
+    /// The units whose exercises are graded
+    pub filter: RenderFilter,
+    pub runner: TaskRunner,
+}
+
+/// How the submission of an exercise was graded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GradeStatus {
+    /// The tests of the submitted crate were run
+    Graded,
+    /// The submitted crate doesn't compile
+    CompileError,
+    /// No crate in the submission matches the exercise
+    Missing,
+}
+
+impl fmt::Display for GradeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GradeStatus::Graded => "graded",
+            GradeStatus::CompileError => "did not compile",
+            GradeStatus::Missing => "not submitted",
+        })
+    }
+}
+
+/// The number of tests that passed and failed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TestCounts {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+impl TestCounts {
+    pub fn total(&self) -> usize {
+        self.passed + self.failed
+    }
+}
+
+/// The grade of the submission of an exercise
+#[derive(Debug, Serialize)]
+pub struct ExerciseGrade {
+    pub number: ExerciseNumber,
+    pub name: String,
+    /// The crate in the submission that was graded for the exercise
+    pub package: Option<PathBuf>,
+    pub status: GradeStatus,
+    /// The tests that came with the submitted crate
+    pub tests: TestCounts,
+    /// The hidden tests of the exercise
+    pub hidden_tests: TestCounts,
+    /// The percentage of the hidden tests that passed, or of the tests of the crate for an
+    /// exercise without hidden tests, and 0 for a crate that is missing or doesn't compile.
+    /// A crate that compiles without any tests to run has no score.
+    pub score: Option<f64>,
+}
+
+/// The grades of a submission
+#[derive(Debug, Serialize)]
+pub struct GradeReport {
+    pub submission: PathBuf,
+    pub exercises: Vec<ExerciseGrade>,
+    /// The crates in the submission that match no exercise
+    pub unmatched: Vec<PathBuf>,
+    /// The average score of the exercises that have one
+    pub score: Option<f64>,
+}
+
+impl GradeReport {
+    pub fn render(&self, format: GradeFormat) -> String {
+        match format {
+            GradeFormat::Text => self.to_string(),
+            GradeFormat::Json => serde_json::to_string_pretty(self).unwrap() + "\n",
+        }
+    }
+}
+
+impl fmt::Display for GradeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for grade in self.exercises.iter() {
+            match grade.score {
+                Some(score) => write!(f, "{score:>5.1}% ")?,
+                None => write!(f, "{:>6} ", "-")?,
+            }
+            write!(f, "{} '{}': ", grade.number, grade.name)?;
+            match grade.status {
+                GradeStatus::Graded => {
+                    write!(
+                        f,
+                        "{} of {} tests passed",
+                        grade.tests.passed,
+                        grade.tests.total()
+                    )?;
+                    if grade.hidden_tests.total() > 0 {
+                        write!(
+                            f,
+                            " (not scored), {} of {} hidden tests passed",
+                            grade.hidden_tests.passed,
+                            grade.hidden_tests.total()
+                        )?;
+                    }
+                }
+                status => write!(f, "{status}")?,
+            }
+            if let Some(package) = &grade.package {
+                write!(f, " ({})", package.to_string_lossy())?;
+            }
+            writeln!(f)?;
+        }
+        for package in self.unmatched.iter() {
+            writeln!(
+                f,
+                "warning: {} matches no exercise",
+                package.to_string_lossy()
+            )?;
+        }
+        match self.score {
+            Some(score) => writeln!(f, "Score: {score:.1}%"),
+            None => writeln!(f, "Score: none, as no exercise has tests to score it by"),
+        }
+    }
+}
+
+/// A crate in the submission, matched to an exercise
+struct Submitted<'track> {
+    grade: usize,
+    exercise: &'track Exercise,
+    package: PathBuf,
+    /// The copy of the crate the tests are run in, which the hidden tests are added to
+    scratch: PathBuf,
+}
+
+impl Track {
+    /// Grade the submission in `submission`, a folder holding the crates of a student's
+    /// exercises, like the rendered `exercises` folder they worked in. Crates are matched
+    /// to the exercises selected by `opts` by their location relative to the submission,
+    /// which is compared to where the exercise is rendered, or else by their package name.
+    /// Exercises that don't come with a Cargo package are left out.
+    /// The tests of every crate are run in a copy of it, to which the hidden tests of its
+    /// exercise are added afterwards.
+    pub fn grade(
+        &self,
+        submission: impl AsRef<Path>,
+        opts: &GradeOptions,
+    ) -> Result<GradeReport, GradeError> {
+        let submission = submission.as_ref();
+        let crates: Vec<_> = exercise_packages(submission)
+            .change_context(GradeError::default())?
+            .into_iter()
+            .filter(|p| {
+                // Build output and downloaded packages are not part of the submission
+                !p.strip_prefix(submission).unwrap().components().any(|c| {
+                    matches!(c, Component::Normal(name) if name == "target" || name.to_string_lossy().starts_with('.'))
+                })
+            })
+            .collect();
+        let names: Vec<_> = crates.iter().map(|c| package_name(c)).collect();
+
+        let mut used = vec![false; crates.len()];
+        let mut grades = vec![];
+        let mut submitted = vec![];
+        let scratch_dir =
+            ScratchDir(std::env::temp_dir().join(format!("modmod_grade_{}", std::process::id())));
+        let selected: Vec<_> = self
+            .exercise_packages(&opts.filter)
+            .into_iter()
+            .map(|p| p.number)
+            .collect();
+        // All exercises are matched, so that crates of exercises that weren't selected don't
+        // count as unmatched
+        for package in self.exercise_packages(&RenderFilter::default()) {
+            let by_path = (1..=package.dir.components().count())
+                .rev()
+                .find_map(|depth| {
+                    let skip = package.dir.components().count() - depth;
+                    let suffix: PathBuf = package.dir.components().skip(skip).collect();
+                    let matches: Vec<_> = (0..crates.len())
+                        .filter(|&i| !used[i] && crates[i].ends_with(&suffix))
+                        .collect();
+                    (matches.len() == 1).then(|| matches[0])
+                });
+            let name = package_name(&package.exercise.path);
+            let by_name = || {
+                let matches: Vec<_> = (0..crates.len())
+                    .filter(|&i| !used[i] && name.is_some() && names[i] == name)
+                    .collect();
+                (matches.len() == 1).then(|| matches[0])
+            };
+            let matched = by_path.or_else(by_name);
+            if let Some(i) = matched {
+                used[i] = true;
+            }
+            // Exercises without a crate to start from can't be graded by their tests
+            if !selected.contains(&package.number)
+                || !package.exercise.path.join("Cargo.toml").is_file()
+            {
+                continue;
+            }
+
+            grades.push(ExerciseGrade {
+                number: package.number,
+                name: package.exercise.name.clone(),
+                package: matched.map(|i| crates[i].strip_prefix(submission).unwrap().to_path_buf()),
+                status: GradeStatus::Missing,
+                tests: TestCounts::default(),
+                hidden_tests: TestCounts::default(),
+                score: Some(0.0),
+            });
+            if let Some(i) = matched {
+                let scratch = scratch_dir.0.join(grades.len().to_string());
+                copy_crate(&crates[i], &scratch)?;
+                submitted.push(Submitted {
+                    grade: grades.len() - 1,
+                    exercise: package.exercise,
+                    package: crates[i].clone(),
+                    scratch,
+                });
+            }
+        }
+
+        self.run_tests(&submitted, &mut grades, &opts.runner)?;
+
+        let scores: Vec<_> = grades.iter().filter_map(|g| g.score).collect();
+        let score = (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64);
+        Ok(GradeReport {
+            submission: submission.to_path_buf(),
+            exercises: grades,
+            unmatched: crates
+                .iter()
+                .zip(used)
+                .filter(|(_, used)| !used)
+                .map(|(c, _)| c.strip_prefix(submission).unwrap().to_path_buf())
+                .collect(),
+            score,
+        })
+    }
+
+    /// Compile the `submitted` crates, run their tests and then their hidden tests, and
+    /// record the outcomes in their `grades`
+    fn run_tests(
+        &self,
+        submitted: &[Submitted],
+        grades: &mut [ExerciseGrade],
+        runner: &TaskRunner,
+    ) -> Result<(), GradeError> {
+        let cargo = |name: &str, s: &Submitted, args: &[&str]| {
+            Task::new(format!("{name} {}", s.package.to_string_lossy()), "cargo")
+                .args(args)
+                .current_dir(&s.scratch)
+                .env("CARGO_TARGET_DIR", exercise_target_dir(s.grade + 1))
+                // A failure is an outcome rather than a glitch
+                .no_retry()
+        };
+        let compiled = runner.run(
+            submitted
+                .iter()
+                .map(|s| cargo("cargo test --no-run", s, &["test", "--no-run"]))
+                .collect(),
+        );
+        let mut compiling = vec![];
+        for (s, output) in submitted.iter().zip(compiled) {
+            check_ran(&output)?;
+            match output.success() {
+                true => compiling.push(s),
+                false => grades[s.grade].status = GradeStatus::CompileError,
+            }
+        }
+
+        let tested = runner.run(
+            compiling
+                .iter()
+                .map(|s| cargo("cargo test", s, &["test", "--no-fail-fast"]))
+                .collect(),
+        );
+        let mut hidden = vec![];
+        for (s, output) in compiling.iter().zip(tested) {
+            check_ran(&output)?;
+            let grade = &mut grades[s.grade];
+            grade.status = GradeStatus::Graded;
+            grade.tests = test_counts(&output.stdout);
+            if let Some(hidden_tests) = &s.exercise.hidden_tests {
+                let targets = add_hidden_tests(hidden_tests, &s.scratch)?;
+                set_test_targets(&s.scratch, &targets)?;
+                if !targets.is_empty() {
+                    hidden.push((s, targets, count_tests(hidden_tests)?));
+                }
+            }
+        }
+
+        let tested = runner.run(
+            hidden
+                .iter()
+                .map(|(s, targets, _)| {
+                    let mut args = vec!["test", "--no-fail-fast"];
+                    for target in targets {
+                        args.extend(["--test", target]);
+                    }
+                    cargo("cargo test hidden", s, &args)
+                })
+                .collect(),
+        );
+        for ((s, _, count), output) in hidden.iter().zip(tested) {
+            check_ran(&output)?;
+            // The submission can print summaries of its own, which can add tests that passed
+            // but can't hide the ones that failed, so the failures count against the number
+            // of hidden tests. Tests that don't pass fail, like all of them do when they
+            // don't compile against the submission.
+            let counts = test_counts(&output.stdout);
+            let passed = counts.passed.min(count.saturating_sub(counts.failed));
+            grades[s.grade].hidden_tests = TestCounts {
+                passed,
+                failed: count - passed,
+            };
+        }
+
+        for grade in grades.iter_mut() {
+            // Students can add tests of their own that pass, so they only count when there
+            // are no hidden tests
+            let counts = match grade.hidden_tests.total() {
+                0 => grade.tests,
+                _ => grade.hidden_tests,
+            };
+            grade.score = match (grade.status, counts.total()) {
+                (GradeStatus::Graded, 0) => None,
+                (GradeStatus::Graded, total) => Some(100.0 * counts.passed as f64 / total as f64),
+                _ => Some(0.0),
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Fail if `output` is of a command that could not be run at all
+fn check_ran(output: &TaskOutput) -> Result<(), GradeError> {
+    match &output.status {
+        TaskStatus::Failed(reason) => Err(Report::new(GradeError::default())
+            .attach_printable(format!("Unable to run {}: {reason}", output.task))),
+        _ => Ok(()),
+    }
+}
+
+/// The name of the package in the `Cargo.toml` in `dir`, if any
+fn package_name(dir: &Path) -> Option<String> {
+    let manifest: toml::Value =
+        toml::from_str(&fs::read_to_string(dir.join("Cargo.toml")).ok()?).ok()?;
+    Some(manifest.get("package")?.get("name")?.as_str()?.to_string())
+}
+
+/// The folder the crates of a submission are copied into, which is removed when it's
+/// dropped
+struct ScratchDir(PathBuf);
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Copy the crate in `from` to `to`, leaving out its build output
+fn copy_crate(from: &Path, to: &Path) -> Result<(), GradeError> {
+    if to.exists() {
+        fs::remove_dir_all(to)
+            .into_report()
+            .change_context(GradeError::default())?;
+    }
+    for file in from.get_dir_content::<GradeError>()?.files {
+        let relative = Path::new(&file).strip_prefix(from).unwrap();
+        if relative.starts_with("target") {
+            continue;
+        }
+        let target = to.join(relative);
+        target.parent().unwrap().create_dir_all()?;
+        Path::new(&file).copy(&target)?;
+    }
+    Ok(())
+}
+
+/// Copy the files in the hidden tests folder `hidden_tests` into the `tests` folder of the
+/// crate in `dir`, replacing any files of the same name. Returns the names of the test
+/// targets they add.
+fn add_hidden_tests(hidden_tests: &Path, dir: &Path) -> Result<Vec<String>, GradeError> {
+    let tests_dir = dir.join("tests");
+    let mut targets = vec![];
+    for file in hidden_tests.get_dir_content::<GradeError>()?.files {
+        let relative = Path::new(&file).strip_prefix(hidden_tests).unwrap();
+        let target = tests_dir.join(relative);
+        target.parent().unwrap().create_dir_all()?;
+        Path::new(&file).copy(&target)?;
+        // Only the files directly in `tests` are test targets
+        if relative.components().count() == 1 && relative.extension().is_some_and(|e| e == "rs") {
+            targets.push(relative.file_stem().unwrap().to_string_lossy().to_string());
+        }
+    }
+    targets.sort();
+    Ok(targets)
+}
+
+/// Make the hidden test `targets` the only test targets of the crate in `dir`, so that test
+/// targets of the submission can't take their names and run instead of them
+fn set_test_targets(dir: &Path, targets: &[String]) -> Result<(), GradeError> {
+    let manifest_path = dir.join("Cargo.toml");
+    let mut manifest = manifest_path
+        .read_to_string::<GradeError>()?
+        .parse::<toml_edit::Document>()
+        .into_report()
+        .attach_printable_lazy(|| format!("Invalid manifest {}", manifest_path.display()))
+        .change_context(GradeError::default())?;
+    manifest.remove("test");
+    if let Some(package) = manifest
+        .get_mut("package")
+        .and_then(|p| p.as_table_like_mut())
+    {
+        package.insert("autotests", toml_edit::value(false));
+    }
+    let mut tests = toml_edit::ArrayOfTables::new();
+    for target in targets {
+        let mut test = toml_edit::Table::new();
+        test.insert("name", toml_edit::value(target));
+        test.insert("path", toml_edit::value(format!("tests/{target}.rs")));
+        tests.push(test);
+    }
+    manifest.insert("test", toml_edit::Item::ArrayOfTables(tests));
+    manifest_path.create_file()?.write_all(manifest.to_string())
+}
+
+/// The number of tests in the Rust files of the hidden tests folder `hidden_tests`
+fn count_tests(hidden_tests: &Path) -> Result<usize, GradeError> {
+    let mut count = 0;
+    for file in hidden_tests.get_dir_content::<GradeError>()?.files {
+        if Path::new(&file).extension().is_some_and(|e| e == "rs") {
+            count += Path::new(&file)
+                .read_to_string::<GradeError>()?
+                .matches("#[test]")
+                .count();
+        }
+    }
+    Ok(count)
+}
+
+/// The tests that passed and failed according to the summaries that the test harness
+/// printed into `stdout`, like `test result: FAILED. 3 passed; 1 failed; ...`
+fn test_counts(stdout: &[u8]) -> TestCounts {
+    let mut counts = TestCounts::default();
+    let stdout = String::from_utf8_lossy(stdout);
+    for summary in stdout
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("test result: "))
+    {
+        for part in summary.split([';', '.']) {
+            match part.trim().split_once(' ') {
+                Some((n, "passed")) => counts.passed += n.parse().unwrap_or(0),
+                Some((n, "failed")) => counts.failed += n.parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_add_up_the_summaries_of_all_test_targets() {
+        let stdout = b"running 2 tests
+test tests::add ... ok
+test tests::sub ... FAILED
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+running 3 tests
+test result: ok. 3 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+";
+        assert_eq!(
+            test_counts(stdout),
+            TestCounts {
+                passed: 4,
+                failed: 1
+            }
+        );
+    }
+}
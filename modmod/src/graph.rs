@@ -0,0 +1,231 @@
+use std::{collections::HashSet, convert::Infallible, fmt::Write, str::FromStr};
+
+use error_stack::Result;
+
+use crate::{
+    load::Indexed,
+    numbering::UnitNumber,
+    visit::{Position, Visitor},
+    Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(format!(
+                "unknown graph format '{s}', expected dot or mermaid"
+            )),
+        }
+    }
+}
+
+/// A topic in the [`TopicGraph`]
+#[derive(Debug)]
+pub struct TopicNode<'t> {
+    /// The topic ID, or a generated key for topics without one
+    pub key: String,
+    /// The unit the topic first appears in
+    pub unit: UnitNumber,
+    pub module_name: &'t str,
+    pub topic: &'t Topic,
+}
+
+impl TopicNode<'_> {
+    fn label(&self) -> String {
+        format!("{} {}", self.unit, self.topic.name)
+    }
+}
+
+/// The prerequisite graph of the topics in a track. Every topic appears once,
+/// in the order it is first covered in the track, with edges pointing from
+/// prerequisites to the topics that require them.
+#[derive(Debug)]
+pub struct TopicGraph<'t> {
+    pub nodes: Vec<TopicNode<'t>>,
+}
+
+impl Track {
+    /// Build the prerequisite graph of the topics in the track
+    pub fn topic_graph(&self) -> TopicGraph<'_> {
+        let mut graph = GraphNodes {
+            module_name: "",
+            seen: HashSet::new(),
+            nodes: vec![],
+        };
+        self.walk_infallible(&mut graph);
+        TopicGraph { nodes: graph.nodes }
+    }
+}
+
+/// Collects the first appearance of every topic while walking over the track
+struct GraphNodes<'t> {
+    /// The name of the module that is being visited
+    module_name: &'t str,
+    seen: HashSet<String>,
+    nodes: Vec<TopicNode<'t>>,
+}
+
+impl<'t> Visitor<'t> for GraphNodes<'t> {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.module_name = &module.data.name;
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), Infallible> {
+        let key = topic.data.id.clone().unwrap_or_else(|| {
+            format!(
+                "topic-{}-{}-{}",
+                position.module,
+                position.unit.unwrap(),
+                topic.index
+            )
+        });
+        if self.seen.insert(key.clone()) {
+            self.nodes.push(TopicNode {
+                key,
+                unit: position.unit_number().unwrap(),
+                module_name: self.module_name,
+                topic: &topic.data,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<'t> TopicGraph<'t> {
+    /// Find the position of the topic with the given ID in [`TopicGraph::nodes`]
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.key == id)
+    }
+
+    /// All prerequisite edges as pairs of node positions. Prerequisites that
+    /// are not part of the track are left out.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, node)| {
+                node.topic
+                    .requires
+                    .iter()
+                    .filter_map(move |req| Some((self.position(req)?, i)))
+            })
+            .collect()
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    /// Render the graph in Graphviz DOT format, with a cluster per module
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph topics {\n    rankdir=LR;\n");
+        for (module_index, module_name, nodes) in self.by_module() {
+            writeln!(dot, "    subgraph cluster_{module_index} {{").unwrap();
+            writeln!(
+                dot,
+                "        label={};",
+                quote(&format!("{module_index} {module_name}"))
+            )
+            .unwrap();
+            for node in nodes {
+                writeln!(
+                    dot,
+                    "        {} [label={}];",
+                    quote(&node.key),
+                    quote(&node.label())
+                )
+                .unwrap();
+            }
+            dot.push_str("    }\n");
+        }
+        for (from, to) in self.edges() {
+            writeln!(
+                dot,
+                "    {} -> {};",
+                quote(&self.nodes[from].key),
+                quote(&self.nodes[to].key)
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as a Mermaid flowchart, with a subgraph per module
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart LR\n");
+        let mut node_i = 0;
+        for (module_index, module_name, nodes) in self.by_module() {
+            writeln!(
+                mermaid,
+                "    subgraph m{module_index} [\"{}\"]",
+                mermaid_escape(&format!("{module_index} {module_name}"))
+            )
+            .unwrap();
+            for node in nodes {
+                node_i += 1;
+                writeln!(
+                    mermaid,
+                    "        t{node_i}[\"{}\"]",
+                    mermaid_escape(&node.label())
+                )
+                .unwrap();
+            }
+            mermaid.push_str("    end\n");
+        }
+        for (from, to) in self.edges() {
+            writeln!(mermaid, "    t{} --> t{}", from + 1, to + 1).unwrap();
+        }
+        mermaid
+    }
+
+    /// The nodes grouped by the module they appear in, in order
+    fn by_module(&self) -> Vec<(usize, &'t str, Vec<&TopicNode<'t>>)> {
+        let mut modules: Vec<(usize, &str, Vec<_>)> = vec![];
+        for node in self.nodes.iter() {
+            match modules.last_mut() {
+                Some((module_index, _, nodes)) if *module_index == node.unit.module => {
+                    nodes.push(node)
+                }
+                _ => modules.push((node.unit.module, node.module_name, vec![node])),
+            }
+        }
+        modules
+    }
+}
+
+/// Quote a DOT identifier. JSON strings are valid DOT strings,
+/// so we let serde_json take care of escaping.
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
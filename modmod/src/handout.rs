@@ -0,0 +1,212 @@
+use std::{fmt, path::Path};
+
+use error_stack::Result;
+
+use crate::{
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    markdown::{fence, slide_notes, split_slides, strip_comments},
+    numbering::section_file_name,
+    visit::{Position, Visitor},
+    Module, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderHandoutError {}
+
+impl fmt::Display for RenderHandoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render slide handouts")
+    }
+}
+
+impl error_stack::Context for RenderHandoutError {}
+
+/// Name of the folder in the book sources the slide handouts are written to
+pub const HANDOUTS_DIR: &str = "handouts";
+
+impl Track {
+    /// Convert each rendered slide deck in `out_dir` into a handout in the book, with a section
+    /// per slide, and link it from the book section of the unit. The handouts are added to the
+    /// end of the book summary, as mdBook would renumber the exercises otherwise.
+    /// If `notes` is set, the speaker notes that were kept in the deck follow each slide.
+    pub fn render_slide_handouts(
+        &self,
+        out_dir: impl AsRef<Path>,
+        notes: bool,
+    ) -> Result<(), RenderHandoutError> {
+        let out_dir = out_dir.as_ref();
+        let book_src_dir = out_dir.join("book").join("src");
+        let slides_dir = out_dir.join("slides");
+        let handouts_dir = book_src_dir.join(HANDOUTS_DIR);
+        handouts_dir.create_dir_all()?;
+
+        let mut handouts = Handouts {
+            book_src_dir: &book_src_dir,
+            slides_dir: &slides_dir,
+            handouts_dir: &handouts_dir,
+            notes,
+            summary: String::new(),
+        };
+        self.walk(&mut handouts)?;
+        let summary = handouts.summary;
+
+        let slide_images_dir = slides_dir.join("images");
+        for image in slide_images_dir
+            .get_dir_content::<RenderHandoutError>()?
+            .files
+        {
+            let image = Path::new(&image);
+            let dest = handouts_dir.join(image.strip_prefix(&slides_dir).unwrap());
+            dest.parent().unwrap().create_dir_all()?;
+            image.copy(dest)?;
+        }
+
+        if !summary.is_empty() {
+            let summary_path = book_src_dir.join("SUMMARY.md");
+            let book_summary = summary_path.read_to_string()?;
+            summary_path
+                .create_file()?
+                .write_all(format!("{book_summary}---\n\n{summary}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the handouts of the units in the book that have slides while walking over the
+/// track, collecting their summary entries
+struct Handouts<'p> {
+    book_src_dir: &'p Path,
+    slides_dir: &'p Path,
+    handouts_dir: &'p Path,
+    notes: bool,
+    summary: String,
+}
+
+impl<'t> Visitor<'t> for Handouts<'_> {
+    type Error = RenderHandoutError;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RenderHandoutError> {
+        let number = position.unit_number().unwrap();
+        let section_path = self.book_src_dir.join(section_file_name(&unit.data.name));
+        let deck_path = self.slides_dir.join(number.deck_file_name(&unit.data.name));
+        // Units that were filtered out, or have no slides, don't get a handout
+        if !section_path.is_file() || !deck_path.is_file() {
+            return Ok(());
+        }
+        let slug = number.deck_slug(&unit.data.name);
+        let title = format!("Unit {number} - {}: handout", unit.data.name);
+        let handout = to_handout(&title, &deck_path.read_to_string()?, self.notes);
+        self.handouts_dir
+            .join(number.deck_file_name(&unit.data.name))
+            .create_file()?
+            .write_all(handout)?;
+        self.summary
+            .push_str(&format!("[{title}]({HANDOUTS_DIR}/{slug}.md)\n"));
+
+        // Link the handout next to the slides
+        let section = section_path.read_to_string()?;
+        let section = section.replacen(
+            "target=\"_blank\">Slides</a>",
+            &format!(
+                "target=\"_blank\">Slides</a> <a href=\"{HANDOUTS_DIR}/{slug}.html\">Handout</a>"
+            ),
+            1,
+        );
+        section_path.create_file()?.write_all(section)
+    }
+}
+
+/// Convert a Slidev deck into a handout titled `title`, with a section per slide
+fn to_handout(title: &str, deck: &str, notes: bool) -> String {
+    let mut handout = format!("# {title}\n");
+    for (slide, slide_i) in split_slides(deck).iter().zip(1..) {
+        let slide_notes = match notes {
+            true => slide_notes(&slide.content).pop(),
+            false => None,
+        };
+        let mut content = String::new();
+        let mut heading = None;
+        let mut open_fence: Option<&str> = None;
+        for line in strip_comments(&slide.content).lines() {
+            let trimmed = line.trim_start();
+            if let Some(marker) = open_fence {
+                if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                    open_fence = None;
+                }
+            } else if let Some(marker) = fence(trimmed) {
+                open_fence = Some(marker);
+            } else if slot(trimmed) {
+                // Slot markers of layouts with several columns
+                continue;
+            } else if heading_level(line).is_some() {
+                // The first heading becomes the title of the section, the others move down a level
+                if heading.is_none() {
+                    heading = Some(line.trim_start_matches('#').trim().to_string());
+                    continue;
+                }
+                if heading_level(line) < Some(6) {
+                    content.push('#');
+                }
+            }
+            content.push_str(line);
+            content.push('\n');
+        }
+        // Slidev serves images from the root of the deck
+        let content = content
+            .replace("](/images/", "](images/")
+            .replace("src=\"/images/", "src=\"images/");
+        let heading = heading.unwrap_or_else(|| format!("Slide {slide_i}"));
+        handout.push_str(&format!("\n## {heading}\n"));
+        let content = content.trim();
+        if !content.is_empty() {
+            handout.push_str(&format!("\n{content}\n"));
+        }
+        if let Some(slide_notes) = slide_notes {
+            handout.push_str("\n> **Notes**\n>\n");
+            for line in slide_notes.notes.lines() {
+                handout.push_str(&format!("> {line}\n").replace("> \n", ">\n"));
+            }
+        }
+    }
+    handout
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// Whether `line` is a slot marker, like `::right::`
+fn slot(line: &str) -> bool {
+    line.trim_end()
+        .strip_prefix("::")
+        .and_then(|l| l.strip_suffix("::"))
+        .is_some_and(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slides_become_sections() {
+        let deck = "---\ntheme: teach-rs\nlayout: cover\n---\n\n# Rust programming\n\nModule 2\n\n---\nlayout: two-cols\n---\n# Ownership\n\n## Moves\n\n![](/images/move.svg)\n\n::right::\n\n```rust\n# fn main() {}\n```\n\n<!--\nAsk about `Copy`\n\nAnd `Clone`\n-->\n\n---\n\n<img src=\"/images/crab.png\" />\n";
+        assert_eq!(
+            to_handout("Unit 2.2 - Ownership: handout", deck, true),
+            "# Unit 2.2 - Ownership: handout\n\n\
+            ## Rust programming\n\nModule 2\n\n\
+            ## Ownership\n\n### Moves\n\n![](images/move.svg)\n\n\n```rust\n# fn main() {}\n```\n\n\
+            > **Notes**\n>\n> Ask about `Copy`\n>\n> And `Clone`\n\n\
+            ## Slide 3\n\n<img src=\"images/crab.png\" />\n"
+        );
+        assert!(!to_handout("Handout", deck, false).contains("Notes"));
+    }
+}
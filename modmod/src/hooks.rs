@@ -0,0 +1,175 @@
+//! Commands the track TOML sets to run around rendering, to extend the pipeline with
+//! external tools without changing modmod, like one that fetches content before the track
+//! is loaded or one that optimizes the slide images after rendering.
+
+use std::{fmt, path::Path};
+
+use error_stack::{IntoReport, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    edition::Edition,
+    io::PathExt,
+    manifest::MANIFEST_FILE,
+    tasks::{Task, TaskRunner},
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct HookError {}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to run hook")
+    }
+}
+
+impl error_stack::Context for HookError {}
+
+/// The commands in the `[hooks]` table of the track TOML, which are run with the shell of
+/// the platform, one after the other
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HooksConfig {
+    /// Run in the folder of the track TOML before the track is loaded, so that they can
+    /// generate the content it refers to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_load: Vec<String>,
+    /// Run in the output folder after the track is rendered, with the output manifest on
+    /// their standard input
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_render: Vec<String>,
+}
+
+impl HooksConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The hooks of the track TOML at `path`, read without loading the track, which the
+    /// pre-load hooks may need to prepare first
+    pub fn of_track_toml(path: impl AsRef<Path>) -> Result<Self, HookError> {
+        #[derive(Deserialize)]
+        struct Def {
+            #[serde(default)]
+            hooks: HooksConfig,
+        }
+        let path = path.as_ref();
+        let def: Def = toml::from_str(&path.read_to_string::<HookError>()?)
+            .into_report()
+            .attach_printable_lazy(|| format!("Invalid [hooks] table in {}", path.display()))
+            .change_context(HookError::default())?;
+        Ok(def.hooks)
+    }
+
+    /// Run the pre-load hooks of the track TOML at `track_toml_path`
+    pub fn run_pre_load(
+        &self,
+        track_toml_path: impl AsRef<Path>,
+        runner: &TaskRunner,
+    ) -> Result<(), HookError> {
+        if self.pre_load.is_empty() {
+            return Ok(());
+        }
+        let track_toml_path = track_toml_path
+            .as_ref()
+            .canonicalize()
+            .into_report()
+            .change_context(HookError::default())?;
+        for command in self.pre_load.iter() {
+            let task = Task::shell(format!("pre-load hook {command}"), command)
+                .current_dir(track_toml_path.parent().unwrap())
+                .env("MODMOD_TRACK_TOML", &track_toml_path);
+            run_hook(task, command, runner)?;
+        }
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Run the post-render hooks of the track on the `edition` rendered into `out_dir`. The
+    /// hooks are passed the output manifest on their standard input, and the paths of the
+    /// output folder and the track folder in the `MODMOD_OUT_DIR` and `MODMOD_TRACK_DIR`
+    /// environment variables.
+    pub fn run_post_render_hooks(
+        &self,
+        out_dir: impl AsRef<Path>,
+        edition: Edition,
+        runner: &TaskRunner,
+    ) -> Result<(), HookError> {
+        if self.hooks.post_render.is_empty() {
+            return Ok(());
+        }
+        let out_dir = out_dir
+            .as_ref()
+            .canonicalize()
+            .into_report()
+            .change_context(HookError::default())?;
+        let manifest = out_dir.join(MANIFEST_FILE);
+        let manifest_content = manifest.read_to_string::<HookError>()?;
+        for command in self.hooks.post_render.iter() {
+            let task = Task::shell(format!("post-render hook {command}"), command)
+                .current_dir(&out_dir)
+                .env("MODMOD_OUT_DIR", &out_dir)
+                .env("MODMOD_TRACK_DIR", &self.root)
+                .env("MODMOD_MANIFEST", &manifest)
+                .env("MODMOD_EDITION", edition.name())
+                .stdin(manifest_content.as_bytes());
+            run_hook(task, command, runner)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the `task` of the hook that runs `command`, which isn't retried if it fails, as
+/// hooks can have side effects like uploads
+fn run_hook(task: Task, command: &str, runner: &TaskRunner) -> Result<(), HookError> {
+    runner
+        .run_one(task.no_retry())
+        .into_result()
+        .attach_printable_lazy(|| format!("Hook '{command}' exited unsuccessfully"))
+        .change_context(HookError::default())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_are_read_from_the_track_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let track_toml = dir.path().join("track.toml");
+        std::fs::write(
+            &track_toml,
+            r#"
+            name = "Rust intro"
+            modules = ["intro/mod.toml"]
+
+            [hooks]
+            pre-load = ["echo generated > generated.md"]
+            post-render = ["optimize-images slides/images"]
+            "#,
+        )
+        .unwrap();
+        let hooks = HooksConfig::of_track_toml(&track_toml).unwrap();
+        assert_eq!(hooks.post_render, ["optimize-images slides/images"]);
+
+        hooks
+            .run_pre_load(&track_toml, &TaskRunner::default())
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("generated.md")).unwrap(),
+            "generated\n"
+        );
+
+        let failing = HooksConfig {
+            pre_load: vec!["exit 3".to_string()],
+            ..Default::default()
+        };
+        assert!(failing
+            .run_pre_load(&track_toml, &TaskRunner::default())
+            .is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::Track;
+
+/// Which content files of a track are available in a language
+#[derive(Debug, Default)]
+pub struct TranslationCoverage {
+    pub language: String,
+    /// Content files for which a translation was found
+    pub translated: BTreeSet<PathBuf>,
+    /// Content files without a translation, which are rendered in the default language
+    pub missing: BTreeSet<PathBuf>,
+}
+
+impl TranslationCoverage {
+    /// Whether every content file was translated
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl Track {
+    /// Switch the track to `language`, using the translations of the content files
+    /// where they exist: `slides.nl.md` next to `slides.md`, for example. Files without
+    /// a translation fall back to the default language of the track and are reported
+    /// as missing in the returned coverage. Titles, summaries and objectives come
+    /// from the TOML definitions and are not translated.
+    pub fn localize(&mut self, language: &str) -> TranslationCoverage {
+        let mut coverage = TranslationCoverage {
+            language: language.to_string(),
+            ..Default::default()
+        };
+        if language == self.language {
+            return coverage;
+        }
+
+        let mut localize = |path: &mut PathBuf| {
+            let translation = localized_path(path, language);
+            if translation.is_file() {
+                coverage.translated.insert(path.clone());
+                *path = translation;
+            } else {
+                coverage.missing.insert(path.clone());
+            }
+        };
+        for module in self.modules.iter_mut() {
+            for unit in module.data.units.iter_mut() {
+                unit.data.template.iter_mut().for_each(&mut localize);
+                unit.data.quizzes.iter_mut().for_each(&mut localize);
+                for topic in unit.data.topics.iter_mut() {
+                    localize(&mut topic.data.content);
+                    for exercise in topic.data.exercises.iter_mut() {
+                        localize(&mut exercise.data.description);
+                        exercise.data.hints.iter_mut().for_each(&mut localize);
+                    }
+                }
+            }
+        }
+        self.language = language.to_string();
+
+        coverage
+    }
+}
+
+/// The translation of the file at `path` into `language`, e.g. `intro.nl.md` for `intro.md`
+pub fn localized_path(path: &Path, language: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{stem}.{language}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{language}"),
+    };
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translations_sit_next_to_the_original() {
+        assert_eq!(
+            localized_path(Path::new("topics/intro/slides.md"), "nl"),
+            Path::new("topics/intro/slides.nl.md")
+        );
+        assert_eq!(
+            localized_path(Path::new("quizzes/basics.toml"), "pt-BR"),
+            Path::new("quizzes/basics.pt-BR.toml")
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Context, Report, Result, ResultExt};
+
+use crate::{diagnostics::Snippet, io::PathExt};
+
+const INCLUDE_PLACEHOLDER_START: &str = "#[modmod:include(";
+const INCLUDE_PLACEHOLDER_END: &str = ")]";
+const ANCHOR_START: &str = "ANCHOR:";
+const ANCHOR_END: &str = "ANCHOR_END:";
+const ELISION: &str = "// ...";
+
+/// Replace `#[modmod:include(path, anchor = "name", context = 2)]` directives in `content`
+/// with the contents of the file at `path`, relative to `base_dir`.
+///
+/// If one or more anchors are passed, only the lines between the corresponding
+/// `ANCHOR: name` and `ANCHOR_END: name` markers are kept, along with `context`
+/// lines around them. Elided regions are replaced with `// ...`.
+/// Anchor marker lines themselves are never included.
+pub fn expand_includes<C: Context + Default>(content: &str, base_dir: &Path) -> Result<String, C> {
+    let mut rest = content;
+    let mut expanded = String::with_capacity(content.len());
+    while let Some((before, directive, after)) = next_directive(rest)? {
+        expanded.push_str(before);
+        let text = &rest[before.len()..rest.len() - after.len()];
+        rest = after;
+
+        let excerpt = base_dir
+            .join(directive.path)
+            .read_to_string()
+            .and_then(|source| excerpt(&source, &directive.anchors, directive.context))
+            .attach_printable(Snippet(text.to_string()));
+        expanded.push_str(&excerpt?);
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The paths of the files included by the directives in `content`, relative to `base_dir`
+pub fn included_sources<C: Context + Default>(
+    content: &str,
+    base_dir: &Path,
+) -> Result<Vec<PathBuf>, C> {
+    let mut rest = content;
+    let mut sources = vec![];
+    while let Some((_, directive, after)) = next_directive(rest)? {
+        sources.push(base_dir.join(directive.path));
+        rest = after;
+    }
+    Ok(sources)
+}
+
+/// Find the first include directive in `content`, returning the content
+/// before it, the parsed directive and the content after it
+fn next_directive<C: Context + Default>(
+    content: &str,
+) -> Result<Option<(&str, IncludeDirective<'_>, &str)>, C> {
+    let Some(start) = content.find(INCLUDE_PLACEHOLDER_START) else {
+        return Ok(None);
+    };
+    let rest = &content[start + INCLUDE_PLACEHOLDER_START.len()..];
+    let Some(end) = rest.find(INCLUDE_PLACEHOLDER_END) else {
+        return Err(Report::new(C::default())
+            .attach_printable("Unterminated #[modmod:include(..)] directive")
+            .attach_printable(Snippet(
+                content[start..].lines().next().unwrap().to_string(),
+            )));
+    };
+    let directive = IncludeDirective::parse(&rest[..end]).attach_printable_lazy(|| {
+        Snippet(content[start..start + INCLUDE_PLACEHOLDER_START.len() + end].to_string())
+    })?;
+    Ok(Some((
+        &content[..start],
+        directive,
+        &rest[end + INCLUDE_PLACEHOLDER_END.len()..],
+    )))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct IncludeDirective<'d> {
+    path: &'d str,
+    anchors: Vec<&'d str>,
+    context: usize,
+}
+
+impl<'d> IncludeDirective<'d> {
+    fn parse<C: Context + Default>(args: &'d str) -> Result<Self, C> {
+        let mut args = args.split(',').map(str::trim);
+        let path = args.next().unwrap_or_default();
+        let mut directive = IncludeDirective {
+            path,
+            anchors: vec![],
+            context: 0,
+        };
+        for arg in args {
+            let invalid = || {
+                Report::new(C::default())
+                    .attach_printable(format!("Invalid #[modmod:include(..)] argument '{arg}'"))
+            };
+            let (key, value) = arg.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "anchor" => directive.anchors.push(value),
+                "context" => directive.context = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(directive)
+    }
+}
+
+/// Keep the regions of `source` marked by `anchors`,
+/// plus `context` lines around each, eliding everything else
+fn excerpt<C: Context + Default>(
+    source: &str,
+    anchors: &[&str],
+    context: usize,
+) -> Result<String, C> {
+    let mut lines = vec![];
+    let mut open_anchors: Vec<&str> = vec![];
+    let mut keep = BTreeSet::new();
+
+    for line in source.lines() {
+        if let Some(name) = anchor_marker(line, ANCHOR_START) {
+            open_anchors.push(name);
+            continue;
+        }
+        if let Some(name) = anchor_marker(line, ANCHOR_END) {
+            open_anchors.retain(|a| *a != name);
+            continue;
+        }
+        if anchors.is_empty() || open_anchors.iter().any(|a| anchors.contains(a)) {
+            keep.insert(lines.len());
+        }
+        lines.push(line);
+    }
+
+    if !anchors.is_empty() && keep.is_empty() {
+        return Err(Report::new(C::default()).attach_printable(format!(
+            "None of the anchors {} were found in included file",
+            anchors.join(", ")
+        )));
+    }
+
+    if lines.is_empty() {
+        return Ok(String::new());
+    }
+
+    let keep: BTreeSet<_> = keep
+        .iter()
+        .flat_map(|&i| i.saturating_sub(context)..=(i + context).min(lines.len() - 1))
+        .collect();
+
+    let mut excerpt = String::new();
+    let mut elided = false;
+    for (i, line) in lines.iter().enumerate() {
+        if keep.contains(&i) {
+            excerpt.push_str(line);
+            excerpt.push('\n');
+            elided = false;
+        } else if !elided {
+            excerpt.push_str(ELISION);
+            excerpt.push('\n');
+            elided = true;
+        }
+    }
+    Ok(excerpt.trim_end().to_string())
+}
+
+/// If `line` is an anchor marker comment like `// ANCHOR: name`, return the anchor name
+fn anchor_marker<'l>(line: &'l str, marker: &str) -> Option<&'l str> {
+    let comment = line.trim().strip_prefix("//")?.trim();
+    comment.strip_prefix(marker).map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::RenderBookError;
+
+    const SOURCE: &str = r#"use std::fmt;
+
+// ANCHOR: point
+struct Point {
+    x: i32,
+    y: i32,
+}
+// ANCHOR_END: point
+
+fn main() {
+    // ANCHOR: print
+    println!("hello");
+    // ANCHOR_END: print
+}"#;
+
+    #[test]
+    fn excerpt_without_anchors_strips_markers() {
+        let excerpt = excerpt::<RenderBookError>(SOURCE, &[], 0).unwrap();
+        assert!(!excerpt.contains("ANCHOR"));
+        assert!(excerpt.starts_with("use std::fmt;"));
+        assert!(!excerpt.contains(ELISION));
+    }
+
+    #[test]
+    fn excerpt_elides_around_anchors() {
+        let excerpt = excerpt::<RenderBookError>(SOURCE, &["print"], 1).unwrap();
+        assert_eq!(excerpt, "// ...\nfn main() {\n    println!(\"hello\");\n}");
+    }
+
+    #[test]
+    fn include_directive_is_parsed() {
+        let directive = IncludeDirective::parse::<RenderBookError>(
+            r#"src/main.rs, anchor = "point", anchor = "print", context = 2"#,
+        )
+        .unwrap();
+        assert_eq!(
+            directive,
+            IncludeDirective {
+                path: "src/main.rs",
+                anchors: vec!["point", "print"],
+                context: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result, ResultExt};
+
+use crate::{
+    io::{PathExt, WriteExt},
+    migrate::FORMAT_VERSION,
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct InitError {}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to create track")
+    }
+}
+
+impl error_stack::Context for InitError {}
+
+const MODULE_TOML: &str = r#"name = "Introduction"
+description = "A first module. Add units to it, or copy it to start another module"
+
+[[units]]
+name = "First steps"
+# The time the unit takes to teach, not counting its exercises
+duration = "30m"
+topics = ["topics/hello-world/topic.toml"]
+"#;
+
+const TOPIC_TOML: &str = r#"name = "Hello, world"
+objectives = ["Write and test a first Rust function"]
+summary = ["Functions take typed parameters and return a value"]
+
+[[exercises]]
+name = "Greeter"
+path = "exercises/greeter"
+duration = "15m"
+"#;
+
+const SLIDES_MD: &str = r#"---
+layout: cover
+---
+
+# Hello, world
+
+---
+layout: default
+---
+
+# A first function
+
+```rust
+fn greet(name: &str) -> String {
+    format!("Hello, {name}!")
+}
+```
+
+<!-- Speaker notes go in comments like this one -->
+
+---
+layout: default
+---
+
+# Exercise
+
+Implement the greeter in `exercises/1-introduction/1-first-steps/1-greeter`
+"#;
+
+const EXERCISE_CARGO_TOML: &str = r#"[package]
+name = "greeter"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#;
+
+const EXERCISE_DESCRIPTION_MD: &str = r#"Open `src/lib.rs` and implement `greet`, which greets the person whose name it is given.
+Run `cargo test` to see whether your implementation is right.
+"#;
+
+const EXERCISE_LIB_RS: &str = r#"/// Greet the person called `name`, like "Hello, Ferris!"
+pub fn greet(name: &str) -> String {
+    todo!("Greet {name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greets_by_name() {
+        assert_eq!(greet("Ferris"), "Hello, Ferris!");
+    }
+}
+"#;
+
+/// Create a skeleton track called `name` in `dir`: a track TOML with a module of one unit,
+/// whose topic has a slide deck and an exercise package with tests. Existing files are only
+/// overwritten if `force` is set. Returns the path of the track TOML.
+pub fn init_track(dir: impl AsRef<Path>, name: &str, force: bool) -> Result<PathBuf, InitError> {
+    let dir = dir.as_ref();
+    let module_dir = dir.join("mods").join("1-introduction");
+    let topic_dir = module_dir.join("topics").join("hello-world");
+    let exercise_dir = topic_dir.join("exercises").join("greeter");
+    let track_toml = format!(
+        "name = {}\nmodules = [\"mods/1-introduction/mod.toml\"]\n",
+        toml::Value::String(name.to_string())
+    );
+    let [track_toml, module_toml, topic_toml] = [track_toml.as_str(), MODULE_TOML, TOPIC_TOML]
+        .map(|toml| format!("format-version = {FORMAT_VERSION}\n{toml}"));
+
+    let files = [
+        (dir.join("track.toml"), track_toml.as_str()),
+        (module_dir.join("mod.toml"), module_toml.as_str()),
+        (topic_dir.join("topic.toml"), topic_toml.as_str()),
+        (topic_dir.join("slides.md"), SLIDES_MD),
+        (exercise_dir.join("Cargo.toml"), EXERCISE_CARGO_TOML),
+        (exercise_dir.join("description.md"), EXERCISE_DESCRIPTION_MD),
+        (exercise_dir.join("src").join("lib.rs"), EXERCISE_LIB_RS),
+    ];
+    // Check all files first, so that nothing is written if any of them exists
+    if !force {
+        if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+            return Err(Report::new(InitError::default()).attach_printable(format!(
+                "{} already exists. Pass --force to overwrite it",
+                path.to_string_lossy()
+            )));
+        }
+    }
+    for (path, content) in files.iter() {
+        path.parent().unwrap().create_dir_all()?;
+        path.create_file()?.write_all(content)?;
+    }
+
+    // Make sure the files are wired together the way modmod expects
+    let track_toml_path = dir.join("track.toml");
+    Track::load(&track_toml_path).change_context(InitError::default())?;
+    Ok(track_toml_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skeleton_track_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = init_track(dir.path(), "Rust \"from scratch\"", false).unwrap();
+        let track = Track::load(path).unwrap();
+        assert_eq!(track.name, "Rust \"from scratch\"");
+        let unit = &track.modules[0].data.units[0];
+        assert_eq!(unit.data.topics[0].data.exercises[0].data.name, "Greeter");
+        assert_eq!(track.duration().minutes, 45);
+
+        assert!(init_track(dir.path(), "Again", false).is_err());
+        init_track(dir.path(), "Again", true).unwrap();
+    }
+}
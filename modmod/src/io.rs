@@ -8,6 +8,8 @@ use std::{
 use error_stack::{Context, IntoReport, Result, ResultExt};
 use fs_extra::dir::DirContent;
 
+use crate::unicode::nfc_path;
+
 pub trait PathExt {
     fn create_dir_all<C: Context + Default>(&self) -> Result<(), C>;
     fn read_to_string<C: Context + Default>(&self) -> Result<String, C>;
@@ -63,6 +65,7 @@ impl<T: AsRef<Path>> PathExt for T {
             .change_context(C::default());
         }
 
+        log::trace!("Writing {}", path.to_string_lossy());
         File::create(path)
             .into_report()
             .attach_printable_lazy(|| {
@@ -137,9 +140,10 @@ impl<W: io::Write> WriteExt for W {
     }
 }
 
+/// Copy `files` into `dest`, with their names normalized to NFC
 pub fn copy_files<P: AsRef<Path>, C: Context + Default>(files: &[P], dest: &Path) -> Result<(), C> {
     files
         .iter()
         .filter_map(|path| path.as_ref().file_name().map(|name| (path, name)))
-        .try_for_each(|(path, name)| path.copy(dest.join(name)))
+        .try_for_each(|(path, name)| path.copy(dest.join(nfc_path(Path::new(name)))))
 }
@@ -1,57 +1,283 @@
-mod book;
-mod exercises;
+mod a11y;
+pub mod anki;
+pub mod archive;
+pub mod book;
+pub mod build;
+pub mod bundle;
+pub mod cache;
+pub mod calendar;
+pub mod check;
+pub mod ci;
+pub mod confluence;
+pub mod deps;
+pub mod devcontainer;
+pub mod diagnostics;
+pub mod doctor;
+pub mod docusaurus;
+pub mod docx;
+pub mod edition;
+pub mod exercises;
+pub mod flaky;
+pub mod freshness;
+pub mod generate;
+pub mod git;
+pub mod glossary;
+pub mod grade;
+pub mod graph;
+pub mod handout;
+pub mod hooks;
+pub mod i18n;
+mod include;
+pub mod init;
 pub mod io;
+pub mod license;
+pub mod lint;
+pub mod lms;
 pub mod load;
+pub mod man;
+pub mod manifest;
+mod markdown;
+pub mod migrate;
+pub mod nix;
+pub mod numbering;
 pub mod patch;
-mod slides;
+pub mod preview;
+pub mod progress;
+pub mod quiz;
+pub mod refactor;
+pub mod refs;
+pub mod release;
+mod render;
+pub mod schedule;
+pub mod scorm;
+pub mod slides;
+pub mod smoke;
+pub mod stats;
+pub mod tags;
+pub mod tasks;
+pub mod template;
+pub mod testing;
+pub mod todo;
+pub mod toolchain;
+mod unicode;
+pub mod vendor;
+pub mod visit;
 
 use self::{
-    book::{Book, BookBuilder, ChapterBuilder, SectionBuilder},
+    book::{Book, SectionSlides},
     load::{Load, TrackDef},
 };
+use archive::PackageFormat;
 use book::BookRenderOptions;
+use calendar::Date;
+use check::Finding;
+use ci::CiConfig;
+use diagnostics::{Errors, Location};
+use edition::Edition;
 use error_stack::{IntoReport, Report, Result, ResultExt};
-use exercises::{
-    ExerciseCollection, ExerciseCollectionBuilder, ModuleExercisesBuilder, UnitExercisesBuilder,
-};
+use exercises::ExerciseCollection;
+use freshness::FreshnessConfig;
+use glossary::GlossaryConfig;
+use hooks::HooksConfig;
 use io::PathExt;
+use license::LicenseConfig;
+use lint::LintConfig;
 use load::Indexed;
-use slides::{SlideDeckBuilder, SlidesPackage, SlidesPackageBuilder};
+use manifest::OutputManifest;
+use nix::NixConfig;
+use numbering::NumberingConfig;
+use progress::Phase;
+use refs::References;
+use render::TrackRenderer;
+use schedule::{Duration, ScheduleConfig};
+use serde::{Deserialize, Serialize};
+use slides::{SlidesConfig, SlidesPackage};
 use std::{
+    collections::BTreeMap,
     fmt::{self, Display},
     fs,
     path::{Path, PathBuf},
 };
+use tags::TagFilter;
+use tasks::TaskRunner;
+use template::TemplateContext;
+use testing::TestExpectation;
+use toolchain::{ExerciseToolchain, ToolchainConfig};
 
 pub use slides::SlidesRenderOptions;
 
-pub struct TrackRenderOptions<'t, 'u, O: AsRef<Path>, P: AsRef<Path>> {
-    pub out_dir: O,
-    pub slide_opts: SlidesRenderOptions<'t, 'u, P>,
+/// How [`Track::render`] renders a track. Apart from `out_dir`, the defaults render the
+/// student edition of every unit, with the slide theme of the track.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// The folder the output is written to, which has to be empty unless
+    /// `clear_output_dir` is set
+    pub out_dir: PathBuf,
+    /// Remove everything in `out_dir` before rendering
     pub clear_output_dir: bool,
+    /// The Slidev theme of the decks, defaulting to the theme in the `[slides]` table of the
+    /// track, or `teach-rs`
+    pub theme: Option<String>,
+    /// The `package.json` stub of the slides package
+    pub package_json: Option<PathBuf>,
+    /// The path the slides are served from on the web server, like `/course`
+    pub slide_url_base: String,
+    /// What the top of each book section shows of the slide deck of its unit
+    pub book_slides: SectionSlides,
+    /// Also write the hints of every exercise into a `hints` folder in its package
+    pub hints_folder: bool,
+    /// Also write the speaker notes of every deck into an instructor handout. Only the
+    /// teacher edition keeps them.
+    pub handouts: bool,
+    /// Add scripts that export every deck to PDF, see [`SlidesRenderOptions::pdf`]
+    pub pdf: bool,
+    /// Also write a deck per module, see [`SlidesRenderOptions::module_decks`]
+    pub module_decks: bool,
+    /// Emit a devcontainer definition and its Dockerfile
+    pub devcontainer: bool,
+    /// Emit a Nix flake that builds the book and provides a development shell
+    pub nix: bool,
+    /// Convert the book into the site structure of a Docusaurus site
+    pub docusaurus: bool,
+    /// Convert the book into a page tree in Confluence storage format
+    pub confluence: bool,
+    /// Convert every unit of the book into a Word document
+    pub docx: bool,
+    /// Export the exercise instructions as man pages and plain text
+    pub man: bool,
+    /// Convert every slide deck into a handout in the book
+    pub slide_handouts: bool,
+    /// Include the speaker notes in the slide handouts. Only the teacher edition keeps them.
+    pub handout_notes: bool,
+    /// Generate a `Cargo.lock` for every exercise package that doesn't include one
+    pub lockfiles: bool,
+    /// Vendor the dependencies of the exercise packages into the output, along with the
+    /// cargo configuration that uses them. Implies [`RenderOptions::lockfiles`].
+    pub vendor: bool,
+    /// Bundle the output into archives of this format, per module and for the whole course
+    pub package: Option<PackageFormat>,
+    /// The modules and units to render. The others are listed in the book summary as drafts.
+    pub filter: RenderFilter,
+    /// The edition to render. The teacher edition adds the teacher-only content, and the
+    /// preview edition only renders the preview units in full.
+    pub edition: Edition,
+    /// Leave the speaker notes out of the slide decks, unless it's the teacher edition
+    pub strip_notes: bool,
+    /// Runs the generate steps of exercises
+    pub runner: TaskRunner,
+    /// Replace emoji and typographic punctuation with ASCII in the slide decks, Word
+    /// documents and man pages, leaving the book as it is
+    pub ascii: bool,
 }
 
+/// What [`Track::render`] rendered
 #[derive(Debug)]
+pub struct RenderOutput {
+    /// The absolute path of the output folder
+    pub out_dir: PathBuf,
+    /// The files rendered into the output folder, with their checksums
+    pub manifest: OutputManifest,
+    /// Problems that don't fail the render, like a day of the schedule that runs long
+    pub warnings: Vec<Finding>,
+}
+
+/// Restricts which parts of the track are rendered. Units that are filtered out
+/// are kept in the book summary as draft entries, so numbering stays consistent.
+#[derive(Debug, Clone, Default)]
+pub struct RenderFilter {
+    /// Module indices or tags, e.g. `2` or `foundations-of-rust`. Empty means all modules.
+    pub modules: Vec<String>,
+    /// Unit numbers or tags, e.g. `2.1` or `basic-syntax`. Empty means all units.
+    pub units: Vec<String>,
+}
+
+impl RenderFilter {
+    pub fn includes(&self, module: &Indexed<Module>, unit: &Indexed<Unit>) -> bool {
+        (self.modules.is_empty() || self.modules.iter().any(|s| module.matches(s)))
+            && (self.units.is_empty() || self.units.iter().any(|s| unit.matches(module.index, s)))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Track {
     pub name: String,
+    /// Language the track is rendered in. See [`Track::localize`].
+    pub language: String,
+    /// Directory containing the track definition
+    pub root: PathBuf,
     pub modules: Vec<Indexed<Module>>,
+    /// The groups of modules that get a part title in the book, in order
+    pub parts: Vec<Part>,
+    pub ci: Option<CiConfig>,
+    pub toolchain: ToolchainConfig,
+    pub nix: NixConfig,
+    pub slides: SlidesConfig,
+    /// Named tag filters, e.g. to render the track for a specific audience
+    pub profiles: BTreeMap<String, TagFilter>,
+    pub schedule: ScheduleConfig,
+    pub freshness: FreshnessConfig,
+    /// How the book labels modules and units
+    pub numbering: NumberingConfig,
+    /// The glossary at the end of the book, with its path resolved
+    pub glossary: Option<GlossaryConfig>,
+    /// The license notices added to what's rendered, with its file path resolved
+    pub license: Option<LicenseConfig>,
+    /// The lints allowed in the exercise packages
+    pub lint: LintConfig,
+    /// The commands run before loading and after rendering
+    pub hooks: HooksConfig,
 }
 
 impl Track {
-    pub fn load_toml_def(path: impl AsRef<Path>) -> Result<Self, LoadTrackError> {
+    /// Load the track defined by the track TOML at `path`, along with its modules, units,
+    /// topics and exercises. Use [`diagnostics::Diagnostic::of_report`] to list the problems if it fails.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadTrackError> {
+        let _phase = Phase::enter("load");
         let def = TrackDef::load(path.as_ref(), None).change_context(LoadTrackError)?;
         def.resolve().change_context(LoadTrackError)
     }
 
-    pub fn render<O: AsRef<Path>, P: AsRef<Path>>(
-        &self,
-        TrackRenderOptions {
-            out_dir,
-            slide_opts,
+    #[deprecated(note = "use Track::load")]
+    pub fn load_toml_def(path: impl AsRef<Path>) -> Result<Self, LoadTrackError> {
+        Self::load(path)
+    }
+
+    /// Render the track as set by `opts`. Problems in the content don't stop the render at
+    /// the first one, so the error reports all of them, see
+    /// [`diagnostics::Diagnostic::of_report`].
+    pub fn render(&self, opts: &RenderOptions) -> Result<RenderOutput, LoadTrackError> {
+        let &RenderOptions {
+            ref out_dir,
             clear_output_dir,
-        }: TrackRenderOptions<'_, '_, O, P>,
-    ) -> Result<(), LoadTrackError> {
-        let out_dir = out_dir.as_ref();
+            ref theme,
+            ref package_json,
+            ref slide_url_base,
+            book_slides,
+            hints_folder,
+            handouts,
+            pdf,
+            module_decks,
+            devcontainer,
+            nix,
+            docusaurus,
+            confluence,
+            docx,
+            man,
+            slide_handouts,
+            handout_notes,
+            lockfiles,
+            vendor,
+            package,
+            ref filter,
+            edition,
+            strip_notes,
+            ref runner,
+            ascii,
+        } = opts;
+        let theme = theme
+            .as_deref()
+            .or(self.slides.theme.as_deref())
+            .unwrap_or("teach-rs");
         out_dir.create_dir_all()?;
         let out_dir = &out_dir
             .canonicalize()
@@ -80,198 +306,382 @@ impl Track {
         out_dir.create_dir_all()?;
 
         // Render the modules in the track
+        let references = References::of_track(self, filter, edition);
+        let templates = TemplateContext::of_track(self, filter, edition);
         let mut book_builder = Book::builder(&self.name);
+        book_builder
+            .language(&self.language)
+            .numbering(self.numbering.clone())
+            .parts(&self.parts)
+            .references(&references)
+            .templates(&templates);
         let mut slides_builder = SlidesPackage::builder(&self.name);
+        slides_builder
+            .language(&self.language)
+            .config(&self.slides)
+            .references(&references)
+            .templates(&templates);
+        if let Some(license) = &self.license {
+            slides_builder.license(license);
+        }
         let mut exercises_builder = ExerciseCollection::builder();
-
-        self.modules.iter().try_for_each(|module| {
-            module.render(
-                &mut book_builder,
-                &mut slides_builder,
-                &mut exercises_builder,
-            )
-        })?;
+        exercises_builder.hints_folder(hints_folder);
+
+        let mut renderer = TrackRenderer::new(
+            filter,
+            edition,
+            book_builder,
+            slides_builder,
+            exercises_builder,
+        );
+        self.walk(&mut renderer)?;
+        let TrackRenderer {
+            book: book_builder,
+            slides: slides_builder,
+            exercises: exercises_builder,
+            ..
+        } = renderer;
+
+        // The exercises, book and slides are rendered even if one of them fails, so that the
+        // problems in their content are reported together
+        let mut errors = Errors::default();
 
         // Build and render exercise packages
+        let phase = Phase::enter("exercises");
         let exercises = exercises_builder.build();
-        let exercise_paths = exercises.render(out_dir).change_context(LoadTrackError)?;
-        // Build and render the exercise book
-        let book = book_builder.build();
-        let book_opts = BookRenderOptions {
-            exercise_paths: &exercise_paths,
-            slides_url_base: slide_opts.url_base,
-        };
-        book.render(book_opts, out_dir)
-            .change_context(LoadTrackError)?;
+        let exercise_paths = errors.collect(
+            exercises
+                .render(out_dir, edition, runner)
+                .change_context(LoadTrackError),
+        );
+        drop(phase);
+        // The CI workflow and the book refer to the exercise packages
+        if let Some(exercise_paths) = &exercise_paths {
+            // Render CI workflow that checks the exercise packages
+            if let Some(ci) = &self.ci {
+                let _phase = Phase::enter("ci");
+                let exercise_dirs: Vec<_> = self
+                    .exercises()
+                    .filter_map(|exercise| exercise_paths.get(&exercise.path))
+                    .collect();
+                ci.render(&exercise_dirs, out_dir)
+                    .change_context(LoadTrackError)?;
+            }
+            // Build and render the exercise book
+            let _phase = Phase::enter("book");
+            let book = book_builder.build();
+            let book_opts = BookRenderOptions {
+                exercise_paths,
+                slides_url_base: slide_url_base,
+                edition,
+                slides: book_slides,
+            };
+            errors.collect(
+                book.render(book_opts, out_dir)
+                    .change_context(LoadTrackError),
+            );
+        }
+        // The schedule is added to the book, which isn't there if it or the exercises failed
+        if self.has_durations() && errors.is_empty() {
+            let _phase = Phase::enter("schedule");
+            self.render_schedule(out_dir, filter)
+                .change_context(LoadTrackError)?;
+        }
 
         // Build and render the slides package
+        let phase = Phase::enter("slides");
         let slides_package = slides_builder.build();
-        slides_package
-            .render(out_dir, slide_opts)
-            .change_context(LoadTrackError)?;
+        errors.collect(
+            slides_package
+                .render(
+                    out_dir,
+                    SlidesRenderOptions {
+                        theme,
+                        package_json: package_json.as_ref(),
+                        url_base: slide_url_base,
+                        edition,
+                        strip_notes,
+                        handouts,
+                        pdf,
+                        module_decks,
+                        ascii,
+                    },
+                )
+                .change_context(LoadTrackError),
+        );
+        drop(phase);
+        errors.finish(())?;
+
+        // The other formats are converted from the book, and carry its footers
+        if self.license.is_some() {
+            let _phase = Phase::enter("license");
+            self.render_license(out_dir)
+                .change_context(LoadTrackError)?;
+        }
+
+        if devcontainer {
+            let _phase = Phase::enter("devcontainer");
+            self.render_devcontainer(out_dir)
+                .change_context(LoadTrackError)?;
+        }
+
+        if nix {
+            let _phase = Phase::enter("nix");
+            self.render_nix(out_dir).change_context(LoadTrackError)?;
+        }
+
+        if docusaurus {
+            let _phase = Phase::enter("docusaurus");
+            self.render_docusaurus(out_dir, filter)
+                .change_context(LoadTrackError)?;
+        }
+
+        if confluence {
+            let _phase = Phase::enter("confluence");
+            self.render_confluence(out_dir, filter)
+                .change_context(LoadTrackError)?;
+        }
+
+        if docx {
+            let _phase = Phase::enter("docx");
+            self.render_docx(out_dir, filter, ascii)
+                .change_context(LoadTrackError)?;
+        }
+
+        if man {
+            let _phase = Phase::enter("man");
+            self.render_man(out_dir, filter, ascii)
+                .change_context(LoadTrackError)?;
+        }
+
+        if slide_handouts {
+            let _phase = Phase::enter("slide handouts");
+            self.render_slide_handouts(out_dir, handout_notes)
+                .change_context(LoadTrackError)?;
+        }
+
+        // The glossary goes last in the summary, and links are added to the sections
+        // after the other formats are converted from them
+        if self.glossary.is_some() {
+            let _phase = Phase::enter("glossary");
+            self.render_glossary(out_dir, filter)
+                .change_context(LoadTrackError)?;
+        }
+
+        if vendor {
+            let _phase = Phase::enter("vendor");
+            self.vendor_dependencies(out_dir, runner)
+                .change_context(LoadTrackError)?;
+        } else if lockfiles {
+            let _phase = Phase::enter("lockfiles");
+            self.render_lockfiles(out_dir, runner)
+                .change_context(LoadTrackError)?;
+        }
+
+        // Bundle everything into archives
+        if let Some(format) = package {
+            let _phase = Phase::enter("package");
+            self.package(out_dir, format)
+                .change_context(LoadTrackError)?;
+        }
 
-        Ok(())
+        // List the checksums of everything rendered, so that builds can be verified
+        let _phase = Phase::enter("manifest");
+        let manifest = OutputManifest::of_dir(out_dir).change_context(LoadTrackError)?;
+        manifest.write(out_dir).change_context(LoadTrackError)?;
+
+        Ok(RenderOutput {
+            out_dir: out_dir.clone(),
+            manifest,
+            warnings: self.schedule_warnings(),
+        })
+    }
+
+    /// Iterate over the markdown content files in the track: module and unit
+    /// introductions, topic content and exercise descriptions, in order of appearance
+    pub fn content_files(&self) -> impl Iterator<Item = &Path> {
+        self.modules.iter().flat_map(|m| {
+            m.data
+                .intro
+                .as_deref()
+                .into_iter()
+                .chain(m.data.units.iter().flat_map(|u| {
+                    u.data
+                        .intro
+                        .as_deref()
+                        .into_iter()
+                        .chain(u.data.topics.iter().flat_map(|t| {
+                            std::iter::once(t.data.content.as_path()).chain(
+                                t.data
+                                    .exercises
+                                    .iter()
+                                    .map(|e| e.data.description.as_path()),
+                            )
+                        }))
+                }))
+        })
+    }
+
+    /// Iterate over all exercises in the track, in order of appearance
+    pub fn exercises(&self) -> impl Iterator<Item = &Exercise> {
+        self.modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .flat_map(|u| &u.data.topics)
+            .flat_map(|t| &t.data.exercises)
+            .map(|e| &e.data)
     }
 }
 
-#[derive(Debug)]
+/// A group of consecutive modules of the track, like `Part I: Fundamentals`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Part {
+    pub title: String,
+    /// The indices of the modules in the part
+    pub modules: Vec<usize>,
+}
+
+impl Track {
+    /// The index into [`Track::parts`] of the part that the module at `module_index` is in
+    pub fn part_of(&self, module_index: usize) -> Option<usize> {
+        self.parts
+            .iter()
+            .position(|p| p.modules.contains(&module_index))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Module {
     pub name: String,
     pub description: String,
+    pub system_packages: Vec<String>,
+    /// Slide settings that take precedence over those of the track. The template of the
+    /// module is already applied to its units.
+    pub slides: SlidesConfig,
     pub units: Vec<Indexed<Unit>>,
+    /// Introduction at the top of the module's chapter page
+    pub intro: Option<PathBuf>,
+    /// Whether the module is an appendix
+    pub appendix: bool,
+    /// The module TOML
+    pub source: Location,
 }
 
 impl Indexed<Module> {
-    fn render<'me>(
-        &'me self,
-        book_builder: &mut BookBuilder<'me>,
-        slides: &mut SlidesPackageBuilder<'me>,
-        exercises: &mut ExerciseCollectionBuilder<'me>,
-    ) -> Result<(), LoadTrackError> {
-        let Indexed {
-            data,
-            index: module_index,
-        } = self;
-
-        let mut chapter = book_builder.chapter(&data.name, *module_index);
-        let mut module_exercises = exercises.module(&data.name, *module_index);
-
-        // Render all units in this module
-        data.units.iter().try_for_each(|unit| {
-            unit.render(
-                &data.name,
-                *module_index,
-                &mut chapter,
-                slides,
-                &mut module_exercises,
-            )
-        })?;
-
-        chapter.add();
-        module_exercises.add();
-        Ok(())
+    /// Whether the module is selected by `selector`, which is either
+    /// the module index or its tag, e.g. `2` or `foundations-of-rust`.
+    pub fn matches(&self, selector: &str) -> bool {
+        selector.parse() == Ok(self.index) || to_tag(&self.data.name) == selector
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Unit {
     pub name: String,
+    pub id: Option<String>,
     pub template: Option<PathBuf>,
     pub topics: Vec<Indexed<Topic>>,
+    pub quizzes: Vec<PathBuf>,
+    pub tags: Vec<String>,
+    /// Whether the unit is rendered in full in the preview edition
+    pub preview: bool,
+    /// The time the unit takes, not counting its exercises
+    pub duration: Option<Duration>,
+    /// The date a maintainer last reviewed the unit
+    pub reviewed: Option<Date>,
+    /// Introduction at the top of the unit's book section
+    pub intro: Option<PathBuf>,
+    /// The edition and toolchain of the exercises of the unit that don't set their own.
+    /// They are already applied to the exercises.
+    pub toolchain: ExerciseToolchain,
+    /// The `[[units]]` table of the unit in the module TOML
+    pub source: Location,
 }
 
 impl Indexed<Unit> {
-    fn render<'me>(
-        &'me self,
-        module_name: &'me str,
-        module_index: usize,
-        chapter: &mut ChapterBuilder<'me, '_>,
-        slides: &mut SlidesPackageBuilder<'me>,
-        module_exercises: &mut ModuleExercisesBuilder<'me, '_>,
-    ) -> Result<(), LoadTrackError> {
-        let Indexed {
-            data,
-            index: unit_index,
-        } = self;
-
-        let mut section = chapter.section(module_index, *unit_index, &data.name);
-        let mut deck = slides.deck(
-            &data.name,
-            module_name,
-            module_index,
-            *unit_index,
-            data.template.as_deref(),
-        );
-        let mut unit_exercises = module_exercises.unit(&data.name, *unit_index);
-
-        data.topics
-            .iter()
-            .try_for_each(|topic| topic.render(&mut section, &mut deck, &mut unit_exercises))?;
-
-        section.add();
-        deck.add();
-        unit_exercises.add();
-
-        Ok(())
+    /// Whether the unit is selected by `selector`, which is either the unit
+    /// number including the module index, or its tag, e.g. `2.1` or `basic-syntax`.
+    pub fn matches(&self, module_index: usize, selector: &str) -> bool {
+        selector == format!("{module_index}.{}", self.index) || to_tag(&self.data.name) == selector
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Topic {
     pub name: String,
+    pub id: Option<String>,
+    pub requires: Vec<String>,
     pub exercises: Vec<Indexed<Exercise>>,
     pub summary: Vec<String>,
     pub objectives: Vec<String>,
     pub content: PathBuf,
     pub further_reading: Vec<String>,
     pub images: Vec<PathBuf>,
+    /// The `flashcards.toml` next to the topic TOML, if any
+    pub flashcards: Option<PathBuf>,
+    pub tags: Vec<String>,
+    /// The time the topic takes, as set in the front matter of its content file. It's
+    /// included in the duration of the unit.
+    pub duration: Option<Duration>,
+    /// The authors of the topic, as listed in the front matter of its content file
+    pub authors: Vec<String>,
+    /// The topic TOML
+    pub source: Location,
 }
 
-impl Indexed<Topic> {
-    fn render<'me>(
-        &'me self,
-        section: &mut SectionBuilder<'me, '_, '_>,
-        deck: &mut SlideDeckBuilder<'me, '_>,
-        unit_exercises: &mut UnitExercisesBuilder<'me, '_, '_>,
-    ) -> Result<(), LoadTrackError> {
-        let Indexed { data, .. } = self;
+/// How hard an exercise is, from 1 to 5, shown as that many stars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Difficulty(u8);
 
-        let mut slides_section = deck.section(&data.content);
+impl TryFrom<u8> for Difficulty {
+    type Error = String;
 
-        data.summary
-            .iter()
-            .for_each(|item| slides_section.summary(item));
-
-        data.objectives
-            .iter()
-            .for_each(|obj| slides_section.objective(obj));
-
-        data.further_reading
-            .iter()
-            .for_each(|item| slides_section.further_reading(item));
-
-        data.images
-            .iter()
-            .for_each(|image| slides_section.image(image));
-
-        data.exercises
-            .iter()
-            .try_for_each(|exercise| exercise.render(section, unit_exercises))?;
+    fn try_from(level: u8) -> std::result::Result<Self, Self::Error> {
+        match level {
+            1..=5 => Ok(Self(level)),
+            _ => Err(format!("invalid difficulty {level}, expected 1 to 5")),
+        }
+    }
+}
 
-        slides_section.add();
+impl From<Difficulty> for u8 {
+    fn from(difficulty: Difficulty) -> Self {
+        difficulty.0
+    }
+}
 
-        Ok(())
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&"⭐".repeat(self.0.into()))
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Exercise {
     pub name: String,
     pub path: PathBuf,
     pub description: PathBuf,
     pub description_images: Vec<PathBuf>,
+    /// The `hint-1.md`, `hint-2.md`, ... files next to the description, in order
+    pub hints: Vec<PathBuf>,
     pub includes: Vec<String>,
-}
-
-impl Indexed<Exercise> {
-    fn render<'me>(
-        &'me self,
-        section: &mut SectionBuilder<'me, '_, '_>,
-        unit_exercises: &mut UnitExercisesBuilder<'me, '_, '_>,
-    ) -> Result<(), LoadTrackError> {
-        let Indexed { data, .. } = self;
-
-        section.subsection(
-            &data.name,
-            &data.description,
-            &data.description_images,
-            &data.path,
-        );
-
-        unit_exercises.package(&data.name, &data.path, &data.includes);
-
-        Ok(())
-    }
+    pub generate: Option<String>,
+    pub solution: Option<PathBuf>,
+    /// The folder of instructor-only tests that grading adds to submissions, which is
+    /// left out of the rendered package
+    pub hidden_tests: Option<PathBuf>,
+    pub tags: Vec<String>,
+    pub duration: Option<Duration>,
+    pub difficulty: Option<Difficulty>,
+    /// What the tests of the exercise do in its starter code
+    pub expect: TestExpectation,
+    /// The edition and toolchain the exercise requires, with what it leaves unset filled
+    /// in from its unit, and the channel or the components from the track if it sets
+    /// only one of them
+    pub toolchain: ExerciseToolchain,
+    /// The `[[exercises]]` table of the exercise in the topic TOML
+    pub source: Location,
 }
 
 #[derive(Debug, Default)]
@@ -293,7 +703,9 @@ where
     to_tag(format!("{p}-{s}"))
 }
 
-fn to_tag<S>(s: S) -> String
+/// Turn `s` into a tag like the ones rendered file and folder names are made of, by lowering
+/// its case and joining its words with dashes
+pub fn to_tag<S>(s: S) -> String
 where
     S: ToString,
 {
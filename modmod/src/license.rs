@@ -0,0 +1,178 @@
+//! The license of a track, which the rendered material carries: an SPDX header in the Rust
+//! files of the exercise packages, a footer on every book page, a license slide at the end
+//! of every deck and a `LICENSE` file in the output folder.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::{PathExt, WriteExt},
+    numbering::{EXERCISES_DIR, SOLUTIONS_DIR},
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderLicenseError {}
+
+impl fmt::Display for RenderLicenseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to add license notices")
+    }
+}
+
+impl error_stack::Context for RenderLicenseError {}
+
+const SPDX_IDENTIFIER: &str = "SPDX-License-Identifier:";
+
+/// License configuration as defined in the `[license]` table of the track TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseConfig {
+    /// SPDX identifier of the license, like `CC-BY-SA-4.0`
+    pub spdx: String,
+    /// The copyright holders, like the authors of the track
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// Where the license can be read, which the notices link to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The full license text, copied to the `LICENSE` file of the output, relative to the
+    /// track TOML. Without it, the `LICENSE` file only holds the notice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+}
+
+impl LicenseConfig {
+    /// Like `Copyright Alex Doe. Licensed under CC-BY-SA-4.0.`, with the license linked to
+    /// its URL if `markdown`
+    pub fn notice(&self, markdown: bool) -> String {
+        let license = match (&self.url, markdown) {
+            (Some(url), true) => format!("[{}]({url})", self.spdx),
+            (Some(url), false) => format!("{} ({url})", self.spdx),
+            (None, _) => self.spdx.clone(),
+        };
+        match self.authors.is_empty() {
+            true => format!("Licensed under {license}."),
+            false => format!(
+                "Copyright {}. Licensed under {license}.",
+                self.authors.join(", ")
+            ),
+        }
+    }
+
+    /// The SPDX header of a generated Rust file
+    pub fn spdx_header(&self) -> String {
+        let mut header = format!("// {SPDX_IDENTIFIER} {}\n", self.spdx);
+        for author in self.authors.iter() {
+            header += &format!("// SPDX-FileCopyrightText: {author}\n");
+        }
+        header + "\n"
+    }
+
+    /// The footer of a book page, on a single line that starts with a tag so that the
+    /// glossary leaves it alone
+    pub fn book_footer(&self) -> String {
+        format!("\n---\n\n<small>{}</small>\n", self.notice(true))
+    }
+
+    /// The slide at the end of a deck
+    pub fn slide(&self) -> String {
+        format!(
+            "\n---\nlayout: center\n---\n\n# License\n\n{}\n",
+            self.notice(true)
+        )
+    }
+}
+
+impl Track {
+    /// Add the license notices of the track, if it has a license, to what's rendered in
+    /// `out_dir`: a `LICENSE` file, SPDX headers in the Rust files of the exercises and
+    /// solutions that don't have one yet, and a footer on the book pages
+    pub fn render_license(&self, out_dir: impl AsRef<Path>) -> Result<(), RenderLicenseError> {
+        let Some(license) = &self.license else {
+            return Ok(());
+        };
+        let out_dir = out_dir.as_ref();
+        let license_text = match &license.file {
+            Some(file) => file.read_to_string()?,
+            None => format!("{}\n", license.notice(false)),
+        };
+        out_dir
+            .join("LICENSE")
+            .create_file()?
+            .write_all(license_text)?;
+
+        let header = license.spdx_header();
+        for dir in [EXERCISES_DIR, SOLUTIONS_DIR].map(|d| out_dir.join(d)) {
+            if !dir.is_dir() {
+                continue;
+            }
+            let files = dir.get_dir_content::<RenderLicenseError>()?.files;
+            for file in files.iter().map(Path::new) {
+                // Leave build output alone
+                let is_build_output = file
+                    .strip_prefix(&dir)
+                    .is_ok_and(|f| f.components().any(|c| c.as_os_str() == "target"));
+                if file.extension().is_none_or(|e| e != "rs") || is_build_output {
+                    continue;
+                }
+                let content = file.read_to_string()?;
+                if content.contains(SPDX_IDENTIFIER) {
+                    continue;
+                }
+                file.create_file()?
+                    .write_all(format!("{header}{content}"))
+                    .attach_printable_lazy(|| {
+                        format!("Unable to add header to {}", file.to_string_lossy())
+                    })?;
+            }
+        }
+
+        let book_src_dir = out_dir.join("book").join("src");
+        if book_src_dir.is_dir() {
+            let footer = license.book_footer();
+            let pages = book_src_dir.get_dir_content::<RenderLicenseError>()?.files;
+            for page in pages.iter().map(Path::new) {
+                if page.extension().is_none_or(|e| e != "md") || page.ends_with("SUMMARY.md") {
+                    continue;
+                }
+                let content = page.read_to_string()?;
+                page.create_file()?
+                    .write_all(format!("{}\n{footer}", content.trim_end()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notices_name_the_license_and_its_holders() {
+        let license = LicenseConfig {
+            spdx: "CC-BY-SA-4.0".to_string(),
+            authors: vec!["Alex Doe".to_string(), "Sam Roe".to_string()],
+            url: Some("https://creativecommons.org/licenses/by-sa/4.0/".to_string()),
+            file: None,
+        };
+        assert_eq!(
+            license.notice(true),
+            "Copyright Alex Doe, Sam Roe. Licensed under \
+            [CC-BY-SA-4.0](https://creativecommons.org/licenses/by-sa/4.0/)."
+        );
+        assert_eq!(
+            license.spdx_header(),
+            "// SPDX-License-Identifier: CC-BY-SA-4.0\n\
+            // SPDX-FileCopyrightText: Alex Doe\n\
+            // SPDX-FileCopyrightText: Sam Roe\n\n"
+        );
+    }
+}
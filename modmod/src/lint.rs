@@ -0,0 +1,191 @@
+//! Linting the rendered exercise packages with clippy and rustfmt, so that the material
+//! follows the practices it teaches. Exercises that need to break a rule, like one about
+//! fixing a clippy warning, can be allowed to in the `[lint]` table of the track TOML.
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    build::exercise_target_dir, tasks::Task, testing::TestExpectation, to_tag, RenderFilter, Track,
+};
+
+/// Lint configuration as defined in the `[lint]` table of the track TOML
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintConfig {
+    /// Lints allowed in every exercise, like `clippy::needless_range_loop`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// The configuration of specific exercises, by the tag of the exercise, or the tag of
+    /// its unit and that of the exercise like `basic-syntax/fizzbuzz`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub exercises: BTreeMap<String, ExerciseLintConfig>,
+}
+
+/// Lint configuration of an exercise, in the `[lint.exercises]` table of the track TOML
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExerciseLintConfig {
+    /// Lints allowed in the exercise, on top of those allowed in every exercise
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Whether the formatting of the exercise is checked
+    #[serde(default = "crate::lint::default_rustfmt")]
+    pub rustfmt: bool,
+}
+
+pub fn default_rustfmt() -> bool {
+    true
+}
+
+impl LintConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The configuration of the exercise named `exercise` in the unit named `unit`,
+    /// preferring the one qualified with the tag of the unit
+    fn of(&self, unit: &str, exercise: &str) -> Option<&ExerciseLintConfig> {
+        let tag = to_tag(exercise);
+        self.exercises
+            .get(&format!("{}/{tag}", to_tag(unit)))
+            .or_else(|| self.exercises.get(&tag))
+    }
+
+    /// The lints allowed in the exercise named `exercise` in the unit named `unit`
+    pub fn allowed(&self, unit: &str, exercise: &str) -> Vec<&str> {
+        let exercise_allowed = self.of(unit, exercise).into_iter().flat_map(|c| &c.allow);
+        self.allow
+            .iter()
+            .chain(exercise_allowed)
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Whether the formatting of the exercise named `exercise` in the unit named `unit` is
+    /// checked
+    pub fn rustfmt(&self, unit: &str, exercise: &str) -> bool {
+        self.of(unit, exercise).is_none_or(|c| c.rustfmt)
+    }
+
+    /// The keys of the `[lint.exercises]` table that don't match any of `exercises`, given
+    /// by the names of their unit and their own, to catch typos and renamed exercises
+    pub(crate) fn unknown_exercises<'u>(
+        &self,
+        exercises: impl IntoIterator<Item = (&'u str, &'u str)>,
+    ) -> Vec<&str> {
+        let mut unknown: Vec<_> = self.exercises.keys().map(String::as_str).collect();
+        for (unit, exercise) in exercises {
+            let tag = to_tag(exercise);
+            let qualified = format!("{}/{tag}", to_tag(unit));
+            unknown.retain(|k| *k != tag && *k != qualified);
+        }
+        unknown
+    }
+}
+
+/// The arguments to `cargo clippy` that deny warnings apart from the `allowed` lints
+pub fn clippy_args<'a>(allowed: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut args = vec![
+        "clippy",
+        "--all-targets",
+        "--quiet",
+        "--",
+        "--deny",
+        "warnings",
+    ];
+    for lint in allowed {
+        args.extend(["--allow", lint]);
+    }
+    args
+}
+
+impl Track {
+    /// The `cargo clippy` and `cargo fmt --check` tasks that lint the exercise packages
+    /// rendered into `out_dir`. Exercises that are expected not to compile are left out.
+    pub fn lint_tasks(&self, out_dir: &Path) -> Vec<Task> {
+        let packages = self
+            .exercise_packages(&RenderFilter::default())
+            .into_iter()
+            .filter(|p| p.exercise.expect != TestExpectation::CompileError)
+            .map(|p| (out_dir.join(&p.dir), p))
+            .filter(|(package, _)| package.join("Cargo.toml").is_file());
+        let mut tasks = vec![];
+        for ((package, p), i) in packages.zip(1..) {
+            let name = p.dir.to_string_lossy();
+            tasks.push(
+                Task::new(format!("cargo clippy {name}"), "cargo")
+                    .args(clippy_args(
+                        self.lint.allowed(&p.unit.name, &p.exercise.name),
+                    ))
+                    .current_dir(&package)
+                    .env("CARGO_TARGET_DIR", exercise_target_dir(i)),
+            );
+            if self.lint.rustfmt(&p.unit.name, &p.exercise.name) {
+                tasks.push(
+                    Task::new(format!("cargo fmt {name}"), "cargo")
+                        .args(["fmt", "--check"])
+                        .current_dir(&package)
+                        .no_retry(),
+                );
+            }
+        }
+        tasks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exercises_are_allowed_lints_by_tag() {
+        let config: LintConfig = toml::from_str(
+            r#"
+            allow = ["clippy::needless_range_loop"]
+
+            [exercises.fizzbuzz]
+            allow = ["clippy::manual_range_contains"]
+
+            [exercises."formatting/fizzbuzz"]
+            allow = ["dead_code"]
+            rustfmt = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.allowed("Basic syntax", "FizzBuzz"),
+            [
+                "clippy::needless_range_loop",
+                "clippy::manual_range_contains"
+            ]
+        );
+        assert!(config.rustfmt("Basic syntax", "FizzBuzz"));
+        assert_eq!(
+            config.allowed("Formatting", "FizzBuzz"),
+            ["clippy::needless_range_loop", "dead_code"]
+        );
+        assert!(!config.rustfmt("Formatting", "FizzBuzz"));
+
+        assert!(config
+            .unknown_exercises([("Basic syntax", "FizzBuzz"), ("Formatting", "FizzBuzz")])
+            .is_empty());
+        assert_eq!(
+            config.unknown_exercises([("Basic syntax", "FizzBuzz")]),
+            ["formatting/fizzbuzz"]
+        );
+
+        assert_eq!(
+            clippy_args(["dead_code"]),
+            [
+                "clippy",
+                "--all-targets",
+                "--quiet",
+                "--",
+                "--deny",
+                "warnings",
+                "--allow",
+                "dead_code"
+            ]
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use std::{
+    fmt::{self, Write as _},
+    fs,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    archive::{entry_mode, entry_name, ENTRY_MODE, PACKAGES_DIR},
+    io::PathExt,
+    load::Indexed,
+    numbering::{exercise_dir, module_exercises_dir, section_file_name, unit_exercises_dir},
+    scorm::xml_escape,
+    to_tag,
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct LmsError {}
+
+impl fmt::Display for LmsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to export course for LMS")
+    }
+}
+
+impl error_stack::Context for LmsError {}
+
+/// The LMS a course export is meant for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmsFormat {
+    /// A Common Cartridge 1.3, in which exercises are assignments
+    Canvas,
+    /// A Common Cartridge 1.1, the version Moodle imports, which has no assignments.
+    /// Exercises are pages linking their code instead.
+    Moodle,
+}
+
+impl LmsFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            LmsFormat::Canvas => "canvas",
+            LmsFormat::Moodle => "moodle",
+        }
+    }
+}
+
+impl FromStr for LmsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "canvas" => Ok(LmsFormat::Canvas),
+            "moodle" => Ok(LmsFormat::Moodle),
+            _ => Err(format!("unknown LMS '{s}', expected canvas or moodle")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LmsOptions {
+    pub format: LmsFormat,
+    /// URL the HTML book built by mdbook is served at
+    pub book_url: String,
+    /// URL the built slide decks are served at, if the unit pages should link to them
+    pub slides_url: Option<String>,
+}
+
+/// A file in the cartridge
+struct Entry {
+    name: String,
+    content: Vec<u8>,
+}
+
+/// Collects the items, resources and files of the cartridge while walking over the track
+struct Cartridge<'p> {
+    out_dir: &'p Path,
+    opts: &'p LmsOptions,
+    book_url: &'p str,
+    slides_url: Option<&'p str>,
+    organization: String,
+    resources: String,
+    entries: Vec<Entry>,
+    /// Where the exercise packages of the unit that is being visited were generated
+    unit_exercises_dir: PathBuf,
+    /// The book section of the unit that is being visited
+    section_url: String,
+}
+
+impl<'t> Visitor<'t> for Cartridge<'_> {
+    type Error = LmsError;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), LmsError> {
+        writeln!(
+            self.organization,
+            r#"        <item identifier="MODULE_{}"><title>{}</title>"#,
+            module.index,
+            xml_escape(&format!("{} {}", module.index, module.data.name))
+        )
+        .unwrap();
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), LmsError> {
+        let number = position.unit_number().unwrap();
+        let title = format!("{number} {}", unit.data.name);
+        self.unit_exercises_dir = self
+            .out_dir
+            .join(module_exercises_dir(&module.data.name, module.index))
+            .join(unit_exercises_dir(&unit.data.name, unit.index));
+        self.section_url = format!(
+            "{}/{}",
+            self.book_url,
+            entry_name(&section_file_name(&unit.data.name).with_extension("html"))
+        );
+
+        let mut page = format!(
+            "<p>Read <a href=\"{}\">Unit {number} in the book</a>.</p>\n",
+            xml_escape(&self.section_url)
+        );
+        if let Some(slides_url) = self.slides_url {
+            writeln!(
+                page,
+                "<p>View <a href=\"{}\">the slides</a>.</p>",
+                xml_escape(&format!("{slides_url}/{}/", number.deck_id()))
+            )
+            .unwrap();
+        }
+        if !unit.data.topics.is_empty() {
+            page.push_str("<p>Topics:</p>\n<ul>\n");
+            for topic in unit.data.topics.iter() {
+                writeln!(page, "<li>{}</li>", xml_escape(&topic.data.name)).unwrap();
+            }
+            page.push_str("</ul>\n");
+        }
+        let page_name = format!("wiki_content/{}.html", number.deck_slug(&unit.data.name));
+        let identifier = format!("RES_{}", number.deck_id());
+        add_item(&mut self.organization, &identifier, &title);
+        add_resource(
+            &mut self.resources,
+            &identifier,
+            "webcontent",
+            Some(&page_name),
+            &[&page_name],
+        );
+        self.entries.push(Entry {
+            name: page_name,
+            content: html_page(&title, &page).into_bytes(),
+        });
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), LmsError> {
+        let number = position.exercise_number().unwrap();
+        let exercise_name = exercise_dir(&exercise.data.name, number.exercise);
+        let exercise_out_dir = self.unit_exercises_dir.join(&exercise_name);
+        let id = format!("{}_{}_{}", number.module, number.unit, number.exercise);
+        let zip_name = format!("{}.zip", exercise_name.to_string_lossy());
+        let title = format!("Exercise {number}: {}", exercise.data.name);
+        let text = format!(
+            "<p>Follow the instructions in <a href=\"{}\">Unit {} in the book</a>, and hand in the code when you're done.</p>\n",
+            xml_escape(&self.section_url),
+            position.unit_number().unwrap()
+        );
+        let code = Entry {
+            name: format!("exercises/{id}/{zip_name}"),
+            content: zip_dir(&exercise_out_dir, &exercise_name)?,
+        };
+        let identifier = format!("RES_{id}");
+        add_item(&mut self.organization, &identifier, &title);
+        let description = match self.opts.format {
+            LmsFormat::Canvas => Entry {
+                name: format!("exercises/{id}/assignment.xml"),
+                content: assignment_xml(&identifier, &title, &text, &zip_name).into_bytes(),
+            },
+            LmsFormat::Moodle => Entry {
+                name: format!("exercises/{id}.html"),
+                content: html_page(
+                    &title,
+                    &format!(
+                        "{text}<p>Download <a href=\"{id}/{}\">the code</a>.</p>\n",
+                        xml_escape(&zip_name)
+                    ),
+                )
+                .into_bytes(),
+            },
+        };
+        let (resource_type, href) = match self.opts.format {
+            LmsFormat::Canvas => ("assignment_xmlv1p0", None),
+            LmsFormat::Moodle => ("webcontent", Some(description.name.as_str())),
+        };
+        add_resource(
+            &mut self.resources,
+            &identifier,
+            resource_type,
+            href,
+            &[&description.name, &code.name],
+        );
+        self.entries.extend([description, code]);
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), LmsError> {
+        self.organization.push_str("        </item>\n");
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Export the track as a Common Cartridge that Canvas or Moodle can import, so a course
+    /// doesn't have to be set up by hand. Every module becomes a section of the course,
+    /// holding a page per unit that links to the unit's book section and slides, followed
+    /// by the unit's exercises, with their code from the exercise packages generated into
+    /// `out_dir` attached as a zip file. Returns the path of the written cartridge.
+    pub fn lms_cartridge(
+        &self,
+        out_dir: impl AsRef<Path>,
+        opts: &LmsOptions,
+    ) -> Result<PathBuf, LmsError> {
+        let out_dir = out_dir.as_ref();
+        let book_url = opts.book_url.trim_end_matches('/');
+        let slides_url = opts.slides_url.as_deref().map(|u| u.trim_end_matches('/'));
+
+        let mut cartridge = Cartridge {
+            out_dir,
+            opts,
+            book_url,
+            slides_url,
+            organization: String::new(),
+            resources: String::new(),
+            entries: vec![],
+            unit_exercises_dir: PathBuf::new(),
+            section_url: String::new(),
+        };
+        self.walk(&mut cartridge)?;
+        let Cartridge {
+            organization,
+            resources,
+            entries,
+            ..
+        } = cartridge;
+
+        let manifest = self.cartridge_manifest(opts.format, &organization, &resources);
+        let cartridge_path = out_dir
+            .join(PACKAGES_DIR)
+            .join(format!("{}-{}", to_tag(&self.name), opts.format.name()))
+            .with_extension("imscc");
+        cartridge_path.parent().unwrap().create_dir_all()?;
+
+        let attach_path = || {
+            format!(
+                "Error writing cartridge {}",
+                cartridge_path.to_string_lossy()
+            )
+        };
+        let mut zip = zip::ZipWriter::new(cartridge_path.create_file()?);
+        let options = zip::write::SimpleFileOptions::default()
+            .last_modified_time(zip::DateTime::default())
+            .unix_permissions(ENTRY_MODE);
+        let manifest = Entry {
+            name: "imsmanifest.xml".to_string(),
+            content: manifest.into_bytes(),
+        };
+        for entry in [manifest].iter().chain(entries.iter()) {
+            zip.start_file(entry.name.as_str(), options)
+                .and_then(|_| Ok(zip.write_all(&entry.content)?))
+                .into_report()
+                .attach_printable_lazy(attach_path)
+                .change_context(LmsError::default())?;
+        }
+        zip.finish()
+            .into_report()
+            .attach_printable_lazy(attach_path)
+            .change_context(LmsError::default())?;
+
+        Ok(cartridge_path)
+    }
+
+    fn cartridge_manifest(&self, format: LmsFormat, organization: &str, resources: &str) -> String {
+        let (namespace, lom_namespace, version) = match format {
+            LmsFormat::Canvas => (
+                "http://www.imsglobal.org/xsd/imsccv1p3/imscp_v1p1",
+                "http://ltsc.ieee.org/xsd/imsccv1p3/LOM/manifest",
+                "1.3.0",
+            ),
+            LmsFormat::Moodle => (
+                "http://www.imsglobal.org/xsd/imsccv1p1/imscp_v1p1",
+                "http://ltsc.ieee.org/xsd/imsccv1p1/LOM/manifest",
+                "1.1.0",
+            ),
+        };
+        let identifier = to_tag(&self.name);
+        let title = xml_escape(&self.name);
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest identifier="{identifier}"
+  xmlns="{namespace}"
+  xmlns:lomimscc="{lom_namespace}">
+  <metadata>
+    <schema>IMS Common Cartridge</schema>
+    <schemaversion>{version}</schemaversion>
+    <lomimscc:lom>
+      <lomimscc:general>
+        <lomimscc:title>
+          <lomimscc:string>{title}</lomimscc:string>
+        </lomimscc:title>
+      </lomimscc:general>
+    </lomimscc:lom>
+  </metadata>
+  <organizations>
+    <organization identifier="ORG" structure="rooted-hierarchy">
+      <item identifier="ROOT">
+{organization}      </item>
+    </organization>
+  </organizations>
+  <resources>
+{resources}  </resources>
+</manifest>
+"#
+        )
+    }
+}
+
+fn add_item(organization: &mut String, identifier: &str, title: &str) {
+    writeln!(
+        organization,
+        r#"          <item identifier="ITEM_{identifier}" identifierref="{identifier}"><title>{}</title></item>"#,
+        xml_escape(title)
+    )
+    .unwrap();
+}
+
+fn add_resource(
+    resources: &mut String,
+    identifier: &str,
+    resource_type: &str,
+    href: Option<&str>,
+    files: &[&str],
+) {
+    let href = href.map_or(String::new(), |h| format!(r#" href="{}""#, xml_escape(h)));
+    writeln!(
+        resources,
+        r#"    <resource identifier="{identifier}" type="{resource_type}"{href}>"#
+    )
+    .unwrap();
+    for file in files {
+        writeln!(resources, r#"      <file href="{}"/>"#, xml_escape(file)).unwrap();
+    }
+    resources.push_str("    </resource>\n");
+}
+
+/// A Common Cartridge 1.3 assignment with the file `attachment` next to it attached
+fn assignment_xml(identifier: &str, title: &str, text: &str, attachment: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<assignment xmlns="http://www.imsglobal.org/xsd/imscc_extensions/assignment" identifier="{identifier}">
+  <title>{}</title>
+  <text texttype="text/html">{}</text>
+  <attachments>
+    <attachment href="{}" role="Learner"/>
+  </attachments>
+  <gradable points_possible="100">true</gradable>
+  <submission_formats>
+    <format type="file"/>
+  </submission_formats>
+</assignment>
+"#,
+        xml_escape(title),
+        xml_escape(text),
+        xml_escape(attachment)
+    )
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = xml_escape(title)
+    )
+}
+
+/// A zip archive of the files in `dir`, in a folder named `name`
+fn zip_dir(dir: &Path, name: &Path) -> Result<Vec<u8>, LmsError> {
+    if !dir.is_dir() {
+        return Err(Report::new(LmsError::default()).attach_printable(format!(
+            "Exercise package {} not found. Generate the track first",
+            dir.to_string_lossy()
+        )));
+    }
+    let mut files: Vec<_> = dir.get_dir_content::<LmsError>()?.files;
+    files.sort();
+    let attach_path = || format!("Error packaging exercise {}", dir.to_string_lossy());
+    let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+    let options =
+        zip::write::SimpleFileOptions::default().last_modified_time(zip::DateTime::default());
+    for file in files {
+        let relative = Path::new(&file).strip_prefix(dir).unwrap();
+        let (content, metadata) = fs::read(&file)
+            .and_then(|content| Ok((content, fs::metadata(&file)?)))
+            .into_report()
+            .attach_printable_lazy(|| format!("Error reading {file}"))
+            .change_context(LmsError::default())?;
+        let options = options.unix_permissions(entry_mode(&metadata));
+        zip.start_file(entry_name(&name.join(relative)), options)
+            .and_then(|_| Ok(zip.write_all(&content)?))
+            .into_report()
+            .attach_printable_lazy(attach_path)
+            .change_context(LmsError::default())?;
+    }
+    let cursor = zip
+        .finish()
+        .into_report()
+        .attach_printable_lazy(attach_path)
+        .change_context(LmsError::default())?;
+    Ok(cursor.into_inner())
+}
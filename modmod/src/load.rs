@@ -1,21 +1,80 @@
 use std::{
     any::type_name,
+    collections::{BTreeMap, HashMap},
     fmt, fs,
     path::{Path, PathBuf},
 };
 
-use error_stack::{IntoReport, Result, ResultExt};
+use error_stack::{IntoReport, Report, Result, ResultExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::io::PathExt;
+use crate::{
+    calendar::Date,
+    ci::CiConfig,
+    diagnostics::{table_lines, Errors, Location},
+    freshness::FreshnessConfig,
+    glossary::GlossaryConfig,
+    hooks::HooksConfig,
+    io::PathExt,
+    license::LicenseConfig,
+    lint::LintConfig,
+    markdown::{front_matter, yaml_front_matter},
+    migrate::{upgrade, FileKind, FormatVersion},
+    nix::NixConfig,
+    numbering::NumberingConfig,
+    schedule::{Duration, ScheduleConfig},
+    slides::SlidesConfig,
+    tags::TagFilter,
+    testing::TestExpectation,
+    toolchain::{ExerciseToolchain, ToolchainConfig},
+};
 
-use super::{Exercise, Module, Topic, Track, Unit};
+use super::{Difficulty, Exercise, Module, Part, Topic, Track, Unit};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TrackDef {
+    /// Version of the format of this file, see [crate::migrate]
+    #[serde(rename = "format-version", default)]
+    pub format_version: FormatVersion,
     pub name: String,
+    /// Language of the content, as an IETF language tag like `en` or `pt-BR`
+    #[serde(default = "crate::load::serde_defaults::track_language")]
+    pub language: String,
+    /// Other track definitions whose modules are included before the modules of this track
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<PathBuf>,
     #[serde(default)]
     pub modules: Vec<PathBuf>,
+    /// Groups of consecutive modules, which get a part title in the book
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<PartDef>,
+    /// Directories containing topics that can be referenced by ID
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topic_pools: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci: Option<CiConfig>,
+    #[serde(default)]
+    pub toolchain: ToolchainConfig,
+    #[serde(default)]
+    pub nix: NixConfig,
+    #[serde(default)]
+    pub slides: SlidesConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, TagFilter>,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub freshness: FreshnessConfig,
+    #[serde(default, skip_serializing_if = "NumberingConfig::is_default")]
+    pub numbering: NumberingConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glossary: Option<GlossaryConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<LicenseConfig>,
+    #[serde(default, skip_serializing_if = "LintConfig::is_default")]
+    pub lint: LintConfig,
+    #[serde(default, skip_serializing_if = "HooksConfig::is_default")]
+    pub hooks: HooksConfig,
 }
 
 impl PathTo<TrackDef> {
@@ -24,66 +83,418 @@ impl PathTo<TrackDef> {
             data,
             path: track_path,
         } = self;
+        let mut module_paths = vec![];
+        let mut topic_pools = vec![];
+        data.collect_includes(
+            &track_path,
+            &mut vec![],
+            &mut vec![],
+            &mut module_paths,
+            &mut topic_pools,
+        )?;
+        let topic_index = TopicIndex::build(&topic_pools)?;
         let TrackDef {
+            format_version: _,
             name,
-            modules: module_paths,
+            language,
+            include: _,
+            modules: _,
+            parts,
+            topic_pools: _,
+            ci,
+            toolchain,
+            nix,
+            mut slides,
+            profiles,
+            schedule,
+            freshness,
+            numbering,
+            mut glossary,
+            mut license,
+            lint,
+            hooks,
         } = data;
 
-        let mut modules = Vec::with_capacity(module_paths.len());
+        let mut modules: Vec<Indexed<Module>> = Vec::with_capacity(module_paths.len());
         let base_path = track_path.parent().unwrap();
-        for (module_path, module_index) in module_paths.into_iter().zip(1..) {
-            modules.push(
-                ModuleDef::load(&module_path, Some(base_path))
-                    .change_context(HydrateTrackError)?
-                    .resolve(module_index)?,
-            );
+        slides.css = slides.css.map(|p| base_path.join(p));
+        slides.logo = slides.logo.map(|p| base_path.join(p));
+        if let Some(glossary) = &mut glossary {
+            glossary.terms = base_path.join(&glossary.terms);
+        }
+        if let Some(license) = &mut license {
+            license.file = license.file.as_ref().map(|f| base_path.join(f));
         }
+        slides.template = slides
+            .template
+            .map(|t| resolve_template(base_path, t))
+            .transpose()?;
+        // Report the problems of all modules, rather than only those of the first
+        let mut errors = Errors::default();
+        for (module_path, module_index) in module_paths.into_iter().zip(numbering.first_module..) {
+            let module = ModuleDef::load(&module_path, None)
+                .change_context(HydrateTrackError)
+                .and_then(|m| m.resolve(module_index, &topic_index, slides.template.as_deref()))
+                .attach_printable_lazy(|| {
+                    format!(
+                        "in module {module_index} at {}",
+                        module_path.to_string_lossy()
+                    )
+                });
+            let Some(module) = errors.collect(module) else {
+                continue;
+            };
+            if let Some(existing) = modules.iter().find(|m| m.data.name == module.data.name) {
+                errors.push(Report::new(HydrateTrackError).attach_printable(format!(
+                    "Modules {} and {} have the same title '{}'",
+                    existing.index, module.index, module.data.name
+                )));
+            }
+            modules.push(module);
+        }
+        let exercises = modules
+            .iter_mut()
+            .flat_map(|m| &mut m.data.units)
+            .flat_map(|u| &mut u.data.topics)
+            .flat_map(|t| &mut t.data.exercises);
+        for exercise in exercises {
+            let exercise = &mut exercise.data;
+            exercise.toolchain = std::mem::take(&mut exercise.toolchain).or_track(&toolchain);
+        }
+        let exercises = modules.iter().flat_map(|m| &m.data.units).flat_map(|u| {
+            u.data
+                .topics
+                .iter()
+                .flat_map(|t| &t.data.exercises)
+                .map(|e| (u.data.name.as_str(), e.data.name.as_str()))
+        });
+        for key in lint.unknown_exercises(exercises) {
+            errors.push(Report::new(HydrateTrackError).attach_printable(format!(
+                "No exercise with tag '{key}' for the [lint.exercises] table to configure"
+            )));
+        }
+        errors.finish(())?;
+        let parts = resolve_parts(parts, &modules)?;
 
-        Ok(Track { name, modules })
+        Ok(Track {
+            name,
+            language,
+            root: base_path.to_path_buf(),
+            modules,
+            parts,
+            ci,
+            toolchain,
+            nix,
+            slides,
+            profiles,
+            schedule,
+            freshness,
+            numbering,
+            glossary,
+            license,
+            lint,
+            hooks,
+        })
     }
 }
 
+/// A `[[parts]]` table of the track TOML
 #[derive(Debug, Serialize, Deserialize)]
+pub struct PartDef {
+    pub title: String,
+    /// Indices or tags of the modules in the part, like `2` or `foundations-of-rust`
+    pub modules: Vec<String>,
+}
+
+/// Find the modules of the `parts`, which must be consecutive, follow each other, and
+/// leave no modules without a part once the first part has started
+fn resolve_parts(
+    parts: Vec<PartDef>,
+    modules: &[Indexed<Module>],
+) -> Result<Vec<Part>, HydrateTrackError> {
+    let mut errors = Errors::default();
+    let mut resolved = Vec::with_capacity(parts.len());
+    for PartDef {
+        title,
+        modules: selectors,
+    } in parts
+    {
+        let mut part_modules = vec![];
+        for selector in selectors.iter() {
+            match modules.iter().position(|m| m.matches(selector)) {
+                Some(position) => part_modules.push(position),
+                None => errors.push(
+                    Report::new(HydrateTrackError)
+                        .attach_printable(format!("No module '{selector}' for part '{title}'")),
+                ),
+            }
+        }
+        resolved.push((title, part_modules));
+    }
+    errors.finish(())?;
+
+    let mut next = resolved
+        .first()
+        .and_then(|(_, modules)| modules.first().copied())
+        .unwrap_or_default();
+    for (title, part_modules) in resolved.iter() {
+        for &position in part_modules.iter() {
+            if position != next {
+                return Err(Report::new(HydrateTrackError).attach_printable(format!(
+                    "Module {} of part '{title}' doesn't follow the module before it; parts must list all modules after the first part in order",
+                    modules[position].index
+                )));
+            }
+            next += 1;
+        }
+    }
+    if let Some(module) = modules.get(next).filter(|_| !resolved.is_empty()) {
+        return Err(Report::new(HydrateTrackError).attach_printable(format!(
+            "Module {} isn't in a part, but follows the parts of the track",
+            module.index
+        )));
+    }
+    Ok(resolved
+        .into_iter()
+        .map(|(title, part_modules)| Part {
+            title,
+            modules: part_modules.into_iter().map(|p| modules[p].index).collect(),
+        })
+        .collect())
+}
+
+impl TrackDef {
+    /// The paths of the TOML files that make up the track at `track_path`: the track and
+    /// the tracks it includes, their modules, and the topics of their units and topic pools.
+    /// Files can be listed more than once.
+    pub(crate) fn toml_files(
+        track_path: &Path,
+    ) -> Result<Vec<(FileKind, PathBuf)>, HydrateTrackError> {
+        let PathTo { data, path } =
+            TrackDef::load(track_path, None).change_context(HydrateTrackError)?;
+        let mut track_paths = vec![];
+        let mut module_paths = vec![];
+        let mut topic_pools = vec![];
+        data.collect_includes(
+            &path,
+            &mut vec![],
+            &mut track_paths,
+            &mut module_paths,
+            &mut topic_pools,
+        )?;
+        let topic_index = TopicIndex::build(&topic_pools)?;
+
+        let mut files: Vec<_> = track_paths
+            .into_iter()
+            .map(|p| (FileKind::Track, p))
+            .collect();
+        let mut topic_paths = vec![];
+        for module_path in module_paths {
+            let PathTo { data, path } =
+                ModuleDef::load(&module_path, None).change_context(HydrateTrackError)?;
+            let base_path = path.parent().unwrap();
+            for topic_ref in data.units.iter().flat_map(|u| u.topics.iter()) {
+                topic_paths.push(match topic_ref {
+                    TopicRef::Path(p) => base_path.join(p),
+                    TopicRef::Id { id } => topic_index.get(id)?.to_path_buf(),
+                });
+            }
+            files.push((FileKind::Module, path));
+        }
+        for pool in topic_pools.iter() {
+            topic_paths.extend(
+                dir_content(pool)?
+                    .into_iter()
+                    .filter(|f| f.file_name() == Some("topic.toml".as_ref())),
+            );
+        }
+        for topic_path in topic_paths {
+            let path = topic_path
+                .canonicalize()
+                .into_report()
+                .change_context(HydrateTrackError)?;
+            files.push((FileKind::Topic, path));
+        }
+        Ok(files)
+    }
+
+    /// Collect the absolute paths of all modules and topic pools in the track, including
+    /// those of included tracks, in order. `visited` holds the tracks that are currently
+    /// being resolved, to detect include cycles. The paths of the track and the tracks it
+    /// includes end up in `track_paths`.
+    fn collect_includes(
+        &self,
+        track_path: &Path,
+        visited: &mut Vec<PathBuf>,
+        track_paths: &mut Vec<PathBuf>,
+        module_paths: &mut Vec<PathBuf>,
+        topic_pools: &mut Vec<PathBuf>,
+    ) -> Result<(), HydrateTrackError> {
+        if visited.iter().any(|p| p == track_path) {
+            return Err(Report::new(HydrateTrackError).attach_printable(format!(
+                "Track {} includes itself",
+                track_path.to_string_lossy()
+            )));
+        }
+        visited.push(track_path.to_path_buf());
+        track_paths.push(track_path.to_path_buf());
+
+        let base_path = track_path.parent().unwrap();
+        for include in self.include.iter() {
+            let PathTo {
+                data: included,
+                path: included_path,
+            } = TrackDef::load(include, Some(base_path)).change_context(HydrateTrackError)?;
+            included.collect_includes(
+                &included_path,
+                visited,
+                track_paths,
+                module_paths,
+                topic_pools,
+            )?;
+        }
+        module_paths.extend(self.modules.iter().map(|m| base_path.join(m)));
+        topic_pools.extend(self.topic_pools.iter().map(|p| base_path.join(p)));
+
+        visited.pop();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ModuleDef {
+    /// Version of the format of this file, see [crate::migrate]
+    #[serde(rename = "format-version", default)]
+    pub format_version: FormatVersion,
     pub name: String,
     pub description: String,
     #[serde(default)]
     pub units: Vec<UnitDef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub system_packages: Vec<String>,
+    /// Slide settings for the decks of this module, which take precedence over those of the track
+    #[serde(default, skip_serializing_if = "SlidesConfig::is_empty")]
+    pub slides: SlidesConfig,
+    /// Markdown file that introduces the module at the top of its chapter page in the book,
+    /// relative to the module TOML
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<PathBuf>,
+    /// Whether the module is an appendix, which is labelled according to the `[numbering]`
+    /// table of the track
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub appendix: bool,
 }
 
 impl PathTo<ModuleDef> {
-    fn resolve(self, module_index: usize) -> Result<Indexed<Module>, HydrateTrackError> {
+    /// Resolve the module. Units without a template of their own use the template of the
+    /// module, or else `track_template`.
+    fn resolve(
+        self,
+        module_index: usize,
+        topic_index: &TopicIndex,
+        track_template: Option<&Path>,
+    ) -> Result<Indexed<Module>, HydrateTrackError> {
         let PathTo {
             data: def,
             path: module_path,
         } = self;
         let ModuleDef {
+            format_version: _,
             name,
             description,
             units: unit_defs,
+            system_packages,
+            mut slides,
+            intro,
+            appendix,
         } = def;
 
+        // These end up in files that are shared by all decks
+        let shared = [
+            ("css", slides.css.is_some()),
+            ("logo", slides.logo.is_some()),
+            ("footer", slides.footer.is_some()),
+        ];
+        if let Some((key, _)) = shared.iter().find(|(_, is_set)| *is_set) {
+            return Err(Report::new(HydrateTrackError).attach_printable(format!(
+                "Module '{name}' sets `{key}` in its [slides] table, which can only be set in the track TOML, as it applies to all decks"
+            )));
+        }
+
         let mut units = Vec::with_capacity(unit_defs.len());
         let base_path = module_path.parent().unwrap();
-        for (unit_def, unit_index) in unit_defs.into_iter().zip(1..) {
-            units.push(unit_def.resolve(unit_index, base_path)?);
+        slides.template = slides
+            .template
+            .map(|t| resolve_template(base_path, t))
+            .transpose()?;
+        let default_template = slides.template.as_deref().or(track_template);
+        let mut errors = Errors::default();
+        let intro =
+            intro.and_then(|intro| errors.collect(existing(base_path.join(intro), "Module intro")));
+        let unit_lines = table_lines(
+            &module_path.read_to_string::<HydrateTrackError>()?,
+            "units",
+            unit_defs.len(),
+        );
+        for ((unit_def, unit_index), line) in unit_defs.into_iter().zip(1..).zip(unit_lines) {
+            let source = Location::new(&module_path, line);
+            let description = format!("in unit {module_index}.{unit_index} '{}'", unit_def.name);
+            let unit = unit_def
+                .resolve(
+                    unit_index,
+                    base_path,
+                    topic_index,
+                    default_template,
+                    source.clone(),
+                )
+                .attach_printable_lazy(|| format!("{description}, defined at {source}"));
+            units.extend(errors.collect(unit));
         }
+        errors.finish(())?;
 
         Ok(Module {
             name,
             description,
+            system_packages,
+            slides,
             units,
+            intro,
+            appendix,
+            source: Location::new(module_path, None),
         }
         .with_index(module_index))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct UnitDef {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub template: Option<PathBuf>,
     #[serde(default)]
-    pub topics: Vec<PathBuf>,
+    pub topics: Vec<TopicRef>,
+    /// Quizzes in the mdbook-quiz format, rendered at the end of the unit's book section
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quizzes: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Whether the unit is rendered in full in the preview edition, rather than as a teaser
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preview: bool,
+    /// The time the unit takes, not counting its exercises
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Duration>,
+    /// The date a maintainer last reviewed the unit, like `2026-03-02`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed: Option<Date>,
+    /// Markdown file that introduces the unit at the top of its book section, relative to
+    /// the module TOML
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<PathBuf>,
+    /// The edition and toolchain of the exercises of the unit that don't set their own
+    #[serde(default, skip_serializing_if = "ExerciseToolchain::is_default")]
+    pub toolchain: ExerciseToolchain,
 }
 
 impl UnitDef {
@@ -91,45 +502,156 @@ impl UnitDef {
         self,
         unit_index: usize,
         base_path: &Path,
+        topic_ids: &TopicIndex,
+        default_template: Option<&Path>,
+        source: Location,
     ) -> Result<Indexed<Unit>, HydrateTrackError> {
         let UnitDef {
             name,
+            id,
             template,
-            topics: topic_paths,
+            topics: topic_refs,
+            quizzes,
+            tags,
+            preview,
+            duration,
+            reviewed,
+            intro,
+            toolchain,
         } = self;
 
-        let mut topics = Vec::with_capacity(topic_paths.len());
-        for (topic_path, topic_index) in topic_paths.into_iter().zip(1..) {
-            topics.push(
-                TopicDef::load(&topic_path, Some(base_path))
+        let mut errors = Errors::default();
+        let intro =
+            intro.and_then(|intro| errors.collect(existing(base_path.join(intro), "Unit intro")));
+        let mut topics = Vec::with_capacity(topic_refs.len());
+        for (topic_ref, topic_index) in topic_refs.into_iter().zip(1..) {
+            let topic = match topic_ref {
+                TopicRef::Path(path) => Ok(base_path.join(path)),
+                TopicRef::Id { id } => topic_ids.get(&id).map(Path::to_path_buf),
+            }
+            .and_then(|topic_path| {
+                TopicDef::load(&topic_path, None)
                     .change_context(HydrateTrackError)?
-                    .resolve(topic_index)?,
-            );
+                    .resolve(topic_index)
+            });
+            topics.extend(errors.collect(topic));
+        }
+
+        let mut quizzes_found = Vec::with_capacity(quizzes.len());
+        for quiz in quizzes {
+            let path = base_path.join(quiz);
+            let quiz = path
+                .canonicalize()
+                .into_report()
+                .attach_printable_lazy(|| format!("Quiz {} not found", path.to_string_lossy()))
+                .change_context(HydrateTrackError);
+            quizzes_found.extend(errors.collect(quiz));
         }
+        let quizzes = quizzes_found;
 
         let template = match template {
-            Some(t) => Some(
-                base_path
-                    .join(t)
-                    .canonicalize()
-                    .into_report()
-                    .change_context(HydrateTrackError)?,
-            ),
-            None => None,
+            Some(t) => errors.collect(resolve_template(base_path, t)),
+            None => default_template.map(Path::to_path_buf),
         };
+        errors.finish(())?;
+
+        for exercise in topics.iter_mut().flat_map(|t| &mut t.data.exercises) {
+            let exercise = &mut exercise.data;
+            exercise.toolchain = std::mem::take(&mut exercise.toolchain).or(&toolchain);
+        }
+
+        // The durations set in the front matter of topic content files add up
+        let duration = topics
+            .iter()
+            .filter_map(|t| t.data.duration)
+            .chain(duration)
+            .reduce(|a, b| a + b);
 
         Ok(Unit {
             name,
+            id,
             template,
             topics,
+            quizzes,
+            tags,
+            preview,
+            duration,
+            reviewed,
+            intro,
+            toolchain,
+            source,
         }
         .with_index(unit_index))
     }
 }
 
+/// The canonical `path` of a file or folder of the content, or an error telling `what` is
+/// not found
+fn existing(path: PathBuf, what: &str) -> Result<PathBuf, HydrateTrackError> {
+    path.canonicalize()
+        .into_report()
+        .attach_printable_lazy(|| format!("{what} {} not found", path.to_string_lossy()))
+        .change_context(HydrateTrackError)
+}
+
+/// Reference to a topic definition in a unit, either by path
+/// relative to the module TOML or by the topic ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TopicRef {
+    Path(PathBuf),
+    Id { id: String },
+}
+
+/// Topics in the track's topic pools, by ID
+#[derive(Debug, Default)]
+struct TopicIndex(HashMap<String, PathBuf>);
+
+impl TopicIndex {
+    fn build(pools: &[PathBuf]) -> Result<Self, HydrateTrackError> {
+        let mut index = HashMap::new();
+        for pool in pools {
+            for file in dir_content(pool)? {
+                if file.file_name() != Some("topic.toml".as_ref()) {
+                    continue;
+                }
+                let PathTo { data, path } =
+                    TopicDef::load(&file, None).change_context(HydrateTrackError)?;
+                let Some(id) = data.id else {
+                    continue;
+                };
+                if let Some(existing) = index.insert(id.clone(), path.clone()) {
+                    return Err(Report::new(HydrateTrackError).attach_printable(format!(
+                        "Topics {} and {} have the same ID '{id}'",
+                        existing.to_string_lossy(),
+                        path.to_string_lossy()
+                    )));
+                }
+            }
+        }
+        Ok(Self(index))
+    }
+
+    fn get(&self, id: &str) -> Result<&Path, HydrateTrackError> {
+        self.0.get(id).map(PathBuf::as_path).ok_or_else(|| {
+            Report::new(HydrateTrackError).attach_printable(format!(
+                "No topic with ID '{id}' in the track's topic pools"
+            ))
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TopicDef {
+    /// Version of the format of this file, see [crate::migrate]
+    #[serde(rename = "format-version", default)]
+    pub format_version: FormatVersion,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// IDs of the topics that should be covered before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub exercises: Vec<ExerciseDef>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -143,17 +665,23 @@ pub struct TopicDef {
     pub content: PathBuf,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub further_reading: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl Default for TopicDef {
     fn default() -> Self {
         Self {
+            format_version: Default::default(),
             name: Default::default(),
+            id: Default::default(),
+            requires: Default::default(),
             exercises: Default::default(),
             summary: Default::default(),
             objectives: Default::default(),
             content: serde_defaults::topic_slides_md(),
             further_reading: Default::default(),
+            tags: Default::default(),
         }
     }
 }
@@ -166,43 +694,165 @@ impl PathTo<TopicDef> {
         } = self;
 
         let TopicDef {
+            format_version: _,
             name,
+            id,
+            requires,
             exercises: exercise_defs,
             summary,
             objectives,
             content,
             further_reading,
+            tags,
         } = def;
 
+        let mut errors = Errors::default();
         let mut exercises = Vec::new();
         let base_path = topic_path.parent().unwrap();
-        for (exercise_def, exercise_index) in exercise_defs.into_iter().zip(1..) {
-            exercises.push(exercise_def.resolve(exercise_index, base_path)?)
+        let exercise_lines = table_lines(
+            &topic_path.read_to_string::<HydrateTrackError>()?,
+            "exercises",
+            exercise_defs.len(),
+        );
+        let exercise_defs = exercise_defs.into_iter().zip(1..).zip(exercise_lines);
+        for ((exercise_def, exercise_index), line) in exercise_defs {
+            let source = Location::new(&topic_path, line);
+            let description = format!("in exercise '{}'", exercise_def.name);
+            let exercise = exercise_def
+                .resolve(exercise_index, base_path, source.clone())
+                .attach_printable_lazy(|| format!("{description}, defined at {source}"));
+            exercises.extend(errors.collect(exercise));
         }
 
-        let content = base_path
-            .join(content)
+        let content_path = base_path.join(content);
+        let content = content_path
             .canonicalize()
             .into_report()
-            .change_context(HydrateTrackError)?;
+            .attach_printable_lazy(|| {
+                format!(
+                    "Content file {} of topic '{name}' not found",
+                    content_path.to_string_lossy()
+                )
+            })
+            .change_context(HydrateTrackError)
+            .attach_printable_lazy(|| format!("at {}", topic_path.to_string_lossy()));
+        let content = errors.collect(content);
+        errors.finish(())?;
+        let content = content.unwrap();
+        let front_matter = FrontMatter::load(&content)?;
+        let name = front_matter.title.unwrap_or(name);
+        let mut tags = tags;
+        for tag in front_matter.tags {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
 
         let images = dir_content(&base_path.join("images"))?;
+        let flashcards = Some(base_path.join("flashcards.toml")).filter(|p| p.is_file());
 
         Ok(Topic {
             name,
+            id,
+            requires,
             exercises,
             summary,
             objectives,
             content,
             further_reading,
             images,
+            flashcards,
+            tags,
+            duration: front_matter.duration,
+            authors: front_matter.authors,
+            source: Location::new(topic_path, None),
         }
         .with_index(topic_index))
     }
 }
 
+/// Metadata at the top of a topic content file, which takes precedence over that in the
+/// topic TOML. See [topic_front_matter].
+#[derive(Debug, Default, Deserialize)]
+struct FrontMatter {
+    /// Overrides the name of the topic
+    title: Option<String>,
+    /// Added to the tags of the topic
+    #[serde(default)]
+    tags: Vec<String>,
+    /// The time the topic takes, which counts towards the duration of its unit
+    duration: Option<Duration>,
+    #[serde(default)]
+    authors: Vec<String>,
+}
+
+impl FrontMatter {
+    /// The keys of the front matter
+    const KEYS: [&'static str; 4] = ["title", "tags", "duration", "authors"];
+
+    /// The front matter of the content file at `path`, if any
+    fn load(path: &Path) -> Result<Self, HydrateTrackError> {
+        let original = path.read_to_string()?;
+        let attach_path = || format!("Invalid front matter in {}", path.to_string_lossy());
+        match topic_front_matter(&original).0 {
+            None => Ok(Self::default()),
+            Some(TopicFrontMatter::Toml(front_matter)) => toml::from_str(front_matter)
+                .into_report()
+                .attach_printable_lazy(attach_path)
+                .change_context(HydrateTrackError),
+            Some(TopicFrontMatter::Yaml(front_matter)) => serde_yaml::from_value(front_matter)
+                .into_report()
+                .attach_printable_lazy(attach_path)
+                .change_context(HydrateTrackError),
+        }
+    }
+}
+
+/// The front matter of a topic content file, before it's parsed into [FrontMatter]
+pub(crate) enum TopicFrontMatter<'c> {
+    Toml(&'c str),
+    Yaml(serde_yaml::Value),
+}
+
+/// Split the topic `content` into its front matter and the rest. The front matter is either
+/// TOML between `+++` lines, or YAML between `---` lines that sets nothing but the keys of
+/// [FrontMatter]. Other YAML is the frontmatter of the first slide, so it's left for Slidev.
+pub(crate) fn topic_front_matter(content: &str) -> (Option<TopicFrontMatter<'_>>, &str) {
+    if let (Some(front_matter), rest) = front_matter(content) {
+        return (Some(TopicFrontMatter::Toml(front_matter)), rest);
+    }
+    let (Some(front_matter), rest) = yaml_front_matter(content) else {
+        return (None, content);
+    };
+    let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(front_matter) else {
+        return (None, content);
+    };
+    let is_metadata = value.as_mapping().is_some_and(|mapping| {
+        !mapping.is_empty()
+            && mapping.keys().all(|key| {
+                key.as_str()
+                    .is_some_and(|key| FrontMatter::KEYS.contains(&key))
+            })
+    });
+    match is_metadata {
+        true => (Some(TopicFrontMatter::Yaml(value)), rest),
+        false => (None, content),
+    }
+}
+
+/// The absolute path of the slide template at `template`, relative to `base_path`
+fn resolve_template(base_path: &Path, template: PathBuf) -> Result<PathBuf, HydrateTrackError> {
+    let path = base_path.join(template);
+    path.canonicalize()
+        .into_report()
+        .attach_printable_lazy(|| format!("Slide template {} not found", path.to_string_lossy()))
+        .change_context(HydrateTrackError)
+}
+
+/// All files in the folder at `path`, sorted so that the order doesn't depend on the file
+/// system, or none if there's no such folder
 pub fn dir_content(path: &Path) -> Result<Vec<PathBuf>, HydrateTrackError> {
-    Ok(path
+    let mut files: Vec<_> = path
         .is_dir()
         .then_some(
             path.get_dir_content()
@@ -211,9 +861,37 @@ pub fn dir_content(path: &Path) -> Result<Vec<PathBuf>, HydrateTrackError> {
         .transpose()?
         .into_iter()
         .flatten()
-        .collect())
+        .collect();
+    files.sort();
+    Ok(files)
 }
 
+/// The `hint-1.md`, `hint-2.md`, ... files next to the exercise `description`, in order.
+/// Hints are numbered from 1 without gaps, so that a missing hint isn't silently left out.
+fn hints_next_to(description: &Path) -> Result<Vec<PathBuf>, HydrateTrackError> {
+    let dir = description.parent().unwrap();
+    let hint = |number: usize| dir.join(format!("hint-{number}.md"));
+    let hints: Vec<_> = (1..).map(hint).take_while(|h| h.is_file()).collect();
+    let numbered = fs::read_dir(dir)
+        .into_report()
+        .change_context(HydrateTrackError)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|name| {
+            name.strip_prefix("hint-")?
+                .strip_suffix(".md")?
+                .parse::<usize>()
+                .ok()
+        })
+        .filter(|&number| number > hints.len());
+    if let Some(number) = numbered.min() {
+        return Err(Report::new(HydrateTrackError).attach_printable(format!(
+            "Hint {} of exercise description {} doesn't exist, but hint {number} does",
+            hints.len() + 1,
+            description.to_string_lossy()
+        )));
+    }
+    Ok(hints)
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExerciseDef {
@@ -229,6 +907,32 @@ pub struct ExerciseDef {
         skip_serializing_if = "crate::load::serde_defaults::is_exercise_includes"
     )]
     pub includes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<String>,
+    /// Directory holding the reference solution, relative to the exercise directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution: Option<PathBuf>,
+    /// Directory holding instructor-only tests, relative to the exercise directory, which
+    /// `modmod grade` adds to the `tests` folder of submissions. They are never rendered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden_tests: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The time students need for the exercise
+    #[serde(
+        default,
+        alias = "estimated_time",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duration: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    /// What the tests of the exercise do in its starter code, checked by `modmod test`
+    #[serde(default, skip_serializing_if = "TestExpectation::is_default")]
+    pub expect: TestExpectation,
+    /// The edition and toolchain the exercise requires
+    #[serde(default, skip_serializing_if = "ExerciseToolchain::is_default")]
+    pub toolchain: ExerciseToolchain,
 }
 
 impl Default for ExerciseDef {
@@ -238,6 +942,14 @@ impl Default for ExerciseDef {
             path: Default::default(),
             description: serde_defaults::exercise_description_md(),
             includes: serde_defaults::exercise_includes(),
+            generate: None,
+            solution: None,
+            hidden_tests: None,
+            tags: Default::default(),
+            duration: None,
+            difficulty: None,
+            expect: TestExpectation::default(),
+            toolchain: ExerciseToolchain::default(),
         }
     }
 }
@@ -247,30 +959,55 @@ impl ExerciseDef {
         self,
         exercise_index: usize,
         base_path: &Path,
+        source: Location,
     ) -> Result<Indexed<Exercise>, HydrateTrackError> {
         let ExerciseDef {
             name,
             path: exercise_path,
             description,
             includes,
+            generate,
+            solution,
+            hidden_tests,
+            tags,
+            duration,
+            difficulty,
+            expect,
+            toolchain,
         } = self;
-        let path = base_path
-            .join(exercise_path)
-            .canonicalize()
-            .into_report()
-            .change_context(HydrateTrackError)?;
-        let description = path
-            .join(description)
-            .canonicalize()
-            .into_report()
-            .change_context(HydrateTrackError)?;
+        let path = existing(base_path.join(exercise_path), "Exercise folder")?;
+        let description = existing(path.join(description), "Exercise description");
+        let mut errors = Errors::default();
+        let solution =
+            solution.and_then(|s| errors.collect(existing(path.join(s), "Solution folder")));
+        let hidden_tests = hidden_tests
+            .and_then(|h| errors.collect(existing(path.join(h), "Hidden tests folder")));
+        let description = match (description, errors.finish(())) {
+            (Ok(description), Ok(())) => description,
+            (Err(mut report), Err(other)) => {
+                report.extend_one(other);
+                return Err(report);
+            }
+            (Err(report), _) | (_, Err(report)) => return Err(report),
+        };
         let description_images = dir_content(&path.join("images"))?;
+        let hints = hints_next_to(&description)?;
         Ok(Exercise {
             name,
             path,
             description,
             description_images,
+            hints,
             includes,
+            generate,
+            solution,
+            hidden_tests,
+            tags,
+            duration,
+            difficulty,
+            expect,
+            toolchain,
+            source,
         }
         .with_index(exercise_index))
     }
@@ -293,6 +1030,8 @@ impl fmt::Display for LoadError {
 impl error_stack::Context for LoadError {}
 
 pub trait Load: DeserializeOwned + Sized + 'static {
+    const KIND: FileKind;
+
     fn load(path: &Path, base_path: Option<&Path>) -> Result<PathTo<Self>, LoadError> {
         let path = base_path.map(|b| b.join(path)).unwrap_or(path.to_owned());
         let path = path
@@ -305,6 +1044,7 @@ pub trait Load: DeserializeOwned + Sized + 'static {
                 )
             })
             .change_context_lazy(|| LoadError(type_name::<Self>(), path.clone()))?;
+        log::debug!("Loading {} {}", Self::KIND, path.to_string_lossy());
         let content = fs::read_to_string(&path)
             .into_report()
             .attach_printable_lazy(|| {
@@ -314,6 +1054,15 @@ pub trait Load: DeserializeOwned + Sized + 'static {
                 )
             })
             .change_context_lazy(|| LoadError(type_name::<Self>(), path.clone()))?;
+        let content = upgrade(Self::KIND, &content)
+            .attach_printable_lazy(|| {
+                format!(
+                    "Unable to upgrade file at path {path} to the latest format version",
+                    path = path.to_string_lossy()
+                )
+            })
+            .change_context_lazy(|| LoadError(type_name::<Self>(), path.clone()))?
+            .content;
         let data: Self = toml::from_str(&content)
             .into_report()
             .attach_printable_lazy(|| {
@@ -324,9 +1073,17 @@ pub trait Load: DeserializeOwned + Sized + 'static {
     }
 }
 
-impl Load for TrackDef {}
-impl Load for ModuleDef {}
-impl Load for TopicDef {}
+impl Load for TrackDef {
+    const KIND: FileKind = FileKind::Track;
+}
+
+impl Load for ModuleDef {
+    const KIND: FileKind = FileKind::Module;
+}
+
+impl Load for TopicDef {
+    const KIND: FileKind = FileKind::Topic;
+}
 
 #[derive(Debug)]
 pub struct PathTo<T> {
@@ -342,12 +1099,19 @@ trait WithPath: Sized {
 
 impl<T> WithPath for T {}
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Indexed<T> {
     pub data: T,
     pub index: usize,
 }
 
+impl<T> Indexed<T> {
+    /// Wrap `data` with its 1-based position in the parent collection
+    pub fn new(data: T, index: usize) -> Self {
+        Self { data, index }
+    }
+}
+
 trait WithIndex: Sized {
     fn with_index(self, index: usize) -> Indexed<Self> {
         Indexed { data: self, index }
@@ -388,6 +1152,10 @@ pub mod serde_defaults {
         includes == &exercise_includes()
     }
 
+    pub fn track_language() -> String {
+        "en".to_string()
+    }
+
     pub fn topic_slides_md() -> PathBuf {
         PathBuf::from("slides.md")
     }
@@ -0,0 +1,386 @@
+use std::{fmt, path::Path};
+
+use error_stack::Result;
+
+use crate::{
+    book::without_quizzes,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    markdown::{blocks, inlines, strip_comments, strip_tags, Block, Inline},
+    numbering::section_file_name,
+    unicode::to_ascii,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderManError {}
+
+impl fmt::Display for RenderManError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render exercise man pages")
+    }
+}
+
+impl error_stack::Context for RenderManError {}
+
+/// Name of the folder in the output directory the man pages and text files are written to.
+/// It can be passed to `man -M` as is.
+pub const MAN_DIR: &str = "man";
+
+/// Width plain text is wrapped at
+const TEXT_WIDTH: usize = 78;
+
+/// An exercise as rendered into a book section
+struct BookExercise<'s> {
+    /// The exercise number, e.g. `2.1.3`
+    number: &'s str,
+    title: &'s str,
+    content: String,
+}
+
+impl Track {
+    /// Export the exercise instructions of the units `filter` selects in the rendered book in
+    /// `out_dir` for reading in a terminal, as a man page in section 7 and as a plain text
+    /// file per exercise, named after the exercise number: `man 7 exercise-2-1-3` shows
+    /// exercise 2.1.3.
+    /// With `ascii`, emoji and typographic punctuation are replaced, for terminals and
+    /// man page formatters that can't show them.
+    pub fn render_man(
+        &self,
+        out_dir: impl AsRef<Path>,
+        filter: &RenderFilter,
+        ascii: bool,
+    ) -> Result<(), RenderManError> {
+        let out_dir = out_dir.as_ref();
+        let book_src_dir = out_dir.join("book").join("src");
+        let man_dir = out_dir.join(MAN_DIR).join("man7");
+        let text_dir = out_dir.join(MAN_DIR).join("txt");
+        man_dir.create_dir_all()?;
+        text_dir.create_dir_all()?;
+
+        let track_name = match ascii {
+            true => to_ascii(&self.name),
+            false => self.name.as_str().into(),
+        };
+        self.walk(&mut ManPages {
+            filter,
+            book_src_dir: &book_src_dir,
+            man_dir: &man_dir,
+            text_dir: &text_dir,
+            ascii,
+            track_name: &track_name,
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Writes the man pages and text files of the exercises in every unit in the book while
+/// walking over the track
+struct ManPages<'p> {
+    /// The units that were rendered into the book
+    filter: &'p RenderFilter,
+    book_src_dir: &'p Path,
+    man_dir: &'p Path,
+    text_dir: &'p Path,
+    ascii: bool,
+    track_name: &'p str,
+}
+
+impl<'t> Visitor<'t> for ManPages<'_> {
+    type Error = RenderManError;
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RenderManError> {
+        if !self.filter.includes(module, unit) {
+            return Ok(());
+        }
+        let section_path = self.book_src_dir.join(section_file_name(&unit.data.name));
+        let content: String = section_path.read_to_string()?;
+        let content = without_quizzes(&strip_comments(&content));
+        let content = match self.ascii {
+            true => to_ascii(&content).into_owned(),
+            false => content,
+        };
+        for exercise in book_exercises(&content) {
+            let name = format!("exercise-{}", exercise.number.replace('.', "-"));
+            self.man_dir
+                .join(&name)
+                .with_extension("7")
+                .create_file()?
+                .write_all(to_man_page(&name, &exercise, self.track_name))?;
+            self.text_dir
+                .join(&name)
+                .with_extension("txt")
+                .create_file()?
+                .write_all(to_text(&exercise))?;
+        }
+        Ok(())
+    }
+}
+
+/// Split a book section into its exercises, which start with an `## Exercise` heading
+fn book_exercises(section: &str) -> Vec<BookExercise<'_>> {
+    let mut exercises: Vec<BookExercise> = vec![];
+    for line in section.lines() {
+        if let Some((number, title)) = line
+            .strip_prefix("## Exercise ")
+            .and_then(|h| h.split_once(": "))
+        {
+            exercises.push(BookExercise {
+                number,
+                title: title.trim(),
+                content: String::new(),
+            });
+        } else if let Some(exercise) = exercises.last_mut() {
+            exercise.content.push_str(line);
+            exercise.content.push('\n');
+        }
+    }
+    exercises
+}
+
+fn to_man_page(name: &str, exercise: &BookExercise, track_name: &str) -> String {
+    let mut page = format!(
+        ".TH {} 7 \"\" \"{}\" \"Exercises\"\n.SH NAME\n{name} \\- {}\n.SH DESCRIPTION\n",
+        name.to_uppercase(),
+        roff_escape(track_name).replace('"', "\\(dq"),
+        roff_escape(&format!("Exercise {}: {}", exercise.number, exercise.title)),
+    );
+    // List items nest by indenting relative to the items they are in
+    let mut list_depth = 0;
+    let mut numbers = vec![];
+    for block in blocks(&exercise.content) {
+        let Block::ListItem {
+            depth,
+            ordered,
+            text,
+        } = &block
+        else {
+            page.push_str(&".RE\n".repeat(list_depth));
+            list_depth = 0;
+            numbers.clear();
+            page.push_str(&roff_block(block));
+            continue;
+        };
+        while list_depth > *depth {
+            page.push_str(".RE\n");
+            list_depth -= 1;
+        }
+        while list_depth < *depth {
+            page.push_str(".RS\n");
+            list_depth += 1;
+        }
+        numbers.resize(depth + 1, 0);
+        numbers[*depth] += 1;
+        let tag = match ordered {
+            true => format!("{}.", numbers[*depth]),
+            false => "\\(bu".to_string(),
+        };
+        page.push_str(&format!(
+            ".IP {tag} 4\n{}\n",
+            roff_line(&roff_inline(&inlines(text)))
+        ));
+    }
+    page.push_str(&".RE\n".repeat(list_depth));
+    page
+}
+
+fn roff_block(block: Block) -> String {
+    match block {
+        Block::Heading { text, .. } => format!(".SS {}\n", roff_inline(&inlines(&text))),
+        Block::Paragraph(text) => format!(".PP\n{}\n", roff_line(&roff_inline(&inlines(&text)))),
+        Block::Quote(text) => format!(".RS 4\n{}\n.RE\n", roff_line(&roff_inline(&inlines(&text)))),
+        Block::Code { code, .. } => {
+            let lines: Vec<_> = code
+                .trim_end()
+                .lines()
+                .map(|l| roff_line(&roff_escape(l)))
+                .collect();
+            format!(".PP\n.RS 4\n.nf\n{}\n.fi\n.RE\n", lines.join("\n"))
+        }
+        Block::Rule => ".PP\n".to_string(),
+        Block::Html(html) => match strip_tags(&html).trim() {
+            "" => String::new(),
+            text => format!(".PP\n{}\n", roff_line(&roff_escape(text))),
+        },
+        Block::ListItem { .. } => unreachable!(),
+    }
+}
+
+fn roff_inline(inlines: &[Inline]) -> String {
+    let mut output = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => output.push_str(&roff_escape(text)),
+            Inline::Code(code) => output.push_str(&format!("\\fB{}\\fP", roff_escape(code))),
+            Inline::Strong(inner) => output.push_str(&format!("\\fB{}\\fP", roff_inline(inner))),
+            Inline::Emphasis(inner) => output.push_str(&format!("\\fI{}\\fP", roff_inline(inner))),
+            Inline::Link { label, target } => {
+                output.push_str(&roff_inline(label));
+                if target.contains("://") && !matches!(label[..], [Inline::Text(t)] if t == *target)
+                {
+                    output.push_str(&format!(" <{}>", roff_escape(target)));
+                }
+            }
+            Inline::Image { alt, .. } => output.push_str(&format!("[image: {}]", roff_escape(alt))),
+        }
+    }
+    output
+}
+
+fn roff_escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+}
+
+/// Keep a line of text from being read as a request
+fn roff_line(line: &str) -> String {
+    if line.starts_with(['.', '\'']) {
+        format!("\\&{line}")
+    } else {
+        line.to_string()
+    }
+}
+
+fn to_text(exercise: &BookExercise) -> String {
+    let title = format!("Exercise {}: {}", exercise.number, exercise.title);
+    let mut text = format!("{title}\n{}\n", "=".repeat(title.chars().count()));
+    let mut numbers = vec![];
+    for block in blocks(&exercise.content) {
+        if !matches!(block, Block::ListItem { .. }) {
+            numbers.clear();
+        }
+        match block {
+            Block::Heading { text: heading, .. } => {
+                let heading = text_inline(&inlines(&heading));
+                text.push_str(&format!(
+                    "\n{heading}\n{}\n",
+                    "-".repeat(heading.chars().count())
+                ));
+            }
+            Block::Paragraph(paragraph) => {
+                text.push('\n');
+                text.push_str(&wrap(&text_inline(&inlines(&paragraph)), "", ""));
+            }
+            Block::ListItem {
+                depth,
+                ordered,
+                text: item,
+            } => {
+                if numbers.is_empty() {
+                    text.push('\n');
+                }
+                numbers.resize(depth + 1, 0);
+                numbers[depth] += 1;
+                let indent = "  ".repeat(depth);
+                let marker = match ordered {
+                    true => format!("{indent}{}. ", numbers[depth]),
+                    false => format!("{indent}- "),
+                };
+                let hanging = " ".repeat(marker.chars().count());
+                text.push_str(&wrap(&text_inline(&inlines(&item)), &marker, &hanging));
+            }
+            Block::Quote(quote) => {
+                text.push('\n');
+                text.push_str(&wrap(&text_inline(&inlines(&quote)), "  | ", "  | "));
+            }
+            Block::Code { code, .. } => {
+                text.push('\n');
+                for line in code.trim_end().lines() {
+                    text.push_str(&format!("    {line}\n").replace("    \n", "\n"));
+                }
+            }
+            Block::Rule => text.push_str(&format!("\n{}\n", "-".repeat(TEXT_WIDTH))),
+            Block::Html(html) => match strip_tags(&html).trim() {
+                "" => {}
+                line => {
+                    text.push('\n');
+                    text.push_str(&wrap(line, "", ""));
+                }
+            },
+        }
+    }
+    text
+}
+
+fn text_inline(inlines: &[Inline]) -> String {
+    let mut output = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => output.push_str(text),
+            Inline::Code(code) => output.push_str(&format!("`{code}`")),
+            Inline::Strong(inner) | Inline::Emphasis(inner) => output.push_str(&text_inline(inner)),
+            Inline::Link { label, target } => {
+                let label = text_inline(label);
+                output.push_str(&label);
+                if target.contains("://") && label != *target {
+                    output.push_str(&format!(" <{target}>"));
+                }
+            }
+            Inline::Image { alt, .. } => output.push_str(&format!("[image: {alt}]")),
+        }
+    }
+    output
+}
+
+/// Wrap `text` at [`TEXT_WIDTH`], starting the first line with `first` and the others with `rest`
+fn wrap(text: &str, first: &str, rest: &str) -> String {
+    let mut output = String::new();
+    let mut line = first.to_string();
+    let mut line_is_empty = true;
+    for word in text.split_whitespace() {
+        if !line_is_empty && line.chars().count() + 1 + word.chars().count() > TEXT_WIDTH {
+            output.push_str(line.trim_end());
+            output.push('\n');
+            line = rest.to_string();
+            line_is_empty = true;
+        }
+        if !line_is_empty {
+            line.push(' ');
+        }
+        line.push_str(word);
+        line_is_empty = false;
+    }
+    output.push_str(line.trim_end());
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTION: &str = "# Unit 2.1 - Basic Syntax\n\n## Exercise 2.1.1: Basic Syntax\n\nOpen `exercises/2-foundations`.\n\n### 2.1.1.A Numbers\n\n1. Run **`cargo run`**\n2. See [the book](https://doc.rust-lang.org/book)\n\n```rust\n.fn main() {}\n```\n";
+
+    #[test]
+    fn exercises_are_converted_to_man_pages_and_text() {
+        let exercises = book_exercises(SECTION);
+        assert_eq!(exercises.len(), 1);
+        assert_eq!(
+            to_man_page("exercise-2-1-1", &exercises[0], "Rust intro"),
+            ".TH EXERCISE-2-1-1 7 \"\" \"Rust intro\" \"Exercises\"\n\
+            .SH NAME\nexercise-2-1-1 \\- Exercise 2.1.1: Basic Syntax\n\
+            .SH DESCRIPTION\n\
+            .PP\nOpen \\fBexercises/2-foundations\\fP.\n\
+            .SS 2.1.1.A Numbers\n\
+            .IP 1. 4\nRun \\fB\\fBcargo run\\fP\\fP\n\
+            .IP 2. 4\nSee the book <https://doc.rust-lang.org/book>\n\
+            .PP\n.RS 4\n.nf\n\\&.fn main() {}\n.fi\n.RE\n"
+        );
+        assert_eq!(
+            to_text(&exercises[0]),
+            "Exercise 2.1.1: Basic Syntax\n============================\n\n\
+            Open `exercises/2-foundations`.\n\n\
+            2.1.1.A Numbers\n---------------\n\n\
+            1. Run `cargo run`\n\
+            2. See the book <https://doc.rust-lang.org/book>\n\n\
+            \x20   .fn main() {}\n"
+        );
+    }
+}
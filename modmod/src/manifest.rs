@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cache::sha256,
+    io::{PathExt, WriteExt},
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ManifestError {}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to update output folder")
+    }
+}
+
+impl error_stack::Context for ManifestError {}
+
+/// The file in the output folder that lists the files modmod rendered into it
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The files modmod rendered into an output folder, relative to that folder, with the
+/// SHA-256 checksums of their contents in hexadecimal. Rendering the same inputs gives the
+/// same files, so CI can verify a build by comparing manifests. Files that were added
+/// later, like those of `npm install` or `mdbook build`, are not listed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutputManifest {
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl OutputManifest {
+    /// The manifest of all files in `dir`, apart from the manifest itself
+    pub fn of_dir(dir: &Path) -> Result<Self, ManifestError> {
+        let mut files = BTreeMap::new();
+        for file in dir.get_dir_content::<ManifestError>()?.files {
+            let relative = Path::new(&file).strip_prefix(dir).unwrap();
+            if relative == Path::new(MANIFEST_FILE) {
+                continue;
+            }
+            let content = fs::read(&file)
+                .into_report()
+                .attach_printable_lazy(|| format!("Unable to read {file}"))
+                .change_context(ManifestError::default())?;
+            files.insert(relative.to_path_buf(), sha256(&content));
+        }
+        Ok(Self { files })
+    }
+
+    /// Load the manifest of `out_dir`, if it has one
+    pub fn load(out_dir: &Path) -> Result<Option<Self>, ManifestError> {
+        let path = out_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        serde_json::from_str(&path.read_to_string()?)
+            .into_report()
+            .attach_printable_lazy(|| format!("Unable to parse {}", path.to_string_lossy()))
+            .change_context(ManifestError::default())
+            .map(Some)
+    }
+
+    pub fn write(&self, out_dir: &Path) -> Result<(), ManifestError> {
+        out_dir
+            .join(MANIFEST_FILE)
+            .create_file()?
+            .write_all(serde_json::to_string_pretty(self).unwrap() + "\n")
+    }
+}
+
+/// What [sync_output] did to the output folder
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    /// The number of files that were created or changed
+    pub written: usize,
+    /// The number of files that were rendered the same as before
+    pub unchanged: usize,
+    /// The files of an earlier render that are no longer produced, which were removed
+    pub removed: Vec<PathBuf>,
+}
+
+/// Bring `out_dir` up to date with the output rendered into `staging_dir`. Changed files
+/// are written, and files that the manifest of `out_dir` lists but are no longer rendered
+/// are removed, along with the folders they leave empty. Files that modmod didn't render,
+/// or that were there before the first render with a manifest, are left alone. Unchanged
+/// files are not written, so that tools that watch the output folder don't rebuild them.
+pub fn sync_output(staging_dir: &Path, out_dir: &Path) -> Result<SyncSummary, ManifestError> {
+    out_dir.create_dir_all()?;
+    let previous = OutputManifest::load(out_dir)?.unwrap_or_default();
+    let manifest = OutputManifest::of_dir(staging_dir)?;
+
+    let mut summary = SyncSummary::default();
+    for file in manifest.files.keys() {
+        let (from, to) = (staging_dir.join(file), out_dir.join(file));
+        let content = fs::read(&from)
+            .into_report()
+            .change_context(ManifestError::default())?;
+        if fs::read(&to).is_ok_and(|existing| existing == content) {
+            summary.unchanged += 1;
+            continue;
+        }
+        to.parent().unwrap().create_dir_all()?;
+        to.create_file()?.write_all(content)?;
+        summary.written += 1;
+    }
+
+    let stale = previous
+        .files
+        .keys()
+        .filter(|file| !manifest.files.contains_key(*file));
+    for file in stale {
+        let path = out_dir.join(file);
+        if !path.is_file() {
+            continue;
+        }
+        fs::remove_file(&path)
+            .into_report()
+            .attach_printable_lazy(|| format!("Unable to remove {}", path.to_string_lossy()))
+            .change_context(ManifestError::default())?;
+        // Removing a folder fails once it has other content
+        for dir in path.ancestors().skip(1).take_while(|d| *d != out_dir) {
+            if fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+        summary.removed.push(file.clone());
+    }
+
+    manifest.write(out_dir)?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_stale_rendered_files_are_removed() {
+        let out_dir = tempfile::tempdir().unwrap();
+        let out_dir = out_dir.path();
+        let render = |files: &[(&str, &str)]| {
+            let staging_dir = tempfile::tempdir().unwrap();
+            for (path, content) in files {
+                let path = staging_dir.path().join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            sync_output(staging_dir.path(), out_dir).unwrap()
+        };
+
+        fs::create_dir_all(out_dir.join("slides/node_modules")).unwrap();
+        fs::write(out_dir.join("slides/node_modules/slidev.js"), "").unwrap();
+        let summary = render(&[
+            ("book/src/1_1-intro.md", "Intro"),
+            ("exercises/1-intro/1-hello/src/main.rs", "fn main() {}"),
+        ]);
+        assert_eq!((summary.written, summary.removed.len()), (2, 0));
+
+        let summary = render(&[
+            ("book/src/1_1-introduction.md", "Intro"),
+            ("exercises/1-intro/1-hello/src/main.rs", "fn main() {}"),
+        ]);
+        assert_eq!((summary.written, summary.unchanged), (1, 1));
+        assert_eq!(summary.removed, [PathBuf::from("book/src/1_1-intro.md")]);
+        let manifest = OutputManifest::load(out_dir).unwrap().unwrap();
+        assert_eq!(
+            manifest.files.keys().collect::<Vec<_>>(),
+            [
+                "book/src/1_1-introduction.md",
+                "exercises/1-intro/1-hello/src/main.rs"
+            ]
+        );
+        assert_eq!(
+            manifest.files[Path::new("book/src/1_1-introduction.md")],
+            "24601bcaae6e170b381367ec4f4475786c6dbef5e8332f8903779c76d298d304"
+        );
+        assert!(!out_dir.join("book/src/1_1-intro.md").exists());
+        assert!(out_dir.join("book/src/1_1-introduction.md").exists());
+        assert!(out_dir.join("slides/node_modules/slidev.js").exists());
+
+        render(&[("book/src/1_1-introduction.md", "Intro")]);
+        assert!(!out_dir.join("exercises").exists());
+    }
+}
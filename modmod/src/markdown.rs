@@ -0,0 +1,964 @@
+use std::path::{Path, PathBuf};
+
+use error_stack::{Context, Report, Result};
+
+const TEACHER_ONLY_START: &str = "#[modmod:teacher-only]";
+const TEACHER_ONLY_END: &str = "#[modmod:end]";
+
+/// A fenced code block in a markdown document
+#[derive(Debug, PartialEq, Eq)]
+pub struct CodeBlock<'c> {
+    /// The info string following the opening fence, e.g. `rust{all|2}`
+    pub info: &'c str,
+    /// 1-based line number of the opening fence
+    pub line: usize,
+    pub code: String,
+}
+
+impl CodeBlock<'_> {
+    /// The language of the block, with Slidev line highlighting and
+    /// rustdoc attributes like `ignore` stripped off
+    pub fn lang(&self) -> &str {
+        self.info
+            .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// Find all fenced code blocks in `content`
+pub fn code_blocks(content: &str) -> Vec<CodeBlock<'_>> {
+    let mut blocks = vec![];
+    let mut current: Option<(&str, &str, usize, String)> = None;
+
+    for (line, line_i) in content.lines().zip(1..) {
+        let trimmed = line.trim_start();
+        match &mut current {
+            None => {
+                if let Some(fence) = fence(trimmed) {
+                    current = Some((fence, trimmed[fence.len()..].trim(), line_i, String::new()))
+                }
+            }
+            Some((fence, info, start, code)) => {
+                if trimmed.starts_with(*fence) && trimmed.trim_end() == *fence {
+                    blocks.push(CodeBlock {
+                        info,
+                        line: *start,
+                        code: std::mem::take(code),
+                    });
+                    current = None;
+                } else {
+                    code.push_str(line);
+                    code.push('\n');
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+pub fn fence(line: &str) -> Option<&str> {
+    for marker in ['`', '~'] {
+        let len = line.chars().take_while(|c| *c == marker).count();
+        if len >= 3 {
+            return Some(&line[..len]);
+        }
+    }
+    None
+}
+
+const FRONT_MATTER_DELIMITER: &str = "+++";
+const YAML_FRONT_MATTER_DELIMITER: &str = "---";
+
+/// Split `content` into its TOML front matter, the lines between the `+++` lines it starts
+/// with, and the rest. Slidev already uses `---` for the headmatter of decks.
+pub fn front_matter(content: &str) -> (Option<&str>, &str) {
+    delimited_front_matter(content, FRONT_MATTER_DELIMITER)
+}
+
+/// Split `content` into its YAML front matter, the lines between the `---` lines it starts
+/// with, and the rest. In slides, these lines can as well be the frontmatter of the first
+/// slide.
+pub fn yaml_front_matter(content: &str) -> (Option<&str>, &str) {
+    delimited_front_matter(content, YAML_FRONT_MATTER_DELIMITER)
+}
+
+fn delimited_front_matter<'c>(content: &'c str, delimiter: &str) -> (Option<&'c str>, &'c str) {
+    let Some(rest) = content
+        .strip_prefix(delimiter)
+        .and_then(|rest| rest.strip_prefix('\n').or(rest.strip_prefix("\r\n")))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+/// Remove HTML comments outside of code blocks from `content`.
+/// Slidev uses these for speaker notes. Lines that only held a comment are dropped.
+pub fn strip_comments(content: &str) -> String {
+    if !content.contains("<!--") {
+        return content.to_string();
+    }
+
+    let mut output = String::with_capacity(content.len());
+    let mut open_fence: Option<&str> = None;
+    let mut in_comment = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+            output.push_str(line);
+            output.push('\n');
+            continue;
+        }
+        if !in_comment {
+            if let Some(marker) = fence(trimmed) {
+                open_fence = Some(marker);
+                output.push_str(line);
+                output.push('\n');
+                continue;
+            }
+        }
+
+        let had_comment = in_comment || line.contains("<!--");
+        let mut rest = line;
+        let mut kept = String::new();
+        loop {
+            if in_comment {
+                let Some(end) = rest.find("-->") else {
+                    break;
+                };
+                rest = &rest[end + "-->".len()..];
+                in_comment = false;
+            } else {
+                let Some(start) = rest.find("<!--") else {
+                    kept.push_str(rest);
+                    break;
+                };
+                kept.push_str(&rest[..start]);
+                rest = &rest[start + "<!--".len()..];
+                in_comment = true;
+            }
+        }
+        if had_comment && kept.trim().is_empty() {
+            continue;
+        }
+        output.push_str(kept.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// The line that starts the speaker notes of a slide in remark-style markdown
+const NOTES_MARKER: &str = "???";
+
+/// Turn remark-style speaker notes, which run from a `???` line to the end of the slide,
+/// into the HTML comment at the end of the slide that Slidev reads notes from
+pub fn notes_to_comments(content: &str) -> String {
+    if !content.lines().any(|l| l.trim() == NOTES_MARKER) {
+        return content.to_string();
+    }
+
+    let mut output = String::with_capacity(content.len());
+    let mut open_fence: Option<&str> = None;
+    let mut in_notes = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+        } else if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+        } else if !in_notes && line.trim() == NOTES_MARKER {
+            in_notes = true;
+            output.push_str("<!--\n");
+            continue;
+        } else if in_notes && line.trim_end() == "---" {
+            in_notes = false;
+            output.push_str("-->\n\n");
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    if in_notes {
+        output.push_str("-->\n");
+    }
+    output
+}
+
+/// The speaker notes of a slide
+#[derive(Debug, PartialEq, Eq)]
+pub struct SlideNotes {
+    /// The first heading on the slide
+    pub title: Option<String>,
+    pub notes: String,
+}
+
+/// Collect the speaker notes from Slidev markdown. Like Slidev, this takes the
+/// comment at the end of each slide as its notes. Other comments are skipped.
+pub fn slide_notes(content: &str) -> Vec<SlideNotes> {
+    let mut slides = vec![];
+    let mut title = None;
+    let mut notes: Option<String> = None;
+    let mut comment: Option<String> = None;
+    let mut open_fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(text) = &mut comment {
+            match line.find("-->") {
+                Some(end) => {
+                    text.push_str(&line[..end]);
+                    notes = comment.take();
+                }
+                None => {
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+            continue;
+        }
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+            continue;
+        }
+
+        if line.trim_end() == "---" {
+            if let Some(notes) = notes.take() {
+                slides.push(SlideNotes {
+                    title: title.take(),
+                    notes: notes.trim().to_string(),
+                });
+            }
+            title = None;
+        } else if let Some(text) = trimmed.strip_prefix("<!--") {
+            match text.find("-->") {
+                Some(end) => notes = Some(text[..end].to_string()),
+                None => comment = Some(format!("{text}\n")),
+            }
+        } else if !trimmed.is_empty() {
+            notes = None;
+            open_fence = fence(trimmed);
+            if title.is_none() && trimmed.starts_with('#') {
+                title = Some(trimmed.trim_start_matches('#').trim().to_string());
+            }
+        }
+    }
+    if let Some(notes) = notes {
+        slides.push(SlideNotes {
+            title,
+            notes: notes.trim().to_string(),
+        });
+    }
+    slides.retain(|s| !s.notes.is_empty());
+    slides
+}
+
+/// A slide in Slidev markdown
+#[derive(Debug, PartialEq, Eq)]
+pub struct Slide {
+    /// The frontmatter of the slide. For the first slide, this is the headmatter of the deck.
+    pub frontmatter: String,
+    pub content: String,
+}
+
+/// Split a Slidev deck into its slides. Slides are separated by `---` lines, which may be
+/// followed by the frontmatter of the next slide, closed by another `---` line.
+/// Slides without content are left out.
+pub fn split_slides(deck: &str) -> Vec<Slide> {
+    let mut slides = vec![];
+    let mut slide = Slide {
+        frontmatter: String::new(),
+        content: String::new(),
+    };
+    let mut lines = deck.lines().peekable();
+    let mut in_frontmatter = lines.next_if(|l| l.trim_end() == "---").is_some();
+    let mut open_fence: Option<&str> = None;
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if in_frontmatter {
+            if line.trim_end() == "---" {
+                in_frontmatter = false;
+            } else {
+                slide.frontmatter.push_str(line);
+                slide.frontmatter.push('\n');
+            }
+            continue;
+        }
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+        } else if line.trim_end() == "---" {
+            slides.push(std::mem::replace(
+                &mut slide,
+                Slide {
+                    frontmatter: String::new(),
+                    content: String::new(),
+                },
+            ));
+            in_frontmatter = lines.peek().is_some_and(|l| is_yaml_key(l));
+            continue;
+        } else {
+            open_fence = fence(trimmed);
+        }
+        slide.content.push_str(line);
+        slide.content.push('\n');
+    }
+    slides.push(slide);
+    slides.retain(|s| !s.content.trim().is_empty());
+    slides
+}
+
+/// Whether `line` starts a mapping entry, like `layout: cover`
+fn is_yaml_key(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(key, _)| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
+}
+
+/// Escape the characters that MDX would parse as JSX expressions or tags, outside of
+/// code blocks and inline code. HTML tags are kept, as MDX accepts them as JSX.
+pub fn escape_mdx(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut open_fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+        } else if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+        } else {
+            let mut in_code = false;
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '`' => in_code = !in_code,
+                    '{' | '}' if !in_code => output.push('\\'),
+                    '<' if !in_code
+                        && !chars
+                            .peek()
+                            .is_some_and(|n| n.is_ascii_alphabetic() || *n == '/') =>
+                    {
+                        output.push_str("&lt;");
+                        continue;
+                    }
+                    _ => {}
+                }
+                output.push(c);
+            }
+            output.push('\n');
+            continue;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+/// A block of a Markdown document, see [`blocks`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Block {
+    Heading {
+        level: usize,
+        text: String,
+    },
+    Paragraph(String),
+    /// An item of a list, at the nesting depth of the list, starting at 0
+    ListItem {
+        depth: usize,
+        ordered: bool,
+        text: String,
+    },
+    Quote(String),
+    Code {
+        info: String,
+        code: String,
+    },
+    Rule,
+    /// A line starting with an HTML tag
+    Html(String),
+}
+
+/// Parse `content` into blocks, for output formats that are not Markdown. This covers what
+/// the book renderer produces: headings, paragraphs, nested lists, block quotes, rules, fenced
+/// code blocks and lines of HTML. The text of blocks can be parsed further with [`inlines`].
+pub fn blocks(content: &str) -> Vec<Block> {
+    let mut parser = BlockParser::default();
+    let mut open_fence: Option<(&str, &str, String)> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some((marker, info, code)) = &mut open_fence {
+            if trimmed.starts_with(*marker) && trimmed.trim_end() == *marker {
+                parser.blocks.push(Block::Code {
+                    info: info.to_string(),
+                    code: std::mem::take(code),
+                });
+                open_fence = None;
+            } else {
+                code.push_str(line);
+                code.push('\n');
+            }
+            continue;
+        }
+
+        if let Some(marker) = fence(trimmed) {
+            parser.close_blocks();
+            open_fence = Some((marker, trimmed[marker.len()..].trim(), String::new()));
+        } else if trimmed.is_empty() {
+            parser.close_blocks();
+        } else if let Some((level, text)) = heading(trimmed) {
+            parser.close_blocks();
+            parser.blocks.push(Block::Heading {
+                level,
+                text: text.to_string(),
+            });
+        } else if matches!(trimmed.trim_end(), "---" | "***" | "___") {
+            parser.close_blocks();
+            parser.blocks.push(Block::Rule);
+        } else if let Some((indent, ordered, text)) = list_item(line) {
+            parser.close_paragraph();
+            parser.close_quote();
+            parser.list_item(indent, ordered, text);
+        } else if let Some(text) = trimmed.strip_prefix('>') {
+            parser.close_paragraph();
+            parser.lists.clear();
+            parser.quote.push(text.trim());
+        } else if trimmed.starts_with('<') && autolink(trimmed).is_none() {
+            parser.close_blocks();
+            parser.blocks.push(Block::Html(trimmed.to_string()));
+        } else if let (false, Some(Block::ListItem { text, .. })) =
+            (parser.lists.is_empty(), parser.blocks.last_mut())
+        {
+            // Continuation of a list item
+            text.push(' ');
+            text.push_str(trimmed.trim_end());
+        } else {
+            parser.close_quote();
+            parser.paragraph.push(trimmed.trim_end());
+        }
+    }
+    if let Some((_, info, code)) = open_fence {
+        parser.blocks.push(Block::Code {
+            info: info.to_string(),
+            code,
+        });
+    }
+    parser.close_blocks();
+    parser.blocks
+}
+
+#[derive(Default)]
+struct BlockParser<'c> {
+    blocks: Vec<Block>,
+    paragraph: Vec<&'c str>,
+    quote: Vec<&'c str>,
+    /// The indentation of the open lists and whether they are ordered, innermost last
+    lists: Vec<(usize, bool)>,
+}
+
+impl BlockParser<'_> {
+    fn close_blocks(&mut self) {
+        self.close_paragraph();
+        self.close_quote();
+        self.lists.clear();
+    }
+
+    fn close_paragraph(&mut self) {
+        if !self.paragraph.is_empty() {
+            self.blocks.push(Block::Paragraph(self.paragraph.join(" ")));
+            self.paragraph.clear();
+        }
+    }
+
+    fn close_quote(&mut self) {
+        if !self.quote.is_empty() {
+            self.blocks.push(Block::Quote(self.quote.join(" ")));
+            self.quote.clear();
+        }
+    }
+
+    fn list_item(&mut self, indent: usize, ordered: bool, text: &str) {
+        while let Some(&(open_indent, open_ordered)) = self.lists.last() {
+            if open_indent > indent || (open_indent == indent && open_ordered != ordered) {
+                self.lists.pop();
+            } else {
+                break;
+            }
+        }
+        if self.lists.last().map(|(i, _)| *i) != Some(indent) {
+            self.lists.push((indent, ordered));
+        }
+        self.blocks.push(Block::ListItem {
+            depth: self.lists.len() - 1,
+            ordered,
+            text: text.trim_end().to_string(),
+        });
+    }
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text.trim()))
+}
+
+/// Parse a list item into its indentation, whether it's part of an ordered list, and its text
+fn list_item(line: &str) -> Option<(usize, bool, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = trimmed.strip_prefix(bullet) {
+            return Some((indent, false, text));
+        }
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let text = trimmed[digits..].strip_prefix(". ")?;
+    (digits > 0).then_some((indent, true, text))
+}
+
+/// Inline Markdown, see [`inlines`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Inline<'t> {
+    Text(&'t str),
+    Code(&'t str),
+    Strong(Vec<Inline<'t>>),
+    Emphasis(Vec<Inline<'t>>),
+    Link {
+        label: Vec<Inline<'t>>,
+        target: &'t str,
+    },
+    Image {
+        alt: &'t str,
+        target: &'t str,
+    },
+}
+
+/// Parse the text of a [`Block`] into inline code, emphasis, links, autolinks and images
+pub fn inlines(text: &str) -> Vec<Inline<'_>> {
+    let mut output = vec![];
+    let mut plain_start = 0;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let Some((inline, len)) = inline(rest) else {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        let position = text.len() - rest.len();
+        if plain_start < position {
+            output.push(Inline::Text(&text[plain_start..position]));
+        }
+        output.push(inline);
+        rest = &rest[len..];
+        plain_start = position + len;
+    }
+    if plain_start < text.len() {
+        output.push(Inline::Text(&text[plain_start..]));
+    }
+    output
+}
+
+/// Parse the inline element `text` starts with, if any, returning it along with its length
+fn inline(text: &str) -> Option<(Inline<'_>, usize)> {
+    if let Some(code) = text.strip_prefix('`') {
+        let end = code.find('`')?;
+        Some((Inline::Code(&code[..end]), end + 2))
+    } else if let Some(strong) = text.strip_prefix("**") {
+        let end = strong.find("**")?;
+        Some((Inline::Strong(inlines(&strong[..end])), end + 4))
+    } else if let Some(emphasis) = text.strip_prefix('*') {
+        let end = emphasis.find('*')?;
+        Some((Inline::Emphasis(inlines(&emphasis[..end])), end + 2))
+    } else if let Some(image) = text.strip_prefix("![") {
+        let (alt, target, len) = link(image)?;
+        Some((Inline::Image { alt, target }, len + 2))
+    } else if let Some(label) = text.strip_prefix('[') {
+        let (label, target, len) = link(label)?;
+        let label = inlines(label);
+        Some((Inline::Link { label, target }, len + 1))
+    } else {
+        let (target, len) = autolink(text)?;
+        let label = vec![Inline::Text(target)];
+        Some((Inline::Link { label, target }, len))
+    }
+}
+
+/// Parse `label](target)`, returning the label, the target and the length of the link
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let target_end = text[label_end + 2..].find(')')? + label_end + 2;
+    Some((
+        &text[..label_end],
+        &text[label_end + 2..target_end],
+        target_end + 1,
+    ))
+}
+
+/// Parse an autolink like `<https://doc.rust-lang.org>`, returning the target and the length of the link
+fn autolink(text: &str) -> Option<(&str, usize)> {
+    let end = text.strip_prefix('<')?.find('>')? + 1;
+    let target = &text[1..end];
+    (target.contains("://") && !target.contains(char::is_whitespace)).then_some((target, end + 1))
+}
+
+/// Replace the targets of links and images outside of code blocks with the result of `rewrite`,
+/// where it returns one. This covers Markdown links as well as the `src` and `href` attributes
+/// of HTML tags.
+pub fn rewrite_links(content: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut open_fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+            output.push_str(line);
+        } else if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+            output.push_str(line);
+        } else {
+            let mut rest = line;
+            while let Some((start, end)) = next_link(rest) {
+                output.push_str(&rest[..start]);
+                let target = &rest[start..end];
+                match rewrite(target) {
+                    Some(target) => output.push_str(&target),
+                    None => output.push_str(target),
+                }
+                rest = &rest[end..];
+            }
+            output.push_str(rest);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Replace the headings outside of code blocks with the result of `rewrite`, which gets their
+/// level and text, where it returns one
+pub fn rewrite_headings(
+    content: &str,
+    mut rewrite: impl FnMut(usize, &str) -> Option<String>,
+) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut open_fence: Option<&str> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+            output.push_str(line);
+        } else if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+            output.push_str(line);
+        } else {
+            match heading(line).and_then(|(level, text)| rewrite(level, text)) {
+                Some(line) => output.push_str(&line),
+                None => output.push_str(line),
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// The targets of the links and images outside of code blocks, as found by [`rewrite_links`],
+/// along with autolinks like `<https://docs.rs>`, with their 1-based line numbers
+pub fn links(content: &str) -> Vec<(usize, &str)> {
+    let mut links = vec![];
+    let mut open_fence: Option<&str> = None;
+    for (line, line_no) in content.lines().zip(1..) {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+        } else if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+        } else {
+            let mut rest = line;
+            while let Some((start, end)) = next_link(rest) {
+                links.push((line_no, &rest[start..end]));
+                rest = &rest[end..];
+            }
+            for (start, _) in line.match_indices("<http") {
+                if let Some((target, _)) = autolink(&line[start..]) {
+                    links.push((line_no, target));
+                }
+            }
+        }
+    }
+    links
+}
+
+/// The start and end of the first link target in `line`
+fn next_link(line: &str) -> Option<(usize, usize)> {
+    ["](", "src=\"", "href=\""]
+        .iter()
+        .filter_map(|open| {
+            let start = line.find(open)? + open.len();
+            let close = if *open == "](" {
+                [')', ' ']
+            } else {
+                ['"', '"']
+            };
+            let end = start + line[start..].find(close)?;
+            Some((start, end))
+        })
+        .min()
+}
+
+/// The path of a link target that refers to a file next to the content, without its
+/// fragment or query. Absolute paths, URLs and modmod placeholders are left alone.
+pub fn local_path(target: &str) -> Option<&str> {
+    let path = target.split(['#', '?']).next().unwrap_or_default();
+    let is_local = !path.is_empty()
+        && !path.starts_with('/')
+        && !path.contains(':')
+        && !target.starts_with("#[modmod:");
+    is_local.then_some(path)
+}
+
+/// The file that a link target in content in `dir` refers to, if it exists.
+/// Links to other Markdown files are not followed.
+pub fn local_file(dir: &Path, target: &str) -> Option<PathBuf> {
+    dir.join(local_path(target)?)
+        .canonicalize()
+        .ok()
+        .filter(|f| f.is_file() && f.extension().is_none_or(|e| e != "md"))
+}
+
+/// The files that the links in content in `dir` refer to, in order of appearance
+pub fn local_files(content: &str, dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    rewrite_links(content, |target| {
+        files.extend(local_file(dir, target));
+        None
+    });
+    files
+}
+
+/// The text of a line of HTML, like a [`Block::Html`]
+pub fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Handle `#[modmod:teacher-only]` ... `#[modmod:end]` regions in `content`. The regions are
+/// removed entirely unless `instructor` is set, in which case only the marker lines are removed.
+/// Markers must be on a line of their own.
+pub fn teacher_only<C: Context + Default>(content: &str, instructor: bool) -> Result<String, C> {
+    if !content.contains(TEACHER_ONLY_START) && !content.contains(TEACHER_ONLY_END) {
+        return Ok(content.to_string());
+    }
+
+    let mut output = String::with_capacity(content.len());
+    let mut open_at = None;
+    for (line, line_i) in content.lines().zip(1..) {
+        match line.trim() {
+            TEACHER_ONLY_START if open_at.is_some() => {
+                return Err(Report::new(C::default()).attach_printable(format!(
+                    "Nested {TEACHER_ONLY_START} block at line {line_i}"
+                )))
+            }
+            TEACHER_ONLY_START => open_at = Some(line_i),
+            TEACHER_ONLY_END if open_at.is_none() => {
+                return Err(Report::new(C::default()).attach_printable(format!(
+                    "{TEACHER_ONLY_END} without {TEACHER_ONLY_START} at line {line_i}"
+                )))
+            }
+            TEACHER_ONLY_END => open_at = None,
+            _ if open_at.is_some() && !instructor => {}
+            _ => {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+    if let Some(line_i) = open_at {
+        return Err(Report::new(C::default()).attach_printable(format!(
+            "Unterminated {TEACHER_ONLY_START} block starting at line {line_i}"
+        )));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::RenderBookError;
+
+    const CONTENT: &str =
+        "# Traits\n#[modmod:teacher-only]\nMention the orphan rule\n#[modmod:end]\nDone\n";
+
+    #[test]
+    fn mdx_expressions_are_escaped_outside_code() {
+        let content = "Use `{}` or {x} if a < b\n\n```rust\nlet v = vec![{ 1 }];\n```\n<a href=\"/\">Slides</a>\n";
+        assert_eq!(
+            escape_mdx(content),
+            "Use `{}` or \\{x\\} if a &lt; b\n\n```rust\nlet v = vec![{ 1 }];\n```\n<a href=\"/\">Slides</a>\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_is_split_from_the_content() {
+        let content = "+++\ntitle = \"Moves\"\n+++\n---\nlayout: cover\n---\n# Moves\n";
+        assert_eq!(
+            front_matter(content),
+            (
+                Some("title = \"Moves\"\n"),
+                "---\nlayout: cover\n---\n# Moves\n"
+            )
+        );
+        assert_eq!(
+            front_matter("+++\nunterminated\n"),
+            (None, "+++\nunterminated\n")
+        );
+        assert_eq!(front_matter("# Moves\n+++\n"), (None, "# Moves\n+++\n"));
+        assert_eq!(
+            yaml_front_matter("---\nlayout: cover\n---\n# Moves\n"),
+            (Some("layout: cover\n"), "# Moves\n")
+        );
+    }
+
+    #[test]
+    fn comments_are_stripped_outside_code() {
+        let content = "# Slide\n\n<!--\nSpeaker notes\n-->\n\n```html\n<!-- kept -->\n```\nText <!-- inline -->\n";
+        assert_eq!(
+            strip_comments(content),
+            "# Slide\n\n\n```html\n<!-- kept -->\n```\nText\n"
+        );
+    }
+
+    #[test]
+    fn speaker_notes_are_taken_from_the_end_of_slides() {
+        let content = "# Ownership\n\n<!-- not a note -->\n- Moves\n\n???\n* Ask about `Copy`\n\n---\n\n```rust\n# fn main() {}\n```\n<!--\n* Run it\n-->\n";
+        let content = notes_to_comments(content);
+        assert_eq!(
+            content,
+            "# Ownership\n\n<!-- not a note -->\n- Moves\n\n<!--\n* Ask about `Copy`\n\n-->\n\n---\n\n```rust\n# fn main() {}\n```\n<!--\n* Run it\n-->\n"
+        );
+        assert_eq!(
+            slide_notes(&content),
+            [
+                SlideNotes {
+                    title: Some("Ownership".to_string()),
+                    notes: "* Ask about `Copy`".to_string(),
+                },
+                SlideNotes {
+                    title: None,
+                    notes: "* Run it".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decks_are_split_into_slides() {
+        let deck = "---\ntheme: default\n---\n\n# Cover\n\n---\nlayout: section\n---\n# Traits\n\n```yaml\n---\nkey: value\n```\n\n---\n\n---\n\nkey: not frontmatter\n";
+        assert_eq!(
+            split_slides(deck),
+            [
+                Slide {
+                    frontmatter: "theme: default\n".to_string(),
+                    content: "\n# Cover\n\n".to_string(),
+                },
+                Slide {
+                    frontmatter: "layout: section\n".to_string(),
+                    content: "# Traits\n\n```yaml\n---\nkey: value\n```\n\n".to_string(),
+                },
+                Slide {
+                    frontmatter: String::new(),
+                    content: "\nkey: not frontmatter\n".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn local_link_targets_are_rewritten_outside_code() {
+        let content = "![diagram](images/a.svg) [docs](https://docs.rs) <img src=\"b.png\" />\n[heap](#heap) [title](c.pdf \"C\")\n```md\n![](images/a.svg)\n```\n";
+        let rewritten = rewrite_links(content, |target| {
+            local_path(target).map(|path| format!("assets/{path}"))
+        });
+        assert_eq!(
+            rewritten,
+            "![diagram](assets/images/a.svg) [docs](https://docs.rs) <img src=\"assets/b.png\" />\n[heap](#heap) [title](assets/c.pdf \"C\")\n```md\n![](images/a.svg)\n```\n"
+        );
+        assert_eq!(
+            links(&format!("{content}See <https://doc.rust-lang.org>")),
+            [
+                (1, "images/a.svg"),
+                (1, "https://docs.rs"),
+                (1, "b.png"),
+                (2, "#heap"),
+                (2, "c.pdf"),
+                (6, "https://doc.rust-lang.org"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_lists_are_parsed_with_their_depth() {
+        let content = "Intro\n- a\n  continued\n    1. b\n- *c*\n";
+        assert_eq!(
+            blocks(content),
+            [
+                Block::Paragraph("Intro".to_string()),
+                Block::ListItem {
+                    depth: 0,
+                    ordered: false,
+                    text: "a continued".to_string(),
+                },
+                Block::ListItem {
+                    depth: 1,
+                    ordered: true,
+                    text: "b".to_string(),
+                },
+                Block::ListItem {
+                    depth: 0,
+                    ordered: false,
+                    text: "*c*".to_string(),
+                },
+            ]
+        );
+        assert_eq!(inlines("*c*"), [Inline::Emphasis(vec![Inline::Text("c")])]);
+    }
+
+    #[test]
+    fn teacher_only_blocks_are_stripped_for_students() {
+        let student = teacher_only::<RenderBookError>(CONTENT, false).unwrap();
+        assert_eq!(student, "# Traits\nDone\n");
+        let instructor = teacher_only::<RenderBookError>(CONTENT, true).unwrap();
+        assert_eq!(instructor, "# Traits\nMention the orphan rule\nDone\n");
+        assert!(teacher_only::<RenderBookError>("#[modmod:teacher-only]\n", false).is_err());
+    }
+}
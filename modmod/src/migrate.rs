@@ -0,0 +1,226 @@
+//! Versions of the format of track, module and topic TOML files. Files written for older
+//! versions are upgraded when they are loaded, so that old tracks keep working, and
+//! [migrate_track] rewrites them to the latest version for good.
+
+use std::{collections::HashSet, fmt, path::Path};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+use toml_edit::{Document, Key};
+
+use crate::{io::PathExt, load::TrackDef, refactor::FileChange};
+
+/// The format version of the files written by this version of modmod
+pub const FORMAT_VERSION: u32 = 2;
+
+const FORMAT_VERSION_KEY: &str = "format-version";
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct MigrateError {}
+
+impl fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to upgrade files to the latest format version")
+    }
+}
+
+impl error_stack::Context for MigrateError {}
+
+/// The kinds of TOML files a track is made of. Each kind changes in its own way between
+/// format versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Track,
+    Module,
+    Topic,
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FileKind::Track => "track",
+            FileKind::Module => "module",
+            FileKind::Topic => "topic",
+        })
+    }
+}
+
+/// The `format-version` of a TOML file, which defaults to the latest version for new files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FormatVersion(pub u32);
+
+impl Default for FormatVersion {
+    fn default() -> Self {
+        Self(FORMAT_VERSION)
+    }
+}
+
+/// The content of a TOML file, upgraded to the latest format version
+#[derive(Debug)]
+pub struct Upgrade {
+    /// The format version of the file before the upgrade
+    pub from: u32,
+    /// What was changed, apart from the format version itself
+    pub changes: Vec<String>,
+    pub content: String,
+}
+
+/// Upgrade the `content` of a TOML file of the given kind to the latest format version,
+/// keeping its comments and layout. Files without a `format-version` have version 1, the
+/// format from before versions were introduced.
+pub fn upgrade(kind: FileKind, content: &str) -> Result<Upgrade, MigrateError> {
+    let mut doc: Document = content
+        .parse()
+        .into_report()
+        .change_context(MigrateError::default())?;
+    let from = match doc.get(FORMAT_VERSION_KEY) {
+        None => 1,
+        Some(item) => item
+            .as_integer()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| {
+                Report::new(MigrateError::default())
+                    .attach_printable("`format-version` must be a positive integer")
+            })?,
+    };
+    if from > FORMAT_VERSION {
+        return Err(Report::new(MigrateError::default()).attach_printable(format!(
+            "The {kind} file has format version {from}, but this version of modmod only supports versions up to {FORMAT_VERSION}. Upgrade modmod to load it"
+        )));
+    }
+
+    let mut changes = vec![];
+    for version in from..FORMAT_VERSION {
+        changes.extend(upgrade_step(kind, version, &mut doc)?);
+    }
+    let content = match doc.contains_key(FORMAT_VERSION_KEY) {
+        true => {
+            if from < FORMAT_VERSION {
+                doc[FORMAT_VERSION_KEY] = toml_edit::value(i64::from(FORMAT_VERSION));
+            }
+            doc.to_string()
+        }
+        // Keep the version at the top, where it's easy to spot
+        false => format!("{FORMAT_VERSION_KEY} = {FORMAT_VERSION}\n{doc}"),
+    };
+    Ok(Upgrade {
+        from,
+        changes,
+        content,
+    })
+}
+
+/// Upgrade `doc` from `version` to the next version, and describe what changed
+fn upgrade_step(
+    kind: FileKind,
+    version: u32,
+    doc: &mut Document,
+) -> Result<Vec<String>, MigrateError> {
+    let mut changes = vec![];
+    match (kind, version) {
+        // Version 1 also accepted `extends` for the tracks to include
+        (FileKind::Track, 1) => {
+            if let Some((key, item)) = doc.remove_entry("extends") {
+                if doc.contains_key("include") {
+                    return Err(Report::new(MigrateError::default()).attach_printable(
+                        "The track sets both `extends` and `include`. Merge them into `include`",
+                    ));
+                }
+                let include = Key::new("include").with_decor(key.decor().clone());
+                doc.insert_formatted(&include, item);
+                changes.push("Renamed `extends` to `include`".to_string());
+            }
+        }
+        (FileKind::Track | FileKind::Module | FileKind::Topic, _) => {}
+    }
+    Ok(changes)
+}
+
+/// A TOML file of a track, rewritten to the latest format version
+#[derive(Debug)]
+pub struct Migration {
+    pub kind: FileKind,
+    /// The format version of the file before the migration
+    pub from: u32,
+    /// What was changed, apart from the format version itself
+    pub changes: Vec<String>,
+    pub change: FileChange,
+}
+
+/// Compute the changes that bring the track at `track_toml_path`, the tracks it includes,
+/// and their modules and topics to the latest format version. Files at the latest version
+/// are left out. Nothing is written to disk until the changes are applied.
+pub fn migrate_track(track_toml_path: impl AsRef<Path>) -> Result<Vec<Migration>, MigrateError> {
+    let files =
+        TrackDef::toml_files(track_toml_path.as_ref()).change_context(MigrateError::default())?;
+    let root = files
+        .first()
+        .and_then(|(_, path)| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let mut migrations = vec![];
+    for (kind, path) in files {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let old: String = path.read_to_string()?;
+        let Upgrade {
+            from,
+            changes,
+            content: new,
+        } = upgrade(kind, &old)
+            .attach_printable_lazy(|| format!("Unable to upgrade {}", path.to_string_lossy()))?;
+        if from == FORMAT_VERSION {
+            continue;
+        }
+        let relative_path = path
+            .strip_prefix(&root)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| path.clone());
+        migrations.push(Migration {
+            kind,
+            from,
+            changes,
+            change: FileChange {
+                path,
+                relative_path,
+                old,
+                new,
+            },
+        });
+    }
+    Ok(migrations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_tracks_are_upgraded_keeping_comments() {
+        let old = "# Combines the fundamentals with our own modules\nname = \"Rust at ACME\"\nextends = [\"../fundamentals.toml\"] # shared\n\n[slides]\ntheme = \"default\"\n";
+        let Upgrade {
+            from,
+            changes,
+            content,
+        } = upgrade(FileKind::Track, old).unwrap();
+        assert_eq!(from, 1);
+        assert_eq!(changes, ["Renamed `extends` to `include`"]);
+        assert_eq!(
+            content,
+            "format-version = 2\n# Combines the fundamentals with our own modules\nname = \"Rust at ACME\"\ninclude = [\"../fundamentals.toml\"] # shared\n\n[slides]\ntheme = \"default\"\n"
+        );
+
+        let current = upgrade(FileKind::Track, &content).unwrap();
+        assert_eq!(current.from, FORMAT_VERSION);
+        assert_eq!(current.content, content);
+
+        let newer = format!("format-version = {}\nname = \"Next\"\n", FORMAT_VERSION + 1);
+        assert!(upgrade(FileKind::Track, &newer).is_err());
+    }
+}
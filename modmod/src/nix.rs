@@ -0,0 +1,170 @@
+use indoc::formatdoc;
+use std::{collections::BTreeSet, fmt, path::Path};
+
+use error_stack::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::{PathExt, WriteExt},
+    numbering::QUIZZES_DIR,
+    to_tag,
+    toolchain::ToolchainConfig,
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderNixError {}
+
+impl fmt::Display for RenderNixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render Nix flake")
+    }
+}
+
+impl error_stack::Context for RenderNixError {}
+
+/// Nix configuration as defined in the `[nix]` table of the track TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NixConfig {
+    /// Flake URL of nixpkgs. Point it at a specific revision to pin it.
+    #[serde(default = "crate::nix::default_nixpkgs")]
+    pub nixpkgs: String,
+    /// Flake URL of the overlay providing the Rust toolchain
+    #[serde(default = "crate::nix::default_rust_overlay")]
+    pub rust_overlay: String,
+    /// Attribute names of additional nixpkgs packages to put in the development shell.
+    /// The `system_packages` of modules are Debian package names, so they are not used here.
+    #[serde(default)]
+    pub packages: Vec<String>,
+}
+
+impl Default for NixConfig {
+    fn default() -> Self {
+        Self {
+            nixpkgs: default_nixpkgs(),
+            rust_overlay: default_rust_overlay(),
+            packages: Default::default(),
+        }
+    }
+}
+
+pub fn default_nixpkgs() -> String {
+    "github:NixOS/nixpkgs/nixos-24.05".to_string()
+}
+
+pub fn default_rust_overlay() -> String {
+    "github:oxalica/rust-overlay".to_string()
+}
+
+impl Track {
+    /// Write a `flake.nix` into `out_dir` with a package that builds the book
+    /// and a development shell with the track toolchain, mdBook and Node.js.
+    /// Books with quizzes get the mdbook-quiz preprocessor as well.
+    /// Running `nix flake lock` pins all inputs in a `flake.lock` next to it.
+    pub fn render_nix(&self, out_dir: impl AsRef<Path>) -> Result<(), RenderNixError> {
+        let out_dir = out_dir.as_ref();
+        // Units that were filtered out don't bring their quizzes into the book
+        let mut book_inputs = vec!["pkgs.mdbook"];
+        if out_dir.join("book").join("src").join(QUIZZES_DIR).is_dir() {
+            book_inputs.push("pkgs.mdbook-quiz");
+        }
+        let mut packages = vec![rust_toolchain(&self.toolchain)];
+        packages.extend(book_inputs.iter().map(|p| p.to_string()));
+        packages.push("pkgs.nodejs".to_string());
+        let extra_packages: BTreeSet<_> = self.nix.packages.iter().collect();
+        packages.extend(extra_packages.into_iter().map(|p| format!("pkgs.{p}")));
+
+        let flake = formatdoc! {r#"
+            {{
+              description = {description};
+
+              inputs = {{
+                nixpkgs.url = {nixpkgs};
+                rust-overlay = {{
+                  url = {rust_overlay};
+                  inputs.nixpkgs.follows = "nixpkgs";
+                }};
+              }};
+
+              outputs = {{ self, nixpkgs, rust-overlay }}:
+                let
+                  systems = [ "x86_64-linux" "aarch64-linux" "x86_64-darwin" "aarch64-darwin" ];
+                  forAllSystems = f: nixpkgs.lib.genAttrs systems (system: f (import nixpkgs {{
+                    inherit system;
+                    overlays = [ rust-overlay.overlays.default ];
+                  }}));
+                in
+                {{
+                  packages = forAllSystems (pkgs: rec {{
+                    book = pkgs.stdenvNoCC.mkDerivation {{
+                      name = "{name}-book";
+                      src = ./book;
+                      nativeBuildInputs = [ {book_inputs} ];
+                      buildPhase = "mdbook build --dest-dir $out";
+                      dontInstall = true;
+                    }};
+                    default = book;
+                  }});
+
+                  devShells = forAllSystems (pkgs: {{
+                    default = pkgs.mkShell {{
+                      packages = [
+                        {packages}
+                      ];
+                    }};
+                  }});
+                }};
+            }}
+            "#,
+            description = nix_string(&self.name),
+            nixpkgs = nix_string(&self.nix.nixpkgs),
+            rust_overlay = nix_string(&self.nix.rust_overlay),
+            name = to_tag(&self.name),
+            book_inputs = book_inputs.join(" "),
+            packages = packages.join("\n            "),
+        };
+
+        out_dir.join("flake.nix").create_file()?.write_all(flake)?;
+
+        Ok(())
+    }
+}
+
+/// The rust-overlay expression for the toolchain, e.g. `pkgs.rust-bin.stable.latest.default`
+/// for the `stable` channel or `pkgs.rust-bin.stable."1.75.0".default` for a specific version
+fn rust_toolchain(toolchain: &ToolchainConfig) -> String {
+    let extensions: Vec<_> = toolchain.components.iter().map(|c| nix_string(c)).collect();
+    let with_extensions = |toolchain: &str| {
+        format!(
+            "{toolchain}.default.override {{ extensions = [ {} ]; }}",
+            extensions.join(" ")
+        )
+    };
+    match toolchain.channel.as_str() {
+        "stable" | "beta" => format!(
+            "(pkgs.rust-bin.{})",
+            with_extensions(&format!("{}.latest", toolchain.channel))
+        ),
+        "nightly" => format!(
+            "(pkgs.rust-bin.selectLatestNightlyWith (toolchain: {}))",
+            with_extensions("toolchain")
+        ),
+        channel => match channel.strip_prefix("nightly-") {
+            Some(date) => format!(
+                "(pkgs.rust-bin.{})",
+                with_extensions(&format!("nightly.{}", nix_string(date)))
+            ),
+            None => format!(
+                "(pkgs.rust-bin.{})",
+                with_extensions(&format!("stable.{}", nix_string(channel)))
+            ),
+        },
+    }
+}
+
+/// Render a Nix string literal. JSON strings are valid Nix strings,
+/// except for `${`, which starts an interpolation in Nix.
+fn nix_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap().replace("${", "\\${")
+}
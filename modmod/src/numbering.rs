@@ -0,0 +1,323 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{to_prefixed_tag, to_tag};
+
+/// Directory in the output dir that holds the exercise packages
+pub const EXERCISES_DIR: &str = "exercises";
+/// Directory in the book source dir that holds the quizzes
+pub const QUIZZES_DIR: &str = "quizzes";
+/// Directory in the output dir that holds the exercise solutions in the teacher edition
+pub const SOLUTIONS_DIR: &str = "solutions";
+
+/// The canonical number of a unit, e.g. `2.1`.
+/// All renderers derive their numbers and output names from this,
+/// so that the book, slides, exercises and packages agree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct UnitNumber {
+    pub module: usize,
+    pub unit: usize,
+}
+
+impl UnitNumber {
+    pub fn new(module: usize, unit: usize) -> Self {
+        Self { module, unit }
+    }
+
+    /// Identifier of the unit's slide deck, as used in slide URLs and script names, e.g. `2_1`
+    pub fn deck_id(&self) -> String {
+        format!("{}_{}", self.module, self.unit)
+    }
+
+    /// Name of the unit's slide deck, e.g. `2_1-basic-syntax`
+    pub fn deck_slug(&self, unit_name: &str) -> String {
+        to_prefixed_tag(unit_name, self.deck_id())
+    }
+
+    /// File name of the unit's slide deck, e.g. `2_1-basic-syntax.md`
+    pub fn deck_file_name(&self, unit_name: &str) -> PathBuf {
+        PathBuf::from(self.deck_slug(unit_name)).with_extension("md")
+    }
+
+    /// File name of the `quiz`th quiz of the unit, relative to the book quizzes dir, e.g. `2_1-1.toml`
+    pub fn quiz_file_name(&self, quiz: usize) -> PathBuf {
+        PathBuf::from(format!("{}-{quiz}", self.deck_id())).with_extension("toml")
+    }
+
+    /// Number of the `exercise`th exercise in the unit
+    pub fn exercise(&self, exercise: usize) -> ExerciseNumber {
+        ExerciseNumber {
+            module: self.module,
+            unit: self.unit,
+            exercise,
+        }
+    }
+}
+
+impl fmt::Display for UnitNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.module, self.unit)
+    }
+}
+
+/// The canonical number of an exercise, e.g. `2.1.3`.
+/// Exercises are numbered within their unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ExerciseNumber {
+    pub module: usize,
+    pub unit: usize,
+    pub exercise: usize,
+}
+
+impl ExerciseNumber {
+    /// Directory the exercise images are copied to,
+    /// relative to the book source dir, e.g. `images/2/1/3`
+    pub fn book_images_dir(&self) -> PathBuf {
+        module_book_images_dir(self.module)
+            .join(self.unit.to_string())
+            .join(self.exercise.to_string())
+    }
+}
+
+impl fmt::Display for ExerciseNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.module, self.unit, self.exercise)
+    }
+}
+
+/// How the book labels modules and units, set in the `[numbering]` table of the track TOML.
+/// The canonical numbers of units and exercises, which output names are derived from, only
+/// depend on `first-module`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NumberingConfig {
+    /// Number of the first module, like 0 for a course that starts with an introduction
+    pub first_module: usize,
+    /// How the modules that are marked as appendix are labelled
+    pub appendices: AppendixNumbering,
+    /// Whether the headings of modules and units start with their label, like `Unit 2.1 - `
+    pub heading_prefix: bool,
+    /// Whether the module numbers continue from one part of the track to the next
+    pub parts: PartNumbering,
+}
+
+impl Default for NumberingConfig {
+    fn default() -> Self {
+        Self {
+            first_module: 1,
+            appendices: AppendixNumbering::default(),
+            heading_prefix: true,
+            parts: PartNumbering::default(),
+        }
+    }
+}
+
+impl NumberingConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The labels of modules, given whether each of them is an appendix and the index of
+    /// the part it's in, if any. Appendices are lettered in the order they appear in.
+    pub fn module_labels(
+        &self,
+        modules: impl IntoIterator<Item = (usize, bool, Option<usize>)>,
+    ) -> Vec<ModuleLabel> {
+        let mut letters = 'A'..='Z';
+        // The part of the previous module, and the number of the next one in its part
+        let mut part_numbers = (None, self.first_module);
+        modules
+            .into_iter()
+            .map(|(module, appendix, part)| {
+                if part != part_numbers.0 {
+                    part_numbers = (part, self.first_module);
+                }
+                match (appendix, self.appendices) {
+                    (false, _) if part.is_some() && self.parts == PartNumbering::Restart => {
+                        part_numbers.1 += 1;
+                        ModuleLabel::Number(part_numbers.1 - 1)
+                    }
+                    (false, _) => ModuleLabel::Number(module),
+                    (true, AppendixNumbering::Letters) => letters
+                        .next()
+                        .map_or(ModuleLabel::Unnumbered, ModuleLabel::Letter),
+                    (true, AppendixNumbering::None) => ModuleLabel::Unnumbered,
+                }
+            })
+            .collect()
+    }
+}
+
+/// How the modules in the parts of a track are numbered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PartNumbering {
+    /// Through the whole track, like they are without parts
+    #[default]
+    Continue,
+    /// From `first-module` in every part
+    Restart,
+}
+
+/// The roman numeral of `number`, like `IV` for 4, which labels the parts of a track
+pub fn roman_numeral(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut numeral = String::new();
+    for (value, letters) in NUMERALS {
+        while number >= value {
+            numeral.push_str(letters);
+            number -= value;
+        }
+    }
+    numeral
+}
+
+/// How appendix modules are labelled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AppendixNumbering {
+    /// With letters, like `A` and `A.1`
+    #[default]
+    Letters,
+    /// Without any label
+    None,
+}
+
+/// The label of a module in the book, see [NumberingConfig]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleLabel {
+    Number(usize),
+    Letter(char),
+    Unnumbered,
+}
+
+impl ModuleLabel {
+    /// The label of something in the module, like unit `[1]` or exercise `[1, 3]`, which is
+    /// `2.1` or `A.1.3`, or none if the module is not numbered
+    pub fn of(&self, numbers: &[usize]) -> Option<String> {
+        let mut label = match self {
+            ModuleLabel::Number(module) => module.to_string(),
+            ModuleLabel::Letter(letter) => letter.to_string(),
+            ModuleLabel::Unnumbered => return None,
+        };
+        for number in numbers {
+            label += &format!(".{number}");
+        }
+        Some(label)
+    }
+}
+
+/// File name of a module's chapter page in the book, e.g. `2-foundations-of-rust.md`
+pub fn chapter_file_name(module_name: &str, module: usize) -> PathBuf {
+    PathBuf::from(to_prefixed_tag(module_name, module)).with_extension("md")
+}
+
+/// File name of a module's slide deck, holding the decks of all its units,
+/// e.g. `2-foundations-of-rust.md`
+pub fn module_deck_file_name(module_name: &str, module: usize) -> PathBuf {
+    PathBuf::from(to_prefixed_tag(module_name, module)).with_extension("md")
+}
+
+/// File name of a unit's book section, e.g. `basic-syntax.md`
+pub fn section_file_name(unit_name: &str) -> PathBuf {
+    PathBuf::from(to_tag(unit_name)).with_extension("md")
+}
+
+/// Directory holding the images of a module's exercises, relative to the book source dir
+pub fn module_book_images_dir(module: usize) -> PathBuf {
+    Path::new("images").join(module.to_string())
+}
+
+/// Directory holding a module's exercises, relative to the output dir,
+/// e.g. `exercises/2-foundations-of-rust`
+pub fn module_exercises_dir(module_name: &str, module: usize) -> PathBuf {
+    Path::new(EXERCISES_DIR).join(to_prefixed_tag(module_name, module))
+}
+
+/// Directory holding a unit's exercises, relative to the module exercises dir
+pub fn unit_exercises_dir(unit_name: &str, unit: usize) -> PathBuf {
+    PathBuf::from(to_prefixed_tag(unit_name, unit))
+}
+
+/// Directory of the solution to the exercise in `exercise_dir`, both relative to the output dir.
+/// The solutions mirror the layout of the exercises.
+pub fn solution_dir(exercise_dir: &Path) -> PathBuf {
+    Path::new(SOLUTIONS_DIR).join(
+        exercise_dir
+            .strip_prefix(EXERCISES_DIR)
+            .unwrap_or(exercise_dir),
+    )
+}
+
+/// Directory of a single exercise package, relative to the unit exercises dir
+pub fn exercise_dir(exercise_name: &str, exercise: usize) -> PathBuf {
+    PathBuf::from(to_prefixed_tag(exercise_name, exercise))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_and_names_agree() {
+        let unit = UnitNumber::new(2, 1);
+        assert_eq!(unit.to_string(), "2.1");
+        assert_eq!(unit.deck_id(), "2_1");
+        assert_eq!(
+            unit.deck_file_name("Basic syntax"),
+            Path::new("2_1-basic-syntax.md")
+        );
+
+        let exercise = unit.exercise(3);
+        assert_eq!(exercise.to_string(), "2.1.3");
+        assert_eq!(exercise.book_images_dir(), Path::new("images/2/1/3"));
+
+        let numbering = NumberingConfig::default();
+        let labels = numbering.module_labels([(1, false, None), (2, true, None), (3, true, None)]);
+        assert_eq!(labels[0].of(&[1, 3]).as_deref(), Some("1.1.3"));
+        assert_eq!(labels[2].of(&[1]).as_deref(), Some("B.1"));
+        let numbering = NumberingConfig {
+            appendices: AppendixNumbering::None,
+            ..numbering
+        };
+        assert_eq!(numbering.module_labels([(2, true, None)])[0].of(&[1]), None);
+
+        let modules = [
+            (1, false, None),
+            (2, false, Some(0)),
+            (3, false, Some(0)),
+            (4, false, Some(1)),
+        ];
+        assert_eq!(
+            numbering.module_labels(modules),
+            [1, 2, 3, 4].map(ModuleLabel::Number)
+        );
+        let numbering = NumberingConfig {
+            parts: PartNumbering::Restart,
+            ..numbering
+        };
+        assert_eq!(
+            numbering.module_labels(modules),
+            [1, 1, 2, 1].map(ModuleLabel::Number)
+        );
+        assert_eq!(roman_numeral(14), "XIV");
+    }
+}
@@ -2,10 +2,11 @@ use error_stack::{IntoReport, Result, ResultExt};
 use similar::TextDiff;
 
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fmt,
     fs::{self},
     io::{self, Read, Seek},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use crate::io::PathExt;
@@ -137,3 +138,178 @@ impl Patch {
         Ok(())
     }
 }
+
+/// How rendering would change a file in the output folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputChangeKind {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// A file in the output folder that rendering would create, modify or delete
+#[derive(Debug)]
+pub struct OutputChange {
+    /// Path relative to the output folder. Folders that would be deleted as a whole,
+    /// like `slides/node_modules`, are listed once rather than file by file.
+    pub path: PathBuf,
+    pub kind: OutputChangeKind,
+    /// The number of files the change affects, which is more than one for folders
+    pub files: usize,
+    folder: bool,
+    old: Vec<u8>,
+    new: Vec<u8>,
+}
+
+impl OutputChange {
+    /// Render the change as a unified diff, or a note for binary files. Deletions give
+    /// `None`, as their content is not of interest.
+    pub fn diff(&self) -> Option<String> {
+        let path = self.path.to_string_lossy();
+        let (old_header, new_header) = match self.kind {
+            OutputChangeKind::Create => ("/dev/null".to_string(), format!("b/{path}")),
+            OutputChangeKind::Modify => (format!("a/{path}"), format!("b/{path}")),
+            OutputChangeKind::Delete => return None,
+        };
+        let diff = match (
+            std::str::from_utf8(&self.old),
+            std::str::from_utf8(&self.new),
+        ) {
+            (Ok(old), Ok(new)) => TextDiff::from_lines(old, new)
+                .unified_diff()
+                .header(&old_header, &new_header)
+                .to_string(),
+            _ => format!("Binary files {old_header} and {new_header} differ\n"),
+        };
+        Some(diff)
+    }
+}
+
+impl fmt::Display for OutputChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.to_string_lossy();
+        match self.kind {
+            OutputChangeKind::Create => write!(f, "create {path}"),
+            OutputChangeKind::Modify => write!(f, "modify {path}"),
+            OutputChangeKind::Delete if self.folder => {
+                let plural = if self.files == 1 { "" } else { "s" };
+                write!(f, "delete {path}/ ({} file{plural})", self.files)
+            }
+            OutputChangeKind::Delete => write!(f, "delete {path}"),
+        }
+    }
+}
+
+/// Compare the output rendered into `new_dir` with the existing output in `old_dir`, which
+/// rendering with `--clear` would replace, and list the files that would change, by path
+pub fn compare_output(new_dir: &Path, old_dir: &Path) -> Result<Vec<OutputChange>, GenPatchError> {
+    let relative_files = |dir: &Path| -> Result<BTreeSet<PathBuf>, GenPatchError> {
+        if !dir.exists() {
+            return Ok(BTreeSet::new());
+        }
+        Ok(dir
+            .get_dir_content()?
+            .files
+            .iter()
+            .map(|f| Path::new(f).strip_prefix(dir).unwrap().to_path_buf())
+            .collect())
+    };
+    let new_files = relative_files(new_dir)?;
+    let old_files = relative_files(old_dir)?;
+
+    let mut changes = vec![];
+    for path in new_files.iter() {
+        let new = fs::read(new_dir.join(path))
+            .into_report()
+            .change_context(GenPatchError)?;
+        let (kind, old) = match old_files.contains(path) {
+            true => {
+                let old = fs::read(old_dir.join(path))
+                    .into_report()
+                    .change_context(GenPatchError)?;
+                if old == new {
+                    continue;
+                }
+                (OutputChangeKind::Modify, old)
+            }
+            false => (OutputChangeKind::Create, vec![]),
+        };
+        changes.push(OutputChange {
+            path: path.clone(),
+            kind,
+            files: 1,
+            folder: false,
+            old,
+            new,
+        });
+    }
+
+    // List deleted files under the outermost folder that is missing from the new output
+    let mut deleted: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    for path in old_files.difference(&new_files) {
+        let deleted_path = path
+            .ancestors()
+            .filter(|a| !a.as_os_str().is_empty() && !new_dir.join(a).exists())
+            .last()
+            .unwrap_or(path);
+        *deleted.entry(deleted_path.to_path_buf()).or_default() += 1;
+    }
+    changes.extend(deleted.into_iter().map(|(path, files)| OutputChange {
+        folder: old_dir.join(&path).is_dir(),
+        path,
+        kind: OutputChangeKind::Delete,
+        files,
+        old: vec![],
+        new: vec![],
+    }));
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_changes_are_listed() {
+        let old_dir = tempfile::tempdir().unwrap();
+        let new_dir = tempfile::tempdir().unwrap();
+        let write = |dir: &Path, path: &str, content: &[u8]| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        for (path, content) in [
+            ("book/src/SUMMARY.md", "# Summary\n"),
+            ("book/src/1-intro.md", "Hello\n"),
+            ("slides/node_modules/a/index.js", ""),
+            ("slides/node_modules/b/index.js", ""),
+            ("slides/1-intro.md", "Slides\n"),
+        ] {
+            write(old_dir.path(), path, content.as_bytes());
+        }
+        for (path, content) in [
+            ("book/src/SUMMARY.md", "# Summary\n"),
+            ("book/src/1-intro.md", "Hello, world\n"),
+            ("book/src/2-next.md", "Next\n"),
+            ("slides/1-intro.md", "Slides\n"),
+        ] {
+            write(new_dir.path(), path, content.as_bytes());
+        }
+
+        let changes = compare_output(new_dir.path(), old_dir.path()).unwrap();
+        let listed: Vec<_> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            listed,
+            [
+                "modify book/src/1-intro.md",
+                "create book/src/2-next.md",
+                "delete slides/node_modules/ (2 files)",
+            ]
+        );
+        assert_eq!(
+            changes[0].diff().unwrap(),
+            "--- a/book/src/1-intro.md\n+++ b/book/src/1-intro.md\n@@ -1 +1 @@\n-Hello\n+Hello, world\n"
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::{self, Write as _},
+    fs,
+    hash::{BuildHasher, Hasher},
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PreviewError {}
+
+impl fmt::Display for PreviewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to serve preview")
+    }
+}
+
+impl error_stack::Context for PreviewError {}
+
+#[derive(Debug)]
+pub struct PreviewOptions {
+    /// The port to listen on, or 0 for any free port
+    pub port: u16,
+    /// Listen on all network interfaces rather than only on localhost, so that the
+    /// preview can be reached through a tunnel or from another machine
+    pub share: bool,
+    /// The password that visitors have to enter, with any user name
+    pub token: Option<String>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            port: 3000,
+            share: false,
+            token: None,
+        }
+    }
+}
+
+/// User name suggested in the printed URL. Any user name is accepted.
+pub const PREVIEW_USER: &str = "preview";
+
+/// How long to wait for a request before giving up on a connection
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A static file server for the rendered output, to look at or share a draft of the
+/// course without publishing it
+#[derive(Debug)]
+pub struct PreviewServer {
+    listener: TcpListener,
+    root: PathBuf,
+    token: Option<String>,
+}
+
+impl PreviewServer {
+    /// Listen for requests for the files in `root`. When sharing without a token, a random
+    /// token is generated, so that a shared draft is never open to anyone who finds it.
+    pub fn bind(root: impl AsRef<Path>, opts: &PreviewOptions) -> Result<Self, PreviewError> {
+        let root = root.as_ref();
+        if !root.is_dir() {
+            return Err(
+                Report::new(PreviewError::default()).attach_printable(format!(
+                    "Output folder {} doesn't exist",
+                    root.to_string_lossy()
+                )),
+            );
+        }
+        let host = match opts.share {
+            true => Ipv4Addr::UNSPECIFIED,
+            false => Ipv4Addr::LOCALHOST,
+        };
+        let listener = TcpListener::bind(SocketAddr::from((host, opts.port)))
+            .into_report()
+            .attach_printable_lazy(|| format!("Error listening on port {}", opts.port))
+            .change_context(PreviewError::default())?;
+        let token = match (&opts.token, opts.share) {
+            (Some(token), _) => Some(token.clone()),
+            (None, true) => Some(random_token()),
+            (None, false) => None,
+        };
+        Ok(Self {
+            listener,
+            root: root.to_path_buf(),
+            token,
+        })
+    }
+
+    /// The port the server listens on
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map_or(0, |a| a.port())
+    }
+
+    /// The password visitors have to enter, if any
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// The URL of the page to start at: the HTML book built by mdbook if there is one,
+    /// or else the listing of the output folder
+    pub fn url(&self) -> String {
+        let credentials = match &self.token {
+            Some(token) => format!("{PREVIEW_USER}:{token}@"),
+            None => String::new(),
+        };
+        let build_dir = Path::new("book").join("target");
+        let page = [build_dir.join("html"), build_dir]
+            .into_iter()
+            .find(|d| self.root.join(d).join("index.html").is_file())
+            .map_or(String::new(), |d| format!("{}/", url_path(&d)));
+        format!("http://{credentials}localhost:{}/{page}", self.port())
+    }
+
+    /// Serve requests until the process is stopped, each on a thread of its own
+    pub fn serve(self) -> Result<(), PreviewError> {
+        for stream in self.listener.incoming() {
+            let stream = stream
+                .into_report()
+                .change_context(PreviewError::default())?;
+            let root = self.root.clone();
+            let token = self.token.clone();
+            thread::spawn(move || {
+                // A client that goes away mid-request only affects its own connection
+                let _ = handle(stream, &root, token.as_deref());
+            });
+        }
+        Ok(())
+    }
+}
+
+fn handle(mut stream: TcpStream, root: &Path, token: Option<&str>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut authorization = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let response = match (method, token) {
+        ("GET" | "HEAD", Some(token)) if !authorized(authorization.as_deref(), token) => {
+            Response::unauthorized()
+        }
+        ("GET" | "HEAD", _) => respond(root, target),
+        _ => Response::text("405 Method Not Allowed", "Only GET and HEAD are supported"),
+    };
+    response.write_to(&mut stream, method == "HEAD")
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    headers: Vec<String>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            headers: vec![],
+            body: format!("{body}\n").into_bytes(),
+        }
+    }
+
+    fn unauthorized() -> Self {
+        let mut response = Self::text("401 Unauthorized", "Enter the preview token");
+        response
+            .headers
+            .push("WWW-Authenticate: Basic realm=\"modmod preview\"".to_string());
+        response
+    }
+
+    fn redirect(location: String) -> Self {
+        let mut response = Self::text("301 Moved Permanently", &location);
+        response.headers.push(format!("Location: {location}"));
+        response
+    }
+
+    fn write_to(&self, stream: &mut TcpStream, head: bool) -> std::io::Result<()> {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        for header in self.headers.iter() {
+            write!(response, "{header}\r\n").unwrap();
+        }
+        response.push_str("\r\n");
+        stream.write_all(response.as_bytes())?;
+        if !head {
+            stream.write_all(&self.body)?;
+        }
+        stream.flush()
+    }
+}
+
+/// The response to a request for `target`, which is looked up in `root`
+fn respond(root: &Path, target: &str) -> Response {
+    let url_path = target.split(['?', '#']).next().unwrap_or("");
+    let Some(relative) = percent_decode(url_path).map(PathBuf::from) else {
+        return Response::text("400 Bad Request", "Malformed path");
+    };
+    // Only plain names, so that requests can't reach outside of the output folder
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::RootDir))
+    {
+        return Response::text("404 Not Found", "Not found");
+    }
+    let path = root.join(relative.strip_prefix("/").unwrap_or(&relative));
+    if path.is_dir() {
+        if !url_path.ends_with('/') {
+            return Response::redirect(format!("{url_path}/"));
+        }
+        let index = path.join("index.html");
+        if index.is_file() {
+            return file_response(&index);
+        }
+        return listing(&path, url_path);
+    }
+    match path.is_file() {
+        true => file_response(&path),
+        false => Response::text("404 Not Found", "Not found"),
+    }
+}
+
+fn file_response(path: &Path) -> Response {
+    match fs::read(path) {
+        Ok(body) => Response {
+            status: "200 OK",
+            content_type: content_type(path),
+            headers: vec![],
+            body,
+        },
+        Err(_) => Response::text("500 Internal Server Error", "Unable to read file"),
+    }
+}
+
+/// A page linking to the entries of the folder `dir`, at `url_path`
+fn listing(dir: &Path, url_path: &str) -> Response {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    match e.path().is_dir() {
+                        true => format!("{name}/"),
+                        false => name,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    let title = html_escape(&percent_decode(url_path).unwrap_or_default());
+    let mut body = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<ul>\n"
+    );
+    if url_path != "/" {
+        body.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    for entry in entries {
+        writeln!(
+            body,
+            "<li><a href=\"{}\">{}</a></li>",
+            html_escape(&percent_encode(&entry)),
+            html_escape(&entry)
+        )
+        .unwrap();
+    }
+    body.push_str("</ul>\n</body>\n</html>\n");
+    Response {
+        status: "200 OK",
+        content_type: "text/html; charset=utf-8",
+        headers: vec![],
+        body: body.into_bytes(),
+    }
+}
+
+/// Whether the `Authorization` header value `authorization` holds basic credentials with
+/// `token` as the password
+fn authorized(authorization: Option<&str>, token: &str) -> bool {
+    let Some(credentials) = authorization
+        .and_then(|a| a.strip_prefix("Basic "))
+        .and_then(|c| base64_decode(c.trim()))
+    else {
+        return false;
+    };
+    match credentials.iter().position(|&b| b == b':') {
+        Some(colon) => constant_time_eq(&credentials[colon + 1..], token.as_bytes()),
+        None => false,
+    }
+}
+
+/// Compare without returning early, so the time taken doesn't tell how much of a guessed
+/// token is right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    let mut output = vec![];
+    for c in input.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            output.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+    Some(output)
+}
+
+/// A token of 32 hex digits
+fn random_token() -> String {
+    let state = RandomState::new();
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let mut token = String::new();
+    for i in 0..2u8 {
+        let mut hasher = state.build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u32(std::process::id());
+        hasher.write_u8(i);
+        write!(token, "{:016x}", hasher.finish()).unwrap();
+    }
+    token
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(b as char)
+            }
+            _ => write!(encoded, "%{b:02X}").unwrap(),
+        }
+    }
+    encoded
+}
+
+/// `path`, relative to the output folder, as the path of a URL
+fn url_path(path: &Path) -> String {
+    let segments: Vec<_> = path
+        .components()
+        .map(|c| percent_encode(&c.as_os_str().to_string_lossy()))
+        .collect();
+    segments.join("/")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "md" | "txt" | "toml" | "rs" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_token_is_accepted_as_password() {
+        // base64 of "preview:s3cret" and "preview:guess"
+        assert!(authorized(Some("Basic cHJldmlldzpzM2NyZXQ="), "s3cret"));
+        assert!(!authorized(Some("Basic cHJldmlldzpndWVzcw=="), "s3cret"));
+        assert!(!authorized(Some("Bearer s3cret"), "s3cret"));
+        assert!(!authorized(None, "s3cret"));
+    }
+}
@@ -0,0 +1,51 @@
+//! What modmod is doing while it loads and renders a track, reported through the [log]
+//! facade. Phases like loading the track or rendering the book log when they start and how
+//! long they took with the [PHASE_TARGET] target. The units being processed are logged with
+//! the [PROGRESS_TARGET] target, along with `phase`, `done` and `total` key-values to draw a
+//! progress bar from.
+
+use std::{fmt::Display, time::Instant};
+
+/// The log target of the records that tell which unit is being processed
+pub const PROGRESS_TARGET: &str = "modmod::progress";
+
+/// The log target of the records that tell when phases start and how long they took
+pub const PHASE_TARGET: &str = "modmod::phase";
+
+/// A phase of the work, which is logged when it starts and, when it's dropped, with the time
+/// it took
+#[derive(Debug)]
+pub struct Phase {
+    name: &'static str,
+    start: Instant,
+}
+
+impl Phase {
+    pub fn enter(name: &'static str) -> Self {
+        log::info!(target: PHASE_TARGET, "{name} started");
+        Self {
+            name,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        log::debug!(
+            target: PHASE_TARGET,
+            "{} took {:.2}s",
+            self.name,
+            self.start.elapsed().as_secs_f32()
+        );
+    }
+}
+
+/// Log that `item` is processed in `phase`, as the `done`th of `total` items
+pub fn progress(phase: &str, done: usize, total: usize, item: impl Display) {
+    log::info!(
+        target: PROGRESS_TARGET,
+        phase = phase, done = done, total = total;
+        "{phase}: [{done}/{total}] {item}"
+    );
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::io::PathExt;
+
+/// A quiz in the [mdbook-quiz](https://github.com/cognitive-engineering-lab/mdbook-quiz)
+/// TOML format. Only the fields modmod needs are modeled, the quiz files are
+/// passed on to mdbook-quiz as they are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quiz {
+    pub questions: Vec<Question>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Question {
+    ShortAnswer {
+        prompt: Prompt,
+        answer: ShortAnswer,
+        context: Option<String>,
+    },
+    MultipleChoice {
+        prompt: MultipleChoicePrompt,
+        answer: MultipleChoiceAnswer,
+        context: Option<String>,
+    },
+    Tracing {
+        prompt: TracingPrompt,
+        answer: TracingAnswer,
+        context: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prompt {
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortAnswer {
+    pub answer: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleChoicePrompt {
+    pub prompt: String,
+    #[serde(default)]
+    pub distractors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipleChoiceAnswer {
+    /// A single correct answer, or several if multiple answers are correct
+    pub answer: OneOrMany,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingPrompt {
+    pub program: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracingAnswer {
+    pub does_compile: bool,
+    pub stdout: Option<String>,
+    pub line_number: Option<usize>,
+}
+
+impl Quiz {
+    /// Load and validate the quiz at `path`
+    pub fn load<C: error_stack::Context + Default>(path: &Path) -> Result<Self, C> {
+        let quiz: Quiz = toml::from_str(&path.read_to_string()?)
+            .into_report()
+            .attach_printable_lazy(|| format!("Invalid quiz file {}", path.to_string_lossy()))
+            .change_context(C::default())?;
+        if quiz.questions.is_empty() {
+            return Err(Report::new(C::default()).attach_printable(format!(
+                "Quiz file {} has no questions",
+                path.to_string_lossy()
+            )));
+        }
+        Ok(quiz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mdbook_quiz_format_is_parsed() {
+        let quiz: Quiz = toml::from_str(
+            r#"
+            [[questions]]
+            type = "MultipleChoice"
+            prompt.prompt = "Which keyword declares a mutable variable?"
+            prompt.distractors = ["var"]
+            answer.answer = ["let mut", "static mut"]
+
+            [[questions]]
+            type = "Tracing"
+            prompt.program = "fn main() { println!(\"hi\"); }"
+            answer.doesCompile = true
+            answer.stdout = "hi"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            &quiz.questions[0],
+            Question::MultipleChoice {
+                answer: MultipleChoiceAnswer {
+                    answer: OneOrMany::Many(answers)
+                },
+                ..
+            } if answers.len() == 2
+        ));
+        assert!(matches!(
+            &quiz.questions[1],
+            Question::Tracing {
+                answer: TracingAnswer {
+                    does_compile: true,
+                    ..
+                },
+                ..
+            }
+        ));
+    }
+}
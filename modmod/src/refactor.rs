@@ -0,0 +1,239 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result, ResultExt};
+use similar::TextDiff;
+
+use crate::{
+    exercises::included_files,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RefactorError {}
+
+impl fmt::Display for RefactorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to refactor track content")
+    }
+}
+
+impl error_stack::Context for RefactorError {}
+
+pub struct RenameTermOptions<'a> {
+    /// The terms to rename, each as a pair of the term and its new name. A term is only
+    /// replaced where it's not part of a longer identifier.
+    pub renames: &'a [(&'a str, &'a str)],
+    /// Restrict the replacement to modules matching any of these selectors.
+    /// If empty, all modules are included.
+    pub modules: &'a [String],
+}
+
+/// A pending change to a single source file
+#[derive(Debug)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// Path relative to the track root, used for display
+    pub relative_path: PathBuf,
+    pub old: String,
+    pub new: String,
+}
+
+impl FileChange {
+    /// Render the change as a unified diff
+    pub fn diff(&self) -> String {
+        let path = self.relative_path.to_string_lossy();
+        TextDiff::from_lines(&self.old, &self.new)
+            .unified_diff()
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string()
+    }
+
+    /// Write the new content to disk
+    pub fn apply(&self) -> Result<(), RefactorError> {
+        self.path.create_file()?.write_all(&self.new)
+    }
+}
+
+impl Track {
+    /// Compute the changes needed to replace all occurrences of the terms
+    /// in the content and exercise sources of the track. Nothing is written
+    /// to disk until the returned changes are applied.
+    pub fn rename_term(
+        &self,
+        RenameTermOptions { renames, modules }: RenameTermOptions,
+    ) -> Result<Vec<FileChange>, RefactorError> {
+        if renames.iter().any(|(from, _)| from.is_empty()) {
+            return Err(Report::new(RefactorError::default())
+                .attach_printable("The term to rename can't be empty"));
+        }
+        let mut changes = vec![];
+        for path in self.source_files(modules)? {
+            // Skip binary files
+            let Ok(old) = path.read_to_string::<RefactorError>() else {
+                continue;
+            };
+            let new = renames.iter().fold(old.clone(), |text, (from, to)| {
+                replace_term(&text, from, to)
+            });
+            if new == old {
+                continue;
+            }
+            let relative_path = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            changes.push(FileChange {
+                path,
+                relative_path,
+                old,
+                new,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// All content and exercise source files that feed into the modules
+    /// matching `modules`, in order of appearance and without duplicates.
+    fn source_files(&self, modules: &[String]) -> Result<Vec<PathBuf>, RefactorError> {
+        let mut collector = SourceFileCollector {
+            modules,
+            selected: false,
+            seen: HashSet::new(),
+            files: vec![],
+        };
+        self.walk(&mut collector)?;
+        Ok(collector.files)
+    }
+}
+
+/// Replace the occurrences of `from` in `text` with `to`, except where they are part of a
+/// longer identifier, like `Error` is of `ErrorKind`
+fn replace_term(text: &str, from: &str, to: &str) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let starts_ident = from.starts_with(is_ident);
+    let ends_ident = from.ends_with(is_ident);
+
+    let mut replaced = String::with_capacity(text.len());
+    let mut rest = 0;
+    for (i, _) in text.match_indices(from) {
+        let end = i + from.len();
+        // Overlaps a replaced occurrence
+        if i < rest {
+            continue;
+        }
+        let joins_before = starts_ident && text[..i].ends_with(is_ident);
+        let joins_after = ends_ident && text[end..].starts_with(is_ident);
+        if joins_before || joins_after {
+            continue;
+        }
+        replaced.push_str(&text[rest..i]);
+        replaced.push_str(to);
+        rest = end;
+    }
+    replaced.push_str(&text[rest..]);
+    replaced
+}
+
+struct SourceFileCollector<'a> {
+    modules: &'a [String],
+    /// Whether the module currently being visited is selected
+    selected: bool,
+    seen: HashSet<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl SourceFileCollector<'_> {
+    fn push(&mut self, path: &Path) {
+        if self.selected && self.seen.insert(path.to_path_buf()) {
+            self.files.push(path.to_path_buf());
+        }
+    }
+}
+
+impl<'t> Visitor<'t> for SourceFileCollector<'_> {
+    type Error = RefactorError;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), RefactorError> {
+        self.selected = self.modules.is_empty() || self.modules.iter().any(|s| module.matches(s));
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), RefactorError> {
+        if let Some(template) = &unit.data.template {
+            self.push(template);
+        }
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), RefactorError> {
+        self.push(&topic.data.content);
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), RefactorError> {
+        if !self.selected {
+            return Ok(());
+        }
+        self.push(&exercise.data.description);
+        for hint in exercise.data.hints.iter() {
+            self.push(hint);
+        }
+        for file in included_files(&exercise.data.path, &exercise.data.includes)
+            .change_context(RefactorError::default())?
+        {
+            self.push(&file);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terms_within_longer_identifiers_are_left_alone() {
+        let text = "fn run() -> Result<(), Box<dyn Error>> {}\n\
+            fn kind() -> Box<dyn ErrorKind> {}\n\
+            struct MyBox<dyn Error>;\n\
+            let e: Error = MyError::new();\n";
+        assert_eq!(
+            replace_term(text, "Box<dyn Error>", "Box<dyn Error + Send + Sync>"),
+            "fn run() -> Result<(), Box<dyn Error + Send + Sync>> {}\n\
+            fn kind() -> Box<dyn ErrorKind> {}\n\
+            struct MyBox<dyn Error>;\n\
+            let e: Error = MyError::new();\n"
+        );
+        assert_eq!(
+            replace_term(text, "Error", "Failure"),
+            "fn run() -> Result<(), Box<dyn Failure>> {}\n\
+            fn kind() -> Box<dyn ErrorKind> {}\n\
+            struct MyBox<dyn Failure>;\n\
+            let e: Failure = MyError::new();\n"
+        );
+    }
+}
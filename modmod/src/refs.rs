@@ -0,0 +1,313 @@
+//! Cross-references in content, like `#[modmod:ref(unit = "basic-syntax")]`, which expand to
+//! a link labelled with the current number of the unit or exercise they refer to, so that
+//! references don't break when modules are reordered.
+
+use std::{collections::HashMap, convert::Infallible};
+
+use error_stack::{Context, Report, Result};
+
+use crate::{
+    check::anchor,
+    diagnostics::Snippet,
+    edition::Edition,
+    load::Indexed,
+    numbering::{section_file_name, ModuleLabel},
+    to_tag,
+    visit::{Position, Visitor},
+    Exercise, Module, RenderFilter, Topic, Track, Unit,
+};
+
+const REF_START: &str = "#[modmod:ref(";
+const REF_END: &str = ")]";
+
+/// The units and exercises of a track that content can refer to, by the ID or tag of a unit
+/// and the tag of an exercise
+#[derive(Debug, Default)]
+pub struct References {
+    units: HashMap<String, Target>,
+    /// Exercises of different units can have the same tag, which makes references to them
+    /// ambiguous unless they are qualified with the tag of the unit, like `unit-one/moves`
+    exercises: HashMap<String, Vec<Target>>,
+}
+
+#[derive(Debug, Clone)]
+struct Target {
+    /// Like `Unit 2.1` or `Exercise 2.1.3`
+    label: String,
+    /// Link to the target in the book, if it's rendered into it, like `basic-syntax.md#..`
+    book_link: Option<String>,
+    /// Identifier of the slide deck of a unit, if it's rendered, like `2_1`
+    deck_id: Option<String>,
+}
+
+/// Where references are expanded, which decides what they link to
+#[derive(Debug, Clone, Copy)]
+pub enum RefTarget<'u> {
+    /// Book sections, which link to each other
+    Book,
+    /// Slide decks, where references to units link to their decks, served from `url_base`
+    Slides { url_base: &'u str },
+}
+
+/// Collects the targets of the units and exercises while walking over the track
+struct Targets<'f> {
+    filter: &'f RenderFilter,
+    edition: Edition,
+    /// The labels of the modules that are yet to be visited
+    labels: std::vec::IntoIter<ModuleLabel>,
+    /// The label of the module that is being visited
+    label: ModuleLabel,
+    /// The book section, tag and rendering of the unit that is being visited
+    section: String,
+    unit_tag: String,
+    in_full: bool,
+    references: References,
+}
+
+impl<'t> Visitor<'t> for Targets<'_> {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.label = self.labels.next().unwrap();
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        let rendered = self.filter.includes(module, unit);
+        self.section = section_file_name(&unit.data.name)
+            .to_string_lossy()
+            .into_owned();
+        self.unit_tag = to_tag(&unit.data.name);
+        // Teasers only list the titles of the exercises, and have no slides
+        self.in_full = rendered && (!self.edition.is_preview() || unit.data.preview);
+        let target = Target {
+            label: match self.label.of(&[unit.index]) {
+                Some(unit_label) => format!("Unit {unit_label}"),
+                None => unit.data.name.clone(),
+            },
+            book_link: rendered.then(|| self.section.clone()),
+            deck_id: self
+                .in_full
+                .then(|| position.unit_number().unwrap().deck_id()),
+        };
+        for key in unit.data.id.iter().cloned().chain([self.unit_tag.clone()]) {
+            self.references.units.insert(key, target.clone());
+        }
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        let number = position.exercise_number().unwrap();
+        let exercise_label = self
+            .label
+            .of(&[number.unit, number.exercise])
+            .unwrap_or_else(|| number.to_string());
+        let heading = format!("Exercise {exercise_label}: {}", exercise.data.name);
+        let target = Target {
+            label: format!("Exercise {exercise_label}"),
+            book_link: self
+                .in_full
+                .then(|| format!("{}#{}", self.section, anchor(&heading))),
+            deck_id: None,
+        };
+        // Qualifying the tag with that of the unit disambiguates it
+        let tag = to_tag(&exercise.data.name);
+        for key in [format!("{}/{tag}", self.unit_tag), tag] {
+            self.references
+                .exercises
+                .entry(key)
+                .or_default()
+                .push(target.clone());
+        }
+        Ok(())
+    }
+}
+
+impl References {
+    /// The references to the units and exercises of `track`, labelled the way the book labels
+    /// them. Units that `filter` leaves out are referred to without a link, as their sections
+    /// are not rendered.
+    pub fn of_track(track: &Track, filter: &RenderFilter, edition: Edition) -> Self {
+        let labels = track.numbering.module_labels(
+            track
+                .modules
+                .iter()
+                .map(|m| (m.index, m.data.appendix, track.part_of(m.index))),
+        );
+        let mut targets = Targets {
+            filter,
+            edition,
+            labels: labels.into_iter(),
+            label: ModuleLabel::Unnumbered,
+            section: String::new(),
+            unit_tag: String::new(),
+            in_full: false,
+            references: Self::default(),
+        };
+        track.walk_infallible(&mut targets);
+        targets.references
+    }
+
+    /// Replace the `#[modmod:ref(unit = "tag")]` and `#[modmod:ref(exercise = "tag")]`
+    /// references in `content` with links to what they refer to
+    pub fn expand<C: Context + Default>(
+        &self,
+        content: &str,
+        target: RefTarget,
+    ) -> Result<String, C> {
+        let mut expanded = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find(REF_START) {
+            expanded.push_str(&rest[..start]);
+            let args = &rest[start + REF_START.len()..];
+            let Some(end) = args.find(REF_END) else {
+                return Err(Report::new(C::default())
+                    .attach_printable("Unterminated #[modmod:ref(..)] reference")
+                    .attach_printable(Snippet(rest[start..].lines().next().unwrap().to_string())));
+            };
+            let text = &rest[start..start + REF_START.len() + end + REF_END.len()];
+            let reference = self.resolve(&args[..end]).map_err(|message| {
+                Report::new(C::default())
+                    .attach_printable(message)
+                    .attach_printable(Snippet(text.to_string()))
+            })?;
+            expanded.push_str(&match (target, &reference.book_link, &reference.deck_id) {
+                (RefTarget::Book, Some(link), _) => format!("[{}]({link})", reference.label),
+                (RefTarget::Slides { url_base }, _, Some(deck_id)) => {
+                    let url_base = url_base.trim_matches('/');
+                    let separator = if url_base.is_empty() { "" } else { "/" };
+                    format!(
+                        "[{}](/{url_base}{separator}slides/{deck_id}/)",
+                        reference.label
+                    )
+                }
+                _ => reference.label.clone(),
+            });
+            rest = &rest[start + text.len()..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    /// The target of the arguments of a reference, like `unit = "basic-syntax"`
+    fn resolve(&self, args: &str) -> std::result::Result<&Target, String> {
+        let invalid = || {
+            format!("Invalid #[modmod:ref(..)] arguments '{args}', expected unit = \"..\" or exercise = \"..\"")
+        };
+        let (kind, key) = args.split_once('=').ok_or_else(invalid)?;
+        let key = key.trim().trim_matches('"');
+        match kind.trim() {
+            "unit" => self
+                .units
+                .get(key)
+                .ok_or_else(|| format!("No unit with ID or tag '{key}' to refer to")),
+            "exercise" => match self.exercises.get(key).map(Vec::as_slice) {
+                Some([target]) => Ok(target),
+                Some(targets) => Err(format!(
+                    "Exercise tag '{key}' refers to {} exercises, qualify it with the tag of \
+                    the unit like 'unit-tag/{key}': {}",
+                    targets.len(),
+                    targets
+                        .iter()
+                        .map(|t| t.label.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+                None => Err(format!("No exercise with tag '{key}' to refer to")),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestError;
+    impl std::fmt::Display for TestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("test error")
+        }
+    }
+    impl Context for TestError {}
+
+    #[test]
+    fn references_link_to_their_targets() {
+        let unit = Target {
+            label: "Unit 2.3".to_string(),
+            book_link: Some("ownership.md".to_string()),
+            deck_id: Some("2_3".to_string()),
+        };
+        let exercise = |label: &str| Target {
+            label: format!("Exercise {label}"),
+            book_link: Some(format!(
+                "ownership.md#exercise-{}-moves",
+                label.replace('.', "")
+            )),
+            deck_id: None,
+        };
+        let references = References {
+            units: HashMap::from([("ownership-basics".to_string(), unit)]),
+            exercises: HashMap::from([
+                ("moves".to_string(), vec![exercise("2.3.1")]),
+                (
+                    "borrows".to_string(),
+                    vec![exercise("2.3.2"), exercise("3.1.1")],
+                ),
+            ]),
+        };
+
+        let content = "See #[modmod:ref(unit = \"ownership-basics\")] and \
+            #[modmod:ref(exercise = \"moves\")].";
+        assert_eq!(
+            references
+                .expand::<TestError>(content, RefTarget::Book)
+                .unwrap(),
+            "See [Unit 2.3](ownership.md) and [Exercise 2.3.1](ownership.md#exercise-231-moves)."
+        );
+        assert_eq!(
+            references
+                .expand::<TestError>(content, RefTarget::Slides { url_base: "/rust/" })
+                .unwrap(),
+            "See [Unit 2.3](/rust/slides/2_3/) and Exercise 2.3.1."
+        );
+
+        for (content, message) in [
+            (
+                "#[modmod:ref(unit = \"lifetimes\")]",
+                "No unit with ID or tag 'lifetimes' to refer to",
+            ),
+            (
+                "#[modmod:ref(exercise = \"borrows\")]",
+                "Exercise tag 'borrows' refers to 2 exercises, qualify it with the tag of the \
+                unit like 'unit-tag/borrows': Exercise 2.3.2, Exercise 3.1.1",
+            ),
+        ] {
+            let report = references
+                .expand::<TestError>(content, RefTarget::Book)
+                .unwrap_err();
+            let messages: Vec<_> = report
+                .frames()
+                .filter_map(|frame| frame.downcast_ref::<String>())
+                .collect();
+            assert_eq!(messages, [message]);
+            assert_eq!(report.downcast_ref::<Snippet>().unwrap().0, content);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Rendering several versions of a track side by side, like the one every cohort was taught
+//! from, into folders of the same output folder with a landing page that links to each of
+//! them. Versions are taken from git refs of the repository the track is in, or from the
+//! working tree.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hooks::HooksConfig,
+    io::{PathExt, WriteExt},
+    manifest::MANIFEST_FILE,
+    tasks::{Task, TaskRunner},
+    RenderOptions, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ReleaseError {}
+
+impl fmt::Display for ReleaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render releases")
+    }
+}
+
+impl error_stack::Context for ReleaseError {}
+
+/// Name of the file in the output folder that lists the rendered versions
+pub const VERSIONS_FILE: &str = "versions.json";
+
+/// A releases TOML, which lists the versions of a track to render
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleasesConfig {
+    /// The title of the landing page
+    pub title: String,
+    /// The versions, from the oldest to the latest
+    pub versions: Vec<ReleaseVersion>,
+    /// The folder of the releases TOML
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+/// A version of the track in the releases TOML
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseVersion {
+    /// Name of the folder the version is rendered into, like `2024-q2`
+    pub name: String,
+    /// The git ref, like a tag or a commit, of the content of the version. Without one, the
+    /// version is rendered from the working tree.
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    /// The track TOML, relative to the releases TOML, as it was at the ref
+    pub track: PathBuf,
+}
+
+/// How to render the versions in the releases TOML
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseOptions {
+    /// How every version is rendered. The versions are rendered into folders of its output
+    /// folder, and their slides are served from folders of its slide URL base.
+    pub render: RenderOptions,
+    /// Render the versions at git refs again, rather than keeping what was rendered before.
+    /// Versions from the working tree are always rendered again.
+    pub force: bool,
+    /// Run the hooks of the track of every version
+    pub hooks: bool,
+}
+
+/// A version, and whether it was rendered, rather than kept from an earlier render
+#[derive(Debug, Clone)]
+pub struct RenderedVersion {
+    pub name: String,
+    pub rendered: bool,
+}
+
+impl ReleasesConfig {
+    /// Read the releases TOML at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReleaseError> {
+        let path = path.as_ref();
+        let mut config: Self = toml::from_str(&path.read_to_string::<ReleaseError>()?)
+            .into_report()
+            .attach_printable_lazy(|| format!("Invalid releases TOML {}", path.display()))
+            .change_context(ReleaseError::default())?;
+        config.root = path
+            .canonicalize()
+            .into_report()
+            .change_context(ReleaseError::default())?
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        for (i, version) in config.versions.iter().enumerate() {
+            let name = Path::new(&version.name);
+            if name.components().count() != 1 || name.file_name().is_none() {
+                return Err(
+                    Report::new(ReleaseError::default()).attach_printable(format!(
+                        "Version name '{}' is not a folder name",
+                        version.name
+                    )),
+                );
+            }
+            if config.versions[..i].iter().any(|v| v.name == version.name) {
+                return Err(Report::new(ReleaseError::default())
+                    .attach_printable(format!("Version '{}' is listed twice", version.name)));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Render every version into the folder of its name in the output folder of `opts`,
+    /// along with a landing page, `index.html`, that links to all of them, and a list of
+    /// the versions, [VERSIONS_FILE], for version switchers.
+    pub fn render(&self, opts: &ReleaseOptions) -> Result<Vec<RenderedVersion>, ReleaseError> {
+        let out_dir = &opts.render.out_dir;
+        out_dir.create_dir_all()?;
+        let url_base = opts.render.slide_url_base.trim_end_matches('/');
+
+        let mut rendered = vec![];
+        for version in self.versions.iter() {
+            let version_out_dir = out_dir.join(&version.name);
+            // The content at a ref doesn't change, so neither does what it renders into
+            let keep = version.git_ref.is_some()
+                && !opts.force
+                && version_out_dir.join(MANIFEST_FILE).is_file();
+            if !keep {
+                let render = RenderOptions {
+                    out_dir: version_out_dir,
+                    clear_output_dir: true,
+                    slide_url_base: format!("{url_base}/{}/", version.name),
+                    ..opts.render.clone()
+                };
+                self.render_version(version, &render, opts.hooks)
+                    .attach_printable_lazy(|| format!("in version '{}'", version.name))?;
+            }
+            rendered.push(RenderedVersion {
+                name: version.name.clone(),
+                rendered: !keep,
+            });
+        }
+
+        let versions: Vec<_> = self.versions.iter().map(|v| &v.name).collect();
+        out_dir
+            .join(VERSIONS_FILE)
+            .create_file()?
+            .write_all(serde_json::to_string_pretty(&versions).unwrap())?;
+        out_dir
+            .join("index.html")
+            .create_file()?
+            .write_all(self.landing_page())?;
+        Ok(rendered)
+    }
+
+    /// Render `version` as set by `opts`, from a checkout of its ref if it has one
+    fn render_version(
+        &self,
+        version: &ReleaseVersion,
+        opts: &RenderOptions,
+        hooks: bool,
+    ) -> Result<(), ReleaseError> {
+        let runner = &opts.runner;
+        let checkout = version
+            .git_ref
+            .as_deref()
+            .map(|git_ref| Checkout::new(&self.root, git_ref, &opts.out_dir, runner))
+            .transpose()?;
+        let root = checkout.as_ref().map_or(&self.root, |c| &c.root);
+        let track_toml = root.join(&version.track);
+
+        if hooks {
+            HooksConfig::of_track_toml(&track_toml)
+                .and_then(|h| h.run_pre_load(&track_toml, runner))
+                .change_context(ReleaseError::default())?;
+        }
+        let track = Track::load(&track_toml).change_context(ReleaseError::default())?;
+        track.render(opts).change_context(ReleaseError::default())?;
+        if hooks {
+            track
+                .run_post_render_hooks(&opts.out_dir, opts.edition, runner)
+                .change_context(ReleaseError::default())?;
+        }
+        Ok(())
+    }
+
+    /// The landing page, which lists the versions from the latest to the oldest. It links to
+    /// the folders that are rendered, as the books are not built here.
+    fn landing_page(&self) -> String {
+        let title = html_escape(&self.title);
+        let mut items = String::new();
+        for (version, i) in self.versions.iter().rev().zip(0..) {
+            let name = html_escape(&version.name);
+            let latest = if i == 0 { " (latest)" } else { "" };
+            items += &format!(
+                "      <li><strong>{name}</strong>{latest}: <a href=\"{name}/book/\">book</a>, <a href=\"{name}/exercises/\">exercises</a></li>\n"
+            );
+        }
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{title}</title>
+  </head>
+  <body>
+    <h1>{title}</h1>
+    <p>The material of every version, as it was taught.</p>
+    <ul>
+{items}    </ul>
+  </body>
+</html>
+"#
+        )
+    }
+}
+
+/// The content of the repository of the releases TOML at a git ref, extracted into a
+/// folder that is removed when it's dropped
+struct Checkout {
+    dir: PathBuf,
+    /// The folder of the releases TOML in the checkout
+    root: PathBuf,
+}
+
+impl Checkout {
+    /// Extract the repository that `root` is in at `git_ref`, into a folder next to the
+    /// output folder `out_dir` of the version
+    fn new(
+        root: &Path,
+        git_ref: &str,
+        out_dir: &Path,
+        runner: &TaskRunner,
+    ) -> Result<Self, ReleaseError> {
+        let git = |name: &str, args: &[&str], dir: &Path| {
+            runner
+                .run_one(
+                    Task::new(name, "git")
+                        .args(args)
+                        .current_dir(dir)
+                        .no_retry(),
+                )
+                .into_result()
+                .attach_printable_lazy(|| {
+                    format!("Unable to get the content at git ref '{git_ref}'")
+                })
+                .change_context(ReleaseError::default())
+        };
+        let top_level = git("git rev-parse", &["rev-parse", "--show-toplevel"], root)?;
+        let top_level = PathBuf::from(String::from_utf8_lossy(&top_level.stdout).trim())
+            .canonicalize()
+            .into_report()
+            .change_context(ReleaseError::default())?;
+        // Run in the top-level folder, as git only archives the current folder otherwise
+        let archive = git(
+            "git archive",
+            &["archive", "--format=tar", git_ref],
+            &top_level,
+        )?
+        .stdout;
+
+        let mut dir = out_dir.as_os_str().to_owned();
+        dir.push(".checkout");
+        let dir = PathBuf::from(dir);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .into_report()
+                .change_context(ReleaseError::default())?;
+        }
+        // Remove the folder again if extracting fails
+        let checkout = Self {
+            root: dir.join(root.strip_prefix(&top_level).unwrap_or(Path::new(""))),
+            dir,
+        };
+        checkout.dir.create_dir_all()?;
+        tar::Archive::new(archive.as_slice())
+            .unpack(&checkout.dir)
+            .into_report()
+            .attach_printable_lazy(|| format!("Unable to extract git ref '{git_ref}'"))
+            .change_context(ReleaseError::default())?;
+        Ok(checkout)
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
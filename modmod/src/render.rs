@@ -0,0 +1,225 @@
+use error_stack::Result;
+
+use crate::{
+    book::BookBuilder,
+    edition::Edition,
+    exercises::ExerciseCollectionBuilder,
+    load::Indexed,
+    slides::SlidesPackageBuilder,
+    visit::{Position, Visitor},
+    Exercise, LoadTrackError, Module, RenderFilter, Topic, Unit,
+};
+
+/// How the unit that is being visited is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnitRendering {
+    /// Filtered out, so only listed in the book summary as a draft
+    Draft,
+    /// Left out of the preview edition, so the book section only lists its learning
+    /// objectives and exercises
+    Teaser,
+    Full,
+}
+
+/// Adds the selected units of a track to the builders of the book, the slides and the
+/// exercise packages, while walking over the track
+pub(crate) struct TrackRenderer<'t, 'f> {
+    pub filter: &'f RenderFilter,
+    pub edition: Edition,
+    pub book: BookBuilder<'t>,
+    pub slides: SlidesPackageBuilder<'t>,
+    pub exercises: ExerciseCollectionBuilder<'t>,
+    unit: UnitRendering,
+    /// Whether the module that is being visited was added to the exercise collection, which
+    /// only holds the modules that have selected units
+    module_exercises: bool,
+}
+
+impl<'t, 'f> TrackRenderer<'t, 'f> {
+    pub fn new(
+        filter: &'f RenderFilter,
+        edition: Edition,
+        book: BookBuilder<'t>,
+        slides: SlidesPackageBuilder<'t>,
+        exercises: ExerciseCollectionBuilder<'t>,
+    ) -> Self {
+        Self {
+            filter,
+            edition,
+            book,
+            slides,
+            exercises,
+            unit: UnitRendering::Draft,
+            module_exercises: false,
+        }
+    }
+}
+
+impl<'t> Visitor<'t> for TrackRenderer<'t, '_> {
+    type Error = LoadTrackError;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), LoadTrackError> {
+        let mut chapter = self.book.chapter(&module.data.name, module.index);
+        if let Some(intro) = &module.data.intro {
+            chapter.intro(intro);
+        }
+        if module.data.appendix {
+            chapter.appendix();
+        }
+        chapter.add();
+        self.module_exercises = false;
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), LoadTrackError> {
+        let Indexed { data, index } = unit;
+        self.unit = if !self.filter.includes(module, unit) {
+            UnitRendering::Draft
+        } else if self.edition.is_preview() && !data.preview {
+            UnitRendering::Teaser
+        } else {
+            UnitRendering::Full
+        };
+
+        let mut chapter = self.book.reopen_chapter();
+        let section = chapter.section(module.index, *index, &data.name);
+        match self.unit {
+            UnitRendering::Draft => {
+                section.draft().add();
+            }
+            UnitRendering::Teaser => {
+                let mut section = section.teaser();
+                if let Some(duration) = unit.known_duration() {
+                    section.duration(duration);
+                }
+                section.add();
+            }
+            UnitRendering::Full => {
+                let mut section = section.source(&data.source);
+                data.quizzes.iter().for_each(|quiz| section.quiz(quiz));
+                if let Some(intro) = &data.intro {
+                    section.intro(intro);
+                }
+                if let Some(duration) = unit.known_duration() {
+                    section.duration(duration);
+                }
+                section.add();
+
+                let mut deck = self.slides.deck(
+                    &data.name,
+                    &module.data.name,
+                    module.index,
+                    *index,
+                    data.template.as_deref(),
+                );
+                deck.config(&module.data.slides).source(&data.source);
+                deck.add();
+
+                let mut module_exercises = match self.module_exercises {
+                    true => self.exercises.reopen_module(),
+                    false => self.exercises.module(&module.data.name, module.index),
+                };
+                module_exercises.unit(&data.name, *index).add();
+                module_exercises.add();
+                self.module_exercises = true;
+            }
+        }
+        chapter.add();
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), LoadTrackError> {
+        let data = &topic.data;
+        match self.unit {
+            UnitRendering::Draft => {}
+            UnitRendering::Teaser => {
+                let mut chapter = self.book.reopen_chapter();
+                let mut section = chapter.reopen_section();
+                data.objectives
+                    .iter()
+                    .for_each(|obj| section.objective(obj));
+                section.add();
+                chapter.add();
+            }
+            UnitRendering::Full => {
+                let mut deck = self.slides.reopen_deck();
+                let mut slides_section = deck.section(&data.content);
+                data.summary
+                    .iter()
+                    .for_each(|item| slides_section.summary(item));
+                data.objectives
+                    .iter()
+                    .for_each(|obj| slides_section.objective(obj));
+                data.further_reading
+                    .iter()
+                    .for_each(|item| slides_section.further_reading(item));
+                data.images
+                    .iter()
+                    .for_each(|image| slides_section.image(image));
+                slides_section.add();
+                deck.add();
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), LoadTrackError> {
+        let data = &exercise.data;
+        if self.unit == UnitRendering::Draft {
+            return Ok(());
+        }
+
+        let mut chapter = self.book.reopen_chapter();
+        let mut section = chapter.reopen_section();
+        let subsection = section.subsection(
+            &data.name,
+            &data.description,
+            &data.description_images,
+            &data.path,
+        );
+        if self.unit == UnitRendering::Full {
+            subsection
+                .difficulty(data.difficulty)
+                .duration(data.duration)
+                .hints(&data.hints);
+
+            let mut module_exercises = self.exercises.reopen_module();
+            let mut unit_exercises = module_exercises.reopen_unit();
+            unit_exercises
+                .package(
+                    &data.name,
+                    &data.path,
+                    &data.includes,
+                    data.generate.as_deref(),
+                    data.solution.as_deref(),
+                )
+                .toolchain(&data.toolchain)
+                .hints(&data.hints)
+                .hidden_tests(data.hidden_tests.as_deref());
+            unit_exercises.add();
+            module_exercises.add();
+        }
+        section.add();
+        chapter.add();
+        Ok(())
+    }
+}
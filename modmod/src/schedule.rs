@@ -0,0 +1,336 @@
+use std::{
+    convert::Infallible,
+    fmt,
+    iter::Sum,
+    ops::Add,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    calendar::{Date, TimeSlot},
+    check::Finding,
+    io::{PathExt, WriteExt},
+    load::Indexed,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderScheduleError {}
+
+impl fmt::Display for RenderScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render schedule")
+    }
+}
+
+impl error_stack::Context for RenderScheduleError {}
+
+/// Name of the schedule page in the book sources
+pub const SCHEDULE_FILE: &str = "schedule.md";
+
+/// A span of time, written like `45m`, `2h` or `1h30m` in TOML
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Duration {
+    pub minutes: u32,
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.minutes / 60, self.minutes % 60) {
+            (0, minutes) => write!(f, "{minutes}m"),
+            (hours, 0) => write!(f, "{hours}h"),
+            (hours, minutes) => write!(f, "{hours}h{minutes}m"),
+        }
+    }
+}
+
+impl FromStr for Duration {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let error = || format!("invalid duration '{s}', expected one like 45m, 2h or 1h30m");
+        let mut minutes = 0;
+        let mut rest = s.trim();
+        if rest.is_empty() {
+            return Err(error());
+        }
+        while !rest.is_empty() {
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?;
+            let amount: u32 = rest[..digits].parse().map_err(|_| error())?;
+            rest = &rest[digits..];
+            let unit_len = rest
+                .find(|c: char| c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            minutes += match rest[..unit_len].trim() {
+                "h" => amount * 60,
+                "m" | "min" => amount,
+                _ => return Err(error()),
+            };
+            rest = &rest[unit_len..];
+        }
+        Ok(Duration { minutes })
+    }
+}
+
+impl TryFrom<String> for Duration {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Duration> for String {
+    fn from(duration: Duration) -> Self {
+        duration.to_string()
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, other: Duration) -> Duration {
+        Duration {
+            minutes: self.minutes + other.minutes,
+        }
+    }
+}
+
+impl Sum for Duration {
+    fn sum<I: Iterator<Item = Duration>>(iter: I) -> Self {
+        iter.fold(Duration::default(), Add::add)
+    }
+}
+
+/// Schedule configuration as defined in the `[schedule]` table of the track TOML
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// The teaching time in a day. Modules that take longer are reported by `modmod check`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_length: Option<Duration>,
+    /// The first day of the course run, for `modmod calendar`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<Date>,
+    /// The teaching time of each day, like `["09:00-12:00", "13:00-17:00"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slots: Vec<TimeSlot>,
+    /// Whether the course is also taught on Saturdays and Sundays
+    #[serde(default)]
+    pub weekends: bool,
+    /// The IANA time zone of the slots, e.g. `Europe/Amsterdam`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// The URL the book is deployed to, which the calendar events link to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book_url: Option<String>,
+}
+
+impl Indexed<Unit> {
+    /// The time the unit takes: the duration of the unit itself, plus that of its exercises
+    pub fn duration(&self) -> Duration {
+        self.data.duration.unwrap_or_default() + self.exercises_duration()
+    }
+
+    /// The time the unit takes, if the unit or any of its exercises has a duration
+    pub fn known_duration(&self) -> Option<Duration> {
+        let has_durations = self.data.duration.is_some()
+            || self
+                .data
+                .topics
+                .iter()
+                .flat_map(|t| &t.data.exercises)
+                .any(|e| e.data.duration.is_some());
+        has_durations.then(|| self.duration())
+    }
+
+    pub(crate) fn exercises_duration(&self) -> Duration {
+        self.data
+            .topics
+            .iter()
+            .flat_map(|t| &t.data.exercises)
+            .filter_map(|e| e.data.duration)
+            .sum()
+    }
+}
+
+impl Indexed<Module> {
+    pub fn duration(&self) -> Duration {
+        self.data.units.iter().map(|u| u.duration()).sum()
+    }
+}
+
+impl Track {
+    /// Whether any unit or exercise in the track has a duration
+    pub fn has_durations(&self) -> bool {
+        self.modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .any(|u| u.known_duration().is_some())
+    }
+
+    /// The time taken by all modules together
+    pub fn duration(&self) -> Duration {
+        self.modules.iter().map(|m| m.duration()).sum()
+    }
+
+    /// Add a schedule to the front of the rendered book in `out_dir`, with a table listing
+    /// the duration of each unit `filter` selects and its exercises, and the totals per module and
+    /// for the whole track
+    pub fn render_schedule(
+        &self,
+        out_dir: impl AsRef<Path>,
+        filter: &RenderFilter,
+    ) -> Result<(), RenderScheduleError> {
+        let book_src_dir = out_dir.as_ref().join("book").join("src");
+
+        let table = String::from(
+            "| Unit | Duration | Exercises | Total |\n|------|---------:|----------:|------:|\n",
+        );
+        let mut rows = ScheduleRows {
+            filter,
+            table,
+            total: Duration::default(),
+            unit_rows: String::new(),
+            module_total: Duration::default(),
+        };
+        self.walk_infallible(&mut rows);
+        let ScheduleRows {
+            mut table, total, ..
+        } = rows;
+        table.push_str(&format!("| **Total** | | | **{total}** |\n"));
+
+        book_src_dir
+            .join(SCHEDULE_FILE)
+            .create_file()?
+            .write_all(format!("# Schedule\n\n{table}"))?;
+
+        // A link before the first numbered chapter makes the schedule a prefix chapter
+        let summary_path = book_src_dir.join("SUMMARY.md");
+        let summary = summary_path.read_to_string()?;
+        let summary = summary.replacen(
+            "# Summary\n\n",
+            &format!("# Summary\n\n[Schedule]({SCHEDULE_FILE})\n\n"),
+            1,
+        );
+        summary_path.create_file()?.write_all(summary)?;
+
+        Ok(())
+    }
+
+    /// Report the modules that take longer than the day length in the `[schedule]` table
+    /// of the track, if it sets one
+    pub fn schedule_warnings(&self) -> Vec<Finding> {
+        let Some(day_length) = self.schedule.day_length else {
+            return vec![];
+        };
+        self.modules
+            .iter()
+            .filter(|m| m.duration() > day_length)
+            .map(|m| Finding {
+                path: PathBuf::from(&self.root),
+                line: None,
+                message: format!(
+                    "Module {} '{}' takes {}, which exceeds the day length of {day_length}",
+                    m.index,
+                    m.data.name,
+                    m.duration()
+                ),
+            })
+            .collect()
+    }
+}
+
+/// Adds the rows of the rendered units and their modules to the schedule table while
+/// walking over the track
+struct ScheduleRows<'p> {
+    /// The units that were rendered into the book
+    filter: &'p RenderFilter,
+    table: String,
+    total: Duration,
+    /// The rows and total of the rendered units of the module that is being visited
+    unit_rows: String,
+    module_total: Duration,
+}
+
+impl<'t> Visitor<'t> for ScheduleRows<'_> {
+    type Error = Infallible;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        if !self.filter.includes(module, unit) {
+            return Ok(());
+        }
+        self.module_total = self.module_total + unit.duration();
+        let exercises = unit.exercises_duration();
+        self.unit_rows.push_str(&format!(
+            "| {} {} | {} | {} | {} |\n",
+            position,
+            escape_cell(&unit.data.name),
+            unit.data
+                .duration
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            match exercises.minutes {
+                0 => String::new(),
+                _ => exercises.to_string(),
+            },
+            unit.duration()
+        ));
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        let unit_rows = std::mem::take(&mut self.unit_rows);
+        let module_total = std::mem::take(&mut self.module_total);
+        if unit_rows.is_empty() {
+            return Ok(());
+        }
+        self.total = self.total + module_total;
+        self.table.push_str(&format!(
+            "| **Module {}: {}** | | | **{module_total}** |\n",
+            module.index,
+            escape_cell(&module.data.name)
+        ));
+        self.table.push_str(&unit_rows);
+        Ok(())
+    }
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_are_parsed_and_displayed() {
+        let parse = |s: &str| s.parse::<Duration>().map(|d| d.minutes);
+        assert_eq!(parse("45m"), Ok(45));
+        assert_eq!(parse("2h"), Ok(120));
+        assert_eq!(parse("1h30m"), Ok(90));
+        assert_eq!(parse("1h 15min"), Ok(75));
+        assert!(parse("").is_err());
+        assert!(parse("45").is_err());
+        assert!(parse("1d").is_err());
+        assert_eq!(Duration { minutes: 45 }.to_string(), "45m");
+        assert_eq!(Duration { minutes: 120 }.to_string(), "2h");
+        assert_eq!(Duration { minutes: 90 }.to_string(), "1h30m");
+    }
+}
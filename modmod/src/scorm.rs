@@ -0,0 +1,378 @@
+use std::{
+    fmt::{self, Write as _},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Context, IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    archive::{entry_name, ENTRY_MODE, PACKAGES_DIR},
+    io::PathExt,
+    load::Indexed,
+    numbering::section_file_name,
+    to_tag,
+    unicode::to_ascii,
+    visit::{Position, Visitor},
+    Module, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ScormError {}
+
+impl fmt::Display for ScormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to create SCORM package")
+    }
+}
+
+impl error_stack::Context for ScormError {}
+
+#[derive(Debug, Default)]
+pub struct ScormOptions {
+    /// Add the built slide decks in `slides/dist` as well
+    pub slides: bool,
+    /// Replace emoji and typographic punctuation in the pages of the package with ASCII, for
+    /// learning management systems that can't handle them. The book itself is left as it is.
+    pub ascii: bool,
+}
+
+/// Script added to every launched page, marking it completed as soon as it is opened.
+/// SCORM 1.2 exposes its API as `API` on one of the parent windows of the SCO.
+const SCO_SCRIPT: &str = r#"<script>
+(function () {
+  var w = window, api = null;
+  for (var i = 0; w && !api && i < 10; i++) { api = w.API || null; w = w.parent === w ? w.opener : w.parent; }
+  if (!api) return;
+  api.LMSInitialize("");
+  api.LMSSetValue("cmi.core.lesson_status", "completed");
+  api.LMSCommit("");
+  window.addEventListener("unload", function () { api.LMSFinish(""); });
+})();
+</script>
+"#;
+
+/// A launchable page in the package, with the other files it needs
+struct Sco {
+    identifier: String,
+    title: String,
+    href: String,
+    files: Vec<String>,
+}
+
+/// Collects the items of the course organization and the SCOs of the units that were
+/// rendered into the book while walking over the track
+struct Organization<'p> {
+    out_dir: &'p Path,
+    book_dir: &'p Path,
+    opts: &'p ScormOptions,
+    organization: String,
+    /// The items of the units of the module that is being visited
+    items: String,
+    scos: Vec<Sco>,
+}
+
+impl<'t> Visitor<'t> for Organization<'_> {
+    type Error = ScormError;
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), ScormError> {
+        let number = position.unit_number().unwrap();
+        let page = section_file_name(&unit.data.name).with_extension("html");
+        if !self.book_dir.join(&page).is_file() {
+            return Ok(());
+        }
+        let title = format!("{number} {}", unit.data.name);
+        let book_sco = Sco {
+            identifier: format!("RES_{}", number.deck_id()),
+            title: title.clone(),
+            href: format!("book/{}", entry_name(&page)),
+            files: vec![],
+        };
+
+        let slides_dir = self
+            .out_dir
+            .join("slides")
+            .join("dist")
+            .join(number.deck_slug(&unit.data.name));
+        if self.opts.slides && slides_dir.join("index.html").is_file() {
+            let prefix = format!("slides/{}", number.deck_slug(&unit.data.name));
+            let slides_sco = Sco {
+                identifier: format!("RES_{}_SLIDES", number.deck_id()),
+                title: format!("{title} (slides)"),
+                href: format!("{prefix}/index.html"),
+                files: files_in(&slides_dir, &prefix)?,
+            };
+            writeln!(
+                self.items,
+                r#"      <item identifier="UNIT_{}"><title>{}</title>"#,
+                number.deck_id(),
+                xml_escape(&title)
+            )
+            .unwrap();
+            for sco in [&book_sco, &slides_sco] {
+                writeln!(
+                    self.items,
+                    r#"        <item identifier="ITEM_{id}" identifierref="{id}"><title>{}</title></item>"#,
+                    xml_escape(&sco.title),
+                    id = sco.identifier,
+                )
+                .unwrap();
+            }
+            self.items.push_str("      </item>\n");
+            self.scos.extend([book_sco, slides_sco]);
+        } else {
+            writeln!(
+                self.items,
+                r#"      <item identifier="UNIT_{}" identifierref="{}"><title>{}</title></item>"#,
+                number.deck_id(),
+                book_sco.identifier,
+                xml_escape(&title)
+            )
+            .unwrap();
+            self.scos.push(book_sco);
+        }
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), ScormError> {
+        let items = std::mem::take(&mut self.items);
+        if items.is_empty() {
+            return Ok(());
+        }
+        writeln!(
+            self.organization,
+            r#"    <item identifier="MODULE_{}"><title>{}</title>"#,
+            module.index,
+            xml_escape(&format!("{} {}", module.index, module.data.name))
+        )
+        .unwrap();
+        self.organization.push_str(&items);
+        self.organization.push_str("    </item>\n");
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Wrap the HTML book built by mdbook in `out_dir`, and optionally the built slide decks,
+    /// into a SCORM 1.2 package that can be uploaded to an LMS. Every module becomes an item
+    /// in the course organization, containing a SCO per unit. Units that weren't rendered are
+    /// left out. Returns the path of the written package.
+    pub fn scorm_package(
+        &self,
+        out_dir: impl AsRef<Path>,
+        opts: &ScormOptions,
+    ) -> Result<PathBuf, ScormError> {
+        let out_dir = out_dir.as_ref();
+        let book_dir = book_html_dir::<ScormError>(out_dir)?;
+        let book_files = files_in(&book_dir, "book")?;
+
+        let mut organization = Organization {
+            out_dir,
+            book_dir: &book_dir,
+            opts,
+            organization: String::new(),
+            items: String::new(),
+            scos: vec![],
+        };
+        self.walk(&mut organization)?;
+        let Organization {
+            organization, scos, ..
+        } = organization;
+
+        if scos.is_empty() {
+            return Err(Report::new(ScormError::default()).attach_printable(format!(
+                "No rendered units found in {}. Build the book with mdbook first",
+                book_dir.to_string_lossy()
+            )));
+        }
+
+        let manifest = self.scorm_manifest(&organization, &scos, &book_files);
+        let manifest = match opts.ascii {
+            true => to_ascii(&manifest).into_owned(),
+            false => manifest,
+        };
+        let package_path = out_dir
+            .join(PACKAGES_DIR)
+            .join(format!("{}-scorm", to_tag(&self.name)))
+            .with_extension("zip");
+        package_path.parent().unwrap().create_dir_all()?;
+        write_package(
+            &package_path,
+            out_dir,
+            &book_dir,
+            &manifest,
+            &scos,
+            &book_files,
+            opts.ascii,
+        )?;
+
+        Ok(package_path)
+    }
+
+    fn scorm_manifest(&self, organization: &str, scos: &[Sco], book_files: &[String]) -> String {
+        let mut resources = String::new();
+        for sco in scos {
+            writeln!(
+                resources,
+                r#"    <resource identifier="{}" type="webcontent" adlcp:scormtype="sco" href="{}">"#,
+                sco.identifier,
+                xml_escape(&sco.href)
+            )
+            .unwrap();
+            for file in sco.files.iter() {
+                writeln!(resources, r#"      <file href="{}"/>"#, xml_escape(file)).unwrap();
+            }
+            if sco.href.starts_with("book/") {
+                resources.push_str("      <dependency identifierref=\"BOOK\"/>\n");
+            }
+            resources.push_str("    </resource>\n");
+        }
+        resources.push_str(
+            "    <resource identifier=\"BOOK\" type=\"webcontent\" adlcp:scormtype=\"asset\">\n",
+        );
+        for file in book_files {
+            writeln!(resources, r#"      <file href="{}"/>"#, xml_escape(file)).unwrap();
+        }
+        resources.push_str("    </resource>\n");
+
+        let identifier = to_tag(&self.name);
+        let title = xml_escape(&self.name);
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest identifier="{identifier}" version="1.0"
+  xmlns="http://www.imsproject.org/xsd/imscp_rootv1p1p2"
+  xmlns:adlcp="http://www.adlnet.org/xsd/adlcp_rootv1p2">
+  <metadata>
+    <schema>ADL SCORM</schema>
+    <schemaversion>1.2</schemaversion>
+  </metadata>
+  <organizations default="ORG">
+    <organization identifier="ORG">
+      <title>{title}</title>
+{organization}    </organization>
+  </organizations>
+  <resources>
+{resources}  </resources>
+</manifest>
+"#
+        )
+    }
+}
+
+/// The folder mdbook wrote the HTML book to. mdbook writes into a subfolder
+/// per renderer if more than one renderer is configured.
+pub(crate) fn book_html_dir<C: Context + Default>(out_dir: &Path) -> Result<PathBuf, C> {
+    let build_dir = out_dir.join("book").join("target");
+    [build_dir.join("html"), build_dir.clone()]
+        .into_iter()
+        .find(|d| d.join("index.html").is_file())
+        .ok_or_else(|| {
+            Report::new(C::default()).attach_printable(format!(
+                "No HTML book found in {}. Build the book with mdbook first",
+                build_dir.to_string_lossy()
+            ))
+        })
+}
+
+/// Entry names of all files in `dir`, sorted and prefixed with `prefix`
+fn files_in(dir: &Path, prefix: &str) -> Result<Vec<String>, ScormError> {
+    let mut files: Vec<_> = dir
+        .get_dir_content::<ScormError>()?
+        .files
+        .into_iter()
+        .map(|f| {
+            let relative = Path::new(&f).strip_prefix(dir).unwrap();
+            format!("{prefix}/{}", entry_name(relative))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn write_package(
+    package_path: &Path,
+    out_dir: &Path,
+    book_dir: &Path,
+    manifest: &str,
+    scos: &[Sco],
+    book_files: &[String],
+    ascii: bool,
+) -> Result<(), ScormError> {
+    let attach_path = || format!("Error writing package {}", package_path.to_string_lossy());
+    let mut zip = zip::ZipWriter::new(package_path.create_file()?);
+    let options = zip::write::SimpleFileOptions::default()
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(ENTRY_MODE);
+
+    let mut entries: Vec<(&str, PathBuf)> = book_files
+        .iter()
+        .map(|f| (f.as_str(), book_dir.join(f.strip_prefix("book/").unwrap())))
+        .collect();
+    entries.extend(scos.iter().flat_map(|sco| sco.files.iter()).map(|f| {
+        (
+            f.as_str(),
+            out_dir
+                .join("slides")
+                .join("dist")
+                .join(f.strip_prefix("slides/").unwrap()),
+        )
+    }));
+
+    zip.start_file("imsmanifest.xml", options)
+        .and_then(|_| Ok(zip.write_all(manifest.as_bytes())?))
+        .into_report()
+        .attach_printable_lazy(attach_path)
+        .change_context(ScormError::default())?;
+    for (name, source) in entries {
+        let mut content = fs::read(&source)
+            .into_report()
+            .attach_printable_lazy(|| format!("Error reading {}", source.to_string_lossy()))
+            .change_context(ScormError::default())?;
+        if scos.iter().any(|sco| sco.href == name) {
+            content = with_sco_script(content);
+        }
+        if ascii && name.ends_with(".html") {
+            content = to_ascii(&String::from_utf8_lossy(&content))
+                .into_owned()
+                .into_bytes();
+        }
+        zip.start_file(name, options)
+            .and_then(|_| Ok(zip.write_all(&content)?))
+            .into_report()
+            .attach_printable_lazy(attach_path)
+            .change_context(ScormError::default())?;
+    }
+    zip.finish()
+        .into_report()
+        .attach_printable_lazy(attach_path)
+        .change_context(ScormError::default())?;
+
+    Ok(())
+}
+
+/// Add [`SCO_SCRIPT`] to the end of the head of an HTML page
+fn with_sco_script(html: Vec<u8>) -> Vec<u8> {
+    let html = String::from_utf8_lossy(&html);
+    match html.find("</head>") {
+        Some(i) => format!("{}{SCO_SCRIPT}{}", &html[..i], &html[i..]).into_bytes(),
+        None => format!("{SCO_SCRIPT}{html}").into_bytes(),
+    }
+}
+
+pub(crate) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,15 +1,29 @@
 #![allow(dead_code)]
 use std::fmt::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use error_stack::{IntoReport, Result, ResultExt};
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
 type JsonObject = serde_json::Map<String, JsonValue>;
 
 use crate::{
+    diagnostics::{locate, Errors, Location},
+    edition::Edition,
+    handout::HANDOUTS_DIR,
     io::{copy_files, PathExt, WriteExt},
+    license::LicenseConfig,
+    load::topic_front_matter,
+    markdown::{
+        local_file, notes_to_comments, rewrite_links, slide_notes, strip_comments, teacher_only,
+    },
+    numbering::{module_deck_file_name, UnitNumber},
+    progress::progress,
+    refs::{RefTarget, References},
+    template::{Scope, TemplateContext},
     to_prefixed_tag, to_tag,
+    unicode::{nfc, nfc_path, to_ascii},
 };
 
 const PACKAGE_JSON_CONTENT_STUB: &str = include_str!("../include/slides/package.json");
@@ -31,25 +45,155 @@ pub struct SlidesRenderOptions<'t, 'u, P: AsRef<Path>> {
     pub theme: &'t str,
     pub package_json: Option<P>,
     pub url_base: &'u str,
+    /// Teacher-only content is only kept in the teacher edition
+    pub edition: Edition,
+    /// Leave the speaker notes out of the decks, unless it's the teacher edition
+    pub strip_notes: bool,
+    /// Also write the speaker notes of every deck into an instructor handout in `slides/handouts`
+    pub handouts: bool,
+    /// Also add `pdf` scripts to `package.json`, which export every deck to PDF, along with
+    /// a deck per module that combines the decks of its units
+    pub pdf: bool,
+    /// Also write a deck per module that combines the decks of its units, with a divider
+    /// slide before each unit, along with `dev`, `build` and `export` scripts for it
+    pub module_decks: bool,
+    /// Replace emoji and typographic punctuation in the decks and handouts with ASCII, for
+    /// PDF pipelines that can't handle them
+    pub ascii: bool,
+}
+
+/// Slide configuration as defined in the `[slides]` table of the track TOML,
+/// to brand the decks without changing the slide templates
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlidesConfig {
+    /// Name of the Slidev theme. The `--theme` option of `generate` takes precedence over
+    /// the theme of the track, and the theme of a module over both.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Stylesheet applied to every deck, relative to the track TOML
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub css: Option<PathBuf>,
+    /// Image shown in the bottom left corner of every slide, relative to the track TOML
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<PathBuf>,
+    /// Text shown at the bottom of every slide, like the course name and license
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<String>,
+    /// Slidev transition between slides, like `slide-left` or `fade`. Templates that
+    /// set a transition in their headmatter keep it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition: Option<String>,
+    /// Slide template for the units that don't set one, relative to the TOML file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<PathBuf>,
+}
+
+impl SlidesConfig {
+    /// Whether none of the settings are set
+    pub fn is_empty(&self) -> bool {
+        let SlidesConfig {
+            theme,
+            css,
+            logo,
+            footer,
+            transition,
+            template,
+        } = self;
+        theme.is_none()
+            && css.is_none()
+            && logo.is_none()
+            && footer.is_none()
+            && transition.is_none()
+            && template.is_none()
+    }
+
+    /// Where the logo is copied to in the `slides` folder. Slidev serves the `public` folder
+    /// from the root of every deck.
+    pub fn logo_output_path(&self) -> Option<PathBuf> {
+        let name = self.logo.as_ref()?.file_name()?;
+        Some(Path::new("public").join(nfc_path(Path::new(name))))
+    }
+
+    /// The component Slidev puts on top of every slide, showing the logo and the footer
+    fn global_bottom(&self) -> Option<String> {
+        if self.logo.is_none() && self.footer.is_none() {
+            return None;
+        }
+        let mut script = String::new();
+        let mut footer = String::new();
+        if let Some(logo) = self.logo_output_path() {
+            let name = logo.file_name().unwrap().to_string_lossy();
+            // The decks are built with a base URL each, which the logo URL has to start with
+            script = format!(
+                "<script setup>\nconst logo = import.meta.env.BASE_URL + {}\n</script>\n\n",
+                serde_json::to_string(&name).unwrap()
+            );
+            footer.push_str("\n    <img :src=\"logo\" class=\"h-6\" />");
+        }
+        if let Some(text) = &self.footer {
+            write!(footer, "\n    <span v-pre>{}</span>", html_escape(text)).unwrap();
+        }
+        Some(format!(
+            "{script}<template>\n  <footer class=\"absolute bottom-2 left-4 flex items-center gap-2 text-sm opacity-60\">{footer}\n  </footer>\n</template>\n"
+        ))
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Add `transition` to the headmatter of a deck, unless it already sets one
+fn with_transition(deck: &str, transition: &str) -> String {
+    let Some(headmatter) = deck.strip_prefix("---\n") else {
+        return format!("---\ntransition: {transition}\n---\n\n{deck}");
+    };
+    let end = headmatter.find("\n---").unwrap_or(headmatter.len());
+    if headmatter[..end]
+        .lines()
+        .any(|l| l.starts_with("transition:"))
+    {
+        return deck.to_string();
+    }
+    format!("---\ntransition: {transition}\n{headmatter}")
 }
 
 #[derive(Debug)]
 pub struct SlidesPackage<'track> {
     /// Name of the package, corresponds to the name of the track
     name: &'track str,
+    /// Language of the slide content, as an IETF language tag
+    language: &'track str,
+    config: Option<&'track SlidesConfig>,
     decks: Vec<SlideDeck<'track>>,
+    /// What `#[modmod:ref(..)]` references in the content refer to
+    references: Option<&'track References>,
+    /// What templates in the content refer to
+    templates: Option<&'track TemplateContext>,
+    /// The license shown on the last slide of every deck
+    license: Option<&'track LicenseConfig>,
 }
 
 impl<'track> SlidesPackage<'track> {
+    /// Start building a slides package. Decks are added using [`SlidesPackageBuilder::deck`].
     pub fn builder(name: &'track str) -> SlidesPackageBuilder<'track> {
         SlidesPackageBuilder {
             package: SlidesPackage {
                 name,
+                language: "en",
+                config: None,
                 decks: vec![],
+                references: None,
+                templates: None,
+                license: None,
             },
         }
     }
 
+    /// Write the slide decks and Slidev `package.json` into the `slides` folder in `out_dir`
     pub fn render<P: AsRef<Path>>(
         &self,
         out_dir: impl AsRef<Path>,
@@ -57,6 +201,12 @@ impl<'track> SlidesPackage<'track> {
             theme,
             package_json,
             url_base,
+            edition,
+            strip_notes,
+            handouts,
+            pdf,
+            module_decks,
+            ascii,
         }: SlidesRenderOptions<'_, '_, P>,
     ) -> Result<(), RenderSlidesError> {
         let mut package_json: JsonObject = match package_json {
@@ -71,23 +221,82 @@ impl<'track> SlidesPackage<'track> {
 
         let output_dir = out_dir.as_ref();
         let slides_output_dir = output_dir.join("slides");
+        let text = |s: String| match ascii {
+            true => to_ascii(&s).into_owned(),
+            false => s,
+        };
         slides_output_dir.create_dir_all()?;
 
         let slide_images_dir = slides_output_dir.join("images");
         slide_images_dir.create_dir_all()?;
+        let config = self.config.cloned().unwrap_or_default();
+        if let Some(css) = &config.css {
+            css.copy(slides_output_dir.join("style.css"))?;
+        }
+        if let (Some(logo), Some(dest)) = (&config.logo, config.logo_output_path()) {
+            let dest = slides_output_dir.join(dest);
+            dest.parent().unwrap().create_dir_all()?;
+            logo.copy(dest)?;
+        }
+        if let Some(global_bottom) = config.global_bottom() {
+            slides_output_dir
+                .join("global-bottom.vue")
+                .create_file()?
+                .write_all(global_bottom)?;
+        }
+
+        let handouts_dir = slides_output_dir.join(HANDOUTS_DIR);
+        if handouts {
+            handouts_dir.create_dir_all()?;
+        }
         let url_base = url_base.trim_matches('/');
         let url_base_separator = if url_base.is_empty() { "" } else { "/" };
-
-        for deck in self.decks.iter() {
-            let deck_prefix = format!("{}_{}", deck.module_index, deck.unit_index);
-            let deck_slug = to_prefixed_tag(deck.name, &deck_prefix);
-            let deck_output = slides_output_dir.join(&deck_slug).with_extension("md");
+        let no_references = References::default();
+        let references = self.references.unwrap_or(&no_references);
+        let no_templates = TemplateContext::default();
+        let templates = self.templates.unwrap_or(&no_templates);
+        let ref_target = RefTarget::Slides { url_base };
+        // The rendered decks, grouped by module, for the combined module decks
+        let mut rendered_decks: Vec<Vec<&SlideDeck>> = vec![];
+        // Render all decks, and report the problems in their content together
+        let mut errors = Errors::default();
+
+        for (deck, done) in self.decks.iter().zip(1..) {
+            let unit_number = UnitNumber::new(deck.module_index, deck.unit_index);
+            progress(
+                "slides",
+                done,
+                self.decks.len(),
+                format!("{unit_number} {}", deck.name),
+            );
+            let deck_id = unit_number.deck_id();
+            let deck_slug = unit_number.deck_slug(deck.name);
+            let deck_output = slides_output_dir.join(unit_number.deck_file_name(deck.name));
             let mut unit_content = String::new();
             let mut unit_objectives = String::new();
             let mut unit_summary = String::new();
+            let mut unit_notes = vec![];
 
             for section in deck.sections.iter() {
-                let topic_content = section.content.read_to_string()?;
+                let topic_content = render_topic(
+                    section,
+                    &slide_images_dir,
+                    references,
+                    templates,
+                    Scope::Unit(unit_number),
+                    ref_target,
+                    edition,
+                )
+                .attach_printable_lazy(|| deck.in_unit());
+                let Some(mut topic_content) = errors.collect(topic_content) else {
+                    continue;
+                };
+                if handouts {
+                    unit_notes.extend(slide_notes(&topic_content));
+                }
+                if strip_notes && !edition.is_teacher() {
+                    topic_content = strip_comments(&topic_content);
+                }
                 let topic_content = topic_content.trim();
 
                 if !topic_content.is_empty() {
@@ -121,19 +330,30 @@ impl<'track> SlidesPackage<'track> {
                     .unwrap();
 
                 package_scripts.insert(
-                    format!("dev-{deck_prefix}"),
+                    format!("dev-{deck_id}"),
                     format!("slidev {deck_output_str}").into(),
                 );
 
                 package_scripts.insert(
-                    format!("build-{deck_prefix}"),
-                    format!("slidev build --download --out dist/{deck_slug} --base /{url_base}{url_base_separator}slides/{}_{}/ {deck_output_str}", deck.module_index, deck.unit_index)
+                    format!("build-{deck_id}"),
+                    format!("slidev build --download --out dist/{deck_slug} --base /{url_base}{url_base_separator}slides/{deck_id}/ {deck_output_str}")
                         .into(),
                 );
                 package_scripts.insert(
-                    format!("export-{deck_prefix}"),
+                    format!("export-{deck_id}"),
                     format!("slidev export {deck_output_str}").into(),
                 );
+                if pdf {
+                    package_scripts.insert(
+                        format!("pdf-{deck_id}"),
+                        format!("slidev export --output pdf/{deck_slug}.pdf {deck_output_str}")
+                            .into(),
+                    );
+                }
+            }
+            match rendered_decks.last_mut() {
+                Some(decks) if decks[0].module_index == deck.module_index => decks.push(deck),
+                _ => rendered_decks.push(vec![deck]),
             }
 
             for section in deck.sections.iter() {
@@ -144,6 +364,28 @@ impl<'track> SlidesPackage<'track> {
                 .template
                 .map(|t| t.read_to_string())
                 .unwrap_or(Ok(SLIDES_TEMPLATE_DEFAULT.to_string()))?;
+            // Templates see what the tokens are replaced with as the `deck` variable
+            let JsonValue::Object(variables) = serde_json::json!({
+                "deck": {
+                    "content": unit_content,
+                    "objectives": unit_objectives,
+                    "summary": unit_summary,
+                    "theme": deck.theme(theme),
+                    "lang": self.language,
+                }
+            }) else {
+                unreachable!()
+            };
+            let rendered =
+                templates.render_with(&template_content, Scope::Unit(unit_number), variables);
+            let rendered = match deck.template {
+                Some(path) => locate(rendered, path, &template_content),
+                None => rendered,
+            }
+            .attach_printable_lazy(|| deck.in_unit());
+            let Some(template_content) = errors.collect(rendered) else {
+                continue;
+            };
             let slides_content = template_content
                 .replace("#[modmod:mod_title]", deck.module_name)
                 .replace("#[modmod:mod_index]", &deck.module_index.to_string())
@@ -152,9 +394,70 @@ impl<'track> SlidesPackage<'track> {
                 .replace("#[modmod:content]", &unit_content)
                 .replace("#[modmod:objectives]", &unit_objectives)
                 .replace("#[modmod:summary]", &unit_summary)
-                .replace("#[modmod:theme]", theme);
+                .replace("#[modmod:theme]", deck.theme(theme))
+                .replace("#[modmod:lang]", self.language);
+            let mut slides_content = match deck.transition(&config) {
+                Some(transition) => with_transition(&slides_content, transition),
+                None => slides_content,
+            };
+            if let Some(license) = self.license {
+                slides_content = format!("{}\n{}", slides_content.trim_end(), license.slide());
+            }
+
+            deck_file.write_all(text(slides_content))?;
+
+            if !unit_notes.is_empty() {
+                let mut handout = format!("# {unit_number} {}: speaker notes\n", deck.name);
+                for slide in unit_notes {
+                    let title = slide.title.as_deref().unwrap_or("Untitled slide");
+                    write!(handout, "\n## {title}\n\n{}\n", slide.notes).unwrap();
+                }
+                handouts_dir
+                    .join(unit_number.deck_file_name(deck.name))
+                    .create_file()?
+                    .write_all(text(handout))?;
+            }
+        }
 
-            deck_file.write_all(slides_content)?;
+        let mut pdf_scripts = vec![];
+        for decks in rendered_decks.iter().filter(|_| pdf || module_decks) {
+            let module_index = decks[0].module_index;
+            let module_slug = to_prefixed_tag(decks[0].module_name, module_index);
+            let module_deck = module_deck_file_name(decks[0].module_name, module_index);
+            let module_deck = module_deck.to_string_lossy();
+            slides_output_dir
+                .join(&*module_deck)
+                .create_file()?
+                .write_all(text(self.module_deck(decks, theme, &config)))?;
+            if module_decks {
+                package_scripts.insert(
+                    format!("dev-{module_index}"),
+                    format!("slidev {module_deck}").into(),
+                );
+                package_scripts.insert(
+                    format!("build-{module_index}"),
+                    format!("slidev build --download --out dist/{module_slug} --base /{url_base}{url_base_separator}slides/{module_index}/ {module_deck}")
+                        .into(),
+                );
+                package_scripts.insert(
+                    format!("export-{module_index}"),
+                    format!("slidev export {module_deck}").into(),
+                );
+            }
+            if pdf {
+                package_scripts.insert(
+                    format!("pdf-{module_index}"),
+                    format!("slidev export --output pdf/{module_slug}.pdf {module_deck}").into(),
+                );
+                for deck in decks {
+                    let deck_id = UnitNumber::new(deck.module_index, deck.unit_index).deck_id();
+                    pdf_scripts.push(format!("npm run pdf-{deck_id}"));
+                }
+                pdf_scripts.push(format!("npm run pdf-{module_index}"));
+            }
+        }
+        if pdf {
+            package_scripts.insert("pdf".into(), pdf_scripts.join(" && ").into());
         }
 
         // Add underscore key, so that preceding lines can have a trailing comma
@@ -178,10 +481,75 @@ impl<'track> SlidesPackage<'track> {
         let mut package_json_file = package_json_file.create_file()?;
         package_json_file.write_all(package_json)?;
 
-        Ok(())
+        errors.finish(())
+    }
+
+    /// A deck for a module, which opens with a cover slide and imports the rendered `decks`
+    /// of its units, each after a divider slide with the unit number
+    fn module_deck(&self, decks: &[&SlideDeck], theme: &str, config: &SlidesConfig) -> String {
+        let title = format!("Module {}: {}", decks[0].module_index, decks[0].module_name);
+        let mut deck = format!(
+            "---\ntheme: {}\ntitle: {}\nlayout: cover\nhtmlAttrs:\n  lang: {}\n---\n\n# {}\n\n## {}\n",
+            decks[0].theme(theme),
+            serde_json::to_string(&title).unwrap(),
+            self.language,
+            self.name,
+            title
+        );
+        for unit in decks {
+            let unit_number = UnitNumber::new(unit.module_index, unit.unit_index);
+            write!(
+                deck,
+                "\n---\nlayout: section\n---\n\n# Unit {unit_number}\n\n## {}\n\n---\nsrc: ./{}\n---\n",
+                unit.name,
+                unit_number.deck_file_name(unit.name).display()
+            )
+            .unwrap();
+        }
+        match decks[0].transition(config) {
+            Some(transition) => with_transition(&deck, transition),
+            None => deck,
+        }
     }
 }
 
+/// Render the content of the topic of `section` for its slide deck, with the speaker notes
+/// as comments, copying the images it uses into `slide_images_dir`
+fn render_topic(
+    section: &Section,
+    slide_images_dir: &Path,
+    references: &References,
+    templates: &TemplateContext,
+    scope: Scope,
+    ref_target: RefTarget,
+    edition: Edition,
+) -> Result<String, RenderSlidesError> {
+    let original = section.content.read_to_string()?;
+    let rendered = locate(
+        templates.render(&nfc(&original), scope),
+        section.content,
+        &original,
+    )?;
+    let topic_content = topic_front_matter(&rendered).1.to_string();
+    // Slidev serves the images folder from the root of the deck
+    let topic_dir = section.content.parent().unwrap();
+    let mut assets = vec![];
+    let topic_content = rewrite_links(&topic_content, |target| {
+        let file = local_file(topic_dir, target)?;
+        let name = nfc_path(Path::new(file.file_name()?));
+        assets.push(file);
+        Some(format!("/images/{}", name.to_string_lossy()))
+    });
+    copy_files(&assets, slide_images_dir)?;
+    let topic_content = locate(
+        teacher_only(&topic_content, edition.is_teacher())
+            .and_then(|content| references.expand(&content, ref_target)),
+        section.content,
+        &original,
+    )?;
+    Ok(notes_to_comments(&topic_content))
+}
+
 #[derive(Debug)]
 pub struct SlideDeck<'track> {
     /// Name of the slide deck, corresponds to the name of the unit in the module
@@ -190,7 +558,37 @@ pub struct SlideDeck<'track> {
     module_index: usize,
     unit_index: usize,
     template: Option<&'track Path>,
+    /// Settings of the module, which take precedence over those of the package
+    config: Option<&'track SlidesConfig>,
     sections: Vec<Section<'track>>,
+    /// Where the unit is defined
+    source: Option<&'track Location>,
+}
+
+impl SlideDeck<'_> {
+    /// Where the unit of the deck is defined, to attach to errors about its content
+    fn in_unit(&self) -> String {
+        let unit_number = UnitNumber::new(self.module_index, self.unit_index);
+        let mut description = format!("in unit {unit_number} '{}'", self.name);
+        if let Some(source) = self.source {
+            description += &format!(", defined at {source}");
+        }
+        description
+    }
+
+    /// The theme of the module if it sets one, or else `theme`
+    fn theme<'s>(&'s self, theme: &'s str) -> &'s str {
+        self.config
+            .and_then(|c| c.theme.as_deref())
+            .unwrap_or(theme)
+    }
+
+    /// The transition of the module if it sets one, or else that of `package_config`
+    fn transition<'s>(&'s self, package_config: &'s SlidesConfig) -> Option<&'s String> {
+        self.config
+            .and_then(|c| c.transition.as_ref())
+            .or(package_config.transition.as_ref())
+    }
 }
 
 #[derive(Debug)]
@@ -202,11 +600,45 @@ pub struct Section<'track> {
     images: Vec<&'track Path>,
 }
 
+/// Builds a [`SlidesPackage`]. See [`SlidesPackage::builder`].
 pub struct SlidesPackageBuilder<'track> {
     package: SlidesPackage<'track>,
 }
 
 impl<'track> SlidesPackageBuilder<'track> {
+    /// Set the language of the slide content. Defaults to `en`.
+    pub fn language(&mut self, language: &'track str) -> &mut Self {
+        self.package.language = language;
+        self
+    }
+
+    /// Set the branding of the decks. See [`SlidesConfig`].
+    pub fn config(&mut self, config: &'track SlidesConfig) -> &mut Self {
+        self.package.config = Some(config);
+        self
+    }
+
+    /// Set what `#[modmod:ref(..)]` references in the content refer to. Without these, every
+    /// reference is an error.
+    pub fn references(&mut self, references: &'track References) -> &mut Self {
+        self.package.references = Some(references);
+        self
+    }
+
+    /// Set what templates in the content refer to. Without these, the variables of
+    /// templates are empty.
+    pub fn templates(&mut self, templates: &'track TemplateContext) -> &mut Self {
+        self.package.templates = Some(templates);
+        self
+    }
+
+    /// Set the license, which is shown on a slide at the end of every deck
+    pub fn license(&mut self, license: &'track LicenseConfig) -> &mut Self {
+        self.package.license = Some(license);
+        self
+    }
+
+    /// Start a new slide deck for a unit. Call [`SlideDeckBuilder::add`] to add it to the package.
     pub fn deck(
         &mut self,
         name: &'track str,
@@ -223,22 +655,48 @@ impl<'track> SlidesPackageBuilder<'track> {
                 module_index,
                 unit_index,
                 template,
+                config: None,
                 sections: vec![],
+                source: None,
             },
         }
     }
 
+    /// Continue the deck that was added last, to add more sections to it
+    pub(crate) fn reopen_deck(&mut self) -> SlideDeckBuilder<'track, '_> {
+        let slide_deck = self.package.decks.pop().expect("a deck was added");
+        SlideDeckBuilder {
+            package_builder: self,
+            slide_deck,
+        }
+    }
+
     pub fn build(self) -> SlidesPackage<'track> {
         self.package
     }
 }
 
+/// Builds a [`SlideDeck`]. See [`SlidesPackageBuilder::deck`].
 pub struct SlideDeckBuilder<'track, 'p> {
     package_builder: &'p mut SlidesPackageBuilder<'track>,
     slide_deck: SlideDeck<'track>,
 }
 
 impl<'track, 'p> SlideDeckBuilder<'track, 'p> {
+    /// Set the slide settings of the module, which take precedence over those of the package.
+    /// See [`SlidesPackageBuilder::config`].
+    pub fn config(&mut self, config: &'track SlidesConfig) -> &mut Self {
+        self.slide_deck.config = Some(config);
+        self
+    }
+
+    /// Set where the unit is defined, which errors about its content point to
+    pub fn source(&mut self, source: &'track Location) -> &mut Self {
+        self.slide_deck.source = Some(source);
+        self
+    }
+
+    /// Start a new section for a topic. Call [`SlidesSectionBuilder::add`] to add it to the deck.
     pub fn section(&mut self, content: &'track Path) -> SlidesSectionBuilder<'track, 'p, '_> {
         SlidesSectionBuilder {
             deck_builder: self,
@@ -252,12 +710,14 @@ impl<'track, 'p> SlideDeckBuilder<'track, 'p> {
         }
     }
 
+    /// Add the deck to the package
     pub fn add(self) -> &'p mut SlidesPackageBuilder<'track> {
         self.package_builder.package.decks.push(self.slide_deck);
         self.package_builder
     }
 }
 
+/// Builds a slide deck section. See [`SlideDeckBuilder::section`].
 pub struct SlidesSectionBuilder<'track, 'p, 'd> {
     deck_builder: &'d mut SlideDeckBuilder<'track, 'p>,
     section: Section<'track>,
@@ -280,8 +740,24 @@ impl<'track, 'p, 'd> SlidesSectionBuilder<'track, 'p, 'd> {
         self.section.images.push(image);
     }
 
+    /// Add the section to the deck
     pub fn add(self) -> &'d mut SlideDeckBuilder<'track, 'p> {
         self.deck_builder.slide_deck.sections.push(self.section);
         self.deck_builder
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_is_added_to_headmatter() {
+        assert_eq!(
+            with_transition("---\ntheme: default\n---\n\n# Intro\n", "fade"),
+            "---\ntransition: fade\ntheme: default\n---\n\n# Intro\n"
+        );
+        let deck = "---\ntransition: none\n---\n\n# Intro\n";
+        assert_eq!(with_transition(deck, "fade"), deck);
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    convert::Infallible,
+    fmt,
+    path::PathBuf,
+};
+
+use error_stack::{Result, ResultExt};
+
+use crate::{
+    check::{Curl, Finding},
+    load::Indexed,
+    numbering::section_file_name,
+    tasks::TaskRunner,
+    visit::{Position, Visitor},
+    Module, RenderFilter, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct SmokeError {}
+
+impl fmt::Display for SmokeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to smoke test published book")
+    }
+}
+
+impl error_stack::Context for SmokeError {}
+
+/// What to smoke test
+#[derive(Debug)]
+pub struct SmokeOptions {
+    /// The URL the book is published at
+    pub url: String,
+    /// The units that are expected to be published
+    pub filter: RenderFilter,
+    /// Stop following links after retrieving this many pages
+    pub max_pages: usize,
+    pub runner: TaskRunner,
+}
+
+impl Track {
+    /// Crawl the book published at the URL of `opts`, and report the units whose page is
+    /// missing, and the pages and assets, like images and stylesheets, that pages of the
+    /// book link to but can't be retrieved. Only links within the book are followed.
+    pub fn smoke_test(&self, opts: &SmokeOptions) -> Result<Vec<Finding>, SmokeError> {
+        let curl = Curl::new(&opts.runner).change_context(SmokeError::default())?;
+        let base = format!("{}/", opts.url.trim_end_matches('/'));
+
+        let mut expected = ExpectedPages {
+            base: &base,
+            filter: &opts.filter,
+            pages: vec![format!("{base}index.html")],
+        };
+        self.walk_infallible(&mut expected);
+        let expected = expected.pages;
+
+        let mut findings = vec![];
+        // The page each page or asset was first linked from, or none for expected pages
+        let mut pages: BTreeMap<String, Option<String>> =
+            expected.iter().map(|p| (p.clone(), None)).collect();
+        let mut assets: BTreeMap<String, String> = BTreeMap::new();
+        let mut queue: VecDeque<String> = expected.into();
+        let mut fetched = 0;
+        while !queue.is_empty() && fetched < opts.max_pages {
+            let batch: Vec<_> = (0..opts.runner.jobs.max(1))
+                .map_while(|_| queue.pop_front())
+                .take(opts.max_pages - fetched)
+                .collect();
+            fetched += batch.len();
+            for (page, (status, body)) in batch.iter().zip(curl.fetch(&batch)) {
+                if !(200..300).contains(&status) {
+                    let problem = match status {
+                        0 => "it could not be reached".to_string(),
+                        _ => format!("the server responded with HTTP status {status}"),
+                    };
+                    findings.push(finding(
+                        page,
+                        match &pages[page] {
+                            Some(from) => format!("Linked from {from}, but {problem}"),
+                            None => format!("Page of the book is missing, as {problem}"),
+                        },
+                    ));
+                    continue;
+                }
+                let body = String::from_utf8_lossy(&body);
+                for link in html_links(&body) {
+                    let Some(url) = resolve_link(page, link).filter(|u| u.starts_with(&base))
+                    else {
+                        continue;
+                    };
+                    if is_page(&url) {
+                        if !pages.contains_key(&url) {
+                            pages.insert(url.clone(), Some(page.clone()));
+                            queue.push_back(url);
+                        }
+                    } else {
+                        assets.entry(url).or_insert_with(|| page.clone());
+                    }
+                }
+            }
+        }
+
+        let (urls, from): (Vec<_>, Vec<_>) = assets.into_iter().unzip();
+        for ((url, from), problem) in urls.iter().zip(from).zip(curl.problems(&urls)) {
+            if let Some(problem) = problem {
+                findings.push(finding(url, format!("Linked from {from}, but {problem}")));
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// Collects the pages of the units that are expected to be published while walking over
+/// the track
+struct ExpectedPages<'p> {
+    base: &'p str,
+    filter: &'p RenderFilter,
+    pages: Vec<String>,
+}
+
+impl<'t> Visitor<'t> for ExpectedPages<'_> {
+    type Error = Infallible;
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        if self.filter.includes(module, unit) {
+            let section = section_file_name(&unit.data.name).with_extension("html");
+            self.pages
+                .push(format!("{}{}", self.base, section.to_string_lossy()));
+        }
+        Ok(())
+    }
+}
+
+fn finding(url: &str, message: String) -> Finding {
+    Finding {
+        path: PathBuf::from(url),
+        line: None,
+        message,
+    }
+}
+
+/// Whether `url` points to a page of the book rather than an asset
+fn is_page(url: &str) -> bool {
+    url.ends_with(".html") || url.ends_with('/')
+}
+
+/// The values of the `href` and `src` attributes in `html`
+fn html_links(html: &str) -> Vec<&str> {
+    let mut links = vec![];
+    for attribute in ["href=", "src="] {
+        for (i, _) in html.match_indices(attribute) {
+            // Leave out attributes like `data-src`
+            if !html[..i].ends_with(char::is_whitespace) {
+                continue;
+            }
+            let value = &html[i + attribute.len()..];
+            let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                continue;
+            };
+            if let Some(end) = value[1..].find(quote) {
+                links.push(&value[1..end + 1]);
+            }
+        }
+    }
+    links
+}
+
+/// The absolute URL that `link` on the page at `page` points to, without its query and
+/// fragment. Links that don't point to a document, like `mailto:` links, give `None`.
+fn resolve_link(page: &str, link: &str) -> Option<String> {
+    let link = link.replace("&amp;", "&");
+    let link = link.split(['#', '?']).next().unwrap_or_default();
+    if link.is_empty() {
+        return None;
+    }
+    if link.starts_with("http://") || link.starts_with("https://") {
+        return Some(link.to_string());
+    }
+    if link.contains(':') && !link.contains('/') || link.starts_with("//") {
+        return None;
+    }
+    let (scheme, rest) = page.split_once("://")?;
+    let host_end = rest.find('/').unwrap_or(rest.len());
+    let origin = format!("{scheme}://{}", &rest[..host_end]);
+    let mut segments: Vec<&str> = match link.strip_prefix('/') {
+        Some(_) => vec![],
+        None => {
+            let path = &rest[host_end..];
+            let mut segments: Vec<_> = path.split('/').filter(|s| !s.is_empty()).collect();
+            // Relative links are relative to the folder of the page
+            if !path.ends_with('/') {
+                segments.pop();
+            }
+            segments
+        }
+    };
+    for segment in link.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let trailing = if link.ends_with('/') { "/" } else { "" };
+    Some(format!("{origin}/{}{trailing}", segments.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_are_resolved_against_the_page() {
+        let html = r#"<link rel="stylesheet" href="css/chrome.css"><img data-src="x.png" src='../images/ferris.png' alt="">
+<a href="2-1-basic-syntax.html#variables">Next</a> <a href="mailto:course@example.com">Mail</a>"#;
+        let page = "https://course.example.com/rust/book/1-1-introduction.html";
+        let links: Vec<_> = html_links(html)
+            .into_iter()
+            .filter_map(|l| resolve_link(page, l))
+            .collect();
+        assert_eq!(
+            links,
+            [
+                "https://course.example.com/rust/book/css/chrome.css",
+                "https://course.example.com/rust/book/2-1-basic-syntax.html",
+                "https://course.example.com/rust/images/ferris.png",
+            ]
+        );
+        assert_eq!(
+            resolve_link(page, "/favicon.svg").as_deref(),
+            Some("https://course.example.com/favicon.svg")
+        );
+        assert_eq!(resolve_link(page, "#top"), None);
+    }
+}
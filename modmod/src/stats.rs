@@ -0,0 +1,363 @@
+use std::{
+    fmt::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use error_stack::{Result, ResultExt};
+use serde::Serialize;
+
+use crate::{
+    exercises::included_files,
+    io::PathExt,
+    load::{topic_front_matter, Indexed},
+    markdown::{fence, split_slides, strip_comments},
+    schedule::Duration,
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct StatsError {}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to collect track statistics")
+    }
+}
+
+impl error_stack::Context for StatsError {}
+
+/// The time a slide takes to present, for units without a duration
+const MINUTES_PER_SLIDE: u32 = 2;
+/// The time students take for an exercise without a duration
+const MINUTES_PER_EXERCISE: u32 = 30;
+
+/// The size of a module, or of the whole track
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Stats {
+    pub units: usize,
+    pub topics: usize,
+    pub exercises: usize,
+    pub slides: usize,
+    /// Words of prose in slides and exercise descriptions, leaving out code blocks and comments
+    pub words: usize,
+    /// Non-blank lines of the Rust sources included in the exercises
+    pub exercise_code_lines: usize,
+    /// The durations of the units and exercises, with estimates for those without one
+    pub duration: Duration,
+    /// Whether any unit or exercise lacks a duration, so that `duration` is partly estimated
+    pub estimated: bool,
+}
+
+impl Stats {
+    fn add(&mut self, other: &Stats) {
+        self.units += other.units;
+        self.topics += other.topics;
+        self.exercises += other.exercises;
+        self.slides += other.slides;
+        self.words += other.words;
+        self.exercise_code_lines += other.exercise_code_lines;
+        self.duration = self.duration + other.duration;
+        self.estimated |= other.estimated;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleStats {
+    pub index: usize,
+    pub name: String,
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrackStats {
+    pub modules: Vec<ModuleStats>,
+    pub total: Stats,
+}
+
+impl TrackStats {
+    pub fn render(&self, format: StatsFormat) -> String {
+        match format {
+            StatsFormat::Text => self.to_table(),
+            StatsFormat::Json => serde_json::to_string_pretty(self).unwrap() + "\n",
+        }
+    }
+
+    /// Render the statistics as a plain text table with a row per module and one for the
+    /// totals. Durations that are partly estimated are marked with a `~`.
+    pub fn to_table(&self) -> String {
+        let row = |name: String, stats: &Stats| {
+            let duration = match stats.estimated {
+                true => format!("~{}", stats.duration),
+                false => stats.duration.to_string(),
+            };
+            [
+                name,
+                stats.units.to_string(),
+                stats.topics.to_string(),
+                stats.exercises.to_string(),
+                stats.slides.to_string(),
+                stats.words.to_string(),
+                stats.exercise_code_lines.to_string(),
+                duration,
+            ]
+        };
+        let mut rows = vec![[
+            "Module",
+            "Units",
+            "Topics",
+            "Exercises",
+            "Slides",
+            "Words",
+            "Code lines",
+            "Duration",
+        ]
+        .map(str::to_string)];
+        for module in self.modules.iter() {
+            rows.push(row(
+                format!("{}. {}", module.index, module.name),
+                &module.stats,
+            ));
+        }
+        rows.push(row("Total".to_string(), &self.total));
+
+        let widths: Vec<_> = (0..rows[0].len())
+            .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap())
+            .collect();
+        let mut table = String::new();
+        for row in rows {
+            // Names are aligned to the left, numbers to the right
+            write!(table, "{:<1$}", row[0], widths[0]).unwrap();
+            for (cell, width) in row.iter().zip(widths.iter()).skip(1) {
+                write!(table, "  {cell:>width$}").unwrap();
+            }
+            table.push('\n');
+        }
+        table
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for StatsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(StatsFormat::Text),
+            "json" => Ok(StatsFormat::Json),
+            _ => Err(format!(
+                "unknown statistics format '{s}', expected text or json"
+            )),
+        }
+    }
+}
+
+impl Track {
+    /// Measure the size of each module of the track: its units, topics, exercises and
+    /// slides, the words of its content, the lines of its exercise code, and how long it takes
+    pub fn stats(&self) -> Result<TrackStats, StatsError> {
+        let mut sizes = ModuleSizes {
+            modules: vec![],
+            total: Stats::default(),
+            stats: Stats::default(),
+            unit_slides: 0,
+        };
+        self.walk(&mut sizes)?;
+        Ok(TrackStats {
+            modules: sizes.modules,
+            total: sizes.total,
+        })
+    }
+}
+
+/// Measures the modules while walking over the track
+struct ModuleSizes {
+    modules: Vec<ModuleStats>,
+    total: Stats,
+    /// The statistics of the module that is being visited
+    stats: Stats,
+    /// The number of slides of the unit that is being visited
+    unit_slides: usize,
+}
+
+impl<'t> Visitor<'t> for ModuleSizes {
+    type Error = StatsError;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), StatsError> {
+        self.stats = Stats::default();
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        _unit: &'t Indexed<Unit>,
+    ) -> Result<(), StatsError> {
+        self.stats.units += 1;
+        self.unit_slides = 0;
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), StatsError> {
+        self.stats.topics += 1;
+        let content = topic.data.content.read_to_string()?;
+        let slides = split_slides(topic_front_matter(&content).1);
+        self.unit_slides += slides.len();
+        self.stats.words += slides
+            .iter()
+            .map(|s| count_words(&s.content))
+            .sum::<usize>();
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), StatsError> {
+        let stats = &mut self.stats;
+        stats.exercises += 1;
+        stats.words += count_words(&exercise.data.description.read_to_string()?);
+        stats.exercise_code_lines += code_lines(
+            &included_files(&exercise.data.path, &exercise.data.includes)
+                .change_context(StatsError::default())?,
+        );
+        stats.duration = stats.duration
+            + exercise.data.duration.unwrap_or_else(|| {
+                stats.estimated = true;
+                Duration {
+                    minutes: MINUTES_PER_EXERCISE,
+                }
+            });
+        Ok(())
+    }
+
+    fn leave_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), StatsError> {
+        let stats = &mut self.stats;
+        stats.slides += self.unit_slides;
+        stats.duration = stats.duration
+            + unit.data.duration.unwrap_or_else(|| {
+                stats.estimated = true;
+                Duration {
+                    minutes: self.unit_slides as u32 * MINUTES_PER_SLIDE,
+                }
+            });
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), StatsError> {
+        let stats = std::mem::take(&mut self.stats);
+        self.total.add(&stats);
+        self.modules.push(ModuleStats {
+            index: module.index,
+            name: module.data.name.clone(),
+            stats,
+        });
+        Ok(())
+    }
+}
+
+/// The number of words of prose in Markdown `content`, leaving out code blocks and comments.
+/// Frontmatter is not recognized, so split decks with [split_slides] first.
+fn count_words(content: &str) -> usize {
+    let mut words = 0;
+    let mut open_fence: Option<&str> = None;
+    for line in strip_comments(content).lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = open_fence {
+            if trimmed.starts_with(marker) && trimmed.trim_end() == marker {
+                open_fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = fence(trimmed) {
+            open_fence = Some(marker);
+            continue;
+        }
+        // Slide separators and Markdown punctuation are no words
+        words += line
+            .split_whitespace()
+            .filter(|w| w.chars().any(char::is_alphanumeric))
+            .count();
+    }
+    words
+}
+
+/// The number of non-blank lines in the Rust sources among `files`
+fn code_lines(files: &[impl AsRef<Path>]) -> usize {
+    files
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|f| f.extension().is_some_and(|e| e == "rs"))
+        .filter_map(|f| std::fs::read_to_string(f).ok())
+        .map(|code| code.lines().filter(|l| !l.trim().is_empty()).count())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_counted_outside_code_and_comments() {
+        assert_eq!(
+            count_words("# Ownership in Rust\n\n<!--\nAsk about Copy\n-->\n\n```rust\nlet x = 5;\n```\n\n- Moves *transfer* ownership\n"),
+            6
+        );
+
+        let stats = TrackStats {
+            modules: vec![ModuleStats {
+                index: 2,
+                name: "Foundations of Rust".to_string(),
+                stats: Stats {
+                    units: 3,
+                    slides: 120,
+                    duration: Duration { minutes: 390 },
+                    estimated: true,
+                    ..Default::default()
+                },
+            }],
+            total: Stats {
+                units: 3,
+                slides: 120,
+                duration: Duration { minutes: 390 },
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            stats.to_table(),
+            "Module                  Units  Topics  Exercises  Slides  Words  Code lines  Duration\n\
+             2. Foundations of Rust      3       0          0     120      0           0    ~6h30m\n\
+             Total                       3       0          0     120      0           0     6h30m\n"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{load::Indexed, Track};
+
+/// Selects content by its tags. Untagged content is always included.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagFilter {
+    /// If not empty, only tagged content with at least one of these tags is included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Content with any of these tags is excluded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_tags: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.exclude_tags.is_empty()
+    }
+
+    /// Combine two filters, including and excluding the tags of both
+    pub fn merge(mut self, other: &TagFilter) -> Self {
+        self.tags.extend(other.tags.iter().cloned());
+        self.exclude_tags.extend(other.exclude_tags.iter().cloned());
+        self
+    }
+
+    pub fn includes(&self, tags: &[String]) -> bool {
+        if tags.iter().any(|t| self.exclude_tags.contains(t)) {
+            return false;
+        }
+        self.tags.is_empty() || tags.is_empty() || tags.iter().any(|t| self.tags.contains(t))
+    }
+}
+
+impl Track {
+    /// Remove the units, topics and exercises that are not included by `filter`,
+    /// renumbering the remaining ones so all rendered output stays consistent
+    pub fn filter_tags(&mut self, filter: &TagFilter) {
+        if filter.is_empty() {
+            return;
+        }
+        for module in self.modules.iter_mut() {
+            retain_indexed(&mut module.data.units, |u| filter.includes(&u.tags));
+            for unit in module.data.units.iter_mut() {
+                retain_indexed(&mut unit.data.topics, |t| filter.includes(&t.tags));
+                for topic in unit.data.topics.iter_mut() {
+                    retain_indexed(&mut topic.data.exercises, |e| filter.includes(&e.tags));
+                }
+            }
+        }
+    }
+}
+
+fn retain_indexed<T>(items: &mut Vec<Indexed<T>>, mut f: impl FnMut(&T) -> bool) {
+    items.retain(|i| f(&i.data));
+    items
+        .iter_mut()
+        .zip(1..)
+        .for_each(|(item, index)| item.index = index);
+}
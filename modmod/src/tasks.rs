@@ -0,0 +1,428 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use error_stack::{Report, Result};
+
+use crate::progress::progress;
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct TaskError {}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("external command failed")
+    }
+}
+
+impl error_stack::Context for TaskError {}
+
+/// The number of lines at the end of the output of a failed task that its error shows
+const ERROR_OUTPUT_LINES: usize = 20;
+
+/// Runs external commands, several at a time. The output of each command is captured
+/// rather than passed on, so that the output of commands running side by side doesn't
+/// get mixed up.
+#[derive(Debug, Clone)]
+pub struct TaskRunner {
+    /// The number of tasks to run at the same time. Defaults to the number of CPUs.
+    pub jobs: usize,
+    /// Kill tasks that run longer than this
+    pub timeout: Option<Duration>,
+    /// How many times to run a failed task again
+    pub retries: usize,
+    /// Write the output of every task into a log file in this folder
+    pub log_dir: Option<PathBuf>,
+    /// Log the progress whenever a task finishes, see [`progress`]
+    pub progress: bool,
+}
+
+impl Default for TaskRunner {
+    fn default() -> Self {
+        Self {
+            jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+            timeout: None,
+            retries: 0,
+            log_dir: None,
+            progress: false,
+        }
+    }
+}
+
+/// An external command to run with a [`TaskRunner`]
+#[derive(Debug, Clone)]
+pub struct Task {
+    /// Describes the task in progress lines and errors, and names its log file
+    pub name: String,
+    program: OsString,
+    args: Vec<OsString>,
+    dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+    stdin: Option<Vec<u8>>,
+    retry: bool,
+}
+
+impl Task {
+    pub fn new(name: impl Into<String>, program: impl AsRef<OsStr>) -> Self {
+        Self {
+            name: name.into(),
+            program: program.as_ref().to_os_string(),
+            args: vec![],
+            dir: None,
+            envs: vec![],
+            stdin: None,
+            retry: true,
+        }
+    }
+
+    /// A task that runs `command` with the shell of the platform, for commands that the
+    /// track TOML sets as a string
+    pub fn shell(name: impl Into<String>, command: &str) -> Self {
+        let (shell, flag) = match cfg!(windows) {
+            true => ("cmd", "/C"),
+            false => ("sh", "-c"),
+        };
+        Self::new(name, shell).args([flag, command])
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(mut self, args: I) -> Self {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Pass `input` to the command on its standard input
+    pub fn stdin(mut self, input: impl Into<Vec<u8>>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    /// Don't run the task again if it fails, for commands whose failure is an answer,
+    /// like rustfmt rejecting code it can't parse
+    pub fn no_retry(mut self) -> Self {
+        self.retry = false;
+        self
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args).envs(self.envs.iter().cloned());
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}", self.program.to_string_lossy())?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg.to_string_lossy())?;
+        }
+        f.write_str("`")?;
+        if let Some(dir) = &self.dir {
+            write!(f, " in {}", dir.to_string_lossy())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum TaskStatus {
+    Exited(ExitStatus),
+    TimedOut(Duration),
+    /// The command could not be started
+    Failed(String),
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskStatus::Exited(status) => write!(f, "{status}"),
+            TaskStatus::TimedOut(timeout) => write!(f, "timed out after {}s", timeout.as_secs()),
+            TaskStatus::Failed(error) => write!(f, "could not be started: {error}"),
+        }
+    }
+}
+
+/// The outcome of the last run of a task
+#[derive(Debug)]
+pub struct TaskOutput {
+    pub task: Task,
+    pub status: TaskStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// The number of times the task was run
+    pub attempts: usize,
+    /// The time taken by all attempts together
+    pub elapsed: Duration,
+    /// The log file of the task, if the runner writes logs
+    pub log: Option<PathBuf>,
+}
+
+impl TaskOutput {
+    pub fn success(&self) -> bool {
+        matches!(self.status, TaskStatus::Exited(status) if status.success())
+    }
+
+    /// Turn a failed task into an error showing the end of its output
+    pub fn into_result(self) -> Result<Self, TaskError> {
+        if self.success() {
+            return Ok(self);
+        }
+        let mut report = Report::new(TaskError::default())
+            .attach_printable(format!("{} {}", self.task, self.status));
+        for (name, output) in [("Stdout", &self.stdout), ("Stderr", &self.stderr)] {
+            let output = String::from_utf8_lossy(output);
+            let lines: Vec<_> = output.lines().collect();
+            if !lines.is_empty() {
+                let tail = &lines[lines.len().saturating_sub(ERROR_OUTPUT_LINES)..];
+                report = report.attach_printable(format!("{name}: \"{}\"", tail.join("\n")));
+            }
+        }
+        if let Some(log) = &self.log {
+            report = report.attach_printable(format!("Full output in {}", log.to_string_lossy()));
+        }
+        Err(report)
+    }
+}
+
+impl TaskRunner {
+    /// Run `tasks`, at most [`jobs`](Self::jobs) at a time, and return their outputs in the
+    /// same order. Failed tasks don't stop the others, see [`TaskOutput::into_result`].
+    pub fn run(&self, tasks: Vec<Task>) -> Vec<TaskOutput> {
+        let count = tasks.len();
+        let next = &AtomicUsize::new(0);
+        let tasks = &tasks;
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.clamp(1, count.max(1)) {
+                let sender = sender.clone();
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(task) = tasks.get(i) else {
+                        break;
+                    };
+                    if sender.send((i, self.execute(task))).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            let mut outputs: Vec<Option<TaskOutput>> = (0..count).map(|_| None).collect();
+            for ((i, output), done) in receiver.into_iter().zip(1..) {
+                if self.progress {
+                    let verdict = match output.success() {
+                        true => "ok    ",
+                        false => "failed",
+                    };
+                    progress(
+                        "tasks",
+                        done,
+                        count,
+                        format!(
+                            "{verdict} {} ({:.1}s)",
+                            output.task.name,
+                            output.elapsed.as_secs_f32()
+                        ),
+                    );
+                }
+                outputs[i] = Some(output);
+            }
+            outputs.into_iter().map(Option::unwrap).collect()
+        })
+    }
+
+    /// Run a single task on the current thread
+    pub fn run_one(&self, task: Task) -> TaskOutput {
+        self.execute(&task)
+    }
+
+    fn execute(&self, task: &Task) -> TaskOutput {
+        let started = Instant::now();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let (status, stdout, stderr) = self.attempt(task);
+            let output = TaskOutput {
+                task: task.clone(),
+                status,
+                stdout,
+                stderr,
+                attempts,
+                elapsed: started.elapsed(),
+                log: None,
+            };
+            if output.success() || !task.retry || attempts > self.retries {
+                return self.write_log(output);
+            }
+        }
+    }
+
+    /// Run `task` once, with its output going to scratch files. Unlike pipes, those don't
+    /// need to be drained while the task runs, and are not kept open by processes that
+    /// outlive a task that timed out.
+    fn attempt(&self, task: &Task) -> (TaskStatus, Vec<u8>, Vec<u8>) {
+        static SCRATCH: AtomicUsize = AtomicUsize::new(0);
+        let scratch = std::env::temp_dir().join(format!(
+            "modmod-task-{}-{}",
+            std::process::id(),
+            SCRATCH.fetch_add(1, Ordering::Relaxed)
+        ));
+        let (stdout_path, stderr_path) = (
+            scratch.with_extension("stdout"),
+            scratch.with_extension("stderr"),
+        );
+
+        let status = (|| {
+            let mut child = task
+                .command()
+                .stdin(match task.stdin {
+                    Some(_) => Stdio::piped(),
+                    None => Stdio::null(),
+                })
+                .stdout(File::create(&stdout_path)?)
+                .stderr(File::create(&stderr_path)?)
+                .spawn()?;
+            if let Some(input) = &task.stdin {
+                // The output goes to files, so the command can't block on writing it
+                // while we write its input
+                child.stdin.take().unwrap().write_all(input)?;
+            }
+            let started = Instant::now();
+            let mut poll = Duration::from_millis(1);
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(TaskStatus::Exited(status));
+                }
+                if let Some(timeout) = self.timeout.filter(|t| started.elapsed() > *t) {
+                    child.kill()?;
+                    child.wait()?;
+                    return Ok(TaskStatus::TimedOut(timeout));
+                }
+                thread::sleep(poll);
+                poll = (poll * 2).min(Duration::from_millis(100));
+            }
+        })()
+        .unwrap_or_else(|e: std::io::Error| TaskStatus::Failed(e.to_string()));
+
+        let read = |path: &Path| {
+            let content = std::fs::read(path).unwrap_or_default();
+            let _ = std::fs::remove_file(path);
+            content
+        };
+        (status, read(&stdout_path), read(&stderr_path))
+    }
+
+    fn write_log(&self, mut output: TaskOutput) -> TaskOutput {
+        let Some(log_dir) = &self.log_dir else {
+            return output;
+        };
+        let file_name: String = output
+            .task
+            .name
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() || c == '_' {
+                true => c,
+                false => '-',
+            })
+            .collect();
+        let log = log_dir.join(file_name).with_extension("log");
+        let content = format!(
+            "{}\n{}, after {} attempt(s) in {:.1}s\n\n--- stdout ---\n{}\n--- stderr ---\n{}",
+            output.task,
+            output.status,
+            output.attempts,
+            output.elapsed.as_secs_f32(),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        // A log that can't be written is no reason to fail the task
+        if std::fs::create_dir_all(log_dir)
+            .and_then(|_| std::fs::write(&log, content))
+            .is_ok()
+        {
+            output.log = Some(log);
+        }
+        output
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_run_in_parallel_with_timeouts_and_retries() {
+        let runner = TaskRunner {
+            jobs: 4,
+            timeout: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let started = Instant::now();
+        let outputs = runner.run(
+            (0..4)
+                .map(|i| {
+                    Task::new(format!("sleep {i}"), "sh")
+                        .args(["-c", &format!("sleep 0.2; echo {i}")])
+                })
+                .collect(),
+        );
+        assert!(started.elapsed() < Duration::from_millis(700));
+        let mut printed: Vec<_> = outputs
+            .iter()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .collect();
+        printed.sort();
+        assert_eq!(printed, ["0", "1", "2", "3"]);
+
+        let output = runner.run_one(Task::new("hang", "sleep").arg("5"));
+        assert!(matches!(output.status, TaskStatus::TimedOut(_)));
+        assert!(output.into_result().is_err());
+
+        let output = runner.run_one(Task::new("cat", "cat").stdin("hello"));
+        assert_eq!(output.into_result().unwrap().stdout, b"hello");
+
+        let runner = TaskRunner {
+            retries: 2,
+            ..Default::default()
+        };
+        assert_eq!(runner.run_one(Task::new("false", "false")).attempts, 3);
+        assert_eq!(
+            runner
+                .run_one(Task::new("false", "false").no_retry())
+                .attempts,
+            1
+        );
+    }
+}
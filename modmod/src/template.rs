@@ -0,0 +1,954 @@
+//! Templates in content files, for what the `#[modmod:..]` tokens can't do, like showing
+//! a paragraph only in the teacher edition or listing the exercises of a unit. Files opt in
+//! with `template = true` in their front matter, as the `{{ }}` of the template syntax is
+//! common in Slidev decks and Rust code. The tokens keep working in every file, as they are
+//! expanded after templates are rendered.
+//!
+//! The syntax is a subset of that of Jinja: `{{ unit.title }}` outputs a value,
+//! `{% if .. %}`, `{% elif .. %}`, `{% else %}` and `{% endif %}` choose what to render,
+//! `{% for exercise in unit.exercises %}` and `{% endfor %}` repeat what's between them,
+//! `{# .. #}` is a comment and `{% raw %}` and `{% endraw %}` leave what's between them
+//! alone. Statements and comments on a line of their own don't leave an empty line.
+
+use std::{convert::Infallible, fmt, path::PathBuf};
+
+use error_stack::{Context, Report, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    diagnostics::Snippet,
+    edition::Edition,
+    load::Indexed,
+    markdown::front_matter,
+    numbering::{
+        exercise_dir, module_exercises_dir, unit_exercises_dir, ExerciseNumber, UnitNumber,
+    },
+    visit::{Position, Visitor},
+    Exercise, Module, RenderFilter, Topic, Track, Unit,
+};
+
+/// The track model that templates refer to: `track`, the `module`, `unit` and `exercise`
+/// a file belongs to, which are `null` where it doesn't belong to one, and `edition`
+#[derive(Debug, Default)]
+pub struct TemplateContext {
+    track: Value,
+    edition: String,
+}
+
+/// What a content file belongs to, which decides the `module`, `unit` and `exercise` its
+/// template refers to
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    Track,
+    Module(usize),
+    Unit(UnitNumber),
+    Exercise(ExerciseNumber),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TemplateFrontMatter {
+    #[serde(default)]
+    template: bool,
+}
+
+/// Builds the model of the modules, units and exercises while walking over the track
+struct TrackModel<'f> {
+    filter: &'f RenderFilter,
+    /// Where the exercise packages of the unit that is being visited are rendered
+    unit_dir: PathBuf,
+    /// The exercises of the unit and the units of the module that are being visited
+    exercises: Vec<Value>,
+    units: Vec<Value>,
+    modules: Vec<Value>,
+}
+
+impl<'t> Visitor<'t> for TrackModel<'_> {
+    type Error = Infallible;
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.unit_dir = module_exercises_dir(&module.data.name, module.index)
+            .join(unit_exercises_dir(&unit.data.name, unit.index));
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        let number = position.exercise_number().unwrap();
+        let dir = self
+            .unit_dir
+            .join(exercise_dir(&exercise.data.name, number.exercise));
+        self.exercises.push(json!({
+            "number": number.to_string(),
+            "index": number.exercise,
+            "name": exercise.data.name,
+            "dir": dir.to_string_lossy(),
+            "tags": exercise.data.tags,
+            "difficulty": exercise.data.difficulty,
+        }));
+        Ok(())
+    }
+
+    fn leave_unit(
+        &mut self,
+        position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.units.push(json!({
+            "number": position.to_string(),
+            "index": unit.index,
+            "title": unit.data.name,
+            "id": unit.data.id,
+            "tags": unit.data.tags,
+            "rendered": self.filter.includes(module, unit),
+            "duration": unit.data.duration.map(|d| d.to_string()),
+            "topics": unit.data.topics.iter().map(|t| &t.data.name).collect::<Vec<_>>(),
+            "exercises": std::mem::take(&mut self.exercises),
+        }));
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.modules.push(json!({
+            "index": module.index,
+            "title": module.data.name,
+            "description": module.data.description,
+            "appendix": module.data.appendix,
+            "units": std::mem::take(&mut self.units),
+        }));
+        Ok(())
+    }
+}
+
+impl TemplateContext {
+    /// The model of `track` as rendered with `filter` into `edition`
+    pub fn of_track(track: &Track, filter: &RenderFilter, edition: Edition) -> Self {
+        let mut model = TrackModel {
+            filter,
+            unit_dir: PathBuf::new(),
+            exercises: vec![],
+            units: vec![],
+            modules: vec![],
+        };
+        track.walk_infallible(&mut model);
+        Self {
+            track: json!({
+                "name": track.name,
+                "language": track.language,
+                "modules": model.modules,
+            }),
+            edition: edition.to_string(),
+        }
+    }
+
+    /// The variables of a template in `scope`
+    fn variables(&self, scope: Scope) -> Map<String, Value> {
+        let module_index = match scope {
+            Scope::Track => None,
+            Scope::Module(module) => Some(module),
+            Scope::Unit(UnitNumber { module, .. })
+            | Scope::Exercise(ExerciseNumber { module, .. }) => Some(module),
+        };
+        let module = module_index.and_then(|index| {
+            self.track["modules"]
+                .as_array()?
+                .iter()
+                .find(|m| m["index"] == index)
+        });
+        let unit = match scope {
+            Scope::Unit(UnitNumber { unit, .. }) | Scope::Exercise(ExerciseNumber { unit, .. }) => {
+                module.and_then(|m| m["units"].as_array()?.iter().find(|u| u["index"] == unit))
+            }
+            _ => None,
+        };
+        let exercise = match scope {
+            Scope::Exercise(number) => unit.and_then(|u| u["exercises"].get(number.exercise - 1)),
+            _ => None,
+        };
+        let mut variables = Map::new();
+        variables.insert("track".to_string(), self.track.clone());
+        for (name, value) in [("module", module), ("unit", unit), ("exercise", exercise)] {
+            variables.insert(name.to_string(), value.cloned().unwrap_or(Value::Null));
+        }
+        variables.insert("edition".to_string(), Value::from(self.edition.as_str()));
+        variables
+    }
+
+    /// Render `content` as a template in `scope` if its front matter sets `template = true`,
+    /// leaving out the front matter. Other content is returned as it is.
+    pub fn render<C: Context + Default>(&self, content: &str, scope: Scope) -> Result<String, C> {
+        self.render_with(content, scope, Map::new())
+    }
+
+    /// Like [`TemplateContext::render`], with `extra` variables on top of those of `scope`
+    pub fn render_with<C: Context + Default>(
+        &self,
+        content: &str,
+        scope: Scope,
+        extra: Map<String, Value>,
+    ) -> Result<String, C> {
+        let Some(body) = templated(content) else {
+            return Ok(content.to_string());
+        };
+        let mut variables = self.variables(scope);
+        variables.extend(extra);
+        render(body, &variables).map_err(|error| {
+            Report::new(C::default())
+                .attach_printable(error.message)
+                .attach_printable(Snippet(error.tag))
+        })
+    }
+}
+
+/// The content of a file that opts into templating, without its front matter
+fn templated(content: &str) -> Option<&str> {
+    let (Some(front_matter), body) = front_matter(content) else {
+        return None;
+    };
+    let front_matter: TemplateFrontMatter = toml::from_str(front_matter).ok()?;
+    front_matter.template.then_some(body)
+}
+
+/// An error in a template, along with the tag it's about
+#[derive(Debug)]
+struct TemplateError {
+    message: String,
+    tag: String,
+}
+
+impl TemplateError {
+    fn new(message: impl fmt::Display, tag: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            tag: tag.to_string(),
+        }
+    }
+}
+
+/// Render `template` with `variables`
+fn render(
+    template: &str,
+    variables: &Map<String, Value>,
+) -> std::result::Result<String, TemplateError> {
+    let mut tokens = lex(template)?.into_iter();
+    let (nodes, end) = parse(&mut tokens, &[])?;
+    debug_assert!(end.is_none());
+    let mut output = String::with_capacity(template.len());
+    let mut env = Env {
+        variables,
+        locals: vec![],
+    };
+    write_nodes(&nodes, &mut env, &mut output)?;
+    Ok(output)
+}
+
+#[derive(Debug)]
+enum Token<'t> {
+    Text(&'t str),
+    /// `{{ .. }}`, with what's between the braces and the whole tag
+    Output(&'t str, &'t str),
+    /// `{% .. %}`
+    Statement(&'t str, &'t str),
+}
+
+/// Split `template` into text and tags. Statements and comments on a line of their own are
+/// taken out along with the line.
+fn lex(template: &str) -> std::result::Result<Vec<Token<'_>>, TemplateError> {
+    let mut tokens = vec![];
+    let mut rest = template;
+    // Whether `rest` starts a line
+    let mut at_line_start = true;
+    while let Some(start) = ["{{", "{%", "{#"]
+        .iter()
+        .filter_map(|open| rest.find(open))
+        .min()
+    {
+        let (text, tag_start) = rest.split_at(start);
+        let close = match &tag_start[..2] {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let Some(end) = tag_start.find(close) else {
+            let tag = tag_start.lines().next().unwrap();
+            return Err(TemplateError::new("Unterminated template tag", tag));
+        };
+        let tag = &tag_start[..end + close.len()];
+        let inner = tag[2..tag.len() - 2].trim();
+        let mut after = &tag_start[tag.len()..];
+
+        let mut text = text;
+        let mut standalone = false;
+        if close != "}}" {
+            // Take out the line of statements that are on a line of their own
+            let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+            let before = &text[line_start..];
+            let line_end = after.find('\n').map_or(after.len(), |i| i + 1);
+            standalone = (line_start > 0 || at_line_start)
+                && before.trim().is_empty()
+                && after[..line_end].trim().is_empty();
+            if standalone {
+                text = &text[..line_start];
+                after = &after[line_end..];
+            }
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        match close {
+            "}}" => tokens.push(Token::Output(inner, tag)),
+            "%}" if inner == "raw" => {
+                let Some(raw_end) = after.find("{% endraw %}") else {
+                    return Err(TemplateError::new("{% raw %} without {% endraw %}", tag));
+                };
+                tokens.push(Token::Text(&after[..raw_end]));
+                after = &after[raw_end + "{% endraw %}".len()..];
+            }
+            "%}" => tokens.push(Token::Statement(inner, tag)),
+            _ => {}
+        }
+        at_line_start = standalone;
+        rest = after;
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Node<'t> {
+    Text(&'t str),
+    Output(Expr, &'t str),
+    If {
+        branches: Vec<(Expr, Vec<Node<'t>>, &'t str)>,
+        otherwise: Vec<Node<'t>>,
+    },
+    For {
+        variable: String,
+        iterable: Expr,
+        body: Vec<Node<'t>>,
+        tag: &'t str,
+    },
+}
+
+/// A statement, along with its tag
+type Statement<'t> = (&'t str, &'t str);
+
+/// Parse `tokens` up to one of the statements in `end`, returning the nodes and the
+/// statement they end with, along with its tag
+fn parse<'t>(
+    tokens: &mut std::vec::IntoIter<Token<'t>>,
+    end: &[&str],
+) -> std::result::Result<(Vec<Node<'t>>, Option<Statement<'t>>), TemplateError> {
+    let mut nodes = vec![];
+    while let Some(token) = tokens.next() {
+        let (statement, tag) = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Output(expr, tag) => {
+                nodes.push(Node::Output(parse_expr(expr, tag)?, tag));
+                continue;
+            }
+            Token::Statement(statement, tag) => (statement, tag),
+        };
+        let keyword = statement.split_whitespace().next().unwrap_or_default();
+        if end.contains(&keyword) {
+            return Ok((nodes, Some((statement, tag))));
+        }
+        match keyword {
+            "if" => {
+                let mut branches = vec![];
+                let mut condition = (statement["if".len()..].trim(), tag);
+                let otherwise = loop {
+                    let expr = parse_expr(condition.0, condition.1)?;
+                    let (body, end) = parse(tokens, &["elif", "else", "endif"])?;
+                    branches.push((expr, body, condition.1));
+                    match end {
+                        Some((statement, tag)) if statement.starts_with("elif") => {
+                            condition = (statement["elif".len()..].trim(), tag);
+                        }
+                        Some(("else", else_tag)) => match parse(tokens, &["endif"])? {
+                            (otherwise, Some(_)) => break otherwise,
+                            (_, None) => {
+                                return Err(TemplateError::new(
+                                    "{% else %} without {% endif %}",
+                                    else_tag,
+                                ))
+                            }
+                        },
+                        Some(("endif", _)) => break vec![],
+                        Some((_, tag)) => return Err(TemplateError::new("Invalid statement", tag)),
+                        None => {
+                            return Err(TemplateError::new("{% if %} without {% endif %}", tag))
+                        }
+                    }
+                };
+                nodes.push(Node::If {
+                    branches,
+                    otherwise,
+                });
+            }
+            "for" => {
+                let invalid = || {
+                    TemplateError::new(
+                        "Invalid loop, expected one like {% for exercise in unit.exercises %}",
+                        tag,
+                    )
+                };
+                let (variable, iterable) = statement["for".len()..]
+                    .trim()
+                    .split_once(" in ")
+                    .ok_or_else(invalid)?;
+                let variable = variable.trim();
+                if variable.is_empty() || !variable.chars().all(is_name_char) {
+                    return Err(invalid());
+                }
+                let iterable = parse_expr(iterable, tag)?;
+                let (body, end) = parse(tokens, &["endfor"])?;
+                if end.is_none() {
+                    return Err(TemplateError::new("{% for %} without {% endfor %}", tag));
+                }
+                nodes.push(Node::For {
+                    variable: variable.to_string(),
+                    iterable,
+                    body,
+                    tag,
+                });
+            }
+            _ => {
+                return Err(TemplateError::new(
+                    format!("Unexpected statement '{statement}'"),
+                    tag,
+                ))
+            }
+        }
+    }
+    Ok((nodes, None))
+}
+
+/// The variables of a template, along with the loop variables in scope
+struct Env<'v> {
+    variables: &'v Map<String, Value>,
+    locals: Vec<(String, Value)>,
+}
+
+impl Env<'_> {
+    fn lookup(&self, path: &[String]) -> Option<&Value> {
+        let (first, rest) = path.split_first()?;
+        let mut value = self
+            .locals
+            .iter()
+            .rev()
+            .find(|(name, _)| name == first)
+            .map(|(_, value)| value)
+            .or_else(|| self.variables.get(first))?;
+        for key in rest {
+            value = match value {
+                Value::Object(map) => map.get(key)?,
+                Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+fn write_nodes(
+    nodes: &[Node],
+    env: &mut Env,
+    output: &mut String,
+) -> std::result::Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Output(expr, tag) => match expr.eval(env, tag)? {
+                Value::String(s) => output.push_str(&s),
+                Value::Null => {}
+                Value::Array(_) | Value::Object(_) => {
+                    return Err(TemplateError::new(
+                        "Unable to output a list or table, loop over it with {% for %} instead",
+                        tag,
+                    ))
+                }
+                value => output.push_str(&value.to_string()),
+            },
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                let mut chosen = otherwise;
+                for (condition, body, tag) in branches {
+                    if truthy(&condition.eval(env, tag)?) {
+                        chosen = body;
+                        break;
+                    }
+                }
+                write_nodes(chosen, env, output)?;
+            }
+            Node::For {
+                variable,
+                iterable,
+                body,
+                tag,
+            } => {
+                let items = match iterable.eval(env, tag)? {
+                    Value::Array(items) => items,
+                    Value::Null => vec![],
+                    _ => {
+                        return Err(TemplateError::new(
+                            "Unable to loop over a value that is not a list",
+                            tag,
+                        ))
+                    }
+                };
+                let length = items.len();
+                for (item, index) in items.into_iter().zip(1..) {
+                    let info = json!({
+                        "index": index,
+                        "first": index == 1,
+                        "last": index == length,
+                    });
+                    env.locals.push(("loop".to_string(), info));
+                    env.locals.push((variable.clone(), item));
+                    let written = write_nodes(body, env, output);
+                    env.locals.truncate(env.locals.len() - 2);
+                    written?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `value` counts as true in a condition: everything but `false`, `null`, zero and
+/// empty strings, lists and tables
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    /// A variable, like `unit.exercises`
+    Path(Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Equals(Box<Expr>, Box<Expr>),
+    /// Whether the list, string or table on the right contains the value on the left
+    In(Box<Expr>, Box<Expr>),
+    /// A value passed through a filter, like `unit.exercises | length`
+    Filter(Box<Expr>, String, Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, env: &Env, tag: &str) -> std::result::Result<Value, TemplateError> {
+        Ok(match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Path(path) => env.lookup(path).cloned().ok_or_else(|| {
+                TemplateError::new(format!("Unknown variable '{}'", path.join(".")), tag)
+            })?,
+            Expr::Not(expr) => Value::Bool(!truthy(&expr.eval(env, tag)?)),
+            Expr::And(a, b) => {
+                Value::Bool(truthy(&a.eval(env, tag)?) && truthy(&b.eval(env, tag)?))
+            }
+            Expr::Or(a, b) => Value::Bool(truthy(&a.eval(env, tag)?) || truthy(&b.eval(env, tag)?)),
+            Expr::Equals(a, b) => Value::Bool(a.eval(env, tag)? == b.eval(env, tag)?),
+            Expr::In(item, container) => {
+                let item = item.eval(env, tag)?;
+                Value::Bool(match (container.eval(env, tag)?, &item) {
+                    (Value::Array(items), _) => items.contains(&item),
+                    (Value::String(s), Value::String(needle)) => s.contains(needle.as_str()),
+                    (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                    (Value::Null, _) => false,
+                    _ => {
+                        return Err(TemplateError::new(
+                            "'in' needs a list, string or table on its right",
+                            tag,
+                        ))
+                    }
+                })
+            }
+            Expr::Filter(expr, filter, args) => {
+                let value = expr.eval(env, tag)?;
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(env, tag))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                apply_filter(value, filter, &args)
+                    .map_err(|message| TemplateError::new(message, tag))?
+            }
+        })
+    }
+}
+
+/// Apply the filter named `filter` to `value`
+fn apply_filter(value: Value, filter: &str, args: &[Value]) -> std::result::Result<Value, String> {
+    let to_text = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    };
+    Ok(match (filter, args) {
+        ("length", []) => Value::from(match &value {
+            Value::Array(items) => items.len(),
+            Value::Object(map) => map.len(),
+            Value::String(s) => s.chars().count(),
+            Value::Null => 0,
+            _ => return Err("'length' needs a list, string or table".to_string()),
+        }),
+        ("upper", []) => Value::String(to_text(&value).to_uppercase()),
+        ("lower", []) => Value::String(to_text(&value).to_lowercase()),
+        ("join", [separator]) => match value {
+            Value::Array(items) => Value::String(
+                items
+                    .iter()
+                    .map(to_text)
+                    .collect::<Vec<_>>()
+                    .join(&to_text(separator)),
+            ),
+            _ => return Err("'join' needs a list".to_string()),
+        },
+        ("default", [default]) => match truthy(&value) {
+            true => value,
+            false => default.clone(),
+        },
+        ("length" | "upper" | "lower" | "join" | "default", _) => {
+            return Err(format!("Wrong number of arguments to filter '{filter}'"))
+        }
+        _ => {
+            return Err(format!(
+                "Unknown filter '{filter}', expected length, upper, lower, join or default"
+            ))
+        }
+    })
+}
+
+/// Parse the expression `source` of `tag`
+fn parse_expr(source: &str, tag: &str) -> std::result::Result<Expr, TemplateError> {
+    let tokens = lex_expr(source).map_err(|message| TemplateError::new(message, tag))?;
+    let mut parser = ExprParser { tokens, pos: 0 };
+    let expr = parser
+        .or()
+        .and_then(|expr| match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected '{token}' in expression")),
+        })
+        .map_err(|message| TemplateError::new(message, tag))?;
+    Ok(expr)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExprToken {
+    Name(String),
+    Literal(Value),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for ExprToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprToken::Name(name) => f.write_str(name),
+            ExprToken::Literal(value) => write!(f, "{value}"),
+            ExprToken::Symbol(symbol) => f.write_str(symbol),
+        }
+    }
+}
+
+fn lex_expr(source: &str) -> std::result::Result<Vec<ExprToken>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut literal = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => literal.extend(chars.next().map(|(_, c)| c)),
+                    Some((_, end)) if end == c => break,
+                    Some((_, c)) => literal.push(c),
+                    None => return Err("Unterminated string in expression".to_string()),
+                }
+            }
+            tokens.push(ExprToken::Literal(Value::String(literal)));
+        } else if c.is_ascii_digit() {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !c.is_ascii_digit() && c != '.' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number: Value = serde_json::from_str(&source[start..end])
+                .map_err(|_| format!("Invalid number '{}'", &source[start..end]))?;
+            tokens.push(ExprToken::Literal(number));
+        } else if is_name_char(c) {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !is_name_char(c) && c != '.' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(match &source[start..end] {
+                "true" => ExprToken::Literal(Value::Bool(true)),
+                "false" => ExprToken::Literal(Value::Bool(false)),
+                "null" => ExprToken::Literal(Value::Null),
+                name => ExprToken::Name(name.to_string()),
+            });
+        } else {
+            let symbol = ["==", "!=", "(", ")", "|", ","]
+                .into_iter()
+                .find(|s| source[start..].starts_with(s))
+                .ok_or_else(|| format!("Unexpected '{c}' in expression"))?;
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push(ExprToken::Symbol(symbol));
+        }
+    }
+    Ok(tokens)
+}
+
+struct ExprParser {
+    tokens: Vec<ExprToken>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&ExprToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<ExprToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        let matched = matches!(self.peek(), Some(ExprToken::Name(n)) if n == name);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let matched = matches!(self.peek(), Some(ExprToken::Symbol(s)) if *s == symbol);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat_name("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.eat_name("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> std::result::Result<Expr, String> {
+        if self.eat_name("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> std::result::Result<Expr, String> {
+        let left = self.filtered()?;
+        if self.eat_symbol("==") {
+            return Ok(Expr::Equals(Box::new(left), Box::new(self.filtered()?)));
+        }
+        if self.eat_symbol("!=") {
+            let right = self.filtered()?;
+            return Ok(Expr::Not(Box::new(Expr::Equals(
+                Box::new(left),
+                Box::new(right),
+            ))));
+        }
+        if self.eat_name("in") {
+            return Ok(Expr::In(Box::new(left), Box::new(self.filtered()?)));
+        }
+        if self.eat_name("not") {
+            if !self.eat_name("in") {
+                return Err("Expected 'in' after 'not'".to_string());
+            }
+            let right = self.filtered()?;
+            return Ok(Expr::Not(Box::new(Expr::In(
+                Box::new(left),
+                Box::new(right),
+            ))));
+        }
+        Ok(left)
+    }
+
+    fn filtered(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.eat_symbol("|") {
+            let Some(ExprToken::Name(filter)) = self.next() else {
+                return Err("Expected the name of a filter after '|'".to_string());
+            };
+            let mut args = vec![];
+            if self.eat_symbol("(") && !self.eat_symbol(")") {
+                loop {
+                    args.push(self.or()?);
+                    if self.eat_symbol(")") {
+                        break;
+                    }
+                    if !self.eat_symbol(",") {
+                        return Err("Expected ',' or ')' in filter arguments".to_string());
+                    }
+                }
+            }
+            expr = Expr::Filter(Box::new(expr), filter, args);
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> std::result::Result<Expr, String> {
+        match self.next() {
+            Some(ExprToken::Literal(value)) => Ok(Expr::Literal(value)),
+            Some(ExprToken::Name(name)) => {
+                Ok(Expr::Path(name.split('.').map(String::from).collect()))
+            }
+            Some(ExprToken::Symbol("(")) => {
+                let expr = self.or()?;
+                match self.eat_symbol(")") {
+                    true => Ok(expr),
+                    false => Err("Expected ')' in expression".to_string()),
+                }
+            }
+            Some(token) => Err(format!("Unexpected '{token}' in expression")),
+            None => Err("Expected an expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Map<String, Value> {
+        let Value::Object(variables) = json!({
+            "edition": "teacher",
+            "unit": {
+                "number": "2.1",
+                "title": "Basic syntax",
+                "tags": ["foundations"],
+                "exercises": [
+                    { "number": "2.1.1", "name": "Numbers" },
+                    { "number": "2.1.2", "name": "Strings" },
+                ],
+            },
+        }) else {
+            unreachable!()
+        };
+        variables
+    }
+
+    #[test]
+    fn templates_render_conditions_and_loops() {
+        let template = "# {{ unit.number }} {{ unit.title | upper }}\n\
+            {% if edition == \"teacher\" and \"foundations\" in unit.tags %}\n\
+            Teacher notes\n\
+            {% else %}\n\
+            No notes\n\
+            {% endif %}\n\
+            {# The exercises #}\n\
+            {% for exercise in unit.exercises %}\n\
+            - {{ exercise.number }}: {{ exercise.name }}{% if not loop.last %},{% endif %}\n\
+            {% endfor %}\n\
+            {{ unit.exercises | length }} exercises, {% raw %}{{ $slidev.nav.currentPage }}{% endraw %}\n";
+        assert_eq!(
+            render(template, &variables()).unwrap(),
+            "# 2.1 BASIC SYNTAX\n\
+            Teacher notes\n\
+            - 2.1.1: Numbers,\n\
+            - 2.1.2: Strings\n\
+            2 exercises, {{ $slidev.nav.currentPage }}\n"
+        );
+
+        for (template, message, tag) in [
+            (
+                "{{ unit.titel }}",
+                "Unknown variable 'unit.titel'",
+                "{{ unit.titel }}",
+            ),
+            (
+                "a\n{% if unit %}\nb\n",
+                "{% if %} without {% endif %}",
+                "{% if unit %}",
+            ),
+            (
+                "{{ unit.title | shout }}",
+                "Unknown filter 'shout', expected length, upper, lower, join or default",
+                "{{ unit.title | shout }}",
+            ),
+        ] {
+            let error = render(template, &variables()).unwrap_err();
+            assert_eq!((error.message.as_str(), error.tag.as_str()), (message, tag));
+        }
+    }
+
+    #[test]
+    fn only_files_that_opt_in_are_templates() {
+        let context = TemplateContext::default();
+        let content = "# {{ Not a template }}\n";
+        assert_eq!(
+            context
+                .render::<crate::book::RenderBookError>(content, Scope::Track)
+                .unwrap(),
+            content
+        );
+        let content = "+++\ntemplate = true\n+++\nRendered for the {{ edition | default(\"student\") }} edition\n";
+        assert_eq!(
+            context
+                .render::<crate::book::RenderBookError>(content, Scope::Track)
+                .unwrap(),
+            "Rendered for the student edition\n"
+        );
+    }
+}
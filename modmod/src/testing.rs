@@ -0,0 +1,312 @@
+//! Running the test suites of the exercises, to catch broken starter code before it reaches
+//! students. Each exercise states what its tests are expected to do, as starter code often
+//! comes with tests that only pass once students have solved the exercise, and some
+//! exercises are about a compile error.
+
+use std::{
+    convert::Infallible,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    build::exercise_target_dir,
+    load::Indexed,
+    numbering::{exercise_dir, module_exercises_dir, unit_exercises_dir, ExerciseNumber},
+    tasks::{Task, TaskOutput, TaskRunner, TaskStatus},
+    visit::{Position, Visitor},
+    Exercise, Module, RenderFilter, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct TestExercisesError {}
+
+impl fmt::Display for TestExercisesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to test exercises")
+    }
+}
+
+impl error_stack::Context for TestExercisesError {}
+
+/// What the tests of an exercise are expected to do, as set by `expect` in its
+/// `[[exercises]]` table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestExpectation {
+    /// The exercise compiles, whether or not its tests pass
+    #[default]
+    Compile,
+    /// The tests pass, as they do for exercises that students extend
+    Pass,
+    /// The exercise compiles, but its tests fail until students solve it
+    Fail,
+    /// The exercise intentionally does not compile, for students to fix
+    CompileError,
+}
+
+impl TestExpectation {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether `outcome` is what's expected
+    pub fn is_met_by(&self, outcome: TestOutcome) -> bool {
+        match self {
+            TestExpectation::Compile => outcome != TestOutcome::CompileError,
+            TestExpectation::Pass => outcome == TestOutcome::Passed,
+            TestExpectation::Fail => outcome == TestOutcome::Failed,
+            TestExpectation::CompileError => outcome == TestOutcome::CompileError,
+        }
+    }
+}
+
+impl fmt::Display for TestExpectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TestExpectation::Compile => "to compile",
+            TestExpectation::Pass => "its tests to pass",
+            TestExpectation::Fail => "its tests to fail",
+            TestExpectation::CompileError => "not to compile",
+        })
+    }
+}
+
+/// What happened when testing an exercise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    CompileError,
+}
+
+impl fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TestOutcome::Passed => "tests passed",
+            TestOutcome::Failed => "tests failed",
+            TestOutcome::CompileError => "did not compile",
+        })
+    }
+}
+
+/// What to test
+#[derive(Debug, Default)]
+pub struct TestOptions {
+    /// The folder the track was rendered into. The rendered exercise packages are tested,
+    /// rather than the exercise sources.
+    pub rendered: Option<PathBuf>,
+    /// The units whose exercises are tested
+    pub filter: RenderFilter,
+    pub runner: TaskRunner,
+}
+
+/// The result of testing an exercise
+#[derive(Debug)]
+pub struct ExerciseTest<'track> {
+    pub number: ExerciseNumber,
+    pub exercise: &'track Exercise,
+    /// The package that was tested
+    pub package: PathBuf,
+    pub outcome: TestOutcome,
+    /// The output of the last `cargo` command run on the package
+    pub output: TaskOutput,
+}
+
+impl ExerciseTest<'_> {
+    /// Whether the exercise did what it's expected to
+    pub fn ok(&self) -> bool {
+        self.exercise.expect.is_met_by(self.outcome)
+    }
+}
+
+impl fmt::Display for ExerciseTest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} '{}': {}",
+            if self.ok() { "ok  " } else { "FAIL" },
+            self.number,
+            self.exercise.name,
+            self.outcome
+        )?;
+        if !self.ok() {
+            write!(f, ", expected {}", self.exercise.expect)?;
+        }
+        Ok(())
+    }
+}
+
+/// An exercise along with where its package is rendered
+#[derive(Debug)]
+pub struct ExercisePackage<'track> {
+    pub number: ExerciseNumber,
+    pub unit: &'track Unit,
+    pub exercise: &'track Exercise,
+    /// The folder the package is rendered into, relative to the output folder
+    pub dir: PathBuf,
+}
+
+/// Collects the exercise packages of the selected units while walking over the track
+struct Packages<'t, 'f> {
+    filter: &'f RenderFilter,
+    /// The unit that is being visited, if it's selected
+    unit: Option<&'t Unit>,
+    unit_dir: PathBuf,
+    packages: Vec<ExercisePackage<'t>>,
+}
+
+impl<'t> Visitor<'t> for Packages<'t, '_> {
+    type Error = Infallible;
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+        unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.unit = self.filter.includes(module, unit).then_some(&unit.data);
+        self.unit_dir = module_exercises_dir(&module.data.name, module.index)
+            .join(unit_exercises_dir(&unit.data.name, unit.index));
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Infallible> {
+        let Some(unit) = self.unit else {
+            return Ok(());
+        };
+        let number = position.exercise_number().unwrap();
+        self.packages.push(ExercisePackage {
+            number,
+            unit,
+            exercise: &exercise.data,
+            dir: self
+                .unit_dir
+                .join(exercise_dir(&exercise.data.name, number.exercise)),
+        });
+        Ok(())
+    }
+}
+
+impl Track {
+    /// The exercise packages of the units selected by `filter`
+    pub fn exercise_packages(&self, filter: &RenderFilter) -> Vec<ExercisePackage<'_>> {
+        let mut packages = Packages {
+            filter,
+            unit: None,
+            unit_dir: PathBuf::new(),
+            packages: vec![],
+        };
+        self.walk_infallible(&mut packages);
+        packages.packages
+    }
+
+    /// Compile the test suite of every exercise selected by `opts` and run it, unless it
+    /// doesn't compile
+    pub fn test_exercises(
+        &self,
+        opts: &TestOptions,
+    ) -> Result<Vec<ExerciseTest<'_>>, TestExercisesError> {
+        let packages: Vec<_> = self
+            .exercise_packages(&opts.filter)
+            .into_iter()
+            .map(|package| {
+                let dir = match &opts.rendered {
+                    Some(out_dir) => out_dir.join(package.dir),
+                    None => package.exercise.path.clone(),
+                };
+                (package.number, package.exercise, dir)
+            })
+            .collect();
+        let cargo = |name: &str, package: &Path, i: usize, args: &[&str]| {
+            Task::new(format!("{name} {}", package.to_string_lossy()), "cargo")
+                .args(args)
+                .current_dir(package)
+                .env("CARGO_TARGET_DIR", exercise_target_dir(i))
+                // A failure is an outcome rather than a glitch
+                .no_retry()
+        };
+
+        let compiled = opts.runner.run(
+            packages
+                .iter()
+                .zip(1..)
+                .map(|((_, _, package), i)| {
+                    cargo("cargo test --no-run", package, i, &["test", "--no-run"])
+                })
+                .collect(),
+        );
+        let mut tested = opts
+            .runner
+            .run(
+                packages
+                    .iter()
+                    .zip(&compiled)
+                    .zip(1..)
+                    .filter(|((_, compiled), _)| compiled.success())
+                    .map(|(((_, _, package), _), i)| cargo("cargo test", package, i, &["test"]))
+                    .collect(),
+            )
+            .into_iter();
+
+        let mut tests = vec![];
+        for ((number, exercise, package), compiled) in packages.into_iter().zip(compiled) {
+            if let TaskStatus::Failed(reason) = &compiled.status {
+                return Err(Report::new(TestExercisesError::default())
+                    .attach_printable(format!("Unable to run {}: {reason}", compiled.task)));
+            }
+            let (outcome, output) = match compiled.success() {
+                true => {
+                    let output = tested.next().unwrap();
+                    let outcome = match output.success() {
+                        true => TestOutcome::Passed,
+                        false => TestOutcome::Failed,
+                    };
+                    (outcome, output)
+                }
+                false => (TestOutcome::CompileError, compiled),
+            };
+            tests.push(ExerciseTest {
+                number,
+                exercise,
+                package,
+                outcome,
+                output,
+            });
+        }
+        Ok(tests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expectations_are_met_by_matching_outcomes() {
+        use TestOutcome::*;
+        let met = |expectation: TestExpectation| {
+            [Passed, Failed, CompileError].map(|outcome| expectation.is_met_by(outcome))
+        };
+        assert_eq!(met(TestExpectation::Compile), [true, true, false]);
+        assert_eq!(met(TestExpectation::Pass), [true, false, false]);
+        assert_eq!(met(TestExpectation::Fail), [false, true, false]);
+        assert_eq!(met(TestExpectation::CompileError), [false, false, true]);
+
+        #[derive(Deserialize)]
+        struct Def {
+            expect: TestExpectation,
+        }
+        let def: Def = toml::from_str("expect = \"compile-error\"").unwrap();
+        assert_eq!(def.expect, TestExpectation::CompileError);
+    }
+}
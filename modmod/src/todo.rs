@@ -0,0 +1,387 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+
+use crate::{
+    exercises::included_files,
+    io::PathExt,
+    load::Indexed,
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct TodoError {}
+
+impl fmt::Display for TodoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to collect to-dos")
+    }
+}
+
+impl error_stack::Context for TodoError {}
+
+/// The markers that start a to-do, in content as well as in exercise sources.
+/// `todo!()` is left alone, as it marks the code students are meant to write.
+const MARKERS: [&str; 3] = ["modmod-todo", "TODO", "FIXME"];
+
+/// Where GitHub, GitLab and Gitea look for a CODEOWNERS file, relative to the repository root
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A to-do left in content or in the sources of an exercise
+#[derive(Debug, Serialize)]
+pub struct Todo {
+    /// Relative to the track root, if the file is in there
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    pub marker: &'static str,
+    /// The rest of the line, without comment delimiters
+    pub text: String,
+    /// The owners of the file according to CODEOWNERS
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+/// The to-dos of the files of a module. Files that are shared between modules are
+/// listed with the first module they are used in.
+#[derive(Debug, Serialize)]
+pub struct ModuleTodos {
+    pub index: usize,
+    pub name: String,
+    /// The owners of the files with to-dos
+    pub owners: BTreeSet<String>,
+    pub todos: Vec<Todo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoReport {
+    /// Only modules with to-dos are listed
+    pub modules: Vec<ModuleTodos>,
+}
+
+impl TodoReport {
+    pub fn render(&self, format: TodoFormat) -> String {
+        match format {
+            TodoFormat::Text => self.to_text(),
+            TodoFormat::Json => serde_json::to_string_pretty(self).unwrap() + "\n",
+        }
+    }
+
+    /// Render the report as a list per module, naming the owners of the module and
+    /// those of the to-dos in files that have other owners
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for module in self.modules.iter() {
+            write!(text, "Module {}: {}", module.index, module.name).unwrap();
+            if !module.owners.is_empty() {
+                let owners: Vec<_> = module.owners.iter().map(String::as_str).collect();
+                write!(text, " ({})", owners.join(", ")).unwrap();
+            }
+            text.push('\n');
+            for todo in module.todos.iter() {
+                write!(
+                    text,
+                    "    {}:{}: {}",
+                    todo.path.to_string_lossy(),
+                    todo.line,
+                    todo.marker
+                )
+                .unwrap();
+                if !todo.text.is_empty() {
+                    write!(text, " {}", todo.text).unwrap();
+                }
+                if module.owners.len() > 1 && !todo.owners.is_empty() {
+                    write!(text, " ({})", todo.owners.join(", ")).unwrap();
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TodoFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for TodoFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(TodoFormat::Text),
+            "json" => Ok(TodoFormat::Json),
+            _ => Err(format!(
+                "unknown to-do report format '{s}', expected text or json"
+            )),
+        }
+    }
+}
+
+impl Track {
+    /// Collect the `TODO`, `FIXME` and `modmod-todo` markers in the slides and exercise
+    /// descriptions of the track, and in the files included in its exercises, grouped by
+    /// module. Their owners are taken from the CODEOWNERS file of the repository the
+    /// track is in, if it has one.
+    pub fn todo_report(&self) -> Result<TodoReport, TodoError> {
+        let code_owners = CodeOwners::find(&self.root)?;
+
+        let mut scan = TodoScan {
+            root: &self.root,
+            code_owners: code_owners.as_ref(),
+            scanned: BTreeSet::new(),
+            files: vec![],
+            modules: vec![],
+        };
+        self.walk(&mut scan)?;
+
+        Ok(TodoReport {
+            modules: scan.modules,
+        })
+    }
+}
+
+/// Collects the to-dos of each module while walking over the track
+struct TodoScan<'p> {
+    root: &'p Path,
+    code_owners: Option<&'p CodeOwners>,
+    scanned: BTreeSet<PathBuf>,
+    /// The files of the module that is being visited
+    files: Vec<PathBuf>,
+    modules: Vec<ModuleTodos>,
+}
+
+impl<'t> Visitor<'t> for TodoScan<'_> {
+    type Error = TodoError;
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        topic: &'t Indexed<Topic>,
+    ) -> Result<(), TodoError> {
+        self.files.push(topic.data.content.clone());
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), TodoError> {
+        self.files.push(exercise.data.description.clone());
+        self.files.extend(exercise.data.hints.iter().cloned());
+        self.files.extend(
+            included_files(&exercise.data.path, &exercise.data.includes)
+                .change_context(TodoError::default())?,
+        );
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        module: &'t Indexed<Module>,
+    ) -> Result<(), TodoError> {
+        let mut todos = vec![];
+        for file in std::mem::take(&mut self.files) {
+            let file = file.canonicalize().unwrap_or(file);
+            if !self.scanned.insert(file.clone()) {
+                continue;
+            }
+            // Binary files can't hold to-dos
+            let Ok(content) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            let owners = self
+                .code_owners
+                .map(|c| c.owners(&file).to_vec())
+                .unwrap_or_default();
+            let path = file.strip_prefix(self.root).unwrap_or(&file);
+            for (line, marker, text) in find_todos(&content) {
+                todos.push(Todo {
+                    path: path.to_path_buf(),
+                    line,
+                    marker,
+                    text,
+                    owners: owners.clone(),
+                });
+            }
+        }
+        if todos.is_empty() {
+            return Ok(());
+        }
+        self.modules.push(ModuleTodos {
+            index: module.index,
+            name: module.data.name.clone(),
+            owners: todos.iter().flat_map(|t| t.owners.clone()).collect(),
+            todos,
+        });
+        Ok(())
+    }
+}
+
+/// The to-dos in `content`, with their 1-based line numbers. A line holds at most one.
+fn find_todos(content: &str) -> Vec<(usize, &'static str, String)> {
+    let mut todos = vec![];
+    for (text, line) in content.lines().zip(1..) {
+        let found = MARKERS.iter().find_map(|marker| {
+            text.match_indices(marker)
+                .find(|(start, _)| {
+                    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+                    let before = text[..*start].chars().next_back();
+                    let after = text[start + marker.len()..].chars().next();
+                    !before.is_some_and(is_word) && !after.is_some_and(is_word)
+                })
+                .map(|(start, _)| (*marker, &text[start + marker.len()..]))
+        });
+        if let Some((marker, rest)) = found {
+            let rest = rest
+                .trim()
+                .trim_end_matches("-->")
+                .trim_end_matches("*/")
+                .trim_start_matches(':');
+            todos.push((line, marker, rest.trim().to_string()));
+        }
+    }
+    todos
+}
+
+/// The rules of a CODEOWNERS file. As in Git, the last rule matching a path determines its owners.
+struct CodeOwners {
+    /// The root of the repository, which the patterns are relative to
+    root: PathBuf,
+    rules: Vec<(GlobSet, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Look for the CODEOWNERS file of the repository that `dir` is in
+    fn find(dir: &Path) -> Result<Option<Self>, TodoError> {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        for root in dir.ancestors() {
+            if let Some(path) = CODEOWNERS_PATHS
+                .iter()
+                .map(|p| root.join(p))
+                .find(|p| p.is_file())
+            {
+                return Self::parse(root, &path.read_to_string()?).map(Some);
+            }
+            // CODEOWNERS files only apply within their repository
+            if root.join(".git").exists() {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse(root: &Path, content: &str) -> Result<Self, TodoError> {
+        let mut rules = vec![];
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let pattern = words.next().unwrap();
+            let owners = words
+                .take_while(|w| !w.starts_with('#'))
+                .map(str::to_string)
+                .collect();
+            let mut globs = GlobSetBuilder::new();
+            for glob in owner_globs(pattern) {
+                globs.add(
+                    GlobBuilder::new(&glob)
+                        .literal_separator(true)
+                        .build()
+                        .into_report()
+                        .attach_printable_lazy(|| {
+                            format!("Error parsing CODEOWNERS pattern '{pattern}'")
+                        })
+                        .change_context(TodoError::default())?,
+                );
+            }
+            rules.push((globs.build().unwrap(), owners));
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+            rules,
+        })
+    }
+
+    fn owners(&self, path: &Path) -> &[String] {
+        let Ok(path) = path.strip_prefix(&self.root) else {
+            return &[];
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|(globs, _)| globs.is_match(path))
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Globs matching the same paths as a CODEOWNERS pattern. Patterns without a slash other
+/// than a trailing one match at any depth, and patterns naming a folder match everything in it.
+fn owner_globs(pattern: &str) -> Vec<String> {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let pattern = match anchored {
+        true => pattern.to_string(),
+        false => format!("**/{pattern}"),
+    };
+    match pattern.strip_suffix('/') {
+        Some(dir) => vec![format!("{dir}/**")],
+        None => vec![pattern.clone(), format!("{pattern}/**")],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn todos_are_found_with_their_owners() {
+        assert_eq!(
+            find_todos("<!-- TODO: swap the image -->\nlet x = todo!();\n// FIXME(x) overflow */\nTODOS\nmodmod-todo add a quiz\n"),
+            [
+                (1, "TODO", "swap the image".to_string()),
+                (3, "FIXME", "(x) overflow".to_string()),
+                (5, "modmod-todo", "add a quiz".to_string()),
+            ]
+        );
+
+        let code_owners = CodeOwners::parse(
+            Path::new("/repo"),
+            "# Owners\n* @teach-rs/maintainers\n/content/mods/A-foundations/ @alice @bob\n*.rs @carol # Rust sources\nexercises/ @dave\n",
+        )
+        .unwrap();
+        let owners = |path: &str| code_owners.owners(Path::new(path)).join(" ");
+        assert_eq!(owners("/repo/README.md"), "@teach-rs/maintainers");
+        assert_eq!(
+            owners("/repo/content/mods/A-foundations/topics/x/slides.md"),
+            "@alice @bob"
+        );
+        assert_eq!(
+            owners("/repo/content/mods/A-foundations/src/main.rs"),
+            "@carol"
+        );
+        assert_eq!(
+            owners("/repo/content/mods/B/topics/y/exercises/z/src/lib.rs"),
+            "@dave"
+        );
+        assert_eq!(owners("/elsewhere/slides.md"), "");
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::RustVersion;
+
+/// Rust toolchain configuration as defined in the `[toolchain]` table of the track TOML
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolchainConfig {
+    #[serde(default = "crate::toolchain::default_channel")]
+    pub channel: String,
+    #[serde(default = "crate::toolchain::default_components")]
+    pub components: Vec<String>,
+}
+
+impl Default for ToolchainConfig {
+    fn default() -> Self {
+        Self {
+            channel: default_channel(),
+            components: default_components(),
+        }
+    }
+}
+
+pub fn default_channel() -> String {
+    "stable".to_string()
+}
+
+pub fn default_components() -> Vec<String> {
+    ["clippy", "rustfmt"].map(String::from).to_vec()
+}
+
+/// A Rust edition, like `"2021"` in the TOML
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RustEdition {
+    #[serde(rename = "2015")]
+    E2015,
+    #[serde(rename = "2018")]
+    E2018,
+    #[serde(rename = "2021")]
+    E2021,
+    #[serde(rename = "2024")]
+    E2024,
+}
+
+impl RustEdition {
+    pub const ALL: [RustEdition; 4] = [
+        RustEdition::E2015,
+        RustEdition::E2018,
+        RustEdition::E2021,
+        RustEdition::E2024,
+    ];
+
+    /// The first Rust release that supports the edition
+    pub fn first_release(&self) -> RustVersion {
+        let minor = match self {
+            RustEdition::E2015 => 0,
+            RustEdition::E2018 => 31,
+            RustEdition::E2021 => 56,
+            RustEdition::E2024 => 85,
+        };
+        RustVersion { major: 1, minor }
+    }
+}
+
+impl fmt::Display for RustEdition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RustEdition::E2015 => "2015",
+            RustEdition::E2018 => "2018",
+            RustEdition::E2021 => "2021",
+            RustEdition::E2024 => "2024",
+        })
+    }
+}
+
+/// The edition and toolchain the exercises of a unit, or a single exercise, require, as
+/// set by the `toolchain` table of a `[[units]]` or `[[exercises]]` table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExerciseToolchain {
+    /// Set in the `[package]` table of the rendered `Cargo.toml`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<RustEdition>,
+    /// Like `1.85` or `nightly-2025-01-01`, pinned in a rendered `rust-toolchain.toml`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Pinned in a rendered `rust-toolchain.toml`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<String>,
+}
+
+impl ExerciseToolchain {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fill in what this leaves unset from `defaults`
+    pub fn or(mut self, defaults: &ExerciseToolchain) -> Self {
+        self.edition = self.edition.or(defaults.edition);
+        self.channel = self.channel.or_else(|| defaults.channel.clone());
+        if self.components.is_empty() {
+            self.components = defaults.components.clone();
+        }
+        self
+    }
+
+    /// Fill in the channel or the components from the track toolchain if only one of them
+    /// is set, as a `rust-toolchain.toml` pins both
+    pub fn or_track(mut self, track: &ToolchainConfig) -> Self {
+        match (&self.channel, self.components.is_empty()) {
+            (None, false) => self.channel = Some(track.channel.clone()),
+            (Some(_), true) => self.components = track.components.clone(),
+            _ => {}
+        }
+        self
+    }
+
+    /// The content of the `rust-toolchain.toml` that pins the toolchain, if one is set
+    pub fn rust_toolchain_toml(&self) -> Option<String> {
+        let channel = self.channel.as_ref()?;
+        let components: Vec<_> = self.components.iter().map(|c| format!("{c:?}")).collect();
+        Some(format!(
+            "[toolchain]\nchannel = {channel:?}\ncomponents = [{}]\n",
+            components.join(", ")
+        ))
+    }
+
+    /// The channel, if it's a Rust release like `1.85` or `1.85.0` rather than `stable`
+    pub fn release(&self) -> Option<RustVersion> {
+        self.channel.as_ref()?.parse().ok()
+    }
+}
+
+/// Set the edition in the `[package]` table of the Cargo manifest `manifest`, keeping
+/// everything else as it is. Manifests without a `[package]` table, like those of
+/// workspaces, and manifests that can't be parsed are returned unchanged.
+pub fn set_edition(manifest: &str, edition: RustEdition) -> String {
+    let Ok(mut doc) = manifest.parse::<toml_edit::Document>() else {
+        return manifest.to_string();
+    };
+    let Some(package) = doc.get_mut("package").and_then(|p| p.as_table_like_mut()) else {
+        return manifest.to_string();
+    };
+    match package.get_mut("edition").and_then(|e| e.as_value_mut()) {
+        // Keep the formatting around the existing value
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = edition.to_string().into();
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            package.insert("edition", toml_edit::value(edition.to_string()));
+        }
+    }
+    doc.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editions_are_set_in_the_package_table() {
+        let manifest =
+            "[package]\nname = \"ex\"\nedition=\"2021\" # the default\n\n[dependencies]\nedition = \"1\"\n";
+        assert_eq!(
+            set_edition(manifest, RustEdition::E2024),
+            "[package]\nname = \"ex\"\nedition=\"2024\" # the default\n\n[dependencies]\nedition = \"1\"\n"
+        );
+        assert_eq!(
+            set_edition("[package]\nname = \"ex\"\n", RustEdition::E2018),
+            "[package]\nname = \"ex\"\nedition = \"2018\"\n"
+        );
+        let workspace = "[workspace]\nmembers = [\"a\"]\n";
+        assert_eq!(set_edition(workspace, RustEdition::E2024), workspace);
+
+        let toolchain = ExerciseToolchain {
+            edition: Some(RustEdition::E2024),
+            channel: Some("1.85".to_string()),
+            components: vec![],
+        }
+        .or_track(&ToolchainConfig::default());
+        assert_eq!(
+            toolchain.rust_toolchain_toml().unwrap(),
+            "[toolchain]\nchannel = \"1.85\"\ncomponents = [\"clippy\", \"rustfmt\"]\n"
+        );
+        assert_eq!(
+            toolchain.release(),
+            Some(RustEdition::E2024.first_release())
+        );
+    }
+}
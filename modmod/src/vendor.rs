@@ -0,0 +1,126 @@
+//! Pinning the dependencies of the rendered exercise packages with lockfiles, and vendoring
+//! them into the output, so that the exercises build without internet access, as at
+//! on-site trainings behind a firewall.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result, ResultExt};
+
+use crate::{
+    build::exercise_packages,
+    diagnostics::Errors,
+    doctor::tool,
+    io::{PathExt, WriteExt},
+    numbering::{EXERCISES_DIR, SOLUTIONS_DIR},
+    tasks::{Task, TaskRunner},
+    Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct VendorError {}
+
+impl fmt::Display for VendorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to pin exercise dependencies")
+    }
+}
+
+impl error_stack::Context for VendorError {}
+
+/// The folder in the output the dependencies are vendored into
+pub const VENDOR_DIR: &str = "vendor";
+
+impl Track {
+    /// Generate a `Cargo.lock` for every exercise package and solution rendered into
+    /// `out_dir` that doesn't include one yet
+    pub fn render_lockfiles(
+        &self,
+        out_dir: impl AsRef<Path>,
+        runner: &TaskRunner,
+    ) -> Result<(), VendorError> {
+        let out_dir = out_dir.as_ref();
+        let tasks = rendered_packages(out_dir)?
+            .into_iter()
+            .filter(|p| !p.join("Cargo.lock").is_file())
+            .map(|package| {
+                let name = package.strip_prefix(out_dir).unwrap_or(&package);
+                Task::new(
+                    format!("cargo generate-lockfile {}", name.to_string_lossy()),
+                    "cargo",
+                )
+                .arg("generate-lockfile")
+                .current_dir(&package)
+            })
+            .collect();
+
+        let mut errors = Errors::default();
+        for output in runner.run(tasks) {
+            errors.collect(output.into_result().change_context(VendorError::default()));
+        }
+        errors.finish(())
+    }
+
+    /// Vendor the dependencies of every exercise package and solution rendered into
+    /// `out_dir` into its `vendor` folder, and write the `.cargo/config.toml` that has cargo
+    /// use them, which applies to every package in `out_dir`. The lockfiles of the packages
+    /// are generated first.
+    pub fn vendor_dependencies(
+        &self,
+        out_dir: impl AsRef<Path>,
+        runner: &TaskRunner,
+    ) -> Result<(), VendorError> {
+        let out_dir = out_dir.as_ref();
+        self.render_lockfiles(out_dir, runner)?;
+        let manifests: Vec<_> = rendered_packages(out_dir)?
+            .into_iter()
+            .map(|p| p.join("Cargo.toml"))
+            .collect();
+        let Some((first, others)) = manifests.split_first() else {
+            return Ok(());
+        };
+
+        let mut task = Task::new("cargo vendor", "cargo")
+            .args(["vendor", "--manifest-path"])
+            .arg(first);
+        for manifest in others {
+            task = task.arg("--sync").arg(manifest);
+        }
+        let output = runner
+            .run_one(task.arg(VENDOR_DIR).current_dir(out_dir))
+            .into_result()
+            .change_context(VendorError::default())?;
+
+        // cargo vendor prints the source replacements that point cargo to the vendored
+        // sources, relative to the output folder
+        let cargo_dir = out_dir.join(".cargo");
+        cargo_dir.create_dir_all()?;
+        cargo_dir
+            .join("config.toml")
+            .create_file()?
+            .write_all(&output.stdout)?;
+        Ok(())
+    }
+}
+
+/// The exercise packages and solutions rendered into `out_dir`
+fn rendered_packages(out_dir: &Path) -> Result<Vec<PathBuf>, VendorError> {
+    let mut packages = vec![];
+    for dir in [EXERCISES_DIR, SOLUTIONS_DIR].map(|d| out_dir.join(d)) {
+        if dir.is_dir() {
+            packages.extend(exercise_packages(&dir).change_context(VendorError::default())?);
+        }
+    }
+    if !packages.is_empty() && !tool("cargo").is_installed() {
+        return Err(
+            Report::new(VendorError::default()).attach_printable(format!(
+                "cargo is not installed. To install it: {}",
+                tool("cargo").install_hint()
+            )),
+        );
+    }
+    Ok(packages)
+}
@@ -0,0 +1,160 @@
+use std::{convert::Infallible, fmt};
+
+use error_stack::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    load::Indexed,
+    numbering::{ExerciseNumber, UnitNumber},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+/// Position of an item in the track. Exercises are numbered
+/// within their unit, continuing across the unit's topics,
+/// matching the numbering used in the book.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub module: usize,
+    pub unit: Option<usize>,
+    pub topic: Option<usize>,
+    pub exercise: Option<usize>,
+}
+
+impl fmt::Display for Position {
+    /// Formats the position as e.g. `2`, `2.1` or `2.1.3`, leaving out the topic
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.module)?;
+        if let Some(unit) = self.unit {
+            write!(f, ".{unit}")?;
+        }
+        if let Some(exercise) = self.exercise {
+            write!(f, ".{exercise}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Position {
+    /// The number of the unit the position is in, if it's in a unit
+    pub fn unit_number(&self) -> Option<UnitNumber> {
+        self.unit.map(|unit| UnitNumber::new(self.module, unit))
+    }
+
+    /// The number of the exercise at the position, if it's an exercise
+    pub fn exercise_number(&self) -> Option<ExerciseNumber> {
+        self.unit_number()
+            .zip(self.exercise)
+            .map(|(u, e)| u.exercise(e))
+    }
+}
+
+/// Callbacks for walking over a resolved track with [`Track::walk`].
+/// Items are visited depth-first in track order, parents before their children.
+/// Modules and units are left after their children have been visited, for output that
+/// nests them.
+/// All methods do nothing by default, so implementors only need to
+/// implement those they are interested in.
+pub trait Visitor<'t> {
+    type Error: Context;
+
+    fn visit_module(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        _unit: &'t Indexed<Unit>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_topic(
+        &mut self,
+        _position: Position,
+        _unit: &'t Indexed<Unit>,
+        _topic: &'t Indexed<Topic>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_exercise(
+        &mut self,
+        _position: Position,
+        _topic: &'t Indexed<Topic>,
+        _exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn leave_unit(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+        _unit: &'t Indexed<Unit>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        _position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Walk over all modules, units, topics and exercises in the track
+    pub fn walk<'t, V: Visitor<'t>>(&'t self, visitor: &mut V) -> Result<(), V::Error> {
+        for module in self.modules.iter() {
+            let module_position = Position {
+                module: module.index,
+                ..Default::default()
+            };
+            visitor.visit_module(module_position, module)?;
+
+            for unit in module.data.units.iter() {
+                let unit_position = Position {
+                    unit: Some(unit.index),
+                    ..module_position
+                };
+                visitor.visit_unit(unit_position, module, unit)?;
+
+                let mut exercise_number = 0;
+                for topic in unit.data.topics.iter() {
+                    let topic_position = Position {
+                        topic: Some(topic.index),
+                        ..unit_position
+                    };
+                    visitor.visit_topic(topic_position, unit, topic)?;
+
+                    for exercise in topic.data.exercises.iter() {
+                        exercise_number += 1;
+                        let exercise_position = Position {
+                            exercise: Some(exercise_number),
+                            ..topic_position
+                        };
+                        visitor.visit_exercise(exercise_position, topic, exercise)?;
+                    }
+                }
+                visitor.leave_unit(unit_position, module, unit)?;
+            }
+            visitor.leave_module(module_position, module)?;
+        }
+        Ok(())
+    }
+
+    /// Walk over the track with a visitor that can't fail, like one that only collects
+    /// information about the track
+    pub fn walk_infallible<'t, V: Visitor<'t, Error = Infallible>>(&'t self, visitor: &mut V) {
+        // A report can't be made without an error, and there are no infallible errors
+        let _ = self.walk(visitor);
+    }
+}
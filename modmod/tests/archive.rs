@@ -0,0 +1,83 @@
+//! The module archives hold all of the output of their module, as it is on disk
+
+use std::{fs, path::Path};
+
+use modmod::{archive::PackageFormat, edition::Edition, RenderOptions, Track};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn module_archives_hold_the_whole_module() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Syntax\"\ntopics = [\"topic.toml\"]\n",
+    );
+    write(
+        &content.join("topic.toml"),
+        r#"
+        name = "Files"
+
+        [[exercises]]
+        name = "Word count"
+        path = "exercises/word-count"
+        includes = ["Cargo.toml", "src/**/*", "run.sh"]
+        solution = "solution"
+        "#,
+    );
+    write(&content.join("slides.md"), "# Files\n");
+    let exercise = content.join("exercises/word-count");
+    write(
+        &exercise.join("Cargo.toml"),
+        "[package]\nname = \"word-count\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    write(&exercise.join("src/lib.rs"), "");
+    write(&exercise.join("solution/src/lib.rs"), "");
+    write(&exercise.join("run.sh"), "#!/bin/sh\ncargo run\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(exercise.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    write(&exercise.join("description.md"), "# Exercise\n");
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            edition: Edition::Teacher,
+            package: Some(PackageFormat::Zip),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let archive = fs::File::open(out_dir.path().join("packages/1-basics.zip")).unwrap();
+    let mut archive = zip::ZipArchive::new(archive).unwrap();
+    let names: Vec<_> = archive.file_names().collect();
+    let package = "exercises/1-basics/1-syntax/1-word-count";
+    for name in [
+        "book/src/1-basics.md",
+        "book/src/syntax.md",
+        "slides/1_1-syntax.md",
+        &format!("{package}/run.sh"),
+        "solutions/1-basics/1-syntax/1-word-count/src/lib.rs",
+    ] {
+        assert!(names.contains(&name), "{name} is not in {names:?}");
+    }
+
+    let mode = |archive: &mut zip::ZipArchive<fs::File>, name: &str| {
+        archive.by_name(name).unwrap().unix_mode().unwrap() & 0o777
+    };
+    assert_eq!(mode(&mut archive, &format!("{package}/Cargo.toml")), 0o644);
+    #[cfg(unix)]
+    assert_eq!(mode(&mut archive, &format!("{package}/run.sh")), 0o755);
+}
@@ -0,0 +1,127 @@
+//! Programmatic course generation through the builder API, without a track TOML
+
+use std::{fs, path::Path};
+
+use modmod::{
+    book::{Book, BookRenderOptions, SectionSlides},
+    edition::Edition,
+    exercises::ExerciseCollection,
+    slides::SlidesPackage,
+    tasks::TaskRunner,
+    SlidesRenderOptions,
+};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn builds_course_programmatically() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+
+    let exercise_path = content_dir.path().join("exercises").join("hello");
+    write(
+        &exercise_path.join("Cargo.toml"),
+        "[package]\nname = \"hello\"\nversion = \"0.1.0\"\n",
+    );
+    write(&exercise_path.join("src/main.rs"), "fn main() {}\n");
+    let description = exercise_path.join("description.md");
+    write(
+        &description,
+        "Open #[modmod:exercise_dir] to start exercise #[modmod:exercise_ref].\n",
+    );
+    let slides = content_dir.path().join("slides.md");
+    write(&slides, "# Hello, world\n");
+    let includes = ["Cargo.toml", "src/**/*"].map(String::from);
+    let hints = ["Start from `main`.", "Use `println!`."].map(|hint| hint.to_string());
+    let hints: Vec<_> = hints
+        .iter()
+        .zip(1..)
+        .map(|(hint, number)| {
+            let path = exercise_path.join(format!("hint-{number}.md"));
+            write(&path, hint);
+            path
+        })
+        .collect();
+
+    let mut exercises = ExerciseCollection::builder();
+    exercises.hints_folder(true);
+    let mut module = exercises.module("Basics", 1);
+    let mut unit = module.unit("Hello", 1);
+    unit.package("Hello", &exercise_path, &includes, None, None)
+        .hints(&hints);
+    unit.add();
+    module.add();
+    let exercise_paths = exercises
+        .build()
+        .render(out_dir.path(), Edition::Student, &TaskRunner::default())
+        .unwrap();
+
+    let mut book = Book::builder("Programmatic course");
+    let mut chapter = book.chapter("Basics", 1);
+    let mut section = chapter.section(1, 1, "Hello");
+    section
+        .subsection("Hello", &description, &[], &exercise_path)
+        .hints(&hints);
+    section.add();
+    chapter.add();
+    book.build()
+        .render(
+            BookRenderOptions {
+                exercise_paths: &exercise_paths,
+                slides_url_base: "/",
+                edition: Edition::Student,
+                slides: SectionSlides::Embed,
+            },
+            out_dir.path(),
+        )
+        .unwrap();
+
+    let mut slides_package = SlidesPackage::builder("Programmatic course");
+    let mut deck = slides_package.deck("Hello", "Basics", 1, 1, None);
+    deck.section(&slides).add();
+    deck.add();
+    slides_package
+        .build()
+        .render(
+            out_dir.path(),
+            SlidesRenderOptions {
+                theme: "default",
+                package_json: None::<&Path>,
+                url_base: "/",
+                edition: Edition::Student,
+                strip_notes: false,
+                handouts: false,
+                pdf: false,
+                module_decks: false,
+                ascii: false,
+            },
+        )
+        .unwrap();
+
+    let out = out_dir.path();
+    let exercise_out = out.join("exercises/1-basics/1-hello/1-hello");
+    assert!(exercise_out.join("Cargo.toml").is_file());
+    assert!(exercise_out.join("src/main.rs").is_file());
+    assert!(!exercise_out.join("description.md").exists());
+    let hint = fs::read_to_string(exercise_out.join("hints/hint-2.md")).unwrap();
+    assert_eq!(hint, "Use `println!`.");
+
+    let summary = fs::read_to_string(out.join("book/src/SUMMARY.md")).unwrap();
+    assert!(summary.contains("- [Basics](1-basics.md)"));
+    assert!(summary.contains("\t- [Hello](hello.md)"));
+    let section = fs::read_to_string(out.join("book/src/hello.md")).unwrap();
+    assert!(section.contains("# Unit 1.1 - Hello"));
+    assert!(section.contains("<iframe src=\"/slides/1_1/\" title=\"Slides of Hello\""));
+    assert!(section.contains("## Exercise 1.1.1: Hello"));
+    assert!(section.contains("Open exercises/1-basics/1-hello/1-hello to start exercise 1.1.1."));
+    assert!(section
+        .contains("<details>\n<summary>Hint 1</summary>\n\nStart from `main`.\n\n</details>"));
+
+    let deck = fs::read_to_string(out.join("slides/1_1-hello.md")).unwrap();
+    assert!(deck.contains("# Hello, world"));
+    let package_json = fs::read_to_string(out.join("slides/package.json")).unwrap();
+    assert!(package_json.contains("\"name\": \"programmatic-course\""));
+}
@@ -0,0 +1,73 @@
+//! The offline bundle holds the built book, slide decks and exercise archives
+
+use std::fs;
+
+use modmod::{
+    archive::PackageFormat,
+    bundle::{BundleKind, BundleOptions},
+    RenderOptions, Track,
+};
+
+#[test]
+fn offline_bundle_holds_book_slides_and_exercises() {
+    let out_dir = tempfile::tempdir().unwrap();
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    // Stand-ins for the output of `mdbook build` and of the slides build scripts
+    let html_dir = out_dir.path().join("book").join("target");
+    fs::create_dir_all(&html_dir).unwrap();
+    fs::write(
+        html_dir.join("index.html"),
+        r#"<html><head><link rel="stylesheet" href="css/general.css"></head></html>"#,
+    )
+    .unwrap();
+    let deck_dir = out_dir.path().join("slides/dist/1_1-introduction");
+    fs::create_dir_all(&deck_dir).unwrap();
+    fs::write(deck_dir.join("index.html"), "<html></html>").unwrap();
+    let package_json = out_dir.path().join("slides/package.json");
+    let mut package: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&package_json).unwrap()).unwrap();
+    package["scripts"]
+        .as_object_mut()
+        .unwrap()
+        .retain(|script, _| !script.starts_with("build-") || script == "build-1_1");
+    fs::write(&package_json, package.to_string()).unwrap();
+
+    let bundle = track
+        .bundle(
+            out_dir.path(),
+            &BundleOptions {
+                kind: BundleKind::Offline,
+                format: Some(PackageFormat::Zip),
+                runner: Default::default(),
+            },
+        )
+        .unwrap();
+    assert!(bundle.assets.is_empty());
+    assert!(bundle.dir.join("book/index.html").is_file());
+    assert!(bundle.dir.join("slides/1_1/index.html").is_file());
+    assert!(bundle
+        .dir
+        .join("exercises/2-foundations-of-rust.zip")
+        .is_file());
+    assert!(bundle.archive.unwrap().is_file());
+
+    let index = fs::read_to_string(bundle.dir.join("index.html")).unwrap();
+    assert!(index.contains(r#"<a href="slides/1_1/index.html">1.1 Introduction</a>"#));
+    assert!(index.contains(r#"<a href="exercises/2-foundations-of-rust.zip">"#));
+
+    // Decks that were not built make the bundle incomplete
+    fs::remove_dir_all(deck_dir).unwrap();
+    let opts = BundleOptions {
+        kind: BundleKind::Offline,
+        format: None,
+        runner: Default::default(),
+    };
+    assert!(track.bundle(out_dir.path(), &opts).is_err());
+}
@@ -0,0 +1,106 @@
+//! Checks of the content of a track folder
+
+use std::{fs, path::Path};
+
+use modmod::{check::CheckOptions, Track};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn introductions_and_included_files_are_not_orphans() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mods/1-basics/mod.toml\"]\n",
+    );
+    write(
+        &content.join("mods/1-basics/mod.toml"),
+        r#"
+        name = "Basics"
+        description = ""
+        intro = "intro.md"
+
+        [[units]]
+        name = "Syntax"
+        intro = "syntax-intro.md"
+        topics = ["topic.toml"]
+        "#,
+    );
+    write(
+        &content.join("mods/1-basics/intro.md"),
+        "# Basics\n\n#[modmod:include(shared/welcome.md)]\n",
+    );
+    write(
+        &content.join("mods/1-basics/shared/welcome.md"),
+        "Welcome!\n",
+    );
+    write(&content.join("mods/1-basics/syntax-intro.md"), "# Syntax\n");
+    write(
+        &content.join("mods/1-basics/topic.toml"),
+        "name = \"Variables\"\n",
+    );
+    write(&content.join("mods/1-basics/slides.md"), "# Variables\n");
+    write(&content.join("mods/1-basics/unused.md"), "# Unused\n");
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let findings = track
+        .check(&CheckOptions {
+            orphans: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let orphans: Vec<_> = findings
+        .iter()
+        .map(|f| {
+            f.path
+                .strip_prefix(content.canonicalize().unwrap())
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(orphans, [Path::new("mods/1-basics/unused.md")]);
+}
+
+#[test]
+fn code_blocks_are_formatted_in_the_edition_of_their_unit() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        r#"
+        name = "Basics"
+        description = ""
+
+        [[units]]
+        name = "Syntax"
+        topics = ["topic.toml"]
+        toolchain = { edition = "2015" }
+        "#,
+    );
+    write(&content.join("topic.toml"), "name = \"Functions\"\n");
+    // `async` is only a keyword as of the 2018 edition
+    write(
+        &content.join("slides.md"),
+        "# Functions\n\n```rust\nfn async(){}\n```\n",
+    );
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let findings = track
+        .check(&CheckOptions {
+            rustfmt: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let messages: Vec<_> = findings.iter().map(|f| f.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["Rust code block is not formatted according to rustfmt"]
+    );
+}
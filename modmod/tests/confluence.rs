@@ -0,0 +1,58 @@
+//! The Confluence pages are converted from the rendered book, so the HTML the book holds has
+//! to be valid in Confluence storage format, which is XML
+
+use std::{fs, path::Path};
+
+use modmod::{book::SectionSlides, RenderOptions, Track};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn embedded_slides_are_valid_storage_format() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Syntax\"\ntopics = [\"topic.toml\"]\n",
+    );
+    write(&content.join("topic.toml"), "name = \"Variables\"\n");
+    write(&content.join("slides.md"), "# Variables\n");
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            book_slides: SectionSlides::Embed,
+            confluence: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+    let page = fs::read_to_string(out_dir.path().join("confluence/pages/1_1-syntax.xml")).unwrap();
+    let iframe = page
+        .lines()
+        .find(|line| line.starts_with("<iframe "))
+        .unwrap();
+    // XML has no attributes without a value
+    let mut attributes = &iframe["<iframe".len()..iframe.find('>').unwrap()];
+    let mut names = vec![];
+    while !attributes.trim().is_empty() {
+        let (name, rest) = attributes.split_once("=\"").unwrap();
+        let name = name.trim();
+        assert!(!name.contains(' '), "Attribute without a value in {iframe}");
+        names.push(name);
+        attributes = &rest[rest.find('"').unwrap() + 1..];
+    }
+    assert_eq!(
+        names,
+        ["src", "title", "loading", "allowfullscreen", "style"]
+    );
+}
@@ -0,0 +1,31 @@
+//! The dependency graph only points at artifacts the renderers actually write
+
+use std::collections::BTreeSet;
+
+use modmod::{archive::PackageFormat, RenderOptions, Track};
+
+#[test]
+fn dependency_graph_matches_rendered_output() {
+    let out_dir = tempfile::tempdir().unwrap();
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            package: Some(PackageFormat::Zip),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let graph = track.dependency_graph(Some(PackageFormat::Zip)).unwrap();
+    let artifacts: BTreeSet<_> = graph.edges().values().flatten().collect();
+    assert!(!artifacts.is_empty());
+    // Every artifact can be traced back to the source files it is made of
+    assert_eq!(graph.inputs().len(), artifacts.len());
+    for artifact in artifacts {
+        assert!(
+            out_dir.path().join(artifact).is_file(),
+            "{} was not rendered",
+            artifact.to_string_lossy()
+        );
+    }
+}
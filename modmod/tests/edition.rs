@@ -0,0 +1,48 @@
+//! The speaker notes stay in the slides, unless they're left out of an edition for students
+
+use std::{fs, path::Path};
+
+use modmod::{edition::Edition, RenderOptions, Track};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn render_deck(track: &Track, edition: Edition, strip_notes: bool) -> String {
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            edition,
+            strip_notes,
+            ..Default::default()
+        })
+        .unwrap();
+    fs::read_to_string(out_dir.path().join("slides/1_1-ownership.md")).unwrap()
+}
+
+#[test]
+fn speaker_notes_are_only_stripped_when_asked_for() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Ownership\"\ntopics = [\"topic.toml\"]\n",
+    );
+    write(&content.join("topic.toml"), "name = \"Moves\"\n");
+    write(
+        &content.join("slides.md"),
+        "# Moves\n\n<!-- Ask about Copy -->\n",
+    );
+    let track = Track::load(content.join("track.toml")).unwrap();
+
+    let note = "<!-- Ask about Copy -->";
+    assert!(render_deck(&track, Edition::Student, false).contains(note));
+    assert!(!render_deck(&track, Edition::Student, true).contains(note));
+    assert!(render_deck(&track, Edition::Teacher, true).contains(note));
+}
@@ -0,0 +1,64 @@
+//! Topic content files can keep their metadata in front matter, without it ending up in the
+//! slides, while the frontmatter of their first slide is left for Slidev
+
+use std::{fs, path::Path};
+
+use modmod::{RenderOptions, Track};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn yaml_front_matter_is_read_unless_it_belongs_to_the_first_slide() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        r#"
+        name = "Basics"
+        description = ""
+
+        [[units]]
+        name = "Ownership"
+        topics = ["moves/topic.toml", "borrows/topic.toml"]
+        "#,
+    );
+    write(&content.join("moves/topic.toml"), "name = \"Moves\"\n");
+    write(
+        &content.join("moves/slides.md"),
+        "---\ntitle: Move semantics\ntags: [advanced]\nduration: 20m\n---\n---\nlayout: cover\n---\n# Move semantics\n",
+    );
+    write(&content.join("borrows/topic.toml"), "name = \"Borrows\"\n");
+    write(
+        &content.join("borrows/slides.md"),
+        "---\ntitle: Borrowing\nlayout: two-cols\n---\n# Borrowing\n",
+    );
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let topics = &track.modules[0].data.units[0].data.topics;
+    assert_eq!(topics[0].data.name, "Move semantics");
+    assert_eq!(topics[0].data.tags, ["advanced"]);
+    // Slidev sets the layout of a slide in its frontmatter
+    assert_eq!(topics[1].data.name, "Borrows");
+
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+    let deck = fs::read_to_string(out_dir.path().join("slides/1_1-ownership.md")).unwrap();
+    assert!(!deck.contains("duration: 20m"), "{deck}");
+    assert!(
+        deck.contains("---\nlayout: cover\n---\n# Move semantics"),
+        "{deck}"
+    );
+    assert!(deck.contains("layout: two-cols"), "{deck}");
+}
@@ -0,0 +1,112 @@
+//! Exercise data is generated at render time without touching the content repository
+
+use std::{fs, path::Path};
+
+use modmod::{
+    generate::{GenerateOptions, OutputMode},
+    RenderOptions, Track,
+};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn generated_data_ends_up_in_the_scaffold_only() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Syntax\"\ntopics = [\"topic.toml\"]\n",
+    );
+    write(
+        &content.join("topic.toml"),
+        r#"
+        name = "Files"
+
+        [[exercises]]
+        name = "Word count"
+        path = "exercises/word-count"
+        includes = ["Cargo.toml", "src/**/*", "data/**/*"]
+        generate = "mkdir data && cat gen/words > data/words.txt && test -d \"$MODMOD_SCAFFOLD_DIR\""
+        "#,
+    );
+    write(&content.join("slides.md"), "# Files\n");
+    let exercise = content.join("exercises/word-count");
+    write(
+        &exercise.join("Cargo.toml"),
+        "[package]\nname = \"word-count\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    );
+    write(&exercise.join("src/lib.rs"), "");
+    write(&exercise.join("gen/words"), "a b c\n");
+    write(&exercise.join("description.md"), "# Exercise\n");
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let scaffold = out_dir
+        .path()
+        .join("exercises/1-basics/1-syntax/1-word-count");
+    assert_eq!(
+        fs::read_to_string(scaffold.join("data/words.txt")).unwrap(),
+        "a b c\n"
+    );
+    assert!(!scaffold.join("gen").exists());
+    assert!(!exercise.join("data").exists());
+}
+
+#[test]
+fn follow_on_steps_work_in_the_output_folder_of_a_clean_render() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Syntax\"\ntopics = [\"topic.toml\"]\n",
+    );
+    write(&content.join("topic.toml"), "name = \"Variables\"\n");
+    write(&content.join("slides.md"), "# Variables\n");
+
+    let out_dir = tempfile::tempdir().unwrap();
+    let generate = |output| {
+        Track::generate(
+            content.join("track.toml"),
+            &GenerateOptions {
+                render: RenderOptions {
+                    out_dir: out_dir.path().to_path_buf(),
+                    ..Default::default()
+                },
+                output,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    };
+
+    let clean = generate(OutputMode::Clean);
+    assert_eq!(clean.out_dir, out_dir.path());
+    assert!(clean.synced.unwrap().written > 0);
+    assert!(out_dir.path().join("book/src/SUMMARY.md").exists());
+
+    // Previews list the changes without rendering into the output folder
+    write(&content.join("slides.md"), "# Variables and mutability\n");
+    let preview = generate(OutputMode::Preview);
+    assert!(!preview.out_dir.exists());
+    assert!(!preview.changes.unwrap().is_empty());
+    let deck = out_dir.path().join("slides/1_1-syntax.md");
+    assert!(!fs::read_to_string(deck).unwrap().contains("mutability"));
+}
@@ -0,0 +1,156 @@
+//! Submissions are graded by the tests of their crates and the hidden tests of the track
+
+use std::{fs, path::Path};
+
+use modmod::{
+    grade::{GradeOptions, GradeStatus},
+    RenderOptions, Track,
+};
+use tempfile::TempDir;
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Render a course of an exercise with hidden tests and one without, returning the content
+/// folder, the track and the output folder
+fn course() -> (TempDir, Track, TempDir) {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Syntax\"\ntopics = [\"topic.toml\"]\n",
+    );
+    write(
+        &content.join("topic.toml"),
+        r#"
+        name = "Functions"
+
+        [[exercises]]
+        name = "Add"
+        path = "exercises/add"
+        hidden_tests = "hidden-tests"
+
+        [[exercises]]
+        name = "Sub"
+        path = "exercises/sub"
+        "#,
+    );
+    write(&content.join("slides.md"), "# Functions\n");
+    for name in ["add", "sub"] {
+        let dir = content.join("exercises").join(name);
+        write(
+            &dir.join("Cargo.toml"),
+            &format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n"),
+        );
+        write(
+            &dir.join("src/lib.rs"),
+            &format!("pub fn {name}(a: i32, b: i32) -> i32 {{\n    todo!()\n}}\n"),
+        );
+        write(&dir.join("description.md"), "# Exercise\n");
+    }
+    write(
+        &content.join("exercises/add/hidden-tests/hidden.rs"),
+        "#[test]\nfn adds() {\n    assert_eq!(add::add(1, 2), 3);\n}\n\n#[test]\nfn adds_negatives() {\n    assert_eq!(add::add(-1, -2), -3);\n}\n",
+    );
+
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+    (content_dir, track, out_dir)
+}
+
+#[test]
+fn submissions_are_scored_with_hidden_tests() {
+    let (_content_dir, track, out_dir) = course();
+    let exercises_dir = out_dir.path().join("exercises");
+    let add_dir = exercises_dir.join("1-basics/1-syntax/1-add");
+    assert!(add_dir.join("src/lib.rs").is_file());
+    assert!(!add_dir.join("hidden-tests").exists());
+
+    // The student got one case wrong, and didn't hand in the other exercise
+    write(
+        &add_dir.join("src/lib.rs"),
+        "pub fn add(a: i32, b: i32) -> i32 {\n    a.abs() + b.abs()\n}\n\n#[test]\nfn adds() {\n    assert_eq!(add(2, 2), 4);\n}\n",
+    );
+    fs::remove_dir_all(exercises_dir.join("1-basics/1-syntax/2-sub")).unwrap();
+
+    let report = track
+        .grade(&exercises_dir, &GradeOptions::default())
+        .unwrap();
+    let add = &report.exercises[0];
+    assert_eq!(add.status, GradeStatus::Graded);
+    assert_eq!((add.tests.passed, add.tests.failed), (1, 0));
+    assert_eq!((add.hidden_tests.passed, add.hidden_tests.failed), (1, 1));
+    assert_eq!(add.score, Some(50.0));
+    assert_eq!(report.exercises[1].status, GradeStatus::Missing);
+    assert_eq!(report.score, Some(25.0));
+    // The hidden tests are only added to a copy of the submission
+    assert!(!add_dir.join("tests").exists());
+}
+
+#[test]
+fn padded_tests_dont_score_and_untouched_starters_have_no_score() {
+    let (_content_dir, track, out_dir) = course();
+    let exercises_dir = out_dir.path().join("exercises");
+
+    // The student padded the exercise with tests that pass, and left the other untouched
+    let padding: String = (0..8)
+        .map(|i| format!("\n#[test]\nfn pads_{i}() {{}}\n"))
+        .collect();
+    write(
+        &exercises_dir.join("1-basics/1-syntax/1-add/src/lib.rs"),
+        &format!("pub fn add(a: i32, b: i32) -> i32 {{\n    a.abs() + b.abs()\n}}\n{padding}"),
+    );
+
+    let report = track
+        .grade(&exercises_dir, &GradeOptions::default())
+        .unwrap();
+    let add = &report.exercises[0];
+    assert_eq!((add.tests.passed, add.tests.failed), (8, 0));
+    assert_eq!(add.score, Some(50.0));
+    let sub = &report.exercises[1];
+    assert_eq!(sub.status, GradeStatus::Graded);
+    assert_eq!(sub.tests.total(), 0);
+    assert_eq!(sub.score, None);
+    assert_eq!(report.score, Some(50.0));
+    assert!(report
+        .to_string()
+        .contains("     - 1.1.2 'Sub': 0 of 0 tests passed"));
+}
+
+#[test]
+fn submissions_cant_replace_or_outnumber_the_hidden_tests() {
+    let (_content_dir, track, out_dir) = course();
+    let exercises_dir = out_dir.path().join("exercises");
+    let add_dir = exercises_dir.join("1-basics/1-syntax/1-add");
+
+    // The student took the name of the hidden test target for a test of their own, and
+    // prints a summary of passing tests where the failing hidden test shows its output
+    write(
+        &add_dir.join("Cargo.toml"),
+        "[package]\nname = \"add\"\nversion = \"0.1.0\"\nedition = \"2021\"\nautotests = false\n\n[[test]]\nname = \"hidden\"\npath = \"src/fake.rs\"\n",
+    );
+    write(&add_dir.join("src/fake.rs"), "#[test]\nfn adds() {}\n");
+    write(
+        &add_dir.join("src/lib.rs"),
+        "pub fn add(a: i32, b: i32) -> i32 {\n    println!(\"test result: ok. 50 passed; 0 failed;\");\n    a.abs() + b.abs()\n}\n",
+    );
+
+    let report = track
+        .grade(&exercises_dir, &GradeOptions::default())
+        .unwrap();
+    let add = &report.exercises[0];
+    assert_eq!((add.hidden_tests.passed, add.hidden_tests.failed), (1, 1));
+    assert_eq!(add.score, Some(50.0));
+}
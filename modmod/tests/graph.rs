@@ -0,0 +1,23 @@
+//! The topic graph covers every topic of the track once, grouped per module
+
+use modmod::{graph::GraphFormat, Track};
+
+#[test]
+fn graph_has_a_node_per_topic() {
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    let graph = track.topic_graph();
+    let topics = track
+        .modules
+        .iter()
+        .flat_map(|m| m.data.units.iter())
+        .flat_map(|u| u.data.topics.iter())
+        .count();
+    assert_eq!(graph.nodes.len(), topics);
+
+    let dot = graph.render(GraphFormat::Dot);
+    assert!(dot.starts_with("digraph topics {"));
+    assert_eq!(
+        dot.matches("subgraph cluster_").count(),
+        track.modules.len()
+    );
+}
@@ -0,0 +1,55 @@
+//! Tools that drive modmod as a library load and render tracks, and get the problems of a
+//! failed render as data
+
+use std::fs;
+
+use modmod::{
+    diagnostics::{Diagnostic, Location},
+    RenderOptions, Track,
+};
+
+#[test]
+fn renders_report_what_they_rendered_and_what_went_wrong() {
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    let output = track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(output
+        .manifest
+        .files
+        .contains_key(std::path::Path::new("book/src/SUMMARY.md")));
+
+    let track_dir = tempfile::tempdir().unwrap();
+    let track_toml = track_dir.path().join("track.toml");
+    fs::write(&track_toml, "name = \"Broken\"\nmodules = [\"mod.toml\"]\n").unwrap();
+    fs::write(
+        track_dir.path().join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"The basics\"\n\n[[units]]\nname = \"Syntax\"\ntopics = []\nintro = \"intro.md\"\n",
+    )
+    .unwrap();
+    let intro = track_dir.path().join("intro.md");
+    fs::write(&intro, "+++\ntemplate = true\n+++\nUnit {{ unit.numbr }}\n").unwrap();
+
+    let track = Track::load(&track_toml).unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    let report = track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap_err();
+    let diagnostics = Diagnostic::of_report(&report);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].location,
+        Some(Location::new(intro.canonicalize().unwrap(), Some(4)))
+    );
+    assert_eq!(
+        diagnostics[0].messages[..2],
+        ["Unknown variable 'unit.numbr'", "in `{{ unit.numbr }}`"]
+    );
+}
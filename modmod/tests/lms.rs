@@ -0,0 +1,60 @@
+//! The LMS cartridge has a section per module, with pages for units and assignments for exercises
+
+use std::{
+    fs,
+    io::{Cursor, Read},
+};
+
+use modmod::{
+    lms::{LmsFormat, LmsOptions},
+    RenderOptions, Track,
+};
+
+#[test]
+fn cartridge_maps_modules_units_and_exercises() {
+    let out_dir = tempfile::tempdir().unwrap();
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let cartridge = track
+        .lms_cartridge(
+            out_dir.path(),
+            &LmsOptions {
+                format: LmsFormat::Canvas,
+                book_url: "https://example.org/book/".to_string(),
+                slides_url: None,
+            },
+        )
+        .unwrap();
+    let mut zip = zip::ZipArchive::new(fs::File::open(cartridge).unwrap()).unwrap();
+    let mut read = |name: &str| {
+        let mut content = vec![];
+        zip.by_name(name)
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        content
+    };
+
+    let manifest = String::from_utf8(read("imsmanifest.xml")).unwrap();
+    assert_eq!(
+        manifest.matches("<item identifier=\"MODULE_").count(),
+        track.modules.len()
+    );
+    assert!(manifest.contains(r#"<title>2.1 Basic Syntax</title>"#));
+    assert!(manifest.contains(r#"type="assignment_xmlv1p0""#));
+
+    let page = String::from_utf8(read("wiki_content/2_1-basic-syntax.html")).unwrap();
+    assert!(page.contains(r#"<a href="https://example.org/book/basic-syntax.html">"#));
+
+    let assignment = String::from_utf8(read("exercises/2_1_1/assignment.xml")).unwrap();
+    assert!(assignment.contains(r#"<attachment href="1-basic-syntax.zip" role="Learner"/>"#));
+    let code =
+        zip::ZipArchive::new(Cursor::new(read("exercises/2_1_1/1-basic-syntax.zip"))).unwrap();
+    assert!(code.file_names().any(|f| f == "1-basic-syntax/Cargo.toml"));
+}
@@ -0,0 +1,69 @@
+//! The Nix flake builds the book with the preprocessors it needs
+
+use std::{fs, path::Path};
+
+use modmod::{RenderFilter, RenderOptions, Track};
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn flake(content: &Path, filter: RenderFilter) -> String {
+    let track = Track::load(content.join("track.toml")).unwrap();
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            nix: true,
+            filter,
+            ..Default::default()
+        })
+        .unwrap();
+    fs::read_to_string(out_dir.path().join("flake.nix")).unwrap()
+}
+
+#[test]
+fn books_with_quizzes_are_built_with_mdbook_quiz() {
+    let content_dir = tempfile::tempdir().unwrap();
+    let content = content_dir.path();
+    write(
+        &content.join("track.toml"),
+        "name = \"Rust\"\nmodules = [\"mod.toml\"]\n",
+    );
+    write(
+        &content.join("mod.toml"),
+        r#"
+        name = "Basics"
+        description = ""
+
+        [[units]]
+        name = "Syntax"
+        topics = ["topic.toml"]
+
+        [[units]]
+        name = "Ownership"
+        topics = ["topic.toml"]
+        quizzes = ["quiz.toml"]
+        "#,
+    );
+    write(&content.join("topic.toml"), "name = \"Variables\"\n");
+    write(&content.join("slides.md"), "# Variables\n");
+    write(
+        &content.join("quiz.toml"),
+        "[[questions]]\ntype = \"ShortAnswer\"\nprompt.prompt = \"Who owns it?\"\nanswer.answer = \"me\"\n",
+    );
+
+    let with_quizzes = flake(content, RenderFilter::default());
+    assert!(with_quizzes.contains("nativeBuildInputs = [ pkgs.mdbook pkgs.mdbook-quiz ];"));
+
+    let without_quizzes = flake(
+        content,
+        RenderFilter {
+            units: vec!["1.1".to_string()],
+            ..Default::default()
+        },
+    );
+    assert!(without_quizzes.contains("nativeBuildInputs = [ pkgs.mdbook ];"));
+    assert!(!without_quizzes.contains("mdbook-quiz"));
+}
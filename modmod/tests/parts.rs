@@ -0,0 +1,63 @@
+//! Tracks that group their modules into parts, which get part titles in the book summary
+
+use std::{fs, path::Path};
+
+use modmod::{RenderOptions, Track};
+
+fn write_track(dir: &Path, parts: &str) -> std::path::PathBuf {
+    for name in ["Setup", "Basics", "Ownership"] {
+        fs::write(
+            dir.join(format!("{name}.toml")),
+            format!("name = \"{name}\"\ndescription = \"\"\n\n[[units]]\nname = \"{name} intro\"\ntopics = []\n"),
+        )
+        .unwrap();
+    }
+    let track_toml = dir.join("track.toml");
+    fs::write(
+        &track_toml,
+        format!("name = \"Parts\"\nmodules = [\"Setup.toml\", \"Basics.toml\", \"Ownership.toml\"]\n\n{parts}"),
+    )
+    .unwrap();
+    track_toml
+}
+
+#[test]
+fn parts_title_their_modules_in_the_summary() {
+    let track_dir = tempfile::tempdir().unwrap();
+    let track_toml = write_track(
+        track_dir.path(),
+        "[numbering]\nparts = \"restart\"\n\n[[parts]]\ntitle = \"Fundamentals\"\nmodules = [\"basics\"]\n\n[[parts]]\ntitle = \"Memory\"\nmodules = [\"3\"]\n",
+    );
+    let track = Track::load(&track_toml).unwrap();
+    assert_eq!(track.part_of(1), None);
+    assert_eq!(track.part_of(3), Some(1));
+
+    let out_dir = tempfile::tempdir().unwrap();
+    track
+        .render(&RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+    let summary = fs::read_to_string(out_dir.path().join("book/src/SUMMARY.md")).unwrap();
+    let fundamentals = summary
+        .find("\n# Part I: Fundamentals\n\n- [Basics]")
+        .unwrap();
+    let memory = summary
+        .find("\n# Part II: Memory\n\n- [Ownership]")
+        .unwrap();
+    assert!(summary.find("- [Setup]").unwrap() < fundamentals);
+    assert!(fundamentals < memory);
+    let ownership = fs::read_dir(out_dir.path().join("book/src"))
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .find(|page| page.starts_with("# Module 1 - Ownership"));
+    assert!(ownership.is_some());
+
+    let track_toml = write_track(
+        track_dir.path(),
+        "[[parts]]\ntitle = \"Fundamentals\"\nmodules = [\"basics\"]\n",
+    );
+    let report = Track::load(&track_toml).unwrap_err();
+    assert!(format!("{report:?}").contains("Module 3 isn't in a part"));
+}
@@ -0,0 +1,115 @@
+//! Versions of a track at git refs and in the working tree rendered side by side
+
+use std::{fs, path::Path, process::Command};
+
+use modmod::{
+    release::{ReleaseOptions, ReleasesConfig},
+    RenderOptions,
+};
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=Teacher",
+            "-c",
+            "user.email=teacher@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn write_track(dir: &Path, name: &str) {
+    fs::write(
+        dir.join("track.toml"),
+        format!("name = \"{name}\"\nmodules = [\"mod.toml\"]\n"),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("mod.toml"),
+        "name = \"Basics\"\ndescription = \"\"\n\n[[units]]\nname = \"Syntax\"\ntopics = []\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn versions_render_side_by_side() {
+    let repo = tempfile::tempdir().unwrap();
+    let content = repo.path().join("content");
+    fs::create_dir(&content).unwrap();
+    git(repo.path(), &["init", "--quiet"]);
+    write_track(&content, "Rust 2023");
+    git(repo.path(), &["add", "."]);
+    git(repo.path(), &["commit", "--quiet", "-m", "Course of 2023"]);
+    git(repo.path(), &["tag", "2023-q4"]);
+    write_track(&content, "Rust 2024");
+
+    let releases_toml = content.join("releases.toml");
+    fs::write(
+        &releases_toml,
+        r#"
+        title = "Rust intro"
+
+        [[versions]]
+        name = "2023-q4"
+        ref = "2023-q4"
+        track = "track.toml"
+
+        [[versions]]
+        name = "2024-q2"
+        track = "track.toml"
+        "#,
+    )
+    .unwrap();
+    let releases = ReleasesConfig::load(&releases_toml).unwrap();
+
+    let out_dir = tempfile::tempdir().unwrap();
+    let opts = ReleaseOptions {
+        render: RenderOptions {
+            out_dir: out_dir.path().to_path_buf(),
+            slide_url_base: "/course".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let rendered = releases.render(&opts).unwrap();
+    assert!(rendered.iter().all(|v| v.rendered));
+    let book_toml = |version: &str| {
+        fs::read_to_string(out_dir.path().join(version).join("book/book.toml")).unwrap()
+    };
+    assert!(book_toml("2023-q4").contains("title = \"Rust 2023\""));
+    assert!(book_toml("2024-q2").contains("title = \"Rust 2024\""));
+    assert!(!out_dir.path().join("2023-q4.checkout").exists());
+
+    let index = fs::read_to_string(out_dir.path().join("index.html")).unwrap();
+    assert!(
+        index
+            .find("<strong>2024-q2</strong> (latest): <a href=\"2024-q2/book/\">")
+            .unwrap()
+            < index.find("<a href=\"2023-q4/book/\">").unwrap()
+    );
+    let links: Vec<_> = index
+        .split("href=\"")
+        .skip(1)
+        .map(|link| &link[..link.find('"').unwrap()])
+        .collect();
+    assert_eq!(links.len(), 4);
+    for link in links {
+        assert!(out_dir.path().join(link).is_dir(), "{link} is not rendered");
+    }
+    let versions = fs::read_to_string(out_dir.path().join("versions.json")).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Vec<String>>(&versions).unwrap(),
+        ["2023-q4", "2024-q2"]
+    );
+
+    // What was rendered from a ref is kept, as the content at the ref can't change
+    let rendered = releases.render(&opts).unwrap();
+    assert_eq!(
+        rendered.iter().map(|v| v.rendered).collect::<Vec<_>>(),
+        [false, true]
+    );
+}
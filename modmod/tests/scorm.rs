@@ -0,0 +1,39 @@
+//! The SCORM package has a SCO for every unit page in the built book
+
+use std::{fs, io::Read};
+
+use modmod::{numbering::section_file_name, scorm::ScormOptions, Track};
+
+#[test]
+fn scorm_package_has_a_sco_per_built_unit() {
+    let out_dir = tempfile::tempdir().unwrap();
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+
+    // Stand-in for the output of `mdbook build`, with only the first unit built
+    let html_dir = out_dir.path().join("book").join("target");
+    fs::create_dir_all(&html_dir).unwrap();
+    fs::write(html_dir.join("index.html"), "<html><head></head></html>").unwrap();
+    let unit_name = &track.modules[0].data.units[0].data.name;
+    let page = section_file_name(unit_name).with_extension("html");
+    fs::write(html_dir.join(&page), "<html><head></head></html>").unwrap();
+
+    let package = track
+        .scorm_package(out_dir.path(), &ScormOptions::default())
+        .unwrap();
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(package).unwrap()).unwrap();
+    let mut manifest = String::new();
+    zip.by_name("imsmanifest.xml")
+        .unwrap()
+        .read_to_string(&mut manifest)
+        .unwrap();
+    assert_eq!(manifest.matches(r#"adlcp:scormtype="sco""#).count(), 1);
+    assert!(manifest.contains(&format!(r#"href="book/{}""#, page.to_string_lossy())));
+
+    let mut sco = String::new();
+    zip.by_name(&format!("book/{}", page.to_string_lossy()))
+        .unwrap()
+        .read_to_string(&mut sco)
+        .unwrap();
+    assert!(sco.contains("LMSInitialize"));
+}
@@ -0,0 +1,40 @@
+//! Resolved course models can be cached to disk and read back
+
+use std::path::{Path, PathBuf};
+
+use modmod::{book::Book, Track};
+
+#[test]
+fn track_round_trips_through_json() {
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    let json = serde_json::to_string(&track).unwrap();
+    let deserialized: Track = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized.name, track.name);
+    assert_eq!(deserialized.modules.len(), track.modules.len());
+    assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+}
+
+#[test]
+fn book_round_trips_through_json() {
+    let content = Path::new("/content/description.md");
+    let exercise = Path::new("/content/exercise");
+    let images = [PathBuf::from("/content/exercise/images/diagram.svg")];
+
+    let mut builder = Book::builder("Round trip");
+    let mut chapter = builder.chapter("Basics", 1);
+    let mut section = chapter.section(1, 1, "Hello");
+    section.subsection("Hello", content, &images, exercise);
+    section.add();
+    chapter.add();
+    let book = builder.build();
+
+    let json = serde_json::to_string(&book).unwrap();
+    let deserialized: Book = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized.title, "Round trip");
+    let subsection = &deserialized.chapters[0].sections[0].subsections[0];
+    assert_eq!(subsection.content, content);
+    assert_eq!(subsection.images, [images[0].as_path()]);
+    assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+}
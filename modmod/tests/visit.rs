@@ -0,0 +1,116 @@
+//! Custom analyzers can walk the resolved track without reimplementing traversal
+
+use std::convert::Infallible;
+
+use error_stack::{Context, Result};
+use modmod::{
+    load::Indexed,
+    visit::{Position, Visitor},
+    Exercise, Module, Topic, Track, Unit,
+};
+
+#[derive(Debug)]
+struct NoError;
+
+impl std::fmt::Display for NoError {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl Context for NoError {}
+
+#[derive(Default)]
+struct ExerciseNumbers(Vec<(String, String)>);
+
+impl<'t> Visitor<'t> for ExerciseNumbers {
+    type Error = NoError;
+
+    fn visit_exercise(
+        &mut self,
+        position: Position,
+        _topic: &'t Indexed<Topic>,
+        exercise: &'t Indexed<Exercise>,
+    ) -> Result<(), NoError> {
+        self.0
+            .push((position.to_string(), exercise.data.name.clone()));
+        Ok(())
+    }
+}
+
+#[test]
+fn exercises_are_numbered_across_topics() {
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    let mut numbers = ExerciseNumbers::default();
+    track.walk(&mut numbers).unwrap();
+
+    let unit_2_3: Vec<_> = numbers
+        .0
+        .iter()
+        .filter(|(n, _)| n.starts_with("2.3."))
+        .map(|(n, _)| n.as_str())
+        .collect();
+    assert_eq!(unit_2_3, ["2.3.1", "2.3.2", "2.3.3", "2.3.4", "2.3.5"]);
+    assert_eq!(numbers.0.len(), track.exercises().count());
+}
+
+/// The modules and units in the order they are entered and left
+#[derive(Default)]
+struct Nesting(Vec<String>);
+
+impl<'t> Visitor<'t> for Nesting {
+    type Error = Infallible;
+
+    fn visit_module(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.0.push(format!("<{position}"));
+        Ok(())
+    }
+
+    fn visit_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        _unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.0.push(format!("<{position}"));
+        Ok(())
+    }
+
+    fn leave_unit(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+        _unit: &'t Indexed<Unit>,
+    ) -> Result<(), Infallible> {
+        self.0.push(format!("{position}>"));
+        Ok(())
+    }
+
+    fn leave_module(
+        &mut self,
+        position: Position,
+        _module: &'t Indexed<Module>,
+    ) -> Result<(), Infallible> {
+        self.0.push(format!("{position}>"));
+        Ok(())
+    }
+}
+
+#[test]
+fn modules_and_units_are_left_after_their_children() {
+    let track = Track::load("../content/rust-intro.track.toml").unwrap();
+    let mut nesting = Nesting::default();
+    track.walk_infallible(&mut nesting);
+
+    let module_1: Vec<_> = nesting
+        .0
+        .iter()
+        .take_while(|n| n.as_str() != "<2")
+        .map(String::as_str)
+        .collect();
+    assert_eq!(module_1, ["<1", "<1.1", "1.1>", "1>"]);
+}